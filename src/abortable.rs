@@ -0,0 +1,59 @@
+use futures_util::future::{AbortHandle, AbortRegistration, Abortable};
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Wake, Waker};
+
+use crate::{ShutdownManager, ShutdownSignal};
+
+/// Waker that aborts an [`Abortable`] future when it is woken.
+struct AbortOnWake(AbortHandle);
+
+impl Wake for AbortOnWake {
+	fn wake(self: Arc<Self>) {
+		self.0.abort();
+	}
+
+	fn wake_by_ref(self: &Arc<Self>) {
+		self.0.abort();
+	}
+}
+
+impl<T: Clone> ShutdownSignal<T> {
+	/// Create an [`AbortRegistration`] that is aborted when the shutdown signal is received.
+	///
+	/// This allows code that uses [`Abortable`] to be cancelled by the shutdown manager
+	/// without rewriting it to use [`Self::wrap_cancel()`].
+	/// Note that [`Abortable`] does not report the shutdown reason.
+	///
+	/// The registration stays registered until the shutdown is triggered,
+	/// even if the abortable future is dropped before that.
+	/// For many short-lived futures, you should use [`Self::wrap_cancel()`] instead.
+	pub fn abort_registration(&self) -> AbortRegistration {
+		let (handle, registration) = AbortHandle::new_pair();
+		let mut inner = self.inner.lock();
+		if inner.signal_reason(self.kind).is_some() {
+			handle.abort();
+		} else {
+			inner.shutdown_waiters(self.kind).register_detached(Waker::from(Arc::new(AbortOnWake(handle))));
+		}
+		registration
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Create an [`AbortRegistration`] that is aborted when the shutdown is triggered.
+	///
+	/// See [`ShutdownSignal::abort_registration()`] for more details.
+	#[inline]
+	pub fn abort_registration(&self) -> AbortRegistration {
+		self.wait_shutdown_triggered().abort_registration()
+	}
+
+	/// Wrap a future in an [`Abortable`] that is aborted when the shutdown is triggered.
+	///
+	/// See [`ShutdownSignal::abort_registration()`] for more details.
+	#[inline]
+	pub fn wrap_abortable<F: Future>(&self, future: F) -> Abortable<F> {
+		Abortable::new(future, self.abort_registration())
+	}
+}
@@ -0,0 +1,33 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::{ShutdownAlreadyStarted, ShutdownManager};
+
+/// A type-erased shutdown reason.
+///
+/// You can use [`downcast_ref()`](https://doc.rust-lang.org/std/any/trait.Any.html#method.downcast_ref) to inspect the concrete reason.
+pub type AnyShutdownReason = Arc<dyn Any + Send + Sync>;
+
+/// A shutdown manager with a type-erased shutdown reason.
+///
+/// Libraries can accept an [`AnyShutdownManager`] without dictating the shutdown reason type of the application.
+/// The application can still use its own reason type, and retrieve it with [`ShutdownManager::shutdown_reason_downcast()`].
+pub type AnyShutdownManager = ShutdownManager<AnyShutdownReason>;
+
+impl ShutdownManager<AnyShutdownReason> {
+	/// Trigger the shutdown with a shutdown reason of any type.
+	///
+	/// This is the same as [`Self::trigger_shutdown()`], except that the reason is type-erased for you.
+	#[inline]
+	pub fn trigger_shutdown_any<R: Any + Send + Sync>(&self, reason: R) -> Result<(), ShutdownAlreadyStarted<AnyShutdownReason>> {
+		self.trigger_shutdown(Arc::new(reason))
+	}
+
+	/// Get the shutdown reason as a specific type.
+	///
+	/// Returns [`None`] if the shutdown has not been triggered yet, or if the shutdown reason is not of type `R`.
+	#[inline]
+	pub fn shutdown_reason_downcast<R: Any + Send + Sync>(&self) -> Option<Arc<R>> {
+		self.shutdown_reason()?.downcast().ok()
+	}
+}
@@ -0,0 +1,134 @@
+use std::task::{Context, Poll};
+
+use crate::hooks::{Hook, HookFuture};
+use crate::shared::Shared;
+
+/// The async drop hooks of a shutdown manager.
+///
+/// The hooks are started once the shutdown has completed,
+/// and they are run by the [`ShutdownComplete`][crate::ShutdownComplete] futures before those resolve.
+pub(crate) struct AsyncDrops<T> {
+	/// Hooks that have not been started yet.
+	pending: Vec<Hook<T>>,
+
+	/// Hooks that have been started, but that are not being polled right now.
+	running: Vec<HookFuture>,
+
+	/// The number of hooks that did not finish yet, including the ones that are being polled right now.
+	unfinished: usize,
+}
+
+impl<T> AsyncDrops<T> {
+	/// Create a new empty set of async drop hooks.
+	pub fn new() -> Self {
+		Self {
+			pending: Vec::new(),
+			running: Vec::new(),
+			unfinished: 0,
+		}
+	}
+
+	/// Add a hook to be started when the shutdown completes.
+	pub fn add(&mut self, hook: Hook<T>) {
+		self.pending.push(hook);
+		self.unfinished += 1;
+	}
+
+	/// Check if all hooks have finished.
+	pub fn is_done(&self) -> bool {
+		self.unfinished == 0
+	}
+}
+
+/// Start and poll the async drop hooks.
+///
+/// Must only be called after the shutdown has completed.
+/// The hooks are polled without holding the lock on the shutdown manager,
+/// so they are free to use the shutdown manager themselves.
+///
+/// Returns `true` if all hooks have finished.
+/// Otherwise, the current task will be woken when they make progress or when they have all finished.
+pub(crate) fn drive_async_drops<T: Clone>(inner: &Shared<T>, reason: &T, context: &mut Context) -> bool {
+	let mut guard = {
+		let mut locked = inner.lock();
+		if locked.async_drops.is_done() {
+			return true;
+		}
+		DriveAsyncDrops {
+			inner,
+			pending: std::mem::take(&mut locked.async_drops.pending).into_iter(),
+			unpolled: std::mem::take(&mut locked.async_drops.running).into_iter(),
+			running: Vec::new(),
+			finished: 0,
+			busy: false,
+		}
+	};
+
+	while let Some(hook) = guard.pending.next() {
+		guard.busy = true;
+		let future = hook(reason.clone());
+		guard.poll(future, context);
+	}
+	while let Some(future) = guard.unpolled.next() {
+		guard.poll(future, context);
+	}
+
+	drop(guard);
+	inner.lock().async_drops.is_done()
+}
+
+/// Guard that puts the hooks back and updates the unfinished count when dropped, even if one of the hooks panicked.
+///
+/// A hook that panics is dropped and counted as finished, so the shutdown completion does not wait for it forever.
+struct DriveAsyncDrops<'a, T: Clone> {
+	inner: &'a Shared<T>,
+
+	/// Hooks that have not been started yet.
+	pending: std::vec::IntoIter<Hook<T>>,
+
+	/// Running hooks that have not been polled yet.
+	unpolled: std::vec::IntoIter<HookFuture>,
+
+	/// Running hooks that have been polled, and that did not finish yet.
+	running: Vec<HookFuture>,
+
+	/// The number of hooks that finished.
+	finished: usize,
+
+	/// If true, a hook is being started or polled.
+	busy: bool,
+}
+
+impl<T: Clone> DriveAsyncDrops<'_, T> {
+	/// Poll a running hook.
+	fn poll(&mut self, mut future: HookFuture, context: &mut Context) {
+		self.busy = true;
+		match future.as_mut().poll(context) {
+			Poll::Pending => self.running.push(future),
+			Poll::Ready(()) => self.finished += 1,
+		}
+		self.busy = false;
+	}
+}
+
+impl<T: Clone> Drop for DriveAsyncDrops<'_, T> {
+	fn drop(&mut self) {
+		let mut inner = self.inner.lock();
+		if self.busy {
+			// A hook panicked, and it was dropped during unwinding.
+			self.finished += 1;
+			// The panicking task will not poll the other hooks again, so let another waiting task do it.
+			inner.on_shutdown_complete.wake_all();
+		}
+		let mut pending: Vec<_> = self.pending.by_ref().collect();
+		pending.append(&mut inner.async_drops.pending);
+		inner.async_drops.pending = pending;
+		inner.async_drops.running.append(&mut self.running);
+		inner.async_drops.running.extend(self.unpolled.by_ref());
+		inner.async_drops.unfinished -= self.finished;
+		if inner.async_drops.is_done() {
+			// Other futures may be waiting for hooks that we were polling.
+			inner.on_shutdown_complete.wake_all();
+		}
+	}
+}
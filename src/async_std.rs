@@ -0,0 +1,109 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use async_std::task::JoinHandle;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager, ShutdownSignal};
+
+pin_project_lite::pin_project! {
+	/// Wrapped async-std [`JoinHandle`] that cancels the task when a shutdown is triggered.
+	///
+	/// Created by [`ShutdownManager::wrap_abort_async_std()`].
+	///
+	/// If the task finishes before the shutdown is triggered, the output of the task is yielded as `Ok(output)`.
+	///
+	/// If the shutdown is triggered first, the task is cancelled.
+	/// The future then waits for the task to actually stop, and yields the shutdown reason as `Err(shutdown_reason)`.
+	/// If the task managed to finish anyway, the output is still yielded as `Ok(output)`.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct AsyncStdWrapAbort<T: Clone, O> {
+		#[pin]
+		shutdown_signal: ShutdownSignal<T>,
+		join_handle: Option<JoinHandle<O>>,
+		cancel: Option<Pin<Box<dyn Future<Output = Option<O>> + Send>>>,
+		reason: Option<T>,
+	}
+}
+
+impl<T: Clone, O> std::fmt::Debug for AsyncStdWrapAbort<T, O> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("AsyncStdWrapAbort")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("task_id", &self.join_handle.as_ref().map(|handle| handle.task().id()))
+			.field("aborted", &self.reason.is_some())
+			.finish()
+	}
+}
+
+impl<T: Clone, O: Send + 'static> Future for AsyncStdWrapAbort<T, O> {
+	type Output = Result<O, T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		if let Some(join_handle) = me.join_handle {
+			if let Poll::Ready(output) = Pin::new(join_handle).poll(context) {
+				return Poll::Ready(Ok(output));
+			}
+			match me.shutdown_signal.as_mut().poll(context) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(reason) => {
+					*me.reason = Some(reason);
+					if let Some(join_handle) = me.join_handle.take() {
+						*me.cancel = Some(Box::pin(join_handle.cancel()));
+					}
+					me.shutdown_signal.inner.record_cancelled();
+				},
+			}
+		}
+
+		// Wait for the task to actually stop.
+		let cancel = me.cancel.as_mut().expect("AsyncStdWrapAbort polled after completion");
+		let output = std::task::ready!(cancel.as_mut().poll(context));
+		*me.cancel = None;
+		match output {
+			Some(output) => Poll::Ready(Ok(output)),
+			None => Poll::Ready(Err(me.reason.take().expect("AsyncStdWrapAbort is missing the shutdown reason"))),
+		}
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Spawn a task on the async-std runtime that is cancelled when the shutdown is triggered.
+	///
+	/// The future is wrapped with [`Self::wrap_cancel()`] and [`Self::wrap_delay_shutdown()`] *before* it is spawned,
+	/// so the shutdown does not complete until the task has been cancelled or has finished.
+	///
+	/// The returned [`JoinHandle`] resolves to `Ok(output)` if the future completed,
+	/// or to `Err(shutdown_reason)` if it was cancelled.
+	/// Dropping the handle does not affect the task.
+	///
+	/// If the shutdown has already completed, this function returns an error and the future is not spawned.
+	pub fn spawn_async_std<F>(&self, future: F) -> Result<JoinHandle<Result<F::Output, T>>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let future = self.wrap_delay_shutdown(self.wrap_cancel(future))?;
+		Ok(async_std::task::spawn(future))
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap an async-std [`JoinHandle`] so that the task is cancelled when the shutdown is triggered.
+	///
+	/// Dropping a [`JoinHandle`] does not stop the task, so wrapping the handle with [`Self::wrap_cancel()`] is usually a mistake.
+	/// This function cancels the task itself instead, and waits until the task has actually been cancelled.
+	///
+	/// See [`AsyncStdWrapAbort`] for the output of the returned future.
+	#[inline]
+	pub fn wrap_abort_async_std<O: Send + 'static>(&self, join_handle: JoinHandle<O>) -> AsyncStdWrapAbort<T, O> {
+		AsyncStdWrapAbort {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			join_handle: Some(join_handle),
+			cancel: None,
+			reason: None,
+		}
+	}
+}
@@ -0,0 +1,36 @@
+use std::sync::{Arc, Mutex};
+
+use crate::lock::MutexExt;
+use crate::shared::Shared;
+
+/// Triggers the shutdown when it is dropped.
+///
+/// Shared by all clones of a [`ShutdownManager`][crate::ShutdownManager] that was built with
+/// [`ShutdownManagerBuilder::trigger_on_drop()`][crate::ShutdownManagerBuilder::trigger_on_drop],
+/// so it is dropped together with the last clone.
+pub(crate) struct AutoTrigger<T: Clone> {
+	/// The state of the current generation of the shutdown manager.
+	current: Arc<Mutex<Arc<Shared<T>>>>,
+
+	/// The shutdown reason to use.
+	reason: Mutex<Option<T>>,
+}
+
+impl<T: Clone> AutoTrigger<T> {
+	/// Create a new auto trigger for the given shutdown manager state.
+	pub fn new(current: Arc<Mutex<Arc<Shared<T>>>>, reason: T) -> Self {
+		Self {
+			current,
+			reason: Mutex::new(Some(reason)),
+		}
+	}
+}
+
+impl<T: Clone> Drop for AutoTrigger<T> {
+	fn drop(&mut self) {
+		if let Some(reason) = self.reason.lock_unpoisoned().take() {
+			let inner = self.current.lock_unpoisoned().clone();
+			inner.shutdown_idempotent(reason);
+		}
+	}
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::Ordering;
+use std::sync::Weak;
+use std::time::Duration;
+
+use crate::shared::Shared;
+use crate::ShutdownBlocker;
+
+/// A periodic report of the delay tokens that are holding up a slow shutdown.
+///
+/// Passed to the callback of [`ShutdownManagerBuilder::report_blockers()`][crate::ShutdownManagerBuilder::report_blockers].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct BlockerReport {
+	/// The time since the shutdown was triggered, measured by the clock of the shutdown manager.
+	pub elapsed: Duration,
+
+	/// The number of delay tokens that still exist.
+	pub delay_tokens: usize,
+
+	/// Information about the delay tokens that still exist, in the order they were created.
+	pub blockers: Vec<ShutdownBlocker>,
+}
+
+/// A callback that receives blocker reports.
+pub(crate) type ReportCallback = Box<dyn FnMut(&BlockerReport) + Send>;
+
+/// Report the delay tokens of a generation every `interval` until the shutdown completes, using the clock of the generation.
+pub(crate) fn spawn_blocker_report<T: Clone + Send + 'static>(inner: Weak<Shared<T>>, interval: Duration, elapsed: Duration, mut report: ReportCallback) {
+	let clock = match inner.upgrade() {
+		Some(inner) => inner.clock(),
+		None => return,
+	};
+	clock.call_after(interval, Box::new(move || {
+		let elapsed = elapsed + interval;
+		let blocker_report = match inner.upgrade() {
+			Some(shared) => {
+				let locked = shared.lock();
+				if locked.completed_reason().is_some() {
+					return;
+				}
+				BlockerReport {
+					elapsed,
+					delay_tokens: shared.delay_tokens.load(Ordering::Relaxed),
+					blockers: locked.blockers.snapshot(),
+				}
+			},
+			None => return,
+		};
+		report(&blocker_report);
+		spawn_blocker_report(inner, interval, elapsed, report);
+	}));
+}
+
+/// Log a blocker report with `tracing`.
+#[cfg(feature = "tracing")]
+pub(crate) fn log_blocker_report(report: &BlockerReport) {
+	tracing::warn!(
+		elapsed = ?report.elapsed,
+		delay_tokens = report.delay_tokens,
+		"shutdown is still waiting for {} delay tokens after {:?}",
+		report.delay_tokens,
+		report.elapsed,
+	);
+	for blocker in &report.blockers {
+		if blocker.group.is_some() || blocker.status.is_some() {
+			tracing::warn!(
+				phase = blocker.phase,
+				group = ?blocker.group,
+				status = ?blocker.status,
+				"shutdown is blocked by delay token",
+			);
+		}
+	}
+}
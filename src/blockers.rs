@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "debug-diagnostics")]
+use std::backtrace::Backtrace;
+#[cfg(feature = "debug-diagnostics")]
+use std::panic::Location;
+use std::sync::{Arc, Mutex};
+
+use crate::lock::MutexExt;
+
+/// Information about a delay token that is holding up the shutdown.
+///
+/// Returned by [`ShutdownManager::pending_blockers()`][crate::ShutdownManager::pending_blockers].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ShutdownBlocker {
+	/// The shutdown phase that the delay token delays.
+	pub phase: usize,
+
+	/// The name of the token group that the delay token belongs to, if any.
+	pub group: Option<String>,
+
+	/// The last status set with [`DelayShutdownToken::set_status()`][crate::DelayShutdownToken::set_status], if any.
+	pub status: Option<String>,
+
+	/// The source location where the delay token was created.
+	///
+	/// All clones of a delay token share the location of the original token.
+	#[cfg(feature = "debug-diagnostics")]
+	pub location: &'static Location<'static>,
+
+	/// The backtrace of the creation of the delay token.
+	///
+	/// The backtrace is only captured if enabled with the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment variables.
+	/// See [`Backtrace::capture()`] for more details.
+	#[cfg(feature = "debug-diagnostics")]
+	pub backtrace: Arc<Backtrace>,
+}
+
+/// The information of a single delay token, shared by all its clones.
+pub(crate) struct BlockerInfo {
+	/// The unique ID of the delay token in the shutdown manager.
+	id: u64,
+
+	/// The shutdown phase that the delay token delays.
+	phase: usize,
+
+	/// The name of the token group that the delay token belongs to, if any.
+	group: Option<String>,
+
+	/// The status of the delay token.
+	status: Mutex<Option<String>>,
+
+	/// The source location where the delay token was created.
+	#[cfg(feature = "debug-diagnostics")]
+	location: &'static Location<'static>,
+
+	/// The backtrace of the creation of the delay token.
+	#[cfg(feature = "debug-diagnostics")]
+	backtrace: Arc<Backtrace>,
+}
+
+impl BlockerInfo {
+	/// Get the current status.
+	pub fn status(&self) -> Option<String> {
+		self.status.lock_unpoisoned().clone()
+	}
+
+	/// Set or clear the current status.
+	pub fn set_status(&self, status: Option<String>) {
+		*self.status.lock_unpoisoned() = status;
+	}
+
+	/// Get the source location where the delay token was created.
+	#[cfg(feature = "debug-diagnostics")]
+	pub fn location(&self) -> &'static Location<'static> {
+		self.location
+	}
+
+	/// Create a snapshot of the information.
+	fn snapshot(&self) -> ShutdownBlocker {
+		ShutdownBlocker {
+			phase: self.phase,
+			group: self.group.clone(),
+			status: self.status(),
+			#[cfg(feature = "debug-diagnostics")]
+			location: self.location,
+			#[cfg(feature = "debug-diagnostics")]
+			backtrace: self.backtrace.clone(),
+		}
+	}
+}
+
+/// The registry of live delay tokens in a shutdown manager.
+#[derive(Default)]
+pub(crate) struct Blockers {
+	/// The ID for the next delay token.
+	next_id: u64,
+
+	/// The live delay tokens, by ID.
+	tokens: BTreeMap<u64, Arc<BlockerInfo>>,
+}
+
+impl Blockers {
+	/// Create a new empty registry.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Register a new delay token.
+	#[track_caller]
+	pub fn add(&mut self, phase: usize, group: Option<String>) -> Arc<BlockerInfo> {
+		let id = self.next_id;
+		self.next_id += 1;
+		let info = Arc::new(BlockerInfo {
+			id,
+			phase,
+			group,
+			status: Mutex::new(None),
+			#[cfg(feature = "debug-diagnostics")]
+			location: Location::caller(),
+			#[cfg(feature = "debug-diagnostics")]
+			backtrace: Arc::new(Backtrace::capture()),
+		});
+		self.tokens.insert(id, info.clone());
+		info
+	}
+
+	/// Remove a delay token from the registry.
+	pub fn remove(&mut self, info: &BlockerInfo) {
+		self.tokens.remove(&info.id);
+	}
+
+	/// Get a snapshot of all live delay tokens, in the order they were created.
+	pub fn snapshot(&self) -> Vec<ShutdownBlocker> {
+		self.tokens.values().map(|info| info.snapshot()).collect()
+	}
+}
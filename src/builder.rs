@@ -0,0 +1,252 @@
+use std::sync::Arc;
+#[cfg(not(target_family = "wasm"))]
+use std::time::Duration;
+
+#[cfg(not(target_family = "wasm"))]
+use crate::{BlockerReport, Clock, SystemClock};
+use crate::{AutoTrigger, ShutdownManager, WakeOrder};
+
+/// What happens when the shutdown is triggered again after it was already started.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[non_exhaustive]
+pub enum RepeatTrigger {
+	/// Return a [`ShutdownAlreadyStarted`][crate::ShutdownAlreadyStarted] error.
+	///
+	/// This is the default.
+	#[default]
+	Error,
+
+	/// Trigger the forced shutdown with the new reason.
+	///
+	/// This mimics the common behaviour of command line tools where a second Ctrl+C stops the application immediately.
+	/// An error is only returned if the forced shutdown was also triggered already.
+	///
+	/// Only explicit calls to [`ShutdownManager::trigger_shutdown()`] escalate, including the ones made by the signal handlers of this crate.
+	/// Dropped trigger tokens, finished [`wrap_trigger_shutdown()`][ShutdownManager::wrap_trigger_shutdown] futures,
+	/// panics caught by `wrap_catch_panic()` and propagated shutdowns never force the shutdown.
+	Force,
+}
+
+/// A function that configures a new shutdown manager.
+type Setup<T> = Box<dyn FnOnce(&ShutdownManager<T>)>;
+
+/// Builder for a [`ShutdownManager`] with custom options.
+///
+/// Created by [`ShutdownManager::builder()`]:
+/// ```
+/// use async_shutdown::{RepeatTrigger, ShutdownManager, WakeOrder};
+///
+/// let shutdown: ShutdownManager<i32> = ShutdownManager::builder()
+///     .completion_wake_order(WakeOrder::Fifo)
+///     .repeat_trigger(RepeatTrigger::Force)
+///     .build();
+/// ```
+#[must_use = "the builder does nothing until you call `build()`"]
+pub struct ShutdownManagerBuilder<T: Clone> {
+	completion_wake_order: WakeOrder,
+	repeat_trigger: RepeatTrigger,
+	drop_reason: Option<T>,
+	#[cfg(not(target_family = "wasm"))]
+	clock: Arc<dyn Clock>,
+	setup: Vec<Setup<T>>,
+}
+
+impl<T: Clone> ShutdownManagerBuilder<T> {
+	/// Create a new builder with the default options.
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			completion_wake_order: WakeOrder::default(),
+			repeat_trigger: RepeatTrigger::default(),
+			drop_reason: None,
+			#[cfg(not(target_family = "wasm"))]
+			clock: Arc::new(SystemClock),
+			setup: Vec::new(),
+		}
+	}
+
+	/// Pre-allocate room for `capacity` waiting futures.
+	///
+	/// Waiting futures now store their own node of the list of waiters, so this does nothing.
+	#[deprecated(note = "waiting futures no longer need room in the shutdown manager")]
+	#[inline]
+	pub fn waker_capacity(self, capacity: usize) -> Self {
+		let _ = capacity;
+		self
+	}
+
+	/// Set the order in which [`ShutdownComplete`][crate::ShutdownComplete] futures are woken when the shutdown completes.
+	///
+	/// See [`ShutdownManager::set_completion_wake_order()`] for more details.
+	#[inline]
+	pub fn completion_wake_order(mut self, wake_order: WakeOrder) -> Self {
+		self.completion_wake_order = wake_order;
+		self
+	}
+
+	/// Set what happens when the shutdown is triggered again after it was already started.
+	///
+	/// The policy is kept when the shutdown manager is [reset][ShutdownManager::reset].
+	#[inline]
+	pub fn repeat_trigger(mut self, policy: RepeatTrigger) -> Self {
+		self.repeat_trigger = policy;
+		self
+	}
+
+	/// Set the clock that drives the timeouts and deadlines of the shutdown manager.
+	///
+	/// By default, the [`SystemClock`] is used.
+	#[cfg_attr(feature = "test-util", doc = "With a [`TestClock`][crate::test_util::TestClock] from the `test-util` feature,")]
+	#[cfg_attr(not(feature = "test-util"), doc = "With a `TestClock` from the `test-util` feature,")]
+	/// you can test the timeout behaviour of your application without real sleeps.
+	/// See [`Clock`] for the features that use the clock.
+	///
+	/// The clock is kept when the shutdown manager is [reset][ShutdownManager::reset].
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	pub fn clock(mut self, clock: impl Clock) -> Self {
+		self.clock = Arc::new(clock);
+		self
+	}
+
+	/// Trigger the shutdown with the given reason when the last clone of the shutdown manager is dropped.
+	///
+	/// This prevents tasks from waiting forever for a shutdown that nobody is left to trigger.
+	/// Only clones of the [`ShutdownManager`] itself count, including the clones used internally by views like [`ShutdownManager::map_reason()`].
+	/// Tokens and futures like [`ShutdownSignal`][crate::ShutdownSignal] and [`DelayShutdownToken`][crate::DelayShutdownToken] do not keep the manager alive.
+	///
+	/// Nothing happens if the shutdown has already been triggered.
+	/// The shutdown manager can still be [reset][ShutdownManager::reset],
+	/// in which case the shutdown of the current generation is triggered.
+	/// ```
+	/// # async fn example() {
+	/// let shutdown = async_shutdown::ShutdownManager::builder().trigger_on_drop("dropped").build();
+	/// let complete = shutdown.wait_shutdown_complete();
+	/// drop(shutdown);
+	/// assert!(complete.await == "dropped");
+	/// # }
+	/// ```
+	#[inline]
+	pub fn trigger_on_drop(mut self, reason: T) -> Self {
+		self.drop_reason = Some(reason);
+		self
+	}
+
+	/// Create the shutdown manager.
+	///
+	/// # Panics
+	/// This function panics if one of the options can not be applied,
+	#[cfg_attr(feature = "ctrl-c", doc = "like [`Self::trigger_on_ctrl_c()`] outside of a tokio runtime.")]
+	#[cfg_attr(not(feature = "ctrl-c"), doc = "like `trigger_on_ctrl_c()` outside of a tokio runtime.")]
+	#[track_caller]
+	pub fn build(self) -> ShutdownManager<T> {
+		let mut manager = ShutdownManager::new();
+		if let Some(reason) = self.drop_reason {
+			manager.auto_trigger = Some(Arc::new(AutoTrigger::new(manager.current.clone(), reason)));
+		}
+		let inner = manager.inner();
+		let mut locked = inner.lock();
+		locked.on_shutdown_complete.set_wake_order(self.completion_wake_order);
+		locked.repeat_trigger = self.repeat_trigger;
+		#[cfg(not(target_family = "wasm"))]
+		{
+			locked.clock = self.clock;
+		}
+		drop(locked);
+		drop(inner);
+		for setup in self.setup {
+			setup(&manager);
+		}
+		manager
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManagerBuilder<T> {
+	/// Force the shutdown to complete when the grace period expires after it was triggered.
+	///
+	/// This applies the deadline of [`ShutdownManager::trigger_shutdown_with_deadline()`] to every way of triggering the shutdown,
+	/// including [`TriggerShutdownToken`][crate::TriggerShutdownToken]s.
+	/// The grace period is not kept when the shutdown manager is [reset][ShutdownManager::reset].
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	pub fn grace_period(mut self, grace_period: Duration) -> Self {
+		self.setup.push(Box::new(move |manager| {
+			let inner = Arc::downgrade(&manager.inner());
+			manager.on_trigger(move |_reason| crate::spawn_deadline(inner, grace_period));
+		}));
+		self
+	}
+
+	/// Report the delay tokens that are still alive every `interval` after the shutdown was triggered, until it completes.
+	///
+	/// This makes a stuck shutdown self-diagnosing:
+	/// the callback receives the number of remaining delay tokens and their [status][crate::DelayShutdownToken::set_status],
+	/// so you can log what the shutdown is still waiting for.
+	/// ```
+	/// use std::time::Duration;
+	///
+	/// let shutdown: async_shutdown::ShutdownManager<()> = async_shutdown::ShutdownManager::builder()
+	///     .report_blockers(Duration::from_secs(5), |report| {
+	///         eprintln!("shutdown is still waiting for {} tasks after {:?}", report.delay_tokens, report.elapsed);
+	///         for blocker in &report.blockers {
+	///             eprintln!("  {:?}", blocker.status);
+	///         }
+	///     })
+	///     .build();
+	/// ```
+	///
+	/// The reports are timed by the [clock][Self::clock] of the shutdown manager.
+	/// The reporter is not kept when the shutdown manager is [reset][ShutdownManager::reset].
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	pub fn report_blockers<F>(mut self, interval: Duration, report: F) -> Self
+	where
+		F: FnMut(&BlockerReport) + Send + 'static,
+	{
+		self.setup.push(Box::new(move |manager| {
+			let inner = Arc::downgrade(&manager.inner());
+			manager.on_trigger(move |_reason| crate::blocker_report::spawn_blocker_report(inner, interval, Duration::ZERO, Box::new(report)));
+		}));
+		self
+	}
+
+	/// Log the delay tokens that are still alive every `interval` after the shutdown was triggered, until it completes.
+	///
+	/// This uses [`Self::report_blockers()`] to log a warning with `tracing`,
+	/// with the number of remaining delay tokens and the group and status of each token that has one.
+	#[cfg(all(feature = "tracing", not(target_family = "wasm")))]
+	#[inline]
+	pub fn log_blockers(self, interval: Duration) -> Self {
+		self.report_blockers(interval, crate::blocker_report::log_blocker_report)
+	}
+
+	/// Trigger the shutdown when the process receives a Ctrl+C signal.
+	///
+	/// The task is spawned by [`Self::build()`] on the current tokio runtime.
+	/// See [`ShutdownManager::trigger_on_ctrl_c()`] for more details.
+	#[cfg(feature = "ctrl-c")]
+	#[inline]
+	pub fn trigger_on_ctrl_c(mut self, reason: T) -> Self {
+		self.setup.push(Box::new(move |manager| {
+			manager.trigger_on_ctrl_c(reason);
+		}));
+		self
+	}
+}
+
+impl<T: Clone> Default for ShutdownManagerBuilder<T> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Create a builder for a shutdown manager with custom options.
+	///
+	/// See [`ShutdownManagerBuilder`] for the available options.
+	#[inline]
+	pub fn builder() -> ShutdownManagerBuilder<T> {
+		ShutdownManagerBuilder::new()
+	}
+}
@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::WrapCancel;
+
+/// The outcome of a future that can be cancelled by a shutdown.
+///
+/// This is an alternative to the `Result<O, T>` output of [`WrapCancel`],
+/// which can get confusing when the wrapped future itself returns a [`Result`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Cancelled<O, T> {
+	/// The wrapped future completed with the given output.
+	Completed(O),
+
+	/// The shutdown was triggered before the wrapped future completed.
+	ShutdownTriggered(T),
+}
+
+impl<O, T> Cancelled<O, T> {
+	/// Check if the wrapped future completed.
+	#[inline]
+	pub fn is_completed(&self) -> bool {
+		matches!(self, Self::Completed(_))
+	}
+
+	/// Check if the wrapped future was cancelled because the shutdown was triggered.
+	#[inline]
+	pub fn is_shutdown_triggered(&self) -> bool {
+		matches!(self, Self::ShutdownTriggered(_))
+	}
+
+	/// Get the output of the wrapped future, if it completed.
+	#[inline]
+	pub fn completed(self) -> Option<O> {
+		match self {
+			Self::Completed(output) => Some(output),
+			Self::ShutdownTriggered(_) => None,
+		}
+	}
+
+	/// Get the shutdown reason, if the wrapped future was cancelled.
+	#[inline]
+	pub fn shutdown_reason(self) -> Option<T> {
+		match self {
+			Self::Completed(_) => None,
+			Self::ShutdownTriggered(reason) => Some(reason),
+		}
+	}
+
+	/// Convert the outcome into a [`Result`], with the shutdown reason as error.
+	#[inline]
+	pub fn into_result(self) -> Result<O, T> {
+		match self {
+			Self::Completed(output) => Ok(output),
+			Self::ShutdownTriggered(reason) => Err(reason),
+		}
+	}
+
+	/// Get the output of the wrapped future.
+	///
+	/// # Panics
+	/// This function panics if the wrapped future was cancelled.
+	#[inline]
+	#[track_caller]
+	pub fn unwrap_completed(self) -> O {
+		match self {
+			Self::Completed(output) => output,
+			Self::ShutdownTriggered(_) => panic!("called `Cancelled::unwrap_completed()` on a `ShutdownTriggered` value"),
+		}
+	}
+}
+
+impl<O, T> From<Result<O, T>> for Cancelled<O, T> {
+	#[inline]
+	fn from(result: Result<O, T>) -> Self {
+		match result {
+			Ok(output) => Self::Completed(output),
+			Err(reason) => Self::ShutdownTriggered(reason),
+		}
+	}
+}
+
+impl<O, T> From<Cancelled<O, T>> for Result<O, T> {
+	#[inline]
+	fn from(cancelled: Cancelled<O, T>) -> Self {
+		cancelled.into_result()
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is automatically cancelled when a shutdown is triggered, with a [`Cancelled`] output.
+	///
+	/// Created by [`WrapCancel::outcome()`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelOutcome<T: Clone, F> {
+		#[pin]
+		pub(crate) wrap_cancel: WrapCancel<T, F>,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for WrapCancelOutcome<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelOutcome")
+			.field("wrap_cancel", &self.wrap_cancel)
+			.finish()
+	}
+}
+
+impl<T: Clone, F: Future> Future for WrapCancelOutcome<T, F> {
+	type Output = Cancelled<F::Output, T>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		self.project().wrap_cancel.poll(context).map(Cancelled::from)
+	}
+}
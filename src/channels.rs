@@ -0,0 +1,99 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::ShutdownManager;
+use crate::lock::MutexExt;
+
+/// A set of independent lifecycle signals, addressed by a key.
+///
+/// Each channel is a separate [`ShutdownManager`] with its own waiters, delay tokens and reason type,
+/// but they can all be reached through one coordinator.
+/// For example, a daemon could use a `"reload"` channel that is triggered by `SIGHUP`,
+/// and a `"shutdown"` channel that is triggered by `SIGTERM`:
+/// ```
+/// # async fn example() {
+/// let channels = async_shutdown::ShutdownChannels::new();
+/// let reload = channels.channel::<()>("reload");
+/// let shutdown = channels.channel::<i32>("shutdown");
+///
+/// channels.channel::<()>("reload").trigger_shutdown(()).ok();
+/// reload.wait_shutdown_triggered().await;
+/// assert!(!shutdown.is_shutdown_triggered());
+/// # }
+/// ```
+///
+/// Channels that can be triggered repeatedly (like a reload) can be reused with [`ShutdownManager::reset()`].
+///
+/// The coordinator can be cloned and shared with multiple tasks.
+/// Each clone uses the same internal state.
+#[derive(Clone)]
+pub struct ShutdownChannels<K: Ord> {
+	channels: Arc<Mutex<BTreeMap<K, Box<dyn Any + Send>>>>,
+}
+
+impl<K: Ord> ShutdownChannels<K> {
+	/// Create a new coordinator without any channels.
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			channels: Arc::new(Mutex::new(BTreeMap::new())),
+		}
+	}
+
+	/// Get the channel for the given key, creating it if it does not exist yet.
+	///
+	/// The reason type of a channel is fixed when it is created.
+	///
+	/// # Panics
+	/// This function panics if the channel already exists with a different reason type.
+	/// Use [`Self::get()`] if you want to handle that case yourself.
+	#[track_caller]
+	pub fn channel<T: Clone + Send + 'static>(&self, key: K) -> ShutdownManager<T> {
+		let mut channels = self.channels.lock_unpoisoned();
+		channels
+			.entry(key)
+			.or_insert_with(|| Box::new(ShutdownManager::<T>::new()))
+			.downcast_ref::<ShutdownManager<T>>()
+			.expect("shutdown channel exists with a different reason type")
+			.clone()
+	}
+
+	/// Get the channel for the given key, if it exists and has reason type `T`.
+	#[inline]
+	pub fn get<T: Clone + Send + 'static>(&self, key: &K) -> Option<ShutdownManager<T>> {
+		self.channels.lock_unpoisoned().get(key)?.downcast_ref::<ShutdownManager<T>>().cloned()
+	}
+
+	/// Check if a channel exists for the given key.
+	#[inline]
+	pub fn contains(&self, key: &K) -> bool {
+		self.channels.lock_unpoisoned().contains_key(key)
+	}
+
+	/// Remove a channel.
+	///
+	/// Existing handles to the channel keep working, but [`Self::channel()`] creates a new channel for the key.
+	///
+	/// Returns `true` if the channel existed.
+	#[inline]
+	pub fn remove(&self, key: &K) -> bool {
+		self.channels.lock_unpoisoned().remove(key).is_some()
+	}
+
+	/// Get the keys of all channels, in sorted order.
+	#[inline]
+	pub fn keys(&self) -> Vec<K>
+	where
+		K: Clone,
+	{
+		self.channels.lock_unpoisoned().keys().cloned().collect()
+	}
+}
+
+impl<K: Ord> Default for ShutdownChannels<K> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,139 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Condvar, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::lock::MutexExt;
+
+/// A clock that drives the timeouts and deadlines of a shutdown manager.
+///
+/// By default, shutdown managers use the [`SystemClock`], which waits in a shared background thread.
+/// You can set a different clock with [`ShutdownManagerBuilder::clock()`][crate::ShutdownManagerBuilder::clock],
+/// for example to control the passing of time in unit tests.
+///
+/// The clock is used by:
+/// * [`ShutdownManager::trigger_shutdown_with_deadline()`][crate::ShutdownManager::trigger_shutdown_with_deadline]
+/// * [`ShutdownManagerBuilder::grace_period()`][crate::ShutdownManagerBuilder::grace_period]
+/// * [`ShutdownManager::wait_shutdown_complete_timeout()`][crate::ShutdownManager::wait_shutdown_complete_timeout]
+/// * [`ShutdownManager::on_shutdown_with_timeout()`][crate::ShutdownManager::on_shutdown_with_timeout]
+/// * [`ShutdownManager::delay_shutdown_lease()`][crate::ShutdownManager::delay_shutdown_lease]
+/// * [`ShutdownManager::wrap_cancel_with_grace()`][crate::ShutdownManager::wrap_cancel_with_grace]
+/// * [`Services::register_with_timeout()`][crate::Services::register_with_timeout]
+pub trait Clock: Send + Sync + 'static {
+	/// Run `callback` once `duration` has passed.
+	///
+	/// The callback must not be run from within this function.
+	/// This function must not panic: it is called while polling futures and from within the shutdown manager.
+	fn call_after(&self, duration: Duration, callback: Box<dyn FnOnce() + Send>);
+}
+
+/// The real time clock.
+///
+/// All timers are driven by one shared background thread, so this works regardless of the async runtime you use.
+/// The thread is started when the first timer is created, and it keeps running for the lifetime of the process.
+///
+/// If the thread can not be started, the timers are queued and starting the thread is retried when the next timer is created.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn call_after(&self, duration: Duration, callback: Box<dyn FnOnce() + Send>) {
+		static QUEUE: OnceLock<&'static TimerQueue> = OnceLock::new();
+		let queue = *QUEUE.get_or_init(|| Box::leak(Box::default()));
+		queue.push(duration, callback);
+	}
+}
+
+/// The timers of the [`SystemClock`], shared with its background thread.
+#[derive(Default)]
+struct TimerQueue {
+	state: Mutex<TimerQueueState>,
+	changed: Condvar,
+}
+
+#[derive(Default)]
+struct TimerQueueState {
+	/// The pending timers, ordered by deadline.
+	timers: BinaryHeap<QueuedTimer>,
+
+	/// The sequence number for the next timer, to run timers with the same deadline in order.
+	next_sequence: u64,
+
+	/// If true, the background thread is running.
+	thread_running: bool,
+}
+
+/// A pending timer of the [`SystemClock`].
+struct QueuedTimer {
+	deadline: Instant,
+	sequence: u64,
+	callback: Box<dyn FnOnce() + Send>,
+}
+
+impl TimerQueue {
+	/// Add a timer to the queue, and start the background thread if it is not running yet.
+	fn push(&'static self, duration: Duration, callback: Box<dyn FnOnce() + Send>) {
+		let now = Instant::now();
+		// Durations too large to represent are effectively infinite.
+		let deadline = now.checked_add(duration).unwrap_or_else(|| now + Duration::from_secs(60 * 60 * 24 * 365 * 100));
+		let mut state = self.state.lock_unpoisoned();
+		let sequence = state.next_sequence;
+		state.next_sequence += 1;
+		state.timers.push(QueuedTimer { deadline, sequence, callback });
+		if !state.thread_running {
+			// If spawning fails, the thread is started by the next timer instead.
+			state.thread_running = std::thread::Builder::new()
+				.name("shutdown-timer".into())
+				.spawn(move || self.run())
+				.is_ok();
+		}
+		drop(state);
+		self.changed.notify_one();
+	}
+
+	/// Run the background thread, calling the callback of each timer when it expires.
+	fn run(&self) {
+		let mut state = self.state.lock_unpoisoned();
+		loop {
+			let now = Instant::now();
+			let deadline = match state.timers.peek() {
+				None => {
+					state = self.changed.wait(state).unwrap_or_else(PoisonError::into_inner);
+					continue;
+				},
+				Some(timer) => timer.deadline,
+			};
+			if deadline > now {
+				state = self.changed.wait_timeout(state, deadline - now).unwrap_or_else(PoisonError::into_inner).0;
+				continue;
+			}
+			let timer = state.timers.pop().expect("timer queue is empty after peek");
+			drop(state);
+			// A panicking callback must not stop the timers of everyone else.
+			std::panic::catch_unwind(AssertUnwindSafe(timer.callback)).ok();
+			state = self.state.lock_unpoisoned();
+		}
+	}
+}
+
+impl PartialEq for QueuedTimer {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for QueuedTimer {}
+
+impl PartialOrd for QueuedTimer {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for QueuedTimer {
+	fn cmp(&self, other: &Self) -> Ordering {
+		// The binary heap is a max-heap, so reverse the order to get the earliest deadline first.
+		(other.deadline, other.sequence).cmp(&(self.deadline, self.sequence))
+	}
+}
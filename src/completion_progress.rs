@@ -0,0 +1,90 @@
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::waker_list::Waiter;
+use crate::ShutdownComplete;
+
+/// Stream of the number of remaining delay tokens while the shutdown is in progress.
+///
+/// Created by [`ShutdownManager::completion_progress()`][crate::ShutdownManager::completion_progress].
+///
+/// Once the shutdown has been triggered, the stream yields the number of delay tokens that are still alive,
+/// and then a new item every time that number changes.
+/// All clones of a [`DelayShutdownToken`][crate::DelayShutdownToken] together count as one.
+/// The stream ends when the shutdown completes, after yielding `0`.
+/// If the shutdown is forced to complete, the last item may be larger than `0`.
+///
+/// If the count changes multiple times before the stream is polled again, only the latest value is reported.
+///
+/// Like [`ShutdownComplete`], the stream drives the async shutdown hooks while waiting for the shutdown to complete.
+#[must_use = "streams do nothing unless polled"]
+pub struct CompletionProgress<T: Clone> {
+	// Boxed so the stream is `Unpin`.
+	pub(crate) complete: Pin<Box<ShutdownComplete<T>>>,
+	pub(crate) waiter: Pin<Box<Waiter>>,
+
+	/// The last reported number of delay tokens.
+	pub(crate) last: Option<usize>,
+
+	/// If true, the stream has ended.
+	pub(crate) done: bool,
+}
+
+impl<T: Clone> std::fmt::Debug for CompletionProgress<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("CompletionProgress")
+			.field("complete", &self.complete)
+			.field("last", &self.last)
+			.field("done", &self.done)
+			.finish()
+	}
+}
+
+impl<T: Clone> Drop for CompletionProgress<T> {
+	fn drop(&mut self) {
+		if self.waiter.is_registered() {
+			let mut inner = self.complete.inner.lock();
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { inner.on_delay_count_change.deregister(&self.waiter) };
+		}
+	}
+}
+
+impl<T: Clone> Stream for CompletionProgress<T> {
+	type Item = usize;
+
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+		let me = self.get_mut();
+		if me.done {
+			return Poll::Ready(None);
+		}
+
+		let completed = me.complete.as_mut().poll(context).is_ready();
+		let inner = me.complete.inner.clone();
+		let mut inner = inner.lock();
+
+		if inner.shutdown_reason.is_some() {
+			let count = inner.delay_tokens;
+			if completed {
+				// SAFETY: The waiter is only registered with this list, and we hold the lock.
+				unsafe { inner.on_delay_count_change.deregister(&me.waiter) };
+				me.done = true;
+				if me.last == Some(count) {
+					return Poll::Ready(None);
+				}
+				return Poll::Ready(Some(count));
+			}
+			if me.last != Some(count) {
+				me.last = Some(count);
+				return Poll::Ready(Some(count));
+			}
+		}
+
+		// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+		// The waiter is deregistered when the stream is dropped.
+		unsafe { inner.on_delay_count_change.register(me.waiter.as_ref(), context.waker()) };
+		Poll::Pending
+	}
+}
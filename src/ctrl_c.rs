@@ -0,0 +1,38 @@
+use crate::ShutdownManager;
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Trigger the shutdown when the process receives a Ctrl+C signal.
+	///
+	/// This spawns a task on the current tokio runtime that waits for Ctrl+C using [`tokio::signal::ctrl_c()`],
+	/// and then triggers the shutdown with the given reason.
+	/// The task stops on its own when the shutdown is triggered by other means.
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() {
+	/// let shutdown = async_shutdown::ShutdownManager::new();
+	/// shutdown.trigger_on_ctrl_c(0);
+	///
+	/// // Run your application...
+	///
+	/// let exit_code = shutdown.wait_shutdown_complete().await;
+	/// std::process::exit(exit_code);
+	/// # }
+	/// ```
+	///
+	/// The returned handle can be used to find out if listening for Ctrl+C failed.
+	/// If it does, the shutdown is not triggered.
+	/// You can also simply drop the handle to detach the task.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn trigger_on_ctrl_c(&self, reason: T) -> tokio::task::JoinHandle<std::io::Result<()>> {
+		let shutdown = self.clone();
+		tokio::spawn(async move {
+			if let Ok(result) = shutdown.wrap_cancel(tokio::signal::ctrl_c()).await {
+				result?;
+				shutdown.trigger_shutdown(reason).ok();
+			}
+			Ok(())
+		})
+	}
+}
@@ -0,0 +1,74 @@
+use std::marker::PhantomData;
+
+use crate::{DelayShutdownToken, ShutdownAlreadyCompleted, ShutdownManager};
+
+/// Guard that delays shutdown completion until the end of a scope.
+///
+/// Created by [`ShutdownManager::delay_shutdown_guard()`].
+///
+/// Unlike a [`DelayShutdownToken`], the guard borrows the shutdown manager and it can not be cloned.
+/// So it can not be moved into a spawned task or stored in a long-lived struct by accident,
+/// and the shutdown is delayed for exactly as long as the guard is in scope:
+/// ```
+/// # async fn flush_buffers() {}
+/// # async fn example(shutdown: &async_shutdown::ShutdownManager<()>) {
+/// {
+///     let _guard = shutdown.delay_shutdown_guard().unwrap();
+///     flush_buffers().await;
+/// } // The shutdown may complete from here on.
+/// # }
+/// ```
+///
+/// Moving the guard to a different thread or task does not compile:
+/// ```compile_fail
+/// let shutdown = async_shutdown::ShutdownManager::<()>::new();
+/// let guard = shutdown.delay_shutdown_guard().unwrap();
+/// std::thread::spawn(move || drop(guard));
+/// ```
+#[must_use = "the guard delays the shutdown only for as long as it exists"]
+pub struct DelayGuard<'a, T: Clone> {
+	token: DelayShutdownToken<T>,
+	manager: PhantomData<&'a ShutdownManager<T>>,
+}
+
+impl<T: Clone> DelayGuard<'_, T> {
+	/// Get the shutdown phase that this guard delays.
+	#[inline]
+	pub fn phase(&self) -> usize {
+		self.token.phase()
+	}
+
+	/// Set a status message for the guard.
+	///
+	/// See [`DelayShutdownToken::set_status()`] for more details.
+	#[inline]
+	pub fn set_status(&self, status: impl Into<String>) {
+		self.token.set_status(status)
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for DelayGuard<'_, T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("DelayGuard")
+			.field("token", &self.token)
+			.finish()
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Get a guard that delays shutdown completion until it goes out of scope.
+	///
+	/// The guard borrows the shutdown manager, so it can not outlive the current scope.
+	/// Use [`Self::delay_shutdown_token()`] if you need to send the delay to a different task.
+	/// See [`DelayGuard`] for more details.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn delay_shutdown_guard(&self) -> Result<DelayGuard<'_, T>, ShutdownAlreadyCompleted<T>> {
+		Ok(DelayGuard {
+			token: self.delay_shutdown_token()?,
+			manager: PhantomData,
+		})
+	}
+}
@@ -0,0 +1,96 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{MappedShutdownManager, MappedShutdownSignal, MappedWrapCancel, ShutdownManager};
+
+/// A shutdown signal without a shutdown reason type.
+///
+/// Created by [`ShutdownManager::dyn_shutdown_signal()`].
+///
+/// Libraries can accept a [`DynShutdownSignal`] in their API without becoming generic over the shutdown reason type of the application.
+/// The signal is a future that completes with `()` when the shutdown is triggered:
+/// ```
+/// async fn serve(shutdown: async_shutdown::DynShutdownSignal) {
+///     shutdown.wrap_cancel(async {
+///         // Serve requests...
+///     }).await.ok();
+/// }
+/// ```
+///
+/// Unlike [`ShutdownSignal`][crate::ShutdownSignal], the signal can also be used without polling it,
+/// with [`Self::is_shutdown_triggered()`].
+///
+/// The signal can be cloned and sent to different threads and tasks freely.
+/// Each clone refers to the same shutdown manager.
+#[must_use = "futures must be polled to make progress"]
+pub struct DynShutdownSignal {
+	view: MappedShutdownManager<()>,
+	signal: Option<MappedShutdownSignal<()>>,
+}
+
+impl std::fmt::Debug for DynShutdownSignal {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("DynShutdownSignal")
+			.field("shutdown_triggered", &self.is_shutdown_triggered())
+			.field("shutdown_completed", &self.is_shutdown_completed())
+			.finish()
+	}
+}
+
+impl Clone for DynShutdownSignal {
+	#[inline]
+	fn clone(&self) -> Self {
+		Self {
+			view: self.view.clone(),
+			signal: None,
+		}
+	}
+}
+
+impl DynShutdownSignal {
+	/// Check if the shutdown has been triggered.
+	#[inline]
+	pub fn is_shutdown_triggered(&self) -> bool {
+		self.view.is_shutdown_triggered()
+	}
+
+	/// Check if the shutdown has completed.
+	#[inline]
+	pub fn is_shutdown_completed(&self) -> bool {
+		self.view.is_shutdown_completed()
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with `Err(())` if the shutdown is triggered,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	#[inline]
+	pub fn wrap_cancel<F: Future>(&self, future: F) -> MappedWrapCancel<(), F> {
+		self.view.wrap_cancel(future)
+	}
+}
+
+impl Future for DynShutdownSignal {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.get_mut();
+		let view = &me.view;
+		let signal = me.signal.get_or_insert_with(|| view.wait_shutdown_triggered());
+		Pin::new(signal).poll(context)
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Get a shutdown signal that does not depend on the shutdown reason type.
+	///
+	/// See [`DynShutdownSignal`] for more details.
+	#[inline]
+	pub fn dyn_shutdown_signal(&self) -> DynShutdownSignal {
+		DynShutdownSignal {
+			view: self.map_reason(|_| ()),
+			signal: None,
+		}
+	}
+}
@@ -0,0 +1,629 @@
+//! Shutdown manager for embedded and `no_std` code.
+//!
+//! The [`StaticShutdownManager`] only needs `core`: it does not allocate, and it can be created in a `static`.
+//! It is available even without the default `std` feature.
+//!
+//! The state of the manager is protected by a [`RawMutex`], which you choose with a type parameter.
+//! If an interrupt handler uses the manager, that should be a critical section.
+#![cfg_attr(feature = "critical-section", doc = "The [`CriticalSectionRawMutex`] from the `critical-section` feature does exactly that.")]
+#![cfg_attr(not(feature = "critical-section"), doc = "The `CriticalSectionRawMutex` from the `critical-section` feature does exactly that.")]
+//! You can also implement [`RawMutex`] for a lock of your own.
+//!
+//! The manager stores the wakers of waiting futures in a fixed table of `N` slots, instead of a growing list.
+//! If more than `N` futures wait for the same event at the same time, the extra futures are woken again immediately,
+//! so they keep getting polled until a slot frees up.
+//! That is wasteful but correct, so pick `N` large enough for the number of tasks that wait at the same time.
+
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+#[cfg(target_has_atomic = "8")]
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use crate::{ShutdownAlreadyCompleted, ShutdownAlreadyStarted};
+
+/// A mutex without data that protects the state of a [`StaticShutdownManager`].
+///
+/// # Safety
+/// Implementations must guarantee that [`Self::lock()`] never runs two closures at the same time for the same mutex,
+/// from any thread or interrupt handler that can reach the mutex.
+///
+/// The closures passed to [`Self::lock()`] by this crate do not lock the same mutex again.
+pub unsafe trait RawMutex {
+	/// An unlocked mutex.
+	const INIT: Self;
+
+	/// Run a function with the mutex locked.
+	fn lock<R>(&self, f: impl FnOnce() -> R) -> R;
+}
+
+/// A [`RawMutex`] that does not lock anything, for a manager that is only used from a single thread.
+///
+/// The mutex is not [`Sync`], so a [`StaticShutdownManager`] that uses it can not be shared between threads or put in a `static`.
+/// It can still be borrowed by all futures of a single threaded executor.
+pub struct NoopRawMutex {
+	_not_sync: PhantomData<*mut ()>,
+}
+
+// SAFETY: The mutex has no state, but it must not be shared between threads.
+unsafe impl Send for NoopRawMutex {}
+
+// SAFETY: The mutex is not `Sync`, so it can only be locked from one thread, and the closures do not lock it again.
+unsafe impl RawMutex for NoopRawMutex {
+	const INIT: Self = Self { _not_sync: PhantomData };
+
+	#[inline]
+	fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+		f()
+	}
+}
+
+impl core::fmt::Debug for NoopRawMutex {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_struct("NoopRawMutex").finish()
+	}
+}
+
+/// A [`RawMutex`] that spins until the lock is available.
+///
+/// This works between threads and between the cores of a multi-core chip.
+/// Do not use it if an interrupt handler uses the manager too:
+/// if the interrupt preempts the lock holder on the same core, it spins forever.
+#[cfg(target_has_atomic = "8")]
+pub struct SpinRawMutex {
+	locked: AtomicBool,
+}
+
+// SAFETY: The atomic flag admits only one closure at a time.
+#[cfg(target_has_atomic = "8")]
+unsafe impl RawMutex for SpinRawMutex {
+	const INIT: Self = Self { locked: AtomicBool::new(false) };
+
+	fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+		while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+			core::hint::spin_loop();
+		}
+		// Release the lock even if the closure panics.
+		let _unlock = SpinUnlock(&self.locked);
+		f()
+	}
+}
+
+#[cfg(target_has_atomic = "8")]
+impl core::fmt::Debug for SpinRawMutex {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_struct("SpinRawMutex")
+			.field("locked", &self.locked.load(Ordering::Relaxed))
+			.finish()
+	}
+}
+
+/// A [`RawMutex`] that runs in a critical section of the [`critical-section`](https://docs.rs/critical-section) crate.
+///
+/// A critical section excludes interrupt handlers and other cores, so the manager can be used everywhere:
+/// ```
+/// use async_shutdown::embedded::{CriticalSectionRawMutex, StaticShutdownManager};
+///
+/// static SHUTDOWN: StaticShutdownManager<u8, CriticalSectionRawMutex, 4> = StaticShutdownManager::new();
+///
+/// // In an interrupt handler:
+/// SHUTDOWN.trigger_shutdown(1).ok();
+/// ```
+///
+/// The application must provide the critical section implementation for its target,
+/// usually through the HAL or the architecture support crate.
+#[cfg(feature = "critical-section")]
+pub struct CriticalSectionRawMutex {
+	_private: (),
+}
+
+// SAFETY: A critical section admits only one closure at a time, and nested critical sections are allowed.
+#[cfg(feature = "critical-section")]
+unsafe impl RawMutex for CriticalSectionRawMutex {
+	const INIT: Self = Self { _private: () };
+
+	#[inline]
+	fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+		critical_section::with(|_| f())
+	}
+}
+
+#[cfg(feature = "critical-section")]
+impl core::fmt::Debug for CriticalSectionRawMutex {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_struct("CriticalSectionRawMutex").finish()
+	}
+}
+
+/// Guard that unlocks a [`SpinRawMutex`] when it is dropped.
+#[cfg(target_has_atomic = "8")]
+struct SpinUnlock<'a>(&'a AtomicBool);
+
+#[cfg(target_has_atomic = "8")]
+impl Drop for SpinUnlock<'_> {
+	fn drop(&mut self) {
+		self.0.store(false, Ordering::Release);
+	}
+}
+
+/// A slot in a waker table.
+enum Slot {
+	/// The slot is not owned by any future.
+	Free,
+
+	/// The slot is owned by a waiting future, with the waker to wake when the event happens.
+	///
+	/// The waker is taken out when the event happens, but the slot stays owned until the future completes or is dropped.
+	Taken(Option<Waker>),
+}
+
+/// A fixed size table with the wakers of the futures that wait for an event.
+struct WakerSlots<const N: usize> {
+	slots: [Slot; N],
+}
+
+impl<const N: usize> WakerSlots<N> {
+	const fn new() -> Self {
+		const FREE: Slot = Slot::Free;
+		Self { slots: [FREE; N] }
+	}
+
+	/// Register the waker of a future in its slot, or in a free slot if the future does not own one yet.
+	///
+	/// Returns false if the table is full.
+	fn register(&mut self, slot: &mut Option<usize>, waker: &Waker) -> bool {
+		let index = match *slot {
+			Some(index) => index,
+			None => match self.slots.iter().position(|slot| matches!(slot, Slot::Free)) {
+				Some(index) => *slot.insert(index),
+				None => return false,
+			},
+		};
+		match &mut self.slots[index] {
+			Slot::Taken(Some(registered)) if registered.will_wake(waker) => (),
+			slot => *slot = Slot::Taken(Some(waker.clone())),
+		}
+		true
+	}
+
+	/// Release the slot of a future.
+	fn release(&mut self, slot: usize) {
+		self.slots[slot] = Slot::Free;
+	}
+
+	/// Take all registered wakers out of the table, so they can be woken without holding the lock.
+	fn take_all(&mut self) -> Wakers<N> {
+		Wakers(core::array::from_fn(|index| match &mut self.slots[index] {
+			Slot::Taken(waker) => waker.take(),
+			Slot::Free => None,
+		}))
+	}
+}
+
+/// Wakers that have been taken out of a [`WakerSlots`] table.
+struct Wakers<const N: usize>([Option<Waker>; N]);
+
+impl<const N: usize> Wakers<N> {
+	/// Wake all tasks.
+	fn wake_all(self) {
+		IntoIterator::into_iter(self.0).flatten().for_each(Waker::wake);
+	}
+}
+
+/// The state of a [`StaticShutdownManager`], protected by its mutex.
+struct State<T, const N: usize> {
+	/// The shutdown reason, if the shutdown has been triggered.
+	shutdown_reason: Option<T>,
+
+	/// The number of existing delay tokens.
+	delay_tokens: usize,
+
+	/// True if the shutdown has completed.
+	completed: bool,
+
+	/// Tasks to wake when the shutdown is triggered.
+	on_shutdown: WakerSlots<N>,
+
+	/// Tasks to wake when the shutdown completes.
+	on_shutdown_complete: WakerSlots<N>,
+}
+
+impl<T: Copy, const N: usize> State<T, N> {
+	/// Trigger the shutdown.
+	///
+	/// Returns the wakers that must be woken after releasing the lock.
+	fn shutdown(&mut self, reason: T) -> Result<(Wakers<N>, Option<Wakers<N>>), ShutdownAlreadyStarted<T>> {
+		if let Some(original_reason) = self.shutdown_reason {
+			return Err(ShutdownAlreadyStarted::new(original_reason, reason));
+		}
+		self.shutdown_reason = Some(reason);
+		Ok((self.on_shutdown.take_all(), self.check_complete()))
+	}
+
+	/// Complete the shutdown if it has been triggered and there are no delay tokens left.
+	///
+	/// Returns the wakers that must be woken after releasing the lock.
+	fn check_complete(&mut self) -> Option<Wakers<N>> {
+		if self.shutdown_reason.is_some() && self.delay_tokens == 0 && !self.completed {
+			self.completed = true;
+			Some(self.on_shutdown_complete.take_all())
+		} else {
+			None
+		}
+	}
+
+	/// Get the waker table and the reason for the event that a future is waiting for.
+	fn event(&mut self, complete: bool) -> (&mut WakerSlots<N>, Option<T>) {
+		if complete {
+			let reason = self.shutdown_reason.filter(|_| self.completed);
+			(&mut self.on_shutdown_complete, reason)
+		} else {
+			(&mut self.on_shutdown, self.shutdown_reason)
+		}
+	}
+}
+
+/// Shutdown manager that does not need the standard library or an allocator.
+///
+/// The manager stores the wakers of up to `N` waiting futures per event in a fixed table,
+/// and its state is protected by the [`RawMutex`] `M`.
+/// See the [module documentation][self] for how to choose both.
+///
+/// The manager can be created in a `static`, and the futures and tokens created from it borrow the manager:
+/// ```
+/// use async_shutdown::embedded::{SpinRawMutex, StaticShutdownManager};
+///
+/// static SHUTDOWN: StaticShutdownManager<u8, SpinRawMutex, 4> = StaticShutdownManager::new();
+///
+/// async fn blink_led() {
+///     // Blink forever...
+/// #   std::future::pending::<()>().await
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let blink = SHUTDOWN.wrap_cancel(blink_led());
+///
+/// // Normally done by another task or an interrupt handler.
+/// SHUTDOWN.trigger_shutdown(1).ok();
+/// assert!(blink.await == Err(1));
+/// # });
+/// ```
+///
+/// Like the [`LocalShutdownManager`][crate::LocalShutdownManager], the manager supports the core functionality of the shutdown manager:
+/// triggering the shutdown, waiting for the shutdown, cancelling futures and delaying shutdown completion.
+///
+/// The shutdown reason must be [`Copy`], so that no user code runs while the lock is held.
+pub struct StaticShutdownManager<T, M, const N: usize> {
+	mutex: M,
+	state: UnsafeCell<State<T, N>>,
+}
+
+// SAFETY: The state is only accessed with the mutex locked, and the mutex can be shared between threads.
+unsafe impl<T: Send, M: RawMutex + Sync, const N: usize> Sync for StaticShutdownManager<T, M, N> {}
+
+impl<T: Copy, M: RawMutex, const N: usize> core::fmt::Debug for StaticShutdownManager<T, M, N> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let (triggered, completed, delay_tokens) = self.lock(|state| (state.shutdown_reason.is_some(), state.completed, state.delay_tokens));
+		f.debug_struct("StaticShutdownManager")
+			.field("shutdown_triggered", &triggered)
+			.field("shutdown_completed", &completed)
+			.field("delay_tokens", &delay_tokens)
+			.finish()
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> StaticShutdownManager<T, M, N> {
+	/// Create a new shutdown manager.
+	///
+	/// This is a `const` function, so it can be used to initialize a `static`.
+	#[inline]
+	pub const fn new() -> Self {
+		Self {
+			mutex: M::INIT,
+			state: UnsafeCell::new(State {
+				shutdown_reason: None,
+				delay_tokens: 0,
+				completed: false,
+				on_shutdown: WakerSlots::new(),
+				on_shutdown_complete: WakerSlots::new(),
+			}),
+		}
+	}
+
+	/// Run a function with the state locked.
+	fn lock<R>(&self, f: impl FnOnce(&mut State<T, N>) -> R) -> R {
+		// SAFETY: The mutex guarantees that only one closure accesses the state at a time,
+		// and the closures in this module do not lock the mutex again.
+		self.mutex.lock(|| f(unsafe { &mut *self.state.get() }))
+	}
+
+	/// Check if the shutdown has been triggered.
+	#[inline]
+	pub fn is_shutdown_triggered(&self) -> bool {
+		self.lock(|state| state.shutdown_reason.is_some())
+	}
+
+	/// Check if the shutdown has completed.
+	#[inline]
+	pub fn is_shutdown_completed(&self) -> bool {
+		self.lock(|state| state.completed)
+	}
+
+	/// Get the shutdown reason, if the shutdown has been triggered.
+	///
+	/// Returns [`None`] if the shutdown has not been triggered yet.
+	#[inline]
+	pub fn shutdown_reason(&self) -> Option<T> {
+		self.lock(|state| state.shutdown_reason)
+	}
+
+	/// Asynchronously wait for the shutdown to be triggered.
+	///
+	/// The returned future completes with the shutdown reason.
+	#[inline]
+	pub fn wait_shutdown_triggered(&self) -> StaticShutdownSignal<'_, T, M, N> {
+		StaticShutdownSignal::new(self, false)
+	}
+
+	/// Asynchronously wait for the shutdown to complete.
+	///
+	/// The returned future completes with the shutdown reason.
+	#[inline]
+	pub fn wait_shutdown_complete(&self) -> StaticShutdownSignal<'_, T, M, N> {
+		StaticShutdownSignal::new(self, true)
+	}
+
+	/// Trigger the shutdown.
+	///
+	/// This can be called from an interrupt handler, if the mutex of the manager is safe to use there.
+	///
+	/// If the shutdown was already triggered, this function returns an error.
+	pub fn trigger_shutdown(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		let (on_shutdown, on_shutdown_complete) = self.lock(|state| state.shutdown(reason))?;
+		on_shutdown.wake_all();
+		if let Some(on_shutdown_complete) = on_shutdown_complete {
+			on_shutdown_complete.wake_all();
+		}
+		Ok(())
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with `Err(shutdown_reason)` if the shutdown is triggered,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	#[inline]
+	pub fn wrap_cancel<F: Future>(&self, future: F) -> StaticWrapCancel<StaticShutdownSignal<'_, T, M, N>, F> {
+		StaticWrapCancel {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			future: Some(future),
+		}
+	}
+
+	/// Get a token that delays shutdown completion as long as it exists.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn delay_shutdown_token(&self) -> Result<StaticDelayShutdownToken<'_, T, M, N>, ShutdownAlreadyCompleted<T>> {
+		self.lock(|state| match state.shutdown_reason {
+			Some(reason) if state.completed => Err(ShutdownAlreadyCompleted::new(reason)),
+			_ => {
+				state.delay_tokens += 1;
+				Ok(())
+			},
+		})?;
+		Ok(StaticDelayShutdownToken { manager: self })
+	}
+
+	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	pub fn wrap_delay_shutdown<F: Future>(&self, future: F) -> Result<StaticWrapDelayShutdown<StaticDelayShutdownToken<'_, T, M, N>, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(StaticWrapDelayShutdown {
+			delay_token: Some(self.delay_shutdown_token()?),
+			future,
+		})
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> Default for StaticShutdownManager<T, M, N> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A future to wait for the shutdown of a [`StaticShutdownManager`] to be triggered or to complete.
+///
+/// Created by [`StaticShutdownManager::wait_shutdown_triggered()`] and [`StaticShutdownManager::wait_shutdown_complete()`].
+#[must_use = "futures must be polled to make progress"]
+pub struct StaticShutdownSignal<'a, T: Copy, M: RawMutex, const N: usize> {
+	manager: &'a StaticShutdownManager<T, M, N>,
+
+	/// True if the signal waits for the shutdown to complete instead of being triggered.
+	complete: bool,
+
+	/// The slot of the future in the waker table, if it has one.
+	slot: Option<usize>,
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> core::fmt::Debug for StaticShutdownSignal<'_, T, M, N> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		let triggered = self.manager.lock(|state| if self.complete { state.completed } else { state.shutdown_reason.is_some() });
+		f.debug_struct("StaticShutdownSignal")
+			.field("complete", &self.complete)
+			.field("triggered", &triggered)
+			.field("waker_registered", &self.slot.is_some())
+			.finish()
+	}
+}
+
+impl<'a, T: Copy, M: RawMutex, const N: usize> StaticShutdownSignal<'a, T, M, N> {
+	fn new(manager: &'a StaticShutdownManager<T, M, N>, complete: bool) -> Self {
+		Self {
+			manager,
+			complete,
+			slot: None,
+		}
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> Clone for StaticShutdownSignal<'_, T, M, N> {
+	fn clone(&self) -> Self {
+		// The slot is personal to each future.
+		Self::new(self.manager, self.complete)
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> Drop for StaticShutdownSignal<'_, T, M, N> {
+	fn drop(&mut self) {
+		if let Some(slot) = self.slot.take() {
+			self.manager.lock(|state| state.event(self.complete).0.release(slot));
+		}
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> Future for StaticShutdownSignal<'_, T, M, N> {
+	type Output = T;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.get_mut();
+		let (manager, complete, slot) = (me.manager, me.complete, &mut me.slot);
+		let registered = manager.lock(|state| {
+			let (waiters, reason) = state.event(complete);
+			if let Some(reason) = reason {
+				if let Some(slot) = slot.take() {
+					waiters.release(slot);
+				}
+				return Ok(reason);
+			}
+			Err(waiters.register(slot, context.waker()))
+		});
+		match registered {
+			Ok(reason) => Poll::Ready(reason),
+			Err(true) => Poll::Pending,
+			Err(false) => {
+				// All slots are taken, so poll again soon to check for a free slot.
+				context.waker().wake_by_ref();
+				Poll::Pending
+			},
+		}
+	}
+}
+
+/// Token that delays the shutdown of a [`StaticShutdownManager`] from completing as long as it exists.
+///
+/// Created by [`StaticShutdownManager::delay_shutdown_token()`].
+pub struct StaticDelayShutdownToken<'a, T: Copy, M: RawMutex, const N: usize> {
+	manager: &'a StaticShutdownManager<T, M, N>,
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> core::fmt::Debug for StaticDelayShutdownToken<'_, T, M, N> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_struct("StaticDelayShutdownToken").finish_non_exhaustive()
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> Clone for StaticDelayShutdownToken<'_, T, M, N> {
+	fn clone(&self) -> Self {
+		self.manager.lock(|state| state.delay_tokens += 1);
+		Self { manager: self.manager }
+	}
+}
+
+impl<T: Copy, M: RawMutex, const N: usize> Drop for StaticDelayShutdownToken<'_, T, M, N> {
+	fn drop(&mut self) {
+		let waiters = self.manager.lock(|state| {
+			state.delay_tokens -= 1;
+			state.check_complete()
+		});
+		if let Some(waiters) = waiters {
+			waiters.wake_all();
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is cancelled when the shutdown of a [`StaticShutdownManager`] is triggered.
+	///
+	/// Created by [`StaticShutdownManager::wrap_cancel()`].
+	///
+	/// The type parameter `S` is the [`StaticShutdownSignal`] of the manager.
+	/// It stands in for the generic parameters of the manager, since `pin_project_lite` does not support const generics.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct StaticWrapCancel<S, F> {
+		shutdown_signal: S,
+		#[pin]
+		future: Option<F>,
+	}
+}
+
+impl<S: core::fmt::Debug, F> core::fmt::Debug for StaticWrapCancel<S, F> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_struct("StaticWrapCancel")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("cancelled", &self.future.is_none())
+			.finish()
+	}
+}
+
+impl<S: Future + Unpin, F: Future> Future for StaticWrapCancel<S, F> {
+	type Output = Result<F::Output, S::Output>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		let future = me.future.as_mut().as_pin_mut().expect("StaticWrapCancel polled after completion");
+		if let Poll::Ready(value) = future.poll(context) {
+			me.future.set(None);
+			return Poll::Ready(Ok(value));
+		}
+
+		match Pin::new(&mut *me.shutdown_signal).poll(context) {
+			Poll::Ready(reason) => {
+				me.future.set(None);
+				Poll::Ready(Err(reason))
+			},
+			Poll::Pending => Poll::Pending,
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that delays the shutdown of a [`StaticShutdownManager`] until it completes or until it is dropped.
+	///
+	/// Created by [`StaticShutdownManager::wrap_delay_shutdown()`].
+	///
+	/// The type parameter `D` is the [`StaticDelayShutdownToken`] of the manager, for the same reason as in [`StaticWrapCancel`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct StaticWrapDelayShutdown<D, F> {
+		delay_token: Option<D>,
+		#[pin]
+		future: F,
+	}
+}
+
+impl<D: core::fmt::Debug, F> core::fmt::Debug for StaticWrapDelayShutdown<D, F> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.debug_struct("StaticWrapDelayShutdown")
+			.field("delay_token", &self.delay_token)
+			.finish()
+	}
+}
+
+impl<D, F: Future> Future for StaticWrapDelayShutdown<D, F> {
+	type Output = F::Output;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		match me.future.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(value) => {
+				*me.delay_token = None;
+				Poll::Ready(value)
+			},
+		}
+	}
+}
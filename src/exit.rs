@@ -0,0 +1,84 @@
+use std::process::ExitCode;
+
+use crate::ShutdownManager;
+
+/// A shutdown reason that can be converted to a process exit code.
+///
+/// This is used by [`ShutdownManager::wait_and_exit()`] to exit the process with the shutdown reason.
+/// It is implemented for integer exit codes, [`ExitCode`] and `()` (which always maps to `0`).
+/// You can implement it for your own shutdown reasons:
+/// ```
+/// #[derive(Clone)]
+/// enum Reason {
+///     Interrupted,
+///     Error,
+/// }
+///
+/// impl async_shutdown::ExitReason for Reason {
+///     fn exit_code(&self) -> i32 {
+///         match self {
+///             Self::Interrupted => 130,
+///             Self::Error => 1,
+///         }
+///     }
+/// }
+/// ```
+pub trait ExitReason {
+	/// Get the exit code for the process.
+	fn exit_code(&self) -> i32;
+}
+
+impl ExitReason for i32 {
+	#[inline]
+	fn exit_code(&self) -> i32 {
+		*self
+	}
+}
+
+impl ExitReason for u8 {
+	#[inline]
+	fn exit_code(&self) -> i32 {
+		(*self).into()
+	}
+}
+
+impl ExitReason for () {
+	#[inline]
+	fn exit_code(&self) -> i32 {
+		0
+	}
+}
+
+impl ExitReason for ExitCode {
+	/// Get the exit code for the process.
+	///
+	/// The standard library does not expose the value of an [`ExitCode`],
+	/// so this compares it against all exit codes that can be created with [`ExitCode::from()`].
+	/// Platform specific exit codes that can not be created that way map to `1`.
+	fn exit_code(&self) -> i32 {
+		(0..=u8::MAX)
+			.find(|&code| ExitCode::from(code) == *self)
+			.map_or(1, i32::from)
+	}
+}
+
+impl<T: Clone + ExitReason> ShutdownManager<T> {
+	/// Wait for the shutdown to complete, and then exit the process with the exit code of the shutdown reason.
+	///
+	/// This replaces the usual dance at the end of `main()`:
+	/// ```no_run
+	/// # async fn example() {
+	/// let shutdown = async_shutdown::ShutdownManager::new();
+	/// // Run your application...
+	/// # shutdown.trigger_shutdown(0).ok();
+	/// shutdown.wait_and_exit().await;
+	/// # }
+	/// ```
+	///
+	/// Note that [`std::process::exit()`] does not run destructors of the current stack frames or of other threads.
+	/// If you return an [`ExitCode`] from `main()`, you can also await [`Self::wait_shutdown_complete()`] and return the reason.
+	pub async fn wait_and_exit(&self) -> ! {
+		let reason = self.wait_shutdown_complete().await;
+		std::process::exit(reason.exit_code())
+	}
+}
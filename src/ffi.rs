@@ -0,0 +1,137 @@
+//! C API for controlling a shutdown manager from a foreign host application.
+//!
+//! The C API uses a [`ShutdownManager`] with an `int` shutdown reason.
+//! All functions take a pointer to an opaque [`AsyncShutdownManager`] created by [`async_shutdown_new()`].
+//! The equivalent C declarations are:
+//! ```c
+//! typedef struct AsyncShutdownManager AsyncShutdownManager;
+//! AsyncShutdownManager * async_shutdown_new(void);
+//! AsyncShutdownManager * async_shutdown_clone(AsyncShutdownManager const * manager);
+//! void async_shutdown_free(AsyncShutdownManager * manager);
+//! bool async_shutdown_trigger(AsyncShutdownManager const * manager, int reason);
+//! bool async_shutdown_is_triggered(AsyncShutdownManager const * manager);
+//! bool async_shutdown_is_completed(AsyncShutdownManager const * manager, int * reason);
+//! void async_shutdown_on_trigger(AsyncShutdownManager const * manager, void (*callback)(int reason, void * user_data), void * user_data);
+//! ```
+//!
+//! The Rust side of the application can get the [`ShutdownManager`] with [`AsyncShutdownManager::manager()`].
+
+use std::os::raw::{c_int, c_void};
+
+use crate::ShutdownManager;
+
+/// Opaque handle to a shutdown manager for the C API.
+pub struct AsyncShutdownManager {
+	manager: ShutdownManager<c_int>,
+}
+
+impl AsyncShutdownManager {
+	/// Get the shutdown manager behind the handle.
+	#[inline]
+	pub fn manager(&self) -> &ShutdownManager<c_int> {
+		&self.manager
+	}
+}
+
+/// Create a new shutdown manager.
+///
+/// The returned pointer must be freed with [`async_shutdown_free()`].
+#[no_mangle]
+pub extern "C" fn async_shutdown_new() -> *mut AsyncShutdownManager {
+	Box::into_raw(Box::new(AsyncShutdownManager {
+		manager: ShutdownManager::new(),
+	}))
+}
+
+/// Create a new handle to the same shutdown manager.
+///
+/// The returned pointer must be freed with [`async_shutdown_free()`].
+///
+/// # Safety
+/// The `manager` pointer must have been created by this API and must not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn async_shutdown_clone(manager: *const AsyncShutdownManager) -> *mut AsyncShutdownManager {
+	Box::into_raw(Box::new(AsyncShutdownManager {
+		manager: (*manager).manager.clone(),
+	}))
+}
+
+/// Free a handle to a shutdown manager.
+///
+/// Passing a null pointer is allowed and does nothing.
+///
+/// # Safety
+/// The `manager` pointer must be null, or it must have been created by this API and must not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn async_shutdown_free(manager: *mut AsyncShutdownManager) {
+	if !manager.is_null() {
+		drop(Box::from_raw(manager));
+	}
+}
+
+/// Trigger the shutdown with the given reason.
+///
+/// Returns `true` if the shutdown was triggered, or `false` if it was already triggered before.
+///
+/// # Safety
+/// The `manager` pointer must have been created by this API and must not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn async_shutdown_trigger(manager: *const AsyncShutdownManager, reason: c_int) -> bool {
+	(*manager).manager.trigger_shutdown(reason).is_ok()
+}
+
+/// Check if the shutdown has been triggered.
+///
+/// # Safety
+/// The `manager` pointer must have been created by this API and must not have been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn async_shutdown_is_triggered(manager: *const AsyncShutdownManager) -> bool {
+	(*manager).manager.is_shutdown_triggered()
+}
+
+/// Check if the shutdown has completed.
+///
+/// If the shutdown has completed and `reason` is not null, the shutdown reason is written to `reason`.
+///
+/// # Safety
+/// The `manager` pointer must have been created by this API and must not have been freed yet.
+/// The `reason` pointer must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn async_shutdown_is_completed(manager: *const AsyncShutdownManager, reason: *mut c_int) -> bool {
+	let manager = &(*manager).manager;
+	if !manager.is_shutdown_completed() {
+		return false;
+	}
+	if let (false, Some(value)) = (reason.is_null(), manager.shutdown_reason()) {
+		*reason = value;
+	}
+	true
+}
+
+/// Register a callback to be called when the shutdown is triggered.
+///
+/// The callback is called exactly once, with the shutdown reason and the `user_data` pointer.
+/// It runs on the thread that triggers the shutdown.
+/// If the shutdown has already been triggered, the callback is called immediately.
+///
+/// # Safety
+/// The `manager` pointer must have been created by this API and must not have been freed yet.
+/// The callback must be safe to call from any thread with the given `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn async_shutdown_on_trigger(
+	manager: *const AsyncShutdownManager,
+	callback: extern "C" fn(reason: c_int, user_data: *mut c_void),
+	user_data: *mut c_void,
+) {
+	let user_data = UserData(user_data);
+	(*manager).manager.shared().on_trigger(Box::new(move |reason| {
+		let user_data = user_data;
+		callback(*reason, user_data.0)
+	}));
+}
+
+/// User data pointer for a callback.
+struct UserData(*mut c_void);
+
+// SAFETY: The caller of `async_shutdown_on_trigger()` promised that the callback can be called from any thread.
+unsafe impl Send for UserData {}
@@ -0,0 +1,67 @@
+/// A flush hook registered with [`ShutdownManager::add_flush_hook()`][crate::ShutdownManager::add_flush_hook].
+pub(crate) type FlushHook = Box<dyn FnOnce() + Send>;
+
+/// The flush hooks of a shutdown manager.
+///
+/// The hooks are run exactly once, by the first [`ShutdownComplete`][crate::ShutdownComplete] future
+/// that observes the completed shutdown.
+pub(crate) enum FlushHooks {
+	/// The hooks have not been run yet.
+	Pending(Vec<FlushHook>),
+
+	/// The hooks are currently being run by a [`ShutdownComplete`][crate::ShutdownComplete] future.
+	Running,
+
+	/// All hooks have been run.
+	Done,
+}
+
+impl FlushHooks {
+	/// Create a new empty list of flush hooks.
+	pub fn new() -> Self {
+		Self::Pending(Vec::new())
+	}
+
+	/// Add a flush hook.
+	///
+	/// Gives back the hook if the hooks have already started running.
+	pub fn add(&mut self, hook: FlushHook) -> Result<(), FlushHook> {
+		match self {
+			Self::Pending(hooks) => {
+				hooks.push(hook);
+				Ok(())
+			},
+			Self::Running | Self::Done => Err(hook),
+		}
+	}
+
+	/// Claim the hooks so they can be run.
+	///
+	/// Returns [`None`] if the hooks are already running or done.
+	/// After running the returned hooks, the caller must call [`Self::finish()`].
+	///
+	/// If there are no hooks at all, this immediately transitions to the done state.
+	pub fn start(&mut self) -> Option<Vec<FlushHook>> {
+		match std::mem::replace(self, Self::Running) {
+			Self::Pending(hooks) if hooks.is_empty() => {
+				*self = Self::Done;
+				None
+			},
+			Self::Pending(hooks) => Some(hooks),
+			other => {
+				*self = other;
+				None
+			},
+		}
+	}
+
+	/// Mark the hooks as done.
+	pub fn finish(&mut self) {
+		*self = Self::Done;
+	}
+
+	/// Check if all hooks have been run.
+	pub fn is_done(&self) -> bool {
+		matches!(self, Self::Done)
+	}
+}
@@ -0,0 +1,102 @@
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::ShutdownSignal;
+
+/// Summary of a [`ForEachUntilShutdown`] future.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct ForEachSummary<T> {
+	/// The number of items that were processed completely.
+	pub processed: usize,
+
+	/// The shutdown reason, or [`None`] if the stream ended before the shutdown was triggered.
+	pub shutdown_reason: Option<T>,
+}
+
+pin_project_lite::pin_project! {
+	/// Future that processes the items of a stream until a shutdown is triggered.
+	///
+	/// Created by [`ShutdownSignal::for_each_until_shutdown()`].
+	///
+	/// When the shutdown is triggered, the item that is currently being processed is finished first.
+	/// No new items are taken from the stream after that.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct ForEachUntilShutdown<T: Clone, S, F, Fut> {
+		#[pin]
+		shutdown_signal: ShutdownSignal<T>,
+		#[pin]
+		stream: S,
+		function: F,
+		#[pin]
+		in_flight: Option<Fut>,
+		processed: usize,
+	}
+}
+
+impl<T: Clone, S, F, Fut> std::fmt::Debug for ForEachUntilShutdown<T, S, F, Fut> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ForEachUntilShutdown")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("processed", &self.processed)
+			.field("in_flight", &self.in_flight.is_some())
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: Clone, S, F, Fut> ForEachUntilShutdown<T, S, F, Fut> {
+	/// Create a new future for a shutdown signal.
+	pub(crate) fn new(shutdown_signal: ShutdownSignal<T>, stream: S, function: F) -> Self {
+		Self {
+			shutdown_signal,
+			stream,
+			function,
+			in_flight: None,
+			processed: 0,
+		}
+	}
+}
+
+impl<T, S, F, Fut> Future for ForEachUntilShutdown<T, S, F, Fut>
+where
+	T: Clone,
+	S: Stream,
+	F: FnMut(S::Item) -> Fut,
+	Fut: Future<Output = ()>,
+{
+	type Output = ForEachSummary<T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		loop {
+			if let Some(in_flight) = me.in_flight.as_mut().as_pin_mut() {
+				if in_flight.poll(context).is_pending() {
+					return Poll::Pending;
+				}
+				me.in_flight.set(None);
+				*me.processed += 1;
+			}
+
+			if let Poll::Ready(reason) = me.shutdown_signal.as_mut().poll(context) {
+				return Poll::Ready(ForEachSummary {
+					processed: *me.processed,
+					shutdown_reason: Some(reason),
+				});
+			}
+
+			match me.stream.as_mut().poll_next(context) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(None) => {
+					return Poll::Ready(ForEachSummary {
+						processed: *me.processed,
+						shutdown_reason: None,
+					})
+				},
+				Poll::Ready(Some(item)) => me.in_flight.set(Some((me.function)(item))),
+			}
+		}
+	}
+}
@@ -0,0 +1,38 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::ShutdownManager;
+use crate::lock::MutexExt;
+
+/// The global shutdown managers, by shutdown reason type.
+static GLOBAL_MANAGERS: OnceLock<Mutex<HashMap<TypeId, Box<dyn Any + Send>>>> = OnceLock::new();
+
+/// Get the global shutdown manager for shutdown reason type `T`.
+///
+/// There is one global shutdown manager for each shutdown reason type.
+/// It is created the first time this function is called for that type,
+/// and every call returns a handle to the same manager.
+///
+/// This allows deeply nested library code to observe the shutdown of the application
+/// without passing a shutdown manager through every layer:
+/// ```
+/// # async fn refresh_loop() {}
+/// async fn background_refresh() {
+///     let shutdown = async_shutdown::global::<i32>();
+///     shutdown.wrap_cancel(refresh_loop()).await.ok();
+/// }
+/// ```
+///
+/// Library crates that do not know the shutdown reason type of the application
+/// can use the global [`AnyShutdownManager`][crate::AnyShutdownManager]: `async_shutdown::global::<AnyShutdownReason>()`.
+pub fn global<T: Clone + Send + 'static>() -> ShutdownManager<T> {
+	let managers = GLOBAL_MANAGERS.get_or_init(Default::default);
+	let mut managers = managers.lock_unpoisoned();
+	managers
+		.entry(TypeId::of::<ShutdownManager<T>>())
+		.or_insert_with(|| Box::new(ShutdownManager::<T>::new()))
+		.downcast_ref::<ShutdownManager<T>>()
+		.expect("global shutdown manager has the wrong type")
+		.clone()
+}
@@ -0,0 +1,130 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::shared::Shared;
+
+/// A boxed future returned by a shutdown hook.
+pub(crate) type HookFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A shutdown hook registered with [`ShutdownManager::on_shutdown()`][crate::ShutdownManager::on_shutdown].
+pub(crate) type Hook<T> = Box<dyn FnOnce(T) -> HookFuture + Send>;
+
+/// The async shutdown hooks of a shutdown manager.
+///
+/// Each hook counts as a delay token for the first shutdown phase until it finishes.
+pub(crate) struct Hooks<T> {
+	/// Hooks that have not been started yet.
+	pending: Vec<Hook<T>>,
+
+	/// Hooks that have been started, but that did not finish yet.
+	running: Vec<HookFuture>,
+}
+
+impl<T> Hooks<T> {
+	/// Create a new empty set of hooks.
+	pub fn new() -> Self {
+		Self {
+			pending: Vec::new(),
+			running: Vec::new(),
+		}
+	}
+
+	/// Add a hook to be started when the shutdown is triggered.
+	pub fn add(&mut self, hook: Hook<T>) {
+		self.pending.push(hook);
+	}
+}
+
+/// Start and poll the shutdown hooks, if the shutdown has been triggered.
+///
+/// The hooks are polled without holding the lock on the shutdown manager,
+/// so they are free to use the shutdown manager themselves.
+///
+/// Returns `true` if there are still hooks running that will wake the current task.
+pub(crate) fn drive_hooks<T: Clone>(inner: &Shared<T>, context: &mut Context) -> bool {
+	let (reason, mut guard) = {
+		let mut locked = inner.lock();
+		let reason = match &locked.shutdown_reason {
+			Some(reason) => reason.clone(),
+			None => return false,
+		};
+		if locked.hooks.pending.is_empty() && locked.hooks.running.is_empty() {
+			return false;
+		}
+		let guard = DriveHooks {
+			inner,
+			pending: std::mem::take(&mut locked.hooks.pending).into_iter(),
+			unpolled: std::mem::take(&mut locked.hooks.running).into_iter(),
+			running: Vec::new(),
+			finished: 0,
+			busy: false,
+		};
+		(reason, guard)
+	};
+
+	while let Some(hook) = guard.pending.next() {
+		guard.busy = true;
+		let future = hook(reason.clone());
+		guard.poll(future, context);
+	}
+	while let Some(future) = guard.unpolled.next() {
+		guard.poll(future, context);
+	}
+
+	!guard.running.is_empty()
+}
+
+/// Guard that puts the hooks back and updates the delay count when dropped, even if one of the hooks panicked.
+///
+/// A hook that panics is dropped and counted as finished, so it does not delay the shutdown forever.
+struct DriveHooks<'a, T: Clone> {
+	inner: &'a Shared<T>,
+
+	/// Hooks that have not been started yet.
+	pending: std::vec::IntoIter<Hook<T>>,
+
+	/// Running hooks that have not been polled yet.
+	unpolled: std::vec::IntoIter<HookFuture>,
+
+	/// Running hooks that have been polled, and that did not finish yet.
+	running: Vec<HookFuture>,
+
+	/// The number of hooks that finished.
+	finished: usize,
+
+	/// If true, a hook is being started or polled.
+	busy: bool,
+}
+
+impl<T: Clone> DriveHooks<'_, T> {
+	/// Poll a running hook.
+	fn poll(&mut self, mut future: HookFuture, context: &mut Context) {
+		self.busy = true;
+		match future.as_mut().poll(context) {
+			Poll::Pending => self.running.push(future),
+			Poll::Ready(()) => self.finished += 1,
+		}
+		self.busy = false;
+	}
+}
+
+impl<T: Clone> Drop for DriveHooks<'_, T> {
+	fn drop(&mut self) {
+		let mut inner = self.inner.lock();
+		if self.busy {
+			// A hook panicked, and it was dropped during unwinding.
+			self.finished += 1;
+			// The panicking task will not poll the other hooks again, so let another waiting task do it.
+			inner.on_shutdown_complete.wake_all();
+		}
+		let mut pending: Vec<_> = self.pending.by_ref().collect();
+		pending.append(&mut inner.hooks.pending);
+		inner.hooks.pending = pending;
+		inner.hooks.running.append(&mut self.running);
+		inner.hooks.running.extend(self.unpolled.by_ref());
+		for _ in 0..self.finished {
+			inner.decrease_delay_count(0);
+		}
+	}
+}
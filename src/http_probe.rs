@@ -0,0 +1,128 @@
+use std::convert::Infallible;
+use std::future::{ready, Ready};
+use std::task::{Context, Poll};
+
+use tower_service::Service;
+
+use crate::ShutdownManager;
+
+/// HTTP service that reports the shutdown state of a [`ShutdownManager`] to health probes.
+///
+/// Created by [`ShutdownManager::readiness_probe()`] or [`ShutdownManager::liveness_probe()`].
+///
+/// The service answers every request with `200 OK` while the application is running,
+/// and with `503 Service Unavailable` once the probe flips.
+/// A readiness probe flips as soon as the shutdown is triggered, so load balancers stop sending new traffic during the graceful shutdown.
+/// A liveness probe only flips when the shutdown has completed, so the process is not restarted while it is still cleaning up.
+///
+/// The probe is a [`tower_service::Service`] for [`http::Request`]s with any body,
+/// so it can be mounted directly in a tower based HTTP server, for example with the `route_service()` function of `axum`.
+/// For other frameworks, you can answer the probe with [`Self::response()`]:
+/// ```
+/// let shutdown = async_shutdown::ShutdownManager::new();
+/// let ready = shutdown.readiness_probe();
+/// assert!(ready.response().status() == http::StatusCode::OK);
+///
+/// shutdown.trigger_shutdown(()).unwrap();
+/// assert!(ready.response().status() == http::StatusCode::SERVICE_UNAVAILABLE);
+/// ```
+///
+/// The state is read directly from the shutdown manager on every request,
+/// so the probe flips back when the shutdown manager is [reset][ShutdownManager::reset].
+#[derive(Clone)]
+pub struct HttpProbe<T: Clone> {
+	shutdown: ShutdownManager<T>,
+	kind: ProbeKind,
+}
+
+/// The kind of health check that a [`HttpProbe`] answers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ProbeKind {
+	/// Fail once the shutdown is triggered.
+	Readiness,
+
+	/// Fail once the shutdown is completed.
+	Liveness,
+}
+
+impl<T: Clone> HttpProbe<T> {
+	/// Check if the probe reports the application as healthy.
+	#[inline]
+	pub fn is_healthy(&self) -> bool {
+		match self.kind {
+			ProbeKind::Readiness => !self.shutdown.is_shutdown_triggered(),
+			ProbeKind::Liveness => !self.shutdown.is_shutdown_completed(),
+		}
+	}
+
+	/// Get the HTTP status code that the probe currently reports.
+	#[inline]
+	pub fn status(&self) -> http::StatusCode {
+		if self.is_healthy() {
+			http::StatusCode::OK
+		} else {
+			http::StatusCode::SERVICE_UNAVAILABLE
+		}
+	}
+
+	/// Create the response for a probe request.
+	///
+	/// This can be used to answer probes from HTTP frameworks that do not work with tower services.
+	pub fn response(&self) -> http::Response<String> {
+		let status = self.status();
+		let body = if status == http::StatusCode::OK { "ok\n" } else { "shutting down\n" };
+		let mut response = http::Response::new(String::from(body));
+		*response.status_mut() = status;
+		response.headers_mut().insert(http::header::CONTENT_TYPE, http::HeaderValue::from_static("text/plain"));
+		response
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for HttpProbe<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("HttpProbe")
+			.field("kind", &self.kind)
+			.field("healthy", &self.is_healthy())
+			.finish()
+	}
+}
+
+impl<T: Clone, B> Service<http::Request<B>> for HttpProbe<T> {
+	type Response = http::Response<String>;
+	type Error = Infallible;
+	type Future = Ready<Result<Self::Response, Infallible>>;
+
+	#[inline]
+	fn poll_ready(&mut self, _context: &mut Context) -> Poll<Result<(), Infallible>> {
+		Poll::Ready(Ok(()))
+	}
+
+	#[inline]
+	fn call(&mut self, _request: http::Request<B>) -> Self::Future {
+		ready(Ok(self.response()))
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Create an HTTP readiness probe that fails once the shutdown is triggered.
+	///
+	/// See [`HttpProbe`] for more details.
+	#[inline]
+	pub fn readiness_probe(&self) -> HttpProbe<T> {
+		HttpProbe {
+			shutdown: self.clone(),
+			kind: ProbeKind::Readiness,
+		}
+	}
+
+	/// Create an HTTP liveness probe that fails once the shutdown has completed.
+	///
+	/// See [`HttpProbe`] for more details.
+	#[inline]
+	pub fn liveness_probe(&self) -> HttpProbe<T> {
+		HttpProbe {
+			shutdown: self.clone(),
+			kind: ProbeKind::Liveness,
+		}
+	}
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::ShutdownSignal;
+
+pin_project_lite::pin_project! {
+	/// A future to wait for a shutdown signal, without the shutdown reason.
+	///
+	/// This is the same as [`ShutdownSignal`], except that it completes with `()`.
+	/// It can be passed directly to APIs that expect a `Future<Output = ()>` as shutdown signal,
+	/// like the `with_graceful_shutdown()` functions of `hyper`, `axum` and `tonic`.
+	///
+	/// Create one with [`ShutdownSignal::ignore_reason()`].
+	#[must_use = "futures must be polled to make progress"]
+	#[derive(Clone)]
+	pub struct IgnoreShutdownReason<T: Clone> {
+		#[pin]
+		pub(crate) shutdown_signal: ShutdownSignal<T>,
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for IgnoreShutdownReason<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("IgnoreShutdownReason")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.finish()
+	}
+}
+
+impl<T: Clone> IgnoreShutdownReason<T> {
+	/// Get the wrapped shutdown signal back.
+	#[inline]
+	pub fn into_inner(self) -> ShutdownSignal<T> {
+		self.shutdown_signal
+	}
+}
+
+impl<T: Clone> Future for IgnoreShutdownReason<T> {
+	type Output = ();
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		self.project().shutdown_signal.poll(context).map(|_reason| ())
+	}
+}
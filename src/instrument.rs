@@ -0,0 +1,142 @@
+//! Instrumentation for `tokio-console`.
+//!
+//! With the `tracing` feature enabled, each shutdown manager is reported as a resource,
+//! and the [`ShutdownSignal`][crate::ShutdownSignal] and [`ShutdownComplete`][crate::ShutdownComplete] futures
+//! are reported as async operations on that resource.
+//!
+//! Without the feature, all types in this module are zero sized and all functions are no-ops.
+
+/// The resource span of a shutdown manager.
+pub(crate) struct Resource {
+	#[cfg(feature = "tracing")]
+	span: tracing::Span,
+}
+
+/// The spans of an async operation on a shutdown manager.
+#[derive(Default)]
+pub(crate) struct AsyncOp {
+	#[cfg(feature = "tracing")]
+	spans: Option<(tracing::Span, tracing::Span)>,
+}
+
+/// Guard that keeps the spans of an async operation entered until it is dropped.
+pub(crate) struct Entered<'a> {
+	#[cfg(feature = "tracing")]
+	_guards: Option<(tracing::span::Entered<'a>, tracing::span::Entered<'a>)>,
+
+	#[cfg(not(feature = "tracing"))]
+	_lifetime: std::marker::PhantomData<&'a ()>,
+}
+
+impl Resource {
+	/// Create a new resource span for a shutdown manager.
+	#[track_caller]
+	#[inline]
+	pub fn new() -> Self {
+		#[cfg(feature = "tracing")]
+		{
+			let location = std::panic::Location::caller();
+			let span = tracing::trace_span!(
+				target: "runtime::resource",
+				"runtime.resource",
+				concrete_type = "ShutdownManager",
+				kind = "Sync",
+				loc.file = location.file(),
+				loc.line = location.line(),
+				loc.col = location.column(),
+			);
+			span.in_scope(|| {
+				tracing::trace!(
+					target: "runtime::resource::state_update",
+					delay_tokens = 0,
+					delay_tokens.op = "override",
+					shutdown_triggered = false,
+					shutdown_triggered.op = "override",
+					shutdown_completed = false,
+					shutdown_completed.op = "override",
+				)
+			});
+			Self { span }
+		}
+
+		#[cfg(not(feature = "tracing"))]
+		Self {}
+	}
+
+	/// Report the number of delay tokens.
+	#[inline]
+	pub fn delay_tokens(&self, _count: usize) {
+		#[cfg(feature = "tracing")]
+		self.span.in_scope(|| {
+			tracing::trace!(
+				target: "runtime::resource::state_update",
+				delay_tokens = _count,
+				delay_tokens.op = "override",
+			)
+		});
+	}
+
+	/// Report that the shutdown was triggered.
+	#[inline]
+	pub fn shutdown_triggered(&self) {
+		#[cfg(feature = "tracing")]
+		self.span.in_scope(|| {
+			tracing::trace!(
+				target: "runtime::resource::state_update",
+				shutdown_triggered = true,
+				shutdown_triggered.op = "override",
+			)
+		});
+	}
+
+	/// Report that the shutdown completed.
+	#[inline]
+	pub fn shutdown_completed(&self) {
+		#[cfg(feature = "tracing")]
+		self.span.in_scope(|| {
+			tracing::trace!(
+				target: "runtime::resource::state_update",
+				shutdown_completed = true,
+				shutdown_completed.op = "override",
+			)
+		});
+	}
+
+	/// Initialize the spans of an async operation on this resource, if they weren't initialized yet.
+	#[inline]
+	pub fn init_async_op(&self, _async_op: &mut AsyncOp, _source: &'static str) {
+		#[cfg(feature = "tracing")]
+		if _async_op.spans.is_none() {
+			let async_op = self.span.in_scope(|| {
+				tracing::trace_span!(
+					target: "runtime::resource::async_op",
+					"runtime.resource.async_op",
+					source = _source,
+					inherits_child_attrs = false,
+				)
+			});
+			let poll = async_op.in_scope(|| {
+				tracing::trace_span!(target: "runtime::resource::async_op::poll", "runtime.resource.async_op.poll")
+			});
+			_async_op.spans = Some((async_op, poll));
+		}
+	}
+}
+
+impl AsyncOp {
+	/// Enter the spans of the async operation.
+	#[inline]
+	pub fn enter(&self) -> Entered<'_> {
+		#[cfg(feature = "tracing")]
+		{
+			Entered {
+				_guards: self.spans.as_ref().map(|(async_op, poll)| (async_op.enter(), poll.enter())),
+			}
+		}
+
+		#[cfg(not(feature = "tracing"))]
+		Entered {
+			_lifetime: std::marker::PhantomData,
+		}
+	}
+}
@@ -0,0 +1,103 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::{ShutdownManager, ShutdownSignal};
+
+/// A ticker that stops when the shutdown is triggered.
+///
+/// Created by [`ShutdownManager::interval()`].
+///
+/// Use [`Self::tick()`] to wait for the next tick.
+/// With the `stream` feature, the interval can also be used as a stream of ticks.
+#[must_use = "intervals do nothing unless polled"]
+pub struct ShutdownInterval<T: Clone> {
+	// Boxed so the interval can be polled through `&mut self`.
+	shutdown_signal: Pin<Box<ShutdownSignal<T>>>,
+	interval: tokio::time::Interval,
+	stopped: bool,
+}
+
+impl<T: Clone> ShutdownInterval<T> {
+	/// Get the period of the interval.
+	#[inline]
+	pub fn period(&self) -> Duration {
+		self.interval.period()
+	}
+
+	/// Wait for the next tick.
+	///
+	/// The first tick completes immediately.
+	/// Returns the moment the tick was scheduled for, or [`None`] if the shutdown has been triggered.
+	#[inline]
+	pub async fn tick(&mut self) -> Option<Instant> {
+		Tick { interval: self }.await
+	}
+
+	/// Poll for the next tick.
+	///
+	/// Returns the moment the tick was scheduled for, or [`None`] if the shutdown has been triggered.
+	pub fn poll_tick(&mut self, context: &mut Context) -> Poll<Option<Instant>> {
+		if self.stopped {
+			return Poll::Ready(None);
+		}
+		if self.shutdown_signal.as_mut().poll(context).is_ready() {
+			self.stopped = true;
+			return Poll::Ready(None);
+		}
+		self.interval.poll_tick(context).map(|instant| Some(instant.into_std()))
+	}
+}
+
+/// Future returned by [`ShutdownInterval::tick()`].
+struct Tick<'a, T: Clone> {
+	interval: &'a mut ShutdownInterval<T>,
+}
+
+impl<T: Clone> Future for Tick<'_, T> {
+	type Output = Option<Instant>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		self.get_mut().interval.poll_tick(context)
+	}
+}
+
+#[cfg(feature = "stream")]
+impl<T: Clone> futures_core::Stream for ShutdownInterval<T> {
+	type Item = Instant;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+		self.get_mut().poll_tick(context)
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Create an interval that ticks with the given period until the shutdown is triggered.
+	///
+	/// This is useful for periodic tasks like flushing buffers or sending heartbeats:
+	/// ```
+	/// # async fn send_heartbeat() {}
+	/// # async fn run(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// let mut interval = shutdown.interval(std::time::Duration::from_secs(10));
+	/// while interval.tick().await.is_some() {
+	///     send_heartbeat().await;
+	/// }
+	/// # }
+	/// ```
+	///
+	/// The interval uses the timer of the tokio runtime.
+	/// Missed ticks are fired as soon as possible, like the default behaviour of [`tokio::time::interval()`].
+	///
+	/// # Panics
+	/// This function panics if `period` is zero, or if it is called outside of a tokio runtime with the timer enabled.
+	#[track_caller]
+	pub fn interval(&self, period: Duration) -> ShutdownInterval<T> {
+		ShutdownInterval {
+			shutdown_signal: Box::pin(self.wait_shutdown_triggered()),
+			interval: tokio::time::interval(period),
+			stopped: false,
+		}
+	}
+}
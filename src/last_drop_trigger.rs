@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use crate::lock::MutexExt;
+use crate::shared::Shared;
+use crate::ShutdownManager;
+
+/// Token that triggers a shutdown when the last clone is dropped.
+///
+/// Created by [`ShutdownManager::last_drop_trigger_token()`].
+///
+/// This is the inverse of a [`TriggerShutdownToken`][crate::TriggerShutdownToken]:
+/// the token can be cloned and sent to different threads and tasks freely,
+/// and the shutdown is only triggered once *all* clones have been dropped.
+/// This models "shut down when all worker handles are gone":
+/// ```
+/// let shutdown = async_shutdown::ShutdownManager::new();
+/// let token = shutdown.last_drop_trigger_token("all workers stopped");
+/// let workers: Vec<_> = (0..4).map(|_| token.clone()).collect();
+/// drop(token);
+///
+/// drop(workers);
+/// assert!(shutdown.shutdown_reason() == Some("all workers stopped"));
+/// ```
+///
+/// Nothing happens if the shutdown was already triggered when the last clone is dropped.
+#[derive(Clone)]
+pub struct LastDropTriggerToken<T: Clone> {
+	state: Arc<LastDropState<T>>,
+}
+
+/// The state shared by all clones of a [`LastDropTriggerToken`].
+struct LastDropState<T: Clone> {
+	/// The state of the generation to trigger.
+	inner: Arc<Shared<T>>,
+
+	/// The shutdown reason, or `None` if the token was disarmed.
+	reason: Mutex<Option<T>>,
+}
+
+impl<T: Clone> LastDropTriggerToken<T> {
+	/// Check if the shutdown is triggered when the last clone of the token is dropped.
+	///
+	/// This returns `false` if the token was disarmed.
+	#[inline]
+	pub fn is_armed(&self) -> bool {
+		self.state.reason.lock_unpoisoned().is_some()
+	}
+
+	/// Disarm the token, so that dropping the last clone does not trigger the shutdown.
+	///
+	/// This disarms all clones of the token.
+	/// Returns the shutdown reason if the token was still armed.
+	#[inline]
+	pub fn disarm(&self) -> Option<T> {
+		self.state.reason.lock_unpoisoned().take()
+	}
+
+	/// Get the number of clones of the token that currently exist, including this one.
+	#[inline]
+	pub fn clone_count(&self) -> usize {
+		Arc::strong_count(&self.state)
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for LastDropTriggerToken<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("LastDropTriggerToken")
+			.field("armed", &self.is_armed())
+			.field("clones", &self.clone_count())
+			.finish()
+	}
+}
+
+impl<T: Clone> Drop for LastDropState<T> {
+	fn drop(&mut self) {
+		if let Some(reason) = self.reason.lock_unpoisoned().take() {
+			self.inner.shutdown_idempotent(reason);
+		}
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Get a token that triggers the shutdown when the last clone of it is dropped.
+	///
+	/// See [`LastDropTriggerToken`] for more details.
+	#[inline]
+	pub fn last_drop_trigger_token(&self, shutdown_reason: T) -> LastDropTriggerToken<T> {
+		LastDropTriggerToken {
+			state: Arc::new(LastDropState {
+				inner: self.inner(),
+				reason: Mutex::new(Some(shutdown_reason)),
+			}),
+		}
+	}
+}
@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::lock::MutexExt;
+use crate::{Clock, DelayShutdownToken};
+
+/// A delay token that expires if it is not renewed in time.
+///
+/// Created by [`ShutdownManager::delay_shutdown_lease()`][crate::ShutdownManager::delay_shutdown_lease].
+///
+/// The lease delays shutdown completion just like a [`DelayShutdownToken`],
+/// until it is dropped or until it expires.
+/// The lease expires when it is not renewed with [`Self::renew()`] within the lease duration.
+/// This protects the application from a single stuck component that would otherwise delay the shutdown forever.
+///
+/// The expiry is timed by the [clock][crate::ShutdownManagerBuilder::clock] of the shutdown manager,
+/// so this works regardless of the async runtime you use.
+pub struct DelayShutdownLease<T: Clone> {
+	state: Arc<Mutex<LeaseState<T>>>,
+	clock: Arc<dyn Clock>,
+	duration: Duration,
+}
+
+struct LeaseState<T: Clone> {
+	/// The delay token, or [`None`] if the lease expired.
+	token: Option<DelayShutdownToken<T>>,
+
+	/// The number of times the lease was renewed.
+	///
+	/// Each renewal starts a new expiry timer, and only the timer of the latest renewal expires the lease.
+	renewals: u64,
+}
+
+impl<T: Clone + Send + 'static> DelayShutdownLease<T> {
+	/// Create a new lease for a delay token.
+	pub(crate) fn new(token: DelayShutdownToken<T>, duration: Duration) -> Self {
+		let clock = token.inner.clock();
+		let lease = Self {
+			state: Arc::new(Mutex::new(LeaseState {
+				token: Some(token),
+				renewals: 0,
+			})),
+			clock,
+			duration,
+		};
+		lease.start_timer(0);
+		lease
+	}
+
+	/// Renew the lease, so that it expires one lease duration from now.
+	///
+	/// Returns `false` if the lease already expired.
+	/// An expired lease can not be renewed.
+	#[inline]
+	pub fn renew(&self) -> bool {
+		let mut state = self.state.lock_unpoisoned();
+		if state.token.is_none() {
+			return false;
+		}
+		state.renewals += 1;
+		let renewals = state.renewals;
+		drop(state);
+		self.start_timer(renewals);
+		true
+	}
+
+	/// Start the timer that expires the lease, unless it is renewed again before the timer fires.
+	fn start_timer(&self, renewals: u64) {
+		let weak_state = Arc::downgrade(&self.state);
+		self.clock.call_after(self.duration, Box::new(move || {
+			// Stop if the lease was dropped.
+			let state = match weak_state.upgrade() {
+				Some(state) => state,
+				None => return,
+			};
+			let mut state = state.lock_unpoisoned();
+			if state.renewals == renewals {
+				let token = state.token.take();
+				drop(state);
+				// Dropping the token can complete the shutdown, so do it without holding the lock.
+				drop(token);
+			}
+		}));
+	}
+}
+
+impl<T: Clone> DelayShutdownLease<T> {
+	/// Check if the lease expired.
+	#[inline]
+	pub fn is_expired(&self) -> bool {
+		self.state.lock_unpoisoned().token.is_none()
+	}
+
+	/// Get the lease duration.
+	#[inline]
+	pub fn duration(&self) -> Duration {
+		self.duration
+	}
+}
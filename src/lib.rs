@@ -53,6 +53,48 @@
 //! If you're not careful, this could still cause data loss on shutdown.
 //! As a rule of thumb, you should usually wrap futures *before* you spawn them on a new task.
 //!
+//! # Optional features
+//! The core of the crate is runtime agnostic, but some integrations are available behind optional features:
+//! * `abortable`: Abort [`Abortable`](https://docs.rs/futures/latest/futures/future/struct.Abortable.html) futures from `futures-util` when the shutdown is triggered.
+//! * `async-std`: Spawn and cancel tasks on the async-std runtime when the shutdown is triggered.
+//! * `catch-unwind`: Trigger the shutdown when a wrapped future panics.
+//! * `critical-section`: A lock for the [`embedded`] shutdown manager that uses the [`critical-section`](https://docs.rs/critical-section) crate. This feature does not need `std`.
+//! * `ctrl-c`: Trigger the shutdown when the process receives Ctrl+C (tokio).
+//! * `debug-diagnostics`: Record where delay tokens are created, to find tokens that block the shutdown.
+//! * `ffi`: A C API to control a shutdown manager from a foreign host application, in the `ffi` module.
+//! * `futures-io`: Make I/O objects shut down gracefully, using the [`AsyncRead`](https://docs.rs/futures-io/latest/futures_io/trait.AsyncRead.html) and [`AsyncWrite`](https://docs.rs/futures-io/latest/futures_io/trait.AsyncWrite.html) traits from `futures-io`.
+//! * `http-probe`: An HTTP readiness and liveness probe that fails once the shutdown is triggered or completed, as a tower service.
+//! * `io-error`: Convert the shutdown reason into an [`io::Error`](std::io::Error), to cancel futures inside I/O loops that return [`io::Result`](std::io::Result).
+//! * `metrics`: Export gauges for the live delay tokens and waiters, counters for cancelled futures and a histogram of the shutdown duration through the [`metrics`](https://docs.rs/metrics) crate facade. See `describe_metrics()` for the list of metrics.
+//! * `process`: Terminate child processes or their process group when the shutdown is triggered (tokio, unix only).
+//! * `select`: The `shutdown_select!` macro, a [`tokio::select!`](https://docs.rs/tokio/latest/tokio/macro.select.html) with an implicit branch for the shutdown signal.
+//! * `signals`: Trigger the shutdown when the process receives one of a set of Unix signals, with a shutdown reason per signal and optional escalation on repeated signals (tokio, unix only).
+//! * `sink`: Sink wrappers, using the [`Sink`](https://docs.rs/futures-sink/latest/futures_sink/trait.Sink.html) trait from `futures-sink`.
+//! * `smol`: Spawn and cancel tasks on the global smol executor when the shutdown is triggered.
+//! * `std`: Everything except the [`embedded`] module, which only needs `core`. This feature is enabled by default.
+//! * `stream`: Stream wrappers, single-item streams of the shutdown signals and a stream of shutdown events, using the [`Stream`](https://docs.rs/futures-core/latest/futures_core/stream/trait.Stream.html) trait from `futures-core`.
+//! * `tcp`: Coordinate the shutdown of remote worker processes over TCP, and wait for them to acknowledge their completion with a deadline (tokio). The shutdown reason is sent with `serde`.
+//! * `test-util`: Assertions and a poll counter to test the shutdown behaviour of your own code, in the `test_util` module.
+//! * `tokio`: Spawn, track and abort tasks on a tokio runtime when the shutdown is triggered, and integrate with tokio intervals, `mpsc` channels and semaphores.
+//! * `tokio-io`: Make I/O objects shut down gracefully, using the [`AsyncRead`](https://docs.rs/tokio/latest/tokio/io/trait.AsyncRead.html) and [`AsyncWrite`](https://docs.rs/tokio/latest/tokio/io/trait.AsyncWrite.html) traits from `tokio`.
+//! * `tonic`: Serve a tonic gRPC server that shuts down gracefully when the shutdown is triggered.
+//! * `tonic-health`: Mark gRPC services as not serving when the shutdown is triggered, and report the draining progress.
+//! * `tower`: A [`Layer`](https://docs.rs/tower-layer/latest/tower_layer/trait.Layer.html) for tower services that rejects new requests once the shutdown is triggered, and delays shutdown completion for in-flight requests.
+//! * `tracing`: Report shutdown managers and their futures as resources to `tokio-console`, and log the delay tokens that hold up a slow shutdown.
+//! * `unix-socket`: Propagate the shutdown to the shutdown managers of other processes over a Unix socket, and wait for their completion (tokio, unix only). The shutdown reason is sent with `serde`.
+//!
+//! # WebAssembly
+//! The crate works on WebAssembly targets, for example to coordinate the teardown of `wasm-bindgen` futures in the browser.
+//! The shutdown reason does not need to be `Send` or `Sync` for the core functionality.
+//! However, functions that rely on background threads are not available on WebAssembly targets:
+//! [`ShutdownManager::trigger_shutdown_with_deadline()`], `ShutdownManager::wait_shutdown_complete_timeout()` and `ShutdownManager::wrap_cancel_with_grace()`.
+//! The time stamps in [`ShutdownMetrics`] are also not recorded there.
+//!
+//! # Embedded and `no_std`
+//! Without the default `std` feature, the crate only provides the [`embedded::StaticShutdownManager`].
+//! It does not allocate, it can be created in a `static`, and it stores the wakers of a fixed number of futures.
+//! Its state is protected by a lock of your choice, such as a critical section on a microcontroller.
+//!
 //! # Example
 //!
 //! This example is a tokio-based TCP echo server.
@@ -100,11 +142,9 @@
 //!         },
 //!     };
 //!
-//!     // Wait for clients to run their cleanup code, then exit.
+//!     // Wait for clients to run their cleanup code, then exit with the exit code.
 //!     // Without this, background tasks could be killed before they can run their cleanup code.
-//!     let exit_code = shutdown.wait_shutdown_complete().await;
-//!
-//!     std::process::exit(exit_code);
+//!     shutdown.wait_and_exit().await;
 //! }
 //!
 //! async fn run_server(shutdown: ShutdownManager<i32>, bind_address: &str) -> std::io::Result<()> {
@@ -165,28 +205,472 @@
 //! }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(feature = "std")]
 use std::future::Future;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "std")]
 mod shutdown_complete;
+#[cfg(feature = "std")]
 pub use shutdown_complete::ShutdownComplete;
 
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+mod shutdown_complete_timeout;
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+pub use shutdown_complete_timeout::ShutdownCompleteTimeout;
+
+#[cfg(feature = "std")]
+mod shutdown_phase_complete;
+#[cfg(feature = "std")]
+pub use shutdown_phase_complete::ShutdownPhaseComplete;
+
+#[cfg(feature = "std")]
 mod shutdown_signal;
+#[cfg(feature = "std")]
+use shutdown_signal::SignalKind;
+#[cfg(feature = "std")]
 pub use shutdown_signal::ShutdownSignal;
 
+#[cfg(feature = "std")]
+mod ignore_shutdown_reason;
+#[cfg(feature = "std")]
+pub use ignore_shutdown_reason::IgnoreShutdownReason;
+
+#[cfg(feature = "std")]
+mod shutdown_signal_then;
+#[cfg(feature = "std")]
+pub use shutdown_signal_then::ShutdownSignalThen;
+
+#[cfg(feature = "std")]
 mod wrap_cancel;
-use waker_list::WakerList;
+#[cfg(feature = "std")]
+use waker_list::{Waiter, WakerList};
+#[cfg(feature = "std")]
 pub use wrap_cancel::WrapCancel;
 
+#[cfg(feature = "std")]
+mod cancelled;
+#[cfg(feature = "std")]
+pub use cancelled::{Cancelled, WrapCancelOutcome};
+
+#[cfg(feature = "std")]
+mod wrap_cancel_map_err;
+#[cfg(feature = "std")]
+pub use wrap_cancel_map_err::WrapCancelMapErr;
+
+#[cfg(feature = "std")]
+mod wrap_cancel_or;
+#[cfg(feature = "std")]
+pub use wrap_cancel_or::{WrapCancelOr, WrapCancelOrElse};
+
+#[cfg(feature = "std")]
+mod try_wrap_cancel;
+#[cfg(feature = "std")]
+pub use try_wrap_cancel::TryWrapCancel;
+
+#[cfg(feature = "std")]
+mod run_then_cleanup;
+#[cfg(feature = "std")]
+pub use run_then_cleanup::{RunSummary, RunThenCleanup};
+
+#[cfg(feature = "std")]
+mod wrap_cancel_with_cleanup;
+#[cfg(feature = "std")]
+pub use wrap_cancel_with_cleanup::WrapCancelWithCleanup;
+
+#[cfg(feature = "std")]
+mod wrap_graceful;
+#[cfg(feature = "std")]
+pub use wrap_graceful::{GracefulOutcome, WrapGraceful};
+
+#[cfg(feature = "std")]
+mod scope;
+#[cfg(feature = "std")]
+pub use scope::{Scope, WrapScope};
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+mod wrap_cancel_with_grace;
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+pub use wrap_cancel_with_grace::{GraceOutcome, WrapCancelWithGrace};
+
+#[cfg(feature = "std")]
+#[cfg(feature = "catch-unwind")]
+mod wrap_catch_panic;
+#[cfg(feature = "std")]
+#[cfg(feature = "catch-unwind")]
+pub use wrap_catch_panic::WrapCatchPanic;
+
+#[cfg(feature = "std")]
 mod wrap_trigger_shutdown;
+#[cfg(feature = "std")]
 pub use wrap_trigger_shutdown::WrapTriggerShutdown;
 
+#[cfg(feature = "std")]
 mod wrap_delay_shutdown;
+#[cfg(feature = "std")]
 pub use wrap_delay_shutdown::WrapDelayShutdown;
 
+#[cfg(feature = "std")]
+mod protect;
+#[cfg(feature = "std")]
+pub use protect::Protect;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+mod shutdown_events;
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+pub use shutdown_events::{ShutdownEvent, ShutdownEvents};
+
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+mod signal_stream;
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+pub use signal_stream::{ShutdownCompleteStream, ShutdownSignalStream};
+
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+mod completion_progress;
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+pub use completion_progress::CompletionProgress;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+mod for_each_until_shutdown;
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+pub use for_each_until_shutdown::{ForEachSummary, ForEachUntilShutdown};
+
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+mod wrap_trigger_shutdown_stream;
+#[cfg(feature = "std")]
+#[cfg(feature = "stream")]
+pub use wrap_trigger_shutdown_stream::WrapTriggerShutdownStream;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "sink")]
+mod wrap_sink;
+#[cfg(feature = "std")]
+#[cfg(feature = "sink")]
+pub use wrap_sink::WrapSink;
+
+#[cfg(feature = "std")]
+#[cfg(any(feature = "tokio-io", feature = "futures-io"))]
+mod wrap_io;
+#[cfg(feature = "std")]
+#[cfg(any(feature = "tokio-io", feature = "futures-io"))]
+pub use wrap_io::WrapIo;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "io-error")]
+mod wrap_cancel_io;
+#[cfg(feature = "std")]
+#[cfg(feature = "io-error")]
+pub use wrap_cancel_io::{shutdown_io_error, WrapCancelIo};
+
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+pub use registry::{ShutdownRegistry, WaitAllComplete};
+
+#[cfg(feature = "std")]
+mod channels;
+#[cfg(feature = "std")]
+pub use channels::ShutdownChannels;
+
+#[cfg(feature = "std")]
+mod local;
+#[cfg(feature = "std")]
+pub use local::{LocalDelayShutdownToken, LocalShutdownManager, LocalShutdownSignal, LocalWrapCancel, LocalWrapDelayShutdown};
+
+pub mod embedded;
+
+#[cfg(feature = "std")]
+mod exit;
+#[cfg(feature = "std")]
+pub use exit::ExitReason;
+
+#[cfg(feature = "std")]
+mod services;
+#[cfg(feature = "std")]
+pub use services::{DependencyCycle, Service, Services};
+
+#[cfg(feature = "std")]
+mod delay_guard;
+#[cfg(feature = "std")]
+pub use delay_guard::DelayGuard;
+
+#[cfg(feature = "std")]
+mod token_group;
+#[cfg(feature = "std")]
+use token_group::GroupState;
+#[cfg(feature = "std")]
+pub use token_group::{DelayTokenGroup, GroupIdle};
+
+#[cfg(feature = "std")]
 mod waker_list;
+#[cfg(feature = "std")]
+pub use waker_list::WakeOrder;
+
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+pub use crate::metrics::ShutdownMetrics;
+
+#[cfg(feature = "std")]
+mod metrics_export;
+#[cfg(feature = "std")]
+#[cfg(feature = "metrics")]
+pub use metrics_export::describe_metrics;
+
+#[cfg(feature = "std")]
+mod blockers;
+#[cfg(feature = "std")]
+use blockers::{BlockerInfo, Blockers};
+#[cfg(feature = "std")]
+pub use blockers::ShutdownBlocker;
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+mod blocker_report;
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+pub use blocker_report::BlockerReport;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "abortable")]
+mod abortable;
+
+#[cfg(feature = "std")]
+mod auto_trigger;
+#[cfg(feature = "std")]
+use auto_trigger::AutoTrigger;
+
+#[cfg(feature = "std")]
+mod last_drop_trigger;
+#[cfg(feature = "std")]
+pub use last_drop_trigger::LastDropTriggerToken;
+
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+pub use builder::{RepeatTrigger, ShutdownManagerBuilder};
+
+#[cfg(feature = "std")]
+mod any_reason;
+#[cfg(feature = "std")]
+pub use any_reason::{AnyShutdownManager, AnyShutdownReason};
+
+#[cfg(feature = "std")]
+mod map_reason;
+#[cfg(feature = "std")]
+pub use map_reason::{MappedShutdownManager, MappedShutdownSignal, MappedWrapCancel};
+
+#[cfg(feature = "std")]
+mod dyn_signal;
+#[cfg(feature = "std")]
+pub use dyn_signal::DynShutdownSignal;
+
+#[cfg(feature = "std")]
+mod global;
+#[cfg(feature = "std")]
+pub use global::global;
+
+#[cfg(feature = "std")]
+mod flush_hooks;
+#[cfg(feature = "std")]
+use flush_hooks::FlushHooks;
+
+#[cfg(feature = "std")]
+mod async_drop;
+#[cfg(feature = "std")]
+use async_drop::AsyncDrops;
+
+#[cfg(feature = "std")]
+mod phases;
+#[cfg(feature = "std")]
+use phases::Phases;
+
+#[cfg(feature = "std")]
+mod shared;
+#[cfg(feature = "std")]
+use shared::{GenerationStatus, Shared};
+
+#[cfg(feature = "std")]
+mod lock;
+#[cfg(feature = "std")]
+use lock::MutexExt;
+
+#[cfg(feature = "std")]
+mod hooks;
+#[cfg(feature = "std")]
+use hooks::Hooks;
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+mod lease;
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+pub use lease::DelayShutdownLease;
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+mod clock;
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+pub use clock::{Clock, SystemClock};
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+mod timer;
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+use timer::Timer;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "ctrl-c")]
+mod ctrl_c;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "select")]
+#[doc(hidden)]
+#[path = "select.rs"]
+pub mod __select;
+
+#[cfg(feature = "std")]
+#[cfg(all(unix, feature = "process"))]
+mod process;
+
+#[cfg(feature = "std")]
+#[cfg(any(feature = "tcp", all(unix, feature = "unix-socket")))]
+mod wire;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tcp")]
+mod tcp;
+#[cfg(feature = "std")]
+#[cfg(feature = "tcp")]
+pub use tcp::CoordinationReport;
+
+#[cfg(feature = "std")]
+#[cfg(all(unix, feature = "unix-socket"))]
+mod unix_socket;
+
+#[cfg(feature = "std")]
+#[cfg(all(unix, feature = "signals"))]
+mod signals;
+#[cfg(feature = "std")]
+#[cfg(all(unix, feature = "signals"))]
+pub use signals::SignalEscalation;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+mod interval;
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+pub use interval::ShutdownInterval;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+mod mpsc;
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+pub use mpsc::ShutdownReceiver;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+mod semaphore;
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+pub use semaphore::{ShutdownPermit, ShutdownSemaphore};
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+mod spawn;
+
+#[cfg(feature = "std")]
+mod spawn_with;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "async-std")]
+mod async_std;
+#[cfg(feature = "std")]
+#[cfg(feature = "async-std")]
+pub use crate::async_std::AsyncStdWrapAbort;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "smol")]
+mod smol;
+#[cfg(feature = "std")]
+#[cfg(feature = "smol")]
+pub use crate::smol::SmolWrapAbort;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+mod task_tracker;
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+pub use task_tracker::{TaskTracker, TaskTrackerWait};
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+mod wrap_abort;
+#[cfg(feature = "std")]
+#[cfg(feature = "tokio")]
+pub use wrap_abort::WrapAbort;
+
+#[cfg(feature = "std")]
+mod instrument;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tonic")]
+mod tonic;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tonic-health")]
+mod tonic_health;
+#[cfg(feature = "std")]
+#[cfg(feature = "tonic-health")]
+pub use tonic_health::DrainProgress;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "http-probe")]
+mod http_probe;
+#[cfg(feature = "std")]
+#[cfg(feature = "http-probe")]
+pub use http_probe::HttpProbe;
+
+#[cfg(feature = "std")]
+#[cfg(feature = "tower")]
+mod tower;
+#[cfg(feature = "std")]
+#[cfg(feature = "tower")]
+pub use tower::{ShutdownLayer, ShutdownService, ShutdownServiceFuture};
 
 /// Shutdown manager for asynchronous tasks and futures.
 ///
@@ -197,31 +681,263 @@ mod waker_list;
 ///
 /// The shutdown manager can be cloned and shared with multiple tasks.
 /// Each clone uses the same internal state.
-#[derive(Clone)]
+///
+/// After a shutdown has completed, the manager can be reused with [`Self::reset()`].
+#[cfg(feature = "std")]
 pub struct ShutdownManager<T: Clone> {
-	inner: Arc<Mutex<ShutdownManagerInner<T>>>,
+	/// The state of the current generation.
+	///
+	/// Futures and tokens keep a reference to the state of the generation they were created for,
+	/// so that they are not affected by a reset.
+	current: Arc<Mutex<Arc<Shared<T>>>>,
+
+	/// The state of the generation that was current when this handle was created.
+	///
+	/// As long as no reset happened since then, the state can be used without locking `current`.
+	/// Handles created before a reset fall back to locking `current`.
+	cached: Arc<Shared<T>>,
+
+	/// The current generation number and whether it has been triggered, readable without locking.
+	status: Arc<GenerationStatus>,
+
+	/// Triggers the shutdown when the last clone of the manager is dropped, if enabled.
+	auto_trigger: Option<Arc<AutoTrigger<T>>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> Clone for ShutdownManager<T> {
+	#[inline]
+	fn clone(&self) -> Self {
+		Self {
+			current: self.current.clone(),
+			cached: self.inner(),
+			status: self.status.clone(),
+			auto_trigger: self.auto_trigger.clone(),
+		}
+	}
 }
 
+#[cfg(feature = "std")]
+impl<T: Clone> std::fmt::Debug for ShutdownManager<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let inner = self.shared();
+		let locked = inner.lock();
+		f.debug_struct("ShutdownManager")
+			.field("generation", &self.generation())
+			.field("shutdown_triggered", &locked.shutdown_reason.is_some())
+			.field("forced_shutdown_triggered", &locked.forced_reason.is_some())
+			.field("shutdown_completed", &locked.completed_reason().is_some())
+			.field("delay_tokens", &locked.delay_tokens)
+			.finish()
+	}
+}
+
+/// A shutdown manager without a shutdown reason.
+///
+/// Checking if the shutdown has been triggered is a single atomic load,
+/// and polling a shutdown signal that is already registered does not take a lock.
+/// With a unit reason, cancelling a future does not need to clone anything.
+#[cfg(feature = "std")]
+pub type Shutdown = ShutdownManager<()>;
+
+#[cfg(feature = "std")]
 impl<T: Clone> ShutdownManager<T> {
 	/// Create a new shutdown manager.
 	#[inline]
+	#[track_caller]
 	pub fn new() -> Self {
+		let status = Arc::new(GenerationStatus::new());
+		let cached = Arc::new(Shared::new(status.clone(), 0));
 		Self {
-			inner: Arc::new(Mutex::new(ShutdownManagerInner::new())),
+			current: Arc::new(Mutex::new(cached.clone())),
+			cached,
+			status,
+			auto_trigger: None,
+		}
+	}
+
+	/// Create a new shutdown manager with room for `capacity` waiting futures.
+	///
+	/// Waiting futures now store their own node of the list of waiters,
+	/// so there is nothing to pre-allocate and this is the same as [`Self::new()`].
+	#[deprecated(note = "waiting futures no longer need room in the shutdown manager, use `ShutdownManager::new()`")]
+	#[inline]
+	pub fn with_waker_capacity(capacity: usize) -> Self {
+		let _ = capacity;
+		Self::new()
+	}
+
+	/// Release memory used to keep track of waiting futures.
+	///
+	/// Waiting futures store their own node of the list of waiters, so they never leave unused memory behind.
+	/// This function releases the memory of abort registrations that were already woken,
+	/// and the bookkeeping of shutdown phases that nobody is waiting for anymore.
+	pub fn shrink_waiters(&self) {
+		let inner = self.shared();
+		let mut inner = inner.lock();
+		inner.on_shutdown.shrink_to_fit();
+		inner.on_drain.shrink_to_fit();
+		inner.on_forced_shutdown.shrink_to_fit();
+		inner.on_shutdown_complete.shrink_to_fit();
+		inner.on_delay_count_change.shrink_to_fit();
+		inner.phases.shrink_waiters();
+	}
+
+	/// Get the state of the current generation.
+	#[inline]
+	pub(crate) fn inner(&self) -> Arc<Shared<T>> {
+		self.shared().into_owned()
+	}
+
+	/// Get the state of the current generation, without cloning it if this handle was created for the current generation.
+	#[inline]
+	pub(crate) fn shared(&self) -> Cow<'_, Arc<Shared<T>>> {
+		if self.cached.generation() == self.status.generation() {
+			Cow::Borrowed(&self.cached)
+		} else {
+			Cow::Owned(self.current.lock_unpoisoned().clone())
 		}
 	}
 
 	/// Check if the shutdown has been triggered.
 	#[inline]
 	pub fn is_shutdown_triggered(&self) -> bool {
-		self.inner.lock().unwrap().shutdown_reason.is_some()
+		self.status.is_triggered()
+	}
+
+	/// Check if the drain has been triggered.
+	///
+	/// This also returns `true` if the shutdown has been triggered, since triggering the shutdown also triggers the drain.
+	#[inline]
+	pub fn is_drain_triggered(&self) -> bool {
+		self.shared().lock().drain_reason.is_some()
+	}
+
+	/// Check if the forced shutdown has been triggered.
+	#[inline]
+	pub fn is_forced_shutdown_triggered(&self) -> bool {
+		self.shared().lock().forced_reason.is_some()
 	}
 
 	/// Check if the shutdown has completed.
 	#[inline]
 	pub fn is_shutdown_completed(&self) -> bool {
-		let inner = self.inner.lock().unwrap();
-		inner.shutdown_reason.is_some() && inner.delay_tokens == 0
+		self.shared().lock().completed_reason().is_some()
+	}
+
+	/// Get the number of futures that are currently waiting for the shutdown to be triggered.
+	///
+	/// This counts all [`ShutdownSignal`] futures (including the ones inside [`WrapCancel`] futures)
+	/// that have been polled and are registered to be woken up when the shutdown is triggered.
+	/// Futures that have not been polled yet are not counted.
+	///
+	/// After the shutdown has been triggered, this always returns 0.
+	#[inline]
+	pub fn trigger_waiter_count(&self) -> usize {
+		self.shared().lock().on_shutdown.len()
+	}
+
+	/// Get the number of futures that are currently waiting for the shutdown to complete.
+	///
+	/// This counts all [`ShutdownComplete`] futures that have been polled
+	/// and are registered to be woken up when the shutdown completes.
+	/// Futures that have not been polled yet are not counted.
+	#[inline]
+	pub fn completion_waiter_count(&self) -> usize {
+		self.shared().lock().on_shutdown_complete.len()
+	}
+
+	/// Get the number of delay tokens that currently exist.
+	///
+	/// This counts all [`DelayShutdownToken`] objects, including clones and the tokens inside [`WrapDelayShutdown`] futures.
+	/// The shutdown can not complete while this is not zero,
+	/// so it can be used to report how many tasks are still holding up the shutdown.
+	///
+	/// Hooks registered with [`Self::on_shutdown()`] also delay the shutdown, but they are not counted.
+	#[inline]
+	pub fn delay_token_count(&self) -> usize {
+		self.shared().delay_tokens.load(Ordering::Relaxed)
+	}
+
+	/// Get the moment the shutdown was triggered.
+	///
+	/// Returns [`None`] if the shutdown has not been triggered yet.
+	#[inline]
+	pub fn triggered_at(&self) -> Option<Instant> {
+		self.shared().lock().triggered_at
+	}
+
+	/// Get the moment the shutdown completed.
+	///
+	/// Returns [`None`] if the shutdown has not completed yet.
+	#[inline]
+	pub fn completed_at(&self) -> Option<Instant> {
+		self.shared().lock().completed_at
+	}
+
+	/// Get the time it took from triggering the shutdown until it completed.
+	///
+	/// Returns [`None`] if the shutdown has not completed yet.
+	#[inline]
+	pub fn shutdown_duration(&self) -> Option<Duration> {
+		let inner = self.shared();
+		let inner = inner.lock();
+		Some(inner.completed_at?.saturating_duration_since(inner.triggered_at?))
+	}
+
+	/// Get information about the delay tokens that are currently holding up the shutdown.
+	///
+	/// Each entry describes one delay token and all its clones, in the order the tokens were created.
+	/// Long running clean-up tasks can report their progress with [`DelayShutdownToken::set_status()`],
+	/// so you can tell operators what the shutdown is still waiting for.
+	///
+	/// Hooks registered with [`Self::on_shutdown()`] also delay the shutdown, but they are not included.
+	pub fn pending_blockers(&self) -> Vec<ShutdownBlocker> {
+		self.shared().lock().blockers.snapshot()
+	}
+
+	/// Get the delay tokens that are still alive when the shutdown was triggered at least `after` ago.
+	///
+	/// Returns an empty list if the shutdown was not triggered yet, or if it was triggered less than `after` ago.
+	/// Each entry includes the [source location][ShutdownBlocker::location] and [backtrace][ShutdownBlocker::backtrace] of the creation of the token,
+	/// which helps to find tokens that are accidentally kept alive:
+	/// ```
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// # use std::time::Duration;
+	/// shutdown.trigger_shutdown(()).ok();
+	/// tokio::time::sleep(Duration::from_secs(10)).await;
+	/// for token in shutdown.leaked_token_report(Duration::from_secs(10)) {
+	///     eprintln!("delay token created at {} is still alive", token.location);
+	/// }
+	/// # }
+	/// ```
+	///
+	/// The time of the trigger is not recorded on `wasm32`, so the list is always empty there.
+	#[cfg(feature = "debug-diagnostics")]
+	pub fn leaked_token_report(&self, after: Duration) -> Vec<ShutdownBlocker> {
+		let inner = self.shared();
+		let inner = inner.lock();
+		match inner.triggered_at {
+			Some(triggered_at) if triggered_at.elapsed() >= after => inner.blockers.snapshot(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Get a snapshot of the delay token count, waiter counts and shutdown timing, for monitoring purposes.
+	///
+	/// See [`ShutdownMetrics`] for more details.
+	pub fn metrics(&self) -> ShutdownMetrics {
+		let inner = self.shared();
+		let locked = inner.lock();
+		ShutdownMetrics {
+			delay_tokens: inner.delay_tokens.load(Ordering::Relaxed),
+			trigger_waiters: locked.on_shutdown.len(),
+			completion_waiters: locked.on_shutdown_complete.len(),
+			cancelled_futures: inner.cancelled_futures.load(Ordering::Relaxed),
+			timed_out_hooks: inner.timed_out_hooks.load(Ordering::Relaxed),
+			triggered_at: locked.triggered_at,
+			completed_at: locked.completed_at,
+		}
 	}
 
 	/// Get the shutdown reason, if the shutdown has been triggered.
@@ -229,7 +945,7 @@ impl<T: Clone> ShutdownManager<T> {
 	/// Returns [`None`] if the shutdown has not been triggered yet.
 	#[inline]
 	pub fn shutdown_reason(&self) -> Option<T> {
-		self.inner.lock().unwrap().shutdown_reason.clone()
+		self.shared().lock().shutdown_reason.clone()
 	}
 
 	/// Asynchronously wait for the shutdown to be triggered.
@@ -239,94 +955,975 @@ impl<T: Clone> ShutdownManager<T> {
 	///
 	/// If the shutdown is already triggered, the returned future immediately resolves.
 	///
-	/// You can also use `ShutdownSignal::wrap_cancel()` of the returned object
-	/// to automatically cancel a future when the shutdown signal is received.
-	/// This is identical to `Self::wrap_cancel()`.
+	/// You can also use `ShutdownSignal::wrap_cancel()` of the returned object
+	/// to automatically cancel a future when the shutdown signal is received.
+	/// This is identical to `Self::wrap_cancel()`.
+	#[inline]
+	pub fn wait_shutdown_triggered(&self) -> ShutdownSignal<T> {
+		ShutdownSignal {
+			inner: self.inner(),
+			kind: SignalKind::Shutdown,
+			waiter: Waiter::new(),
+			waker: None,
+			async_op: instrument::AsyncOp::default(),
+		}
+	}
+
+	/// Asynchronously wait for the drain to be triggered.
+	///
+	/// This returns a future that completes when [`Self::trigger_drain()`] is called, or when the shutdown is triggered.
+	/// It completes with the reason of the drain.
+	///
+	/// You can use `ShutdownSignal::wrap_cancel()` of the returned object
+	/// to cancel a future when the drain is triggered.
+	/// This is identical to [`Self::wrap_cancel_on_drain()`].
+	#[inline]
+	pub fn wait_drain_triggered(&self) -> ShutdownSignal<T> {
+		ShutdownSignal {
+			inner: self.inner(),
+			kind: SignalKind::Drain,
+			waiter: Waiter::new(),
+			waker: None,
+			async_op: instrument::AsyncOp::default(),
+		}
+	}
+
+	/// Asynchronously wait for the forced shutdown to be triggered.
+	///
+	/// This returns a future that completes when [`Self::trigger_forced_shutdown()`] is called.
+	/// It does not complete for a regular shutdown.
+	///
+	/// You can use `ShutdownSignal::wrap_cancel()` of the returned object
+	/// to cancel a future only when the forced shutdown is triggered.
+	/// This is identical to [`Self::wrap_cancel_forced()`].
+	#[inline]
+	pub fn wait_forced_shutdown_triggered(&self) -> ShutdownSignal<T> {
+		ShutdownSignal {
+			inner: self.inner(),
+			kind: SignalKind::Forced,
+			waiter: Waiter::new(),
+			waker: None,
+			async_op: instrument::AsyncOp::default(),
+		}
+	}
+
+	/// Get a stream of shutdown events.
+	///
+	/// The stream yields a [`ShutdownEvent`] when the shutdown is triggered, when the forced shutdown is triggered and when the shutdown completes.
+	/// This allows you to observe the state of the shutdown manager without polling it in a loop.
+	/// Events that already happened before this function is called are also reported.
+	///
+	/// Note that the stream drives the async shutdown hooks while waiting for the shutdown to complete,
+	/// just like [`ShutdownComplete`].
+	#[cfg(feature = "stream")]
+	#[inline]
+	pub fn events(&self) -> ShutdownEvents<T> {
+		ShutdownEvents {
+			triggered: Some(Box::pin(self.wait_shutdown_triggered())),
+			forced: Some(Box::pin(self.wait_forced_shutdown_triggered())),
+			completed: Some(Box::pin(self.wait_shutdown_complete())),
+		}
+	}
+
+	/// Get a stream of the number of remaining delay tokens while the shutdown is in progress.
+	///
+	/// This can be used to show the progress of the shutdown to a user:
+	/// ```
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// use futures::StreamExt;
+	///
+	/// let mut progress = shutdown.completion_progress();
+	/// while let Some(remaining) = progress.next().await {
+	///     println!("waiting for {remaining} tasks");
+	/// }
+	/// # }
+	/// ```
+	///
+	/// See [`CompletionProgress`] for more details.
+	#[cfg(feature = "stream")]
+	#[inline]
+	pub fn completion_progress(&self) -> CompletionProgress<T> {
+		CompletionProgress {
+			complete: Box::pin(self.wait_shutdown_complete()),
+			waiter: Box::pin(Waiter::new()),
+			last: None,
+			done: false,
+		}
+	}
+
+	/// Asynchronously wait for the shutdown to complete.
+	///
+	/// This returns a future that completes when the shutdown is complete.
+	/// The future can be cloned and sent to other threads or tasks freely.
+	///
+	/// The shutdown is complete when all [`DelayShutdownToken`] are dropped
+	/// and all [`WrapDelayShutdown`] futures have completed or are dropped.
+	#[inline]
+	pub fn wait_shutdown_complete(&self) -> ShutdownComplete<T> {
+		ShutdownComplete {
+			inner: self.inner(),
+			waiter: Waiter::new(),
+			driving_hooks: false,
+			async_op: instrument::AsyncOp::default(),
+		}
+	}
+
+	/// Asynchronously wait for the shutdown to complete, but give up after a timeout.
+	///
+	/// The returned future completes with `Ok(reason)` if the shutdown completes before the timeout expires.
+	/// Otherwise, it completes with a [`ShutdownTimedOut`] error that tells you how many delay tokens were still alive.
+	/// Note that the timeout starts when this function is called, not when the shutdown is triggered.
+	///
+	/// This is useful to give clean-up code a limited amount of time in your `main` function,
+	/// and to log what is still holding up the shutdown before exiting:
+	/// ```no_run
+	/// # async fn example() {
+	/// # use std::time::Duration;
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// shutdown.trigger_shutdown(()).ok();
+	/// if let Err(e) = shutdown.wait_shutdown_complete_timeout(Duration::from_secs(30)).await {
+	///     eprintln!("{}", e);
+	/// }
+	/// # }
+	/// ```
+	///
+	/// The timeout is enforced by a background thread, so this works regardless of the async runtime you use.
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	pub fn wait_shutdown_complete_timeout(&self, timeout: Duration) -> ShutdownCompleteTimeout<T> {
+		ShutdownCompleteTimeout {
+			shutdown_complete: self.wait_shutdown_complete(),
+			timer: Timer::new(&*self.shared().clock(), timeout),
+		}
+	}
+
+	/// Asynchronously wait for a shutdown phase to complete.
+	///
+	/// This returns a future that completes when the given phase is complete.
+	/// The future can be cloned and sent to other threads or tasks freely.
+	///
+	/// A phase is complete when the shutdown has been triggered,
+	/// and all delay tokens for that phase and all earlier phases have been dropped.
+	/// See [`Self::delay_shutdown_token_in_phase()`] for more information about shutdown phases.
+	#[inline]
+	pub fn wait_phase_complete(&self, phase: usize) -> ShutdownPhaseComplete<T> {
+		ShutdownPhaseComplete {
+			inner: self.inner(),
+			phase,
+			waiter: Waiter::new(),
+			driving_hooks: false,
+			async_op: instrument::AsyncOp::default(),
+		}
+	}
+
+	/// Asynchronously wait for all clean-up futures of a priority level and all higher priority levels to complete.
+	///
+	/// Priority levels are the same as shutdown phases, so this is equivalent to [`Self::wait_phase_complete()`].
+	/// See [`Self::wrap_delay_shutdown_with_priority()`] for more information.
+	#[inline]
+	pub fn wait_priority_complete(&self, priority: usize) -> ShutdownPhaseComplete<T> {
+		self.wait_phase_complete(priority)
+	}
+
+	/// Set the order in which [`ShutdownComplete`] futures are woken when the shutdown completes.
+	///
+	/// By default, no particular order is guaranteed.
+	/// With [`WakeOrder::Fifo`], the futures are woken in the order in which they were first polled.
+	/// This can be used to make sure that the main task of your application gets to run its exit path promptly,
+	/// even if thousands of other tasks are also waiting for the shutdown to complete.
+	#[inline]
+	pub fn set_completion_wake_order(&self, wake_order: WakeOrder) {
+		self.shared().lock().on_shutdown_complete.set_wake_order(wake_order);
+	}
+
+	/// Get the generation of the shutdown manager.
+	///
+	/// The generation starts at 0 and is incremented by every call to [`Self::reset()`].
+	#[inline]
+	pub fn generation(&self) -> u64 {
+		self.status.generation()
+	}
+
+	/// Reset the shutdown manager after a shutdown has completed, so that it can be used again.
+	///
+	/// This starts a new generation for the manager and all its clones.
+	/// The shutdown is no longer triggered, and new futures and tokens can be created as if the manager was new.
+	/// Settings like the [completion wake order][Self::set_completion_wake_order] and the [`RepeatTrigger`] policy are kept.
+	///
+	/// Futures and tokens created before the reset stay bound to the previous generation:
+	/// * [`ShutdownSignal`], [`ShutdownComplete`] and [`ShutdownPhaseComplete`] futures still resolve with the old shutdown reason.
+	/// * [`TriggerShutdownToken`]s no longer affect the manager.
+	/// * [`DelayShutdownToken`]s that remained after a [forced completion][Self::trigger_shutdown_with_deadline] do not delay the new generation.
+	///
+	/// On success, the shutdown reason of the previous generation is returned.
+	/// If the shutdown has not completed yet, this function returns an error and the manager is left untouched.
+	pub fn reset(&self) -> Result<T, ShutdownNotCompleted> {
+		let mut current = self.current.lock_unpoisoned();
+		let previous = current.lock();
+		let reason = previous.completed_reason().ok_or_else(ShutdownNotCompleted::new)?;
+
+		let generation = current.generation() + 1;
+		let next = Shared::new(self.status.clone(), generation);
+		let mut locked = next.lock();
+		locked.on_shutdown_complete.set_wake_order(previous.on_shutdown_complete.wake_order());
+		locked.repeat_trigger = previous.repeat_trigger;
+		#[cfg(not(target_family = "wasm"))]
+		{
+			locked.clock = previous.clock.clone();
+		}
+		drop(locked);
+		drop(previous);
+		self.status.start(generation);
+		*current = Arc::new(next);
+		Ok(reason)
+	}
+
+	/// Trigger the shutdown.
+	///
+	/// This will cause all [`ShutdownSignal`] and [`WrapCancel`] futures associated with this shutdown manager to be resolved.
+	///
+	/// The shutdown will not be considered complete until all [`DelayShutdownTokens`][DelayShutdownToken] are dropped.
+	///
+	/// If the shutdown was already started, this function returns an error.
+	#[inline]
+	pub fn trigger_shutdown(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		self.shared().trigger(reason)
+	}
+
+	/// Trigger the shutdown if it was not triggered yet.
+	///
+	/// This is the same as [`Self::trigger_shutdown()`],
+	/// except that it does not create an error with both shutdown reasons if the shutdown was already started.
+	/// Use it instead of `shutdown.trigger_shutdown(reason).ok()`.
+	///
+	/// Returns `true` if this call triggered the shutdown, and `false` if the shutdown was already started.
+	#[inline]
+	pub fn trigger_shutdown_idempotent(&self, reason: T) -> bool {
+		self.shared().shutdown_idempotent(reason)
+	}
+
+	/// Trigger the shutdown, and force it to complete after a deadline.
+	///
+	/// This is the same as [`Self::trigger_shutdown()`],
+	/// except that the shutdown is considered complete when the deadline expires,
+	/// even if there are still [`DelayShutdownTokens`][DelayShutdownToken] alive.
+	/// All [`ShutdownComplete`] futures will resolve at that point.
+	///
+	/// This prevents stuck clean-up code from keeping your process alive forever.
+	///
+	/// The deadline is enforced by a background thread, so this works regardless of the async runtime you use.
+	///
+	/// If the shutdown was already started, this function returns an error and the deadline is not applied.
+	#[cfg(not(target_family = "wasm"))]
+	pub fn trigger_shutdown_with_deadline(&self, reason: T, deadline: Duration) -> Result<(), ShutdownAlreadyStarted<T>>
+	where
+		T: Send + 'static,
+	{
+		// Apply the deadline to the current generation only, even if the manager is reset in the meantime.
+		let inner = self.shared();
+		inner.shutdown(reason)?;
+		spawn_deadline(Arc::downgrade(&inner), deadline);
+		Ok(())
+	}
+
+	/// Trigger the drain.
+	///
+	/// The drain is an event that happens before the shutdown.
+	/// It can be used to stop accepting new work, like deregistering from a load balancer or closing the listening sockets,
+	/// while work that is already in progress is not cancelled yet.
+	/// Futures wrapped with [`Self::wrap_cancel_on_drain()`] are cancelled when the drain is triggered.
+	///
+	/// Triggering the drain does not trigger the shutdown.
+	/// However, triggering the shutdown also triggers the drain with the same reason if it was not triggered yet,
+	/// so the drain always happens before the shutdown.
+	///
+	/// If the drain was already triggered, this function returns an error.
+	#[inline]
+	pub fn trigger_drain(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		self.shared().drain(reason)
+	}
+
+	/// Trigger the forced shutdown.
+	///
+	/// This can be used to implement a two-step shutdown:
+	/// first trigger a regular shutdown to let tasks finish gracefully,
+	/// and then trigger the forced shutdown to abort everything that is still running,
+	/// for example because an operator asked for it.
+	///
+	/// Futures wrapped with [`Self::wrap_cancel_forced()`] are cancelled when the forced shutdown is triggered.
+	/// If the regular shutdown was not triggered yet, it is triggered with the same reason.
+	///
+	/// Note that triggering the forced shutdown does not force the shutdown to complete.
+	/// Use [`Self::trigger_shutdown_with_deadline()`] for that.
+	///
+	/// If the forced shutdown was already triggered, this function returns an error.
+	#[inline]
+	pub fn trigger_forced_shutdown(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		self.shared().shutdown_forced(reason)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with `Err(shutdown_reason)` if the shutdown is triggered,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	#[inline]
+	pub fn wrap_cancel<F: Future>(&self, future: F) -> WrapCancel<T, F> {
+		self.wait_shutdown_triggered().wrap_cancel(future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the regular shutdown is triggered.
+	///
+	/// This is identical to [`Self::wrap_cancel()`].
+	/// It exists to make the intent clear in code that also uses [`Self::wrap_cancel_forced()`].
+	#[inline]
+	pub fn wrap_cancel_graceful<F: Future>(&self, future: F) -> WrapCancel<T, F> {
+		self.wrap_cancel(future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the drain is triggered.
+	///
+	/// The returned future completes with `Err(reason)` if the drain is triggered with [`Self::trigger_drain()`] or by the shutdown,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	#[inline]
+	pub fn wrap_cancel_on_drain<F: Future>(&self, future: F) -> WrapCancel<T, F> {
+		self.wait_drain_triggered().wrap_cancel(future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) only when the forced shutdown is triggered.
+	///
+	/// The returned future completes with `Err(reason)` if the forced shutdown is triggered with [`Self::trigger_forced_shutdown()`],
+	/// and with `Ok(x)` if the wrapped future completes first.
+	/// A regular shutdown does not cancel the future.
+	#[inline]
+	pub fn wrap_cancel_forced<F: Future>(&self, future: F) -> WrapCancel<T, F> {
+		self.wait_forced_shutdown_triggered().wrap_cancel(future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered, with a custom error.
+	///
+	/// The returned future completes with `Err(map_err(shutdown_reason))` if the shutdown is triggered,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	///
+	/// This allows you to convert the shutdown reason into your own error type,
+	/// so you can directly use the `?` operator on the result:
+	/// ```
+	/// # async fn run() -> Result<(), String> {
+	/// # let shutdown = async_shutdown::ShutdownManager::<i32>::new();
+	/// # let future = async { () };
+	/// shutdown.wrap_cancel_map_err(future, |code| format!("shutdown with exit code {code}")).await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[inline]
+	pub fn wrap_cancel_map_err<F: Future, M: FnOnce(T) -> E, E>(&self, future: F, map_err: M) -> WrapCancelMapErr<T, F, M> {
+		self.wait_shutdown_triggered().wrap_cancel_map_err(future, map_err)
+	}
+
+	/// Wrap a fallible future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with `Err(E::from(shutdown_reason))` if the shutdown is triggered,
+	/// and with the output of the wrapped future if it completes first.
+	///
+	/// This avoids a nested `Result` when the shutdown reason can be converted into the error type of the future:
+	/// ```
+	/// # #[derive(Debug)]
+	/// # enum Error { Shutdown(i32), Io(std::io::Error) }
+	/// # impl From<i32> for Error { fn from(code: i32) -> Self { Error::Shutdown(code) } }
+	/// # async fn handle_request() -> Result<(), Error> { Ok(()) }
+	/// # async fn run() -> Result<(), Error> {
+	/// # let shutdown = async_shutdown::ShutdownManager::<i32>::new();
+	/// shutdown.try_wrap_cancel(handle_request()).await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[inline]
+	pub fn try_wrap_cancel<F, V, E>(&self, future: F) -> TryWrapCancel<T, F>
+	where
+		F: Future<Output = Result<V, E>>,
+		E: From<T>,
+	{
+		self.wait_shutdown_triggered().try_wrap_cancel(future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered, with a fallback value.
+	///
+	/// The returned future completes with `default` if the shutdown is triggered,
+	/// and with the output of the wrapped future if it completes first.
+	#[inline]
+	pub fn wrap_cancel_or<F: Future>(&self, default: F::Output, future: F) -> WrapCancelOr<T, F> {
+		self.wait_shutdown_triggered().wrap_cancel_or(default, future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered, with a computed fallback value.
+	///
+	/// The returned future completes with `default(shutdown_reason)` if the shutdown is triggered,
+	/// and with the output of the wrapped future if it completes first.
+	#[inline]
+	pub fn wrap_cancel_or_else<F: Future, D: FnOnce(T) -> F::Output>(&self, default: D, future: F) -> WrapCancelOrElse<T, F, D> {
+		self.wait_shutdown_triggered().wrap_cancel_or_else(default, future)
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered, and then run an async clean-up function.
+	///
+	/// When the shutdown is triggered, the wrapped future is dropped and the clean-up function is called with the shutdown reason.
+	/// The returned future then completes with `Err(shutdown_reason)` once the clean-up future completes.
+	/// If the wrapped future completes first, the returned future completes with `Ok(x)` and the clean-up function is not called.
+	///
+	/// The returned future holds a delay token,
+	/// so the shutdown will not complete until the wrapped future or the clean-up future has completed:
+	/// ```
+	/// # async fn handle_connection() {}
+	/// # async fn send_goodbye(_reason: &str) {}
+	/// # async fn run() {
+	/// # let shutdown = async_shutdown::ShutdownManager::new();
+	/// let result = shutdown.wrap_cancel_with_cleanup(handle_connection(), |reason: &'static str| async move {
+	///     send_goodbye(reason).await;
+	/// }).unwrap().await;
+	/// # }
+	/// ```
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_cancel_with_cleanup<F, C, CF>(&self, future: F, cleanup: C) -> Result<WrapCancelWithCleanup<T, F, C, CF>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		C: FnOnce(T) -> CF,
+		CF: Future<Output = ()>,
+	{
+		self.wait_shutdown_triggered().wrap_cancel_with_cleanup(future, cleanup)
+	}
+
+	/// Run a future until the shutdown is triggered, and then run a clean-up future.
+	///
+	/// When the shutdown is triggered, the main future is dropped and the clean-up function is called with the shutdown reason.
+	/// The returned future then completes with a [`RunSummary`] that holds the shutdown reason and the output of the clean-up future.
+	/// If the main future completes first, the clean-up function is not called.
+	///
+	/// The returned future holds a delay token,
+	/// so the shutdown will not complete until the main future or the clean-up future has completed:
+	/// ```
+	/// # async fn serve() {}
+	/// # async fn flush_buffers() -> usize { 0 }
+	/// # async fn run() {
+	/// # let shutdown = async_shutdown::ShutdownManager::new();
+	/// use async_shutdown::RunSummary;
+	///
+	/// match shutdown.run_then_cleanup(serve(), |_reason: ()| flush_buffers()).unwrap().await {
+	///     RunSummary::Completed(()) => println!("server stopped by itself"),
+	///     RunSummary::CleanedUp { cleanup: flushed, .. } => println!("flushed {flushed} buffers"),
+	/// }
+	/// # }
+	/// ```
+	///
+	/// This is like [`Self::wrap_cancel_with_cleanup()`], except that the clean-up future can produce a value.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn run_then_cleanup<F, C, CF>(&self, future: F, cleanup: C) -> Result<RunThenCleanup<T, F, C, CF>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		C: FnOnce(T) -> CF,
+		CF: Future,
+	{
+		self.wait_shutdown_triggered().run_then_cleanup(future, cleanup)
+	}
+
+	/// Wrap a future so that it is cancelled when a grace period expires after the shutdown is triggered.
+	///
+	/// When the shutdown is triggered, the wrapped future is allowed to keep running for the duration of the grace period.
+	/// If it has not completed by then, it is dropped.
+	/// The returned future completes with a [`GraceOutcome`] that tells you which of these happened.
+	///
+	/// The returned future delays shutdown completion until it completes.
+	/// The grace period starts when the first wrapped future with the same grace period notices the shutdown.
+	/// All of them share one timer, so they are cancelled at the same time.
+	/// The grace period is enforced by a background thread, so this works regardless of the async runtime you use.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	#[track_caller]
+	pub fn wrap_cancel_with_grace<F: Future>(&self, grace: Duration, future: F) -> Result<WrapCancelWithGrace<T, F>, ShutdownAlreadyCompleted<T>> {
+		self.wait_shutdown_triggered().wrap_cancel_with_grace(grace, future)
+	}
+
+	/// Wrap a future to cause a shutdown when the future completes or when it is dropped.
+	#[inline]
+	pub fn wrap_trigger_shutdown<F: Future>(&self, shutdown_reason: T, future: F) -> WrapTriggerShutdown<T, F> {
+		self.trigger_shutdown_token(shutdown_reason).wrap_future(future)
+	}
+
+	/// Wrap a stream to cause a shutdown when the stream ends or when it is dropped.
+	///
+	/// This can be used to treat the end of an event source as a fatal condition that should bring down the service.
+	#[cfg(feature = "stream")]
+	#[inline]
+	pub fn wrap_trigger_shutdown_stream<S: futures_core::Stream>(&self, shutdown_reason: T, stream: S) -> WrapTriggerShutdownStream<T, S> {
+		self.trigger_shutdown_token(shutdown_reason).wrap_stream(stream)
+	}
+
+	/// Process the items of a stream until the shutdown is triggered.
+	///
+	/// See [`ShutdownSignal::for_each_until_shutdown()`] for more details.
+	#[cfg(feature = "stream")]
+	#[inline]
+	pub fn for_each_until_shutdown<S, F, Fut>(&self, stream: S, function: F) -> ForEachUntilShutdown<T, S, F, Fut>
+	where
+		S: futures_core::Stream,
+		F: FnMut(S::Item) -> Fut,
+		Fut: Future<Output = ()>,
+	{
+		self.wait_shutdown_triggered().for_each_until_shutdown(stream, function)
+	}
+
+	/// Wrap a sink so that it is flushed and closed when the shutdown is triggered.
+	///
+	/// The returned [`WrapSink`] can be used as a normal sink.
+	/// It can also be awaited: it then waits for the shutdown to be triggered, and flushes and closes the wrapped sink.
+	/// This makes sure that buffered items are not lost when the task that feeds the sink is cancelled:
+	/// ```
+	/// # use futures::{SinkExt, StreamExt};
+	/// # async fn run() -> Result<(), futures::channel::mpsc::SendError> {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// # let (messages, _) = futures::channel::mpsc::channel::<u32>(16);
+	/// # let mut incoming = futures::stream::iter([1, 2, 3]);
+	/// let mut messages = shutdown.wrap_sink(messages.buffer(16)).unwrap();
+	/// while let Ok(Some(message)) = shutdown.wrap_cancel(incoming.next()).await {
+	///     messages.feed(message).await?;
+	/// }
+	/// messages.await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// The wrapper delays shutdown completion until the sink has been closed, or until the wrapper is dropped.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[cfg(feature = "sink")]
+	#[inline]
+	#[track_caller]
+	pub fn wrap_sink<S: futures_sink::Sink<Item>, Item>(&self, sink: S) -> Result<WrapSink<T, S, Item>, ShutdownAlreadyCompleted<T>> {
+		Ok(WrapSink {
+			shutdown_signal: Box::pin(self.wait_shutdown_triggered()),
+			delay_token: Some(self.delay_shutdown_token()?),
+			reason: None,
+			sink,
+			item: std::marker::PhantomData,
+		})
+	}
+
+	/// Wrap an I/O object so that it shuts down gracefully when the shutdown is triggered.
+	///
+	/// Once the shutdown is triggered, the write side of the I/O object is flushed and shut down,
+	/// reads return end-of-file and writes fail with [`std::io::ErrorKind::BrokenPipe`].
+	/// This lets protocol handlers see a normal end-of-file and finish cleanly,
+	/// while the peer receives all data that was written before the shutdown:
+	/// ```
+	/// # #[cfg(feature = "tokio-io")]
+	/// # async fn handle_client(shutdown: async_shutdown::ShutdownManager<()>, stream: tokio::net::TcpStream) -> std::io::Result<()> {
+	/// let mut stream = shutdown.wrap_io(stream);
+	/// let (mut read, mut write) = tokio::io::split(&mut stream);
+	/// tokio::io::copy(&mut read, &mut write).await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	#[cfg(any(feature = "tokio-io", feature = "futures-io"))]
+	#[inline]
+	pub fn wrap_io<IO>(&self, io: IO) -> WrapIo<T, IO> {
+		WrapIo {
+			state: wrap_io::WrapIoState::new(self.wait_shutdown_triggered()),
+			io,
+		}
+	}
+
+	/// Create a server future with a graceful shutdown signal, and delay shutdown completion until the server is done.
+	///
+	/// The `build` function receives a shutdown signal that completes with `()`,
+	/// which can be passed directly to the `with_graceful_shutdown()` functions of servers like `hyper`, `axum` and `tonic`.
+	/// The future returned by `build` is wrapped so that the shutdown does not complete until the server has finished draining its connections:
+	/// ```
+	/// # async fn run(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// # struct Server;
+	/// # impl Server {
+	/// #     async fn with_graceful_shutdown(self, signal: impl std::future::Future<Output = ()>) -> std::io::Result<()> {
+	/// #         signal.await;
+	/// #         Ok(())
+	/// #     }
+	/// # }
+	/// # let server = Server;
+	/// let server = shutdown.wrap_graceful_server(|signal| server.with_graceful_shutdown(signal)).unwrap();
+	/// if let Err(e) = server.await {
+	///     eprintln!("Server failed: {e}");
+	/// }
+	/// # }
+	/// ```
+	///
+	/// If the shutdown has already completed, this function returns an error and `build` is not called.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_graceful_server<F, B>(&self, build: B) -> Result<WrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		B: FnOnce(IgnoreShutdownReason<T>) -> F,
+	{
+		let delay_token = self.delay_shutdown_token()?;
+		Ok(delay_token.wrap_future(build(self.wait_shutdown_triggered().ignore_reason())))
+	}
+
+	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped.
+	///
+	/// The returned future transparently completes with the value of the wrapped future.
+	/// However, the shutdown will not be considered complete until the wrapped future completes or is dropped.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_delay_shutdown<F: Future>(&self, future: F) -> Result<WrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(self.delay_shutdown_token()?.wrap_future(future))
+	}
+
+	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped, if the shutdown has not completed yet.
+	///
+	/// This is the same as [`Self::wrap_delay_shutdown()`],
+	/// except that the future is still returned if the shutdown has already completed.
+	/// In that case, the returned future simply runs the wrapped future without delaying anything.
+	/// You can check with [`WrapDelayShutdown::delay_token()`] if the shutdown is being delayed.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_delay_shutdown_or_run<F: Future>(&self, future: F) -> WrapDelayShutdown<T, F> {
+		WrapDelayShutdown {
+			delay_token: self.delay_shutdown_token().ok(),
+			future,
+		}
+	}
+
+	/// Get a token that delays shutdown completion as long as it exists.
+	///
+	/// The manager keeps track of all the tokens it hands out.
+	/// The tokens can be cloned and sent to different threads and tasks.
+	/// All tokens (including the clones) must be dropped before the shutdown is considered to be complete.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	///
+	/// If you want to delay the shutdown until a future completes,
+	/// consider using [`Self::wrap_delay_shutdown()`] instead.
+	///
+	/// The returned token delays the completion of the first shutdown phase (phase 0).
+	/// See [`Self::delay_shutdown_token_in_phase()`] if you need to order the clean-up of different components.
+	#[inline]
+	#[track_caller]
+	pub fn delay_shutdown_token(&self) -> Result<DelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
+		self.delay_shutdown_token_in_phase(0)
+	}
+
+	/// Get a token that delays the completion of a specific shutdown phase as long as it exists.
+	///
+	/// Shutdown phases allow you to order the clean-up of different components.
+	/// Phase `N` is complete when the shutdown has been triggered,
+	/// and all delay tokens for phase `N` and all earlier phases have been dropped.
+	/// The shutdown as a whole is complete when all phases are complete.
+	///
+	/// Tasks holding a token for a later phase should wait for the previous phase to complete
+	/// with [`Self::wait_phase_complete()`] before they start their own clean-up.
+	/// For example, you could drain all connections in phase 0, close the database pool in phase 1,
+	/// and flush the metrics exporter in phase 2:
+	/// ```
+	/// # async fn close_database_pool() {}
+	/// # async fn run() {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// let token = shutdown.delay_shutdown_token_in_phase(1).unwrap();
+	/// tokio::spawn({
+	///     let shutdown = shutdown.clone();
+	///     async move {
+	///         // Wait for all connections to be drained.
+	///         shutdown.wait_phase_complete(0).await;
+	///         close_database_pool().await;
+	///         drop(token);
+	///     }
+	/// });
+	/// # }
+	/// ```
+	///
+	/// If the phase has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn delay_shutdown_token_in_phase(&self, phase: usize) -> Result<DelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
+		DelayShutdownToken::new(self.inner(), phase)
+	}
+
+	/// Get a lease that delays shutdown completion until it is dropped, or until it expires.
+	///
+	/// The lease expires if it is not renewed with [`DelayShutdownLease::renew()`] within the given duration.
+	/// After that, it no longer delays the shutdown.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	#[track_caller]
+	pub fn delay_shutdown_lease(&self, duration: Duration) -> Result<DelayShutdownLease<T>, ShutdownAlreadyCompleted<T>>
+	where
+		T: Send + 'static,
+	{
+		Ok(DelayShutdownLease::new(self.delay_shutdown_token()?, duration))
+	}
+
+	/// Wrap a future to delay the completion of a specific shutdown phase until the wrapped future completes or until it is dropped.
+	///
+	/// See [`Self::delay_shutdown_token_in_phase()`] for more information about shutdown phases.
+	///
+	/// If the phase has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_delay_shutdown_in_phase<F: Future>(&self, phase: usize, future: F) -> Result<WrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(self.delay_shutdown_token_in_phase(phase)?.wrap_future(future))
+	}
+
+	/// Create a named group of delay tokens.
+	///
+	/// Tokens created from the group delay the shutdown like any other delay token,
+	/// but they can also be counted and awaited separately.
+	/// See [`DelayTokenGroup`] for more information.
+	#[inline]
+	pub fn token_group(&self, name: impl Into<String>) -> DelayTokenGroup<T> {
+		DelayTokenGroup::new(self.clone(), name.into())
+	}
+
+	/// Wrap a clean-up future with a priority level to delay the completion of the shutdown.
+	///
+	/// Priority `0` is the highest priority.
+	/// A priority level only counts as complete when all wrapped futures of that level and all higher priority levels have completed.
+	/// Use [`Self::wait_priority_complete()`] to wait for a priority level to complete.
+	/// For example, a database could flush its write-ahead log with priority `0`,
+	/// and let the network layer wait for priority `0` before it reports that it is done with priority `1`.
+	///
+	/// Priority levels are the same as shutdown phases, so this is equivalent to [`Self::wrap_delay_shutdown_in_phase()`].
+	///
+	/// If the priority level has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_delay_shutdown_with_priority<F: Future>(&self, priority: usize, future: F) -> Result<WrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>> {
+		self.wrap_delay_shutdown_in_phase(priority, future)
+	}
+
+	/// Register a callback to run synchronously when the shutdown is triggered.
+	///
+	/// The callback is called exactly once with the shutdown reason, on the thread that triggers the shutdown.
+	/// It runs without holding the internal lock, so it is free to use the shutdown manager.
+	/// If the shutdown has already been triggered, the callback runs immediately.
+	///
+	/// This is meant for lightweight work that must happen the instant the shutdown begins,
+	/// like flipping a readiness flag or writing a log line:
+	/// ```
+	/// # use std::sync::Arc;
+	/// # use std::sync::atomic::{AtomicBool, Ordering};
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// let ready = Arc::new(AtomicBool::new(true));
+	/// shutdown.on_trigger({
+	///     let ready = ready.clone();
+	///     move |_reason| ready.store(false, Ordering::Relaxed)
+	/// });
+	/// ```
+	///
+	/// The callback only applies to the current generation: it is not kept when the manager is [reset][Self::reset].
 	#[inline]
-	pub fn wait_shutdown_triggered(&self) -> ShutdownSignal<T> {
-		ShutdownSignal {
-			inner: self.inner.clone(),
-			waker_token: None,
-		}
+	pub fn on_trigger<F: FnOnce(&T) + Send + 'static>(&self, callback: F) {
+		self.shared().on_trigger(Box::new(callback))
 	}
 
-	/// Asynchronously wait for the shutdown to complete.
+	/// Register a callback to run synchronously when the shutdown completes.
 	///
-	/// This returns a future that completes when the shutdown is complete.
-	/// The future can be cloned and sent to other threads or tasks freely.
+	/// The callback is called exactly once with the shutdown reason, on the thread that completes the shutdown.
+	/// This is usually the thread that drops the last delay token.
+	/// It runs without holding the internal lock, so it is free to use the shutdown manager.
+	/// If the shutdown has already completed, the callback runs immediately.
 	///
-	/// The shutdown is complete when all [`DelayShutdownToken`] are dropped
-	/// and all [`WrapDelayShutdown`] futures have completed or are dropped.
+	/// The callback only applies to the current generation: it is not kept when the manager is [reset][Self::reset].
 	#[inline]
-	pub fn wait_shutdown_complete(&self) -> ShutdownComplete<T> {
-		ShutdownComplete {
-			inner: self.inner.clone(),
-			waker_token: None,
-		}
+	pub fn on_complete<F: FnOnce(&T) + Send + 'static>(&self, callback: F) {
+		self.shared().on_complete(Box::new(callback))
 	}
 
-	/// Trigger the shutdown.
+	/// Propagate the shutdown of this manager to another shutdown manager.
 	///
-	/// This will cause all [`ShutdownSignal`] and [`WrapCancel`] futures associated with this shutdown manager to be resolved.
+	/// When the shutdown of this manager is triggered, the shutdown of `other` is triggered with `map_reason(reason)`.
+	/// If the shutdown of this manager has already been triggered, the shutdown of `other` is triggered immediately.
+	/// If the shutdown of `other` was already triggered, it keeps its original reason.
 	///
-	/// The shutdown will not be considered complete until all [`DelayShutdownTokens`][DelayShutdownToken] are dropped.
+	/// This allows you to chain shutdown managers of different libraries that each insist on owning their own manager.
+	/// Use [`Self::propagate_to_and_wait()`] if the shutdown of this manager should also wait for `other` to complete.
 	///
-	/// If the shutdown was already started, this function returns an error.
-	#[inline]
-	pub fn trigger_shutdown(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
-		self.inner.lock().unwrap().shutdown(reason)
+	/// This only applies to the current generation of this manager: it is not kept when the manager is [reset][Self::reset].
+	pub fn propagate_to<U, F>(&self, other: &ShutdownManager<U>, map_reason: F)
+	where
+		U: Clone + Send + 'static,
+		F: FnOnce(T) -> U + Send + 'static,
+	{
+		let other = other.clone();
+		self.on_trigger(move |reason| {
+			other.trigger_shutdown_idempotent(map_reason(reason.clone()));
+		});
 	}
 
-	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered.
+	/// Propagate the shutdown of this manager to another shutdown manager, and wait for it to complete.
 	///
-	/// The returned future completes with `Err(shutdown_reason)` if the shutdown is triggered,
-	/// and with `Ok(x)` if the wrapped future completes first.
-	#[inline]
-	pub fn wrap_cancel<F: Future>(&self, future: F) -> WrapCancel<T, F> {
-		self.wait_shutdown_triggered().wrap_cancel(future)
+	/// This is the same as [`Self::propagate_to()`],
+	/// except that the shutdown of this manager does not complete until the shutdown of `other` has completed.
+	/// The waiting is done by a shutdown hook, so see [`Self::on_shutdown()`] for the details.
+	///
+	/// If the shutdown of this manager has already completed, this function returns an error.
+	pub fn propagate_to_and_wait<U, F>(&self, other: &ShutdownManager<U>, map_reason: F) -> Result<(), ShutdownAlreadyCompleted<T>>
+	where
+		T: 'static,
+		U: Clone + Send + 'static,
+		F: FnOnce(T) -> U + Send + 'static,
+	{
+		let completed = other.wait_shutdown_complete();
+		self.on_shutdown(move |_reason| async move {
+			completed.await;
+		})?;
+		self.propagate_to(other, map_reason);
+		Ok(())
 	}
 
-	/// Wrap a future to cause a shutdown when the future completes or when it is dropped.
-	#[inline]
-	pub fn wrap_trigger_shutdown<F: Future>(&self, shutdown_reason: T, future: F) -> WrapTriggerShutdown<T, F> {
-		self.trigger_shutdown_token(shutdown_reason).wrap_future(future)
+	/// Register an async hook to run when the shutdown is triggered.
+	///
+	/// When the shutdown is triggered, the hook is called with the shutdown reason,
+	/// and the returned future is run to completion.
+	/// The shutdown (or more precisely: the first shutdown phase) will not complete until the future has finished.
+	/// This allows components to run a small piece of async clean-up code without spawning a task for it:
+	/// ```
+	/// # async fn flush_cache() {}
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// shutdown.on_shutdown(|_reason| async move {
+	///     flush_cache().await;
+	/// }).unwrap();
+	/// ```
+	///
+	/// The hooks are not spawned on an executor.
+	/// Instead, they are run by the [`ShutdownComplete`] and [`ShutdownPhaseComplete`] futures while those are being polled.
+	/// This means that you must wait for the shutdown to complete somewhere in your application for the hooks to run,
+	/// for example with [`Self::wait_shutdown_complete()`] in your `main` function.
+	///
+	/// If the shutdown has been triggered already, the hook will be run as soon as possible.
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn on_shutdown<F, Fut>(&self, hook: F) -> Result<(), ShutdownAlreadyCompleted<T>>
+	where
+		T: 'static,
+		F: FnOnce(T) -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		let inner = self.shared();
+		let mut inner = inner.lock();
+		if let Some(reason) = inner.phase_completed(0) {
+			return Err(ShutdownAlreadyCompleted::new(reason));
+		}
+
+		inner.increase_delay_count(0);
+		inner.hooks.add(Box::new(move |reason| Box::pin(hook(reason))));
+		if inner.shutdown_reason.is_some() {
+			// Make sure someone picks up the new hook.
+			inner.wake_hook_drivers();
+		}
+		Ok(())
 	}
 
-	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped.
+	/// Register an async hook to run when the shutdown is triggered, with a timeout.
 	///
-	/// The returned future transparently completes with the value of the wrapped future.
-	/// However, the shutdown will not be considered complete until the wrapped future completes or is dropped.
+	/// This is the same as [`Self::on_shutdown()`],
+	/// except that the future returned by the hook is dropped if it does not finish within `timeout` after it was started.
+	/// This way, a single stuck hook can not hold up the whole shutdown.
+	/// Hooks that timed out are counted in [`ShutdownMetrics::timed_out_hooks`].
 	///
 	/// If the shutdown has already completed, this function returns an error.
-	#[inline]
-	pub fn wrap_delay_shutdown<F: Future>(&self, future: F) -> Result<WrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>> {
-		Ok(self.delay_shutdown_token()?.wrap_future(future))
+	#[cfg(not(target_family = "wasm"))]
+	pub fn on_shutdown_with_timeout<F, Fut>(&self, timeout: Duration, hook: F) -> Result<(), ShutdownAlreadyCompleted<T>>
+	where
+		T: Send + 'static,
+		F: FnOnce(T) -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		// A weak reference, so that the hook does not keep the state alive if the shutdown is never triggered.
+		let clock = self.shared().clock();
+		let inner = Arc::downgrade(&self.inner());
+		self.on_shutdown(move |reason| {
+			let future = hook(reason);
+			async move {
+				if timer::timeout(&*clock, timeout, future).await.is_none() {
+					if let Some(inner) = inner.upgrade() {
+						inner.record_timed_out();
+					}
+				}
+			}
+		})
 	}
 
-	/// Get a token that delays shutdown completion as long as it exists.
+	/// Register an async hook to run exactly once when the shutdown completes.
 	///
-	/// The manager keeps track of all the tokens it hands out.
-	/// The tokens can be cloned and sent to different threads and tasks.
-	/// All tokens (including the clones) must be dropped before the shutdown is considered to be complete.
+	/// This gives resources that are owned by a struct instead of a task a place to run their async teardown code,
+	/// like closing a connection pool after all tasks that use it have finished:
+	/// ```
+	/// # struct Pool;
+	/// # impl Pool { async fn close(self) {} }
+	/// # let pool = Pool;
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// shutdown.register_async_drop(move |_reason| async move {
+	///     pool.close().await;
+	/// }).unwrap();
+	/// ```
+	///
+	/// The hooks are started when the shutdown completes, so after all delay tokens have been dropped, or when the completion is forced.
+	/// Like the hooks of [`Self::on_shutdown()`], they are not spawned on an executor.
+	/// Instead, they are run by the [`ShutdownComplete`] futures, and no [`ShutdownComplete`] future resolves until all of them have finished.
+	/// This means that you must wait for the shutdown to complete somewhere in your application for the hooks to run,
+	/// for example with [`Self::wait_shutdown_complete()`] in your `main` function.
+	/// Note that [`Self::is_shutdown_completed()`] already returns `true` while the hooks are running.
+	///
+	/// The [flush hooks][Self::add_flush_hook] are run after all async drop hooks have finished.
 	///
 	/// If the shutdown has already completed, this function returns an error.
+	pub fn register_async_drop<F, Fut>(&self, hook: F) -> Result<(), ShutdownAlreadyCompleted<T>>
+	where
+		T: 'static,
+		F: FnOnce(T) -> Fut + Send + 'static,
+		Fut: Future<Output = ()> + Send + 'static,
+	{
+		let inner = self.shared();
+		let mut inner = inner.lock();
+		if let Some(reason) = inner.completed_reason() {
+			return Err(ShutdownAlreadyCompleted::new(reason));
+		}
+		inner.async_drops.add(Box::new(move |reason| Box::pin(hook(reason))));
+		Ok(())
+	}
+
+	/// Register a hook to run as the very last step before the shutdown completes.
 	///
-	/// If you want to delay the shutdown until a future completes,
-	/// consider using [`Self::wrap_delay_shutdown()`] instead.
+	/// Flush hooks are run exactly once, by the first [`ShutdownComplete`] future that sees that the shutdown has completed.
+	/// No [`ShutdownComplete`] future will resolve until all flush hooks have finished running.
+	///
+	/// This can be used to make sure that the final log lines actually reach the disk before the process exits,
+	/// for example by flushing stdout or by dropping the guard of a background log writer:
+	/// ```
+	/// # use std::io::Write;
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// shutdown.add_flush_hook(|| {
+	///     std::io::stdout().flush().ok();
+	/// }).unwrap();
+	/// ```
+	///
+	/// If the flush hooks have already been run, this function returns an error.
 	#[inline]
-	pub fn delay_shutdown_token(&self) -> Result<DelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
-		let mut inner = self.inner.lock().unwrap();
-		// Shutdown already completed, can't delay completion anymore.
-		if inner.delay_tokens == 0 {
-			if let Some(reason) = &inner.shutdown_reason {
-				return Err(ShutdownAlreadyCompleted::new(reason.clone()));
-			}
+	pub fn add_flush_hook<F: FnOnce() + Send + 'static>(&self, hook: F) -> Result<(), ShutdownAlreadyCompleted<T>> {
+		let inner = self.shared();
+		let mut inner = inner.lock();
+		if inner.flush_hooks.add(Box::new(hook)).is_err() {
+			// The hooks only start running after the shutdown completed, so the reason must be set.
+			let reason = inner.shutdown_reason.clone().unwrap();
+			return Err(ShutdownAlreadyCompleted::new(reason));
 		}
-
-		inner.increase_delay_count();
-		Ok(DelayShutdownToken {
-			inner: self.inner.clone(),
-		})
+		Ok(())
 	}
 
 	/// Get a token that triggers a shutdown when dropped.
@@ -337,15 +1934,17 @@ impl<T: Clone> ShutdownManager<T> {
 	///
 	/// You can also use [`Self::wrap_trigger_shutdown()`] to wrap a future so that a shutdown is triggered
 	/// when the future completes or if it is dropped.
+	/// If the shutdown should only be triggered when all clones are dropped, use [`Self::last_drop_trigger_token()`] instead.
 	#[inline]
 	pub fn trigger_shutdown_token(&self, shutdown_reason: T) -> TriggerShutdownToken<T> {
 		TriggerShutdownToken {
 			shutdown_reason: Arc::new(Mutex::new(Some(shutdown_reason))),
-			inner: self.inner.clone(),
+			inner: self.inner(),
 		}
 	}
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> Default for ShutdownManager<T> {
 	#[inline]
 	fn default() -> Self {
@@ -358,11 +1957,110 @@ impl<T: Clone> Default for ShutdownManager<T> {
 /// The token can be cloned and sent to different threads and tasks freely.
 ///
 /// All clones must be dropped before the shutdown can complete.
+#[cfg(feature = "std")]
 pub struct DelayShutdownToken<T: Clone> {
-	inner: Arc<Mutex<ShutdownManagerInner<T>>>,
+	inner: Arc<Shared<T>>,
+	phase: usize,
+
+	/// The number of clones of this token.
+	///
+	/// All clones together count as a single delay token in the locked state,
+	/// so that cloning and dropping a token does not need to take a lock, unless it is the last clone.
+	clones: Arc<AtomicUsize>,
+
+	/// The token group that this token is counted in, if any.
+	group: Option<Arc<GroupState>>,
+
+	/// The status information of this token, shared by all clones.
+	blocker: Arc<BlockerInfo>,
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> DelayShutdownToken<T> {
+	/// Create a new delay token for a shutdown phase.
+	///
+	/// If the phase has already completed, this function returns an error.
+	#[track_caller]
+	pub(crate) fn new(inner: Arc<Shared<T>>, phase: usize) -> Result<Self, ShutdownAlreadyCompleted<T>> {
+		Self::new_in_group(inner, phase, None)
+	}
+
+	/// Create a new delay token for a shutdown phase that is counted in a token group.
+	///
+	/// If the phase has already completed, this function returns an error.
+	#[track_caller]
+	pub(crate) fn new_in_group(inner: Arc<Shared<T>>, phase: usize, group: Option<Arc<GroupState>>) -> Result<Self, ShutdownAlreadyCompleted<T>> {
+		let mut locked = inner.lock();
+		// Phase already completed, can't delay completion anymore.
+		if let Some(reason) = locked.phase_completed(phase) {
+			return Err(ShutdownAlreadyCompleted::new(reason));
+		}
+
+		locked.increase_delay_count(phase);
+		let blocker = locked.blockers.add(phase, group.as_ref().map(|group| group.name().to_owned()));
+		let total = inner.delay_tokens.fetch_add(1, Ordering::Relaxed) + 1;
+		inner.delay_token_gauge.add(1);
+		locked.resource.delay_tokens(total);
+		drop(locked);
+		if let Some(group) = &group {
+			group.increase();
+		}
+		Ok(Self {
+			inner,
+			phase,
+			clones: Arc::new(AtomicUsize::new(1)),
+			group,
+			blocker,
+		})
+	}
+
+	/// Get the shutdown phase that this token delays.
+	#[inline]
+	pub fn phase(&self) -> usize {
+		self.phase
+	}
+
+	/// Set a status message for this token.
+	///
+	/// The status is shared by all clones of the token,
+	/// and it is reported by [`ShutdownManager::pending_blockers()`] and the [`Debug`][std::fmt::Debug] output of the token.
+	/// This can be used to report the progress of long running clean-up tasks:
+	/// ```
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// let token = shutdown.delay_shutdown_token().unwrap();
+	/// token.set_status("flushed 3/10 segments");
+	/// assert!(shutdown.pending_blockers()[0].status.as_deref() == Some("flushed 3/10 segments"));
+	/// ```
+	#[inline]
+	pub fn set_status(&self, status: impl Into<String>) {
+		self.blocker.set_status(Some(status.into()));
+	}
+
+	/// Clear the status message of this token.
+	#[inline]
+	pub fn clear_status(&self) {
+		self.blocker.set_status(None);
+	}
+
+	/// Get the current status message of this token.
+	#[inline]
+	pub fn status(&self) -> Option<String> {
+		self.blocker.status()
+	}
+
+	/// Create a weak token that does not delay the shutdown, but that can be upgraded to a real token later.
+	///
+	/// This is useful for things like connection pools,
+	/// that want to create delay tokens on demand without blocking shutdown completion while they are idle.
+	#[inline]
+	pub fn downgrade(&self) -> WeakDelayShutdownToken<T> {
+		WeakDelayShutdownToken {
+			inner: self.inner.clone(),
+			phase: self.phase,
+			group: self.group.clone(),
+		}
+	}
+
 	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped.
 	///
 	/// This consumes the token to avoid keeping an unused token around by accident, which would delay shutdown indefinitely.
@@ -385,20 +2083,97 @@ impl<T: Clone> DelayShutdownToken<T> {
 	}
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> Clone for DelayShutdownToken<T> {
 	#[inline]
 	fn clone(&self) -> Self {
-		self.inner.lock().unwrap().increase_delay_count();
+		// We hold a clone ourselves, so the count can not drop to zero concurrently.
+		self.clones.fetch_add(1, Ordering::Relaxed);
+		self.inner.delay_tokens.fetch_add(1, Ordering::Relaxed);
+		self.inner.delay_token_gauge.add(1);
+		if let Some(group) = &self.group {
+			group.increase();
+		}
 		DelayShutdownToken {
 			inner: self.inner.clone(),
+			phase: self.phase,
+			clones: self.clones.clone(),
+			group: self.group.clone(),
+			blocker: self.blocker.clone(),
 		}
 	}
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> Drop for DelayShutdownToken<T> {
 	#[inline]
 	fn drop(&mut self) {
-		self.inner.lock().unwrap().decrease_delay_count();
+		let total = self.inner.delay_tokens.fetch_sub(1, Ordering::Relaxed) - 1;
+		self.inner.delay_token_gauge.sub(1);
+		if let Some(group) = &self.group {
+			group.decrease();
+		}
+		if self.clones.fetch_sub(1, Ordering::Release) == 1 {
+			// Synchronize with the other clones before releasing the delay token, like `Arc` does.
+			std::sync::atomic::fence(Ordering::Acquire);
+			let mut inner = self.inner.lock();
+			inner.blockers.remove(&self.blocker);
+			inner.decrease_delay_count(self.phase);
+			inner.resource.delay_tokens(total);
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> std::fmt::Debug for DelayShutdownToken<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let mut f = f.debug_struct("DelayShutdownToken");
+		f.field("phase", &self.phase)
+			.field("group", &self.group.as_ref().map(|group| group.name()))
+			.field("status", &self.status());
+		#[cfg(feature = "debug-diagnostics")]
+		f.field("location", &self.blocker.location());
+		f.finish()
+	}
+}
+
+/// Weak version of a [`DelayShutdownToken`] that does not delay the shutdown.
+///
+/// Created by [`DelayShutdownToken::downgrade()`].
+/// It can be upgraded to a real delay token for the same shutdown phase, as long as that phase has not completed yet.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct WeakDelayShutdownToken<T: Clone> {
+	inner: Arc<Shared<T>>,
+	phase: usize,
+	group: Option<Arc<GroupState>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> std::fmt::Debug for WeakDelayShutdownToken<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WeakDelayShutdownToken")
+			.field("phase", &self.phase)
+			.field("group", &self.group.as_ref().map(|group| group.name()))
+			.finish()
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> WeakDelayShutdownToken<T> {
+	/// Get the shutdown phase that this token delays when upgraded.
+	#[inline]
+	pub fn phase(&self) -> usize {
+		self.phase
+	}
+
+	/// Upgrade the weak token to a real [`DelayShutdownToken`].
+	///
+	/// If the shutdown phase has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn upgrade(&self) -> Result<DelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
+		DelayShutdownToken::new_in_group(self.inner.clone(), self.phase, self.group.clone())
 	}
 }
 
@@ -407,12 +2182,23 @@ impl<T: Clone> Drop for DelayShutdownToken<T> {
 /// The token can be cloned and sent to different threads and tasks freely.
 /// If *one* of the cloned tokens is dropped, a shutdown is triggered.
 /// Even if the rest of the clones still exist.
+#[cfg(feature = "std")]
 #[derive(Clone)]
 pub struct TriggerShutdownToken<T: Clone> {
 	shutdown_reason: Arc<Mutex<Option<T>>>,
-	inner: Arc<Mutex<ShutdownManagerInner<T>>>,
+	inner: Arc<Shared<T>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> std::fmt::Debug for TriggerShutdownToken<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("TriggerShutdownToken")
+			.field("armed", &self.is_armed())
+			.finish()
+	}
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> TriggerShutdownToken<T> {
 	/// Wrap a future to trigger a shutdown when it completes or is dropped.
 	///
@@ -433,6 +2219,19 @@ impl<T: Clone> TriggerShutdownToken<T> {
 		}
 	}
 
+	/// Wrap a stream to trigger a shutdown when it ends or is dropped.
+	///
+	/// This consumes the token to avoid accidentally dropping the token
+	/// after wrapping a stream and instantly causing a shutdown.
+	#[cfg(feature = "stream")]
+	#[inline]
+	pub fn wrap_stream<S: futures_core::Stream>(self, stream: S) -> WrapTriggerShutdownStream<T, S> {
+		WrapTriggerShutdownStream {
+			trigger_shutdown_token: Some(self),
+			stream,
+		}
+	}
+
 	/// Drop the token without causing a shutdown.
 	///
 	/// This is equivalent to calling [`std::mem::forget()`] on the token.
@@ -440,64 +2239,252 @@ impl<T: Clone> TriggerShutdownToken<T> {
 	pub fn forget(self) {
 		std::mem::forget(self)
 	}
+
+	/// Disarm the token and all of its clones, so that dropping them no longer causes a shutdown.
+	///
+	/// Unlike [`Self::forget()`], this does not leak any memory.
+	/// The token can be armed again with [`Self::rearm()`].
+	///
+	/// Returns the shutdown reason that the token would have used, or [`None`] if it was not armed.
+	#[inline]
+	pub fn disarm(&self) -> Option<T> {
+		self.shutdown_reason.lock_unpoisoned().take()
+	}
+
+	/// Arm the token and all of its clones again, so that dropping any of them causes a shutdown with the given reason.
+	///
+	/// Returns the previous shutdown reason, or [`None`] if the token was not armed.
+	#[inline]
+	pub fn rearm(&self, shutdown_reason: T) -> Option<T> {
+		self.shutdown_reason.lock_unpoisoned().replace(shutdown_reason)
+	}
+
+	/// Change the shutdown reason that is used when the token (or any of its clones) is dropped.
+	///
+	/// This allows a task to create the token as soon as it starts,
+	/// and to update the reason once it knows more about why it stopped.
+	///
+	/// If the token is not armed, the reason is not changed and the token stays disarmed.
+	/// Use [`Self::rearm()`] if you also want to arm the token.
+	///
+	/// Returns `true` if the reason was changed.
+	#[inline]
+	pub fn set_reason(&self, shutdown_reason: T) -> bool {
+		let mut current = self.shutdown_reason.lock_unpoisoned();
+		match current.as_mut() {
+			Some(current) => {
+				*current = shutdown_reason;
+				true
+			},
+			None => false,
+		}
+	}
+
+	/// Check if dropping the token (or any of its clones) causes a shutdown.
+	///
+	/// This returns `false` if the token was disarmed, or if one of the clones was already dropped.
+	#[inline]
+	pub fn is_armed(&self) -> bool {
+		self.shutdown_reason.lock_unpoisoned().is_some()
+	}
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> Drop for TriggerShutdownToken<T> {
 	#[inline]
 	fn drop(&mut self) {
-		let mut inner = self.inner.lock().unwrap();
-		let reason = self.shutdown_reason.lock().unwrap().take();
+		let reason = self.shutdown_reason.lock_unpoisoned().take();
 		if let Some(reason) = reason {
-			inner.shutdown(reason).ok();
+			self.inner.shutdown_idempotent(reason);
 		}
 	}
 }
 
+#[cfg(feature = "std")]
 struct ShutdownManagerInner<T> {
 	/// The shutdown reason.
 	shutdown_reason: Option<T>,
 
 	/// Number of delay tokens in existence.
 	///
+	/// All clones of a [`DelayShutdownToken`] together count as one.
 	/// Must reach 0 before shutdown can complete.
 	delay_tokens: usize,
 
+	/// The number of delay tokens per shutdown phase, and the tasks waiting for a phase to complete.
+	phases: Phases,
+
+	/// The live delay tokens, with their status information.
+	blockers: Blockers,
+
+	/// If true, the shutdown was forced to complete even though there may still be delay tokens.
+	completion_forced: bool,
+
+	/// The moment the shutdown was triggered.
+	triggered_at: Option<Instant>,
+
+	/// The moment the shutdown completed.
+	completed_at: Option<Instant>,
+
 	/// Tasks to wake when a shutdown is triggered.
 	on_shutdown: WakerList,
 
+	/// The reason of the drain.
+	drain_reason: Option<T>,
+
+	/// Tasks to wake when the drain is triggered.
+	on_drain: WakerList,
+
+	/// The reason of the forced shutdown.
+	forced_reason: Option<T>,
+
+	/// Tasks to wake when the forced shutdown is triggered.
+	on_forced_shutdown: WakerList,
+
 	/// Tasks to wake when the shutdown is complete.
 	on_shutdown_complete: WakerList,
+
+	/// Tasks to wake when the number of delay tokens changes after the shutdown was triggered.
+	on_delay_count_change: WakerList,
+
+	/// Async hooks to run when the shutdown is triggered.
+	hooks: Hooks<T>,
+
+	/// Callbacks to run synchronously when the shutdown is triggered.
+	trigger_callbacks: Vec<shared::TriggerCallback<T>>,
+
+	/// Callbacks to run synchronously when the shutdown completes.
+	completion_callbacks: Vec<shared::TriggerCallback<T>>,
+
+	/// Completion callbacks that are ready to run as soon as the lock is released.
+	ready_completion_callbacks: Option<(Vec<shared::TriggerCallback<T>>, T)>,
+
+	/// Async hooks to run when the shutdown completes.
+	async_drops: AsyncDrops<T>,
+
+	/// Hooks to run right before the shutdown completes.
+	flush_hooks: FlushHooks,
+
+	/// What happens when the shutdown is triggered again.
+	repeat_trigger: RepeatTrigger,
+
+	/// The clock that drives timeouts and deadlines.
+	#[cfg(not(target_family = "wasm"))]
+	clock: Arc<dyn Clock>,
+
+	/// The grace timers of [`ShutdownManager::wrap_cancel_with_grace()`], shared by all wrapped futures with the same grace period.
+	#[cfg(not(target_family = "wasm"))]
+	grace_timers: Vec<(Duration, Timer)>,
+
+	/// Instrumentation for `tokio-console`.
+	resource: instrument::Resource,
 }
 
+#[cfg(feature = "std")]
 impl<T: Clone> ShutdownManagerInner<T> {
+	#[track_caller]
 	fn new() -> Self {
 		Self {
 			shutdown_reason: None,
 			delay_tokens: 0,
-			on_shutdown_complete: WakerList::new(),
-			on_shutdown: WakerList::new(),
+			phases: Phases::new(),
+			blockers: Blockers::new(),
+			completion_forced: false,
+			triggered_at: None,
+			completed_at: None,
+			on_shutdown_complete: WakerList::with_gauge(metrics_export::COMPLETION_WAITERS),
+			on_delay_count_change: WakerList::new(),
+			on_shutdown: WakerList::with_gauge(metrics_export::TRIGGER_WAITERS),
+			drain_reason: None,
+			on_drain: WakerList::new(),
+			forced_reason: None,
+			on_forced_shutdown: WakerList::new(),
+			hooks: Hooks::new(),
+			trigger_callbacks: Vec::new(),
+			completion_callbacks: Vec::new(),
+			ready_completion_callbacks: None,
+			async_drops: AsyncDrops::new(),
+			flush_hooks: FlushHooks::new(),
+			repeat_trigger: RepeatTrigger::default(),
+			#[cfg(not(target_family = "wasm"))]
+			clock: Arc::new(SystemClock),
+			#[cfg(not(target_family = "wasm"))]
+			grace_timers: Vec::new(),
+			resource: instrument::Resource::new(),
 		}
 	}
 
-	fn increase_delay_count(&mut self) {
+	fn increase_delay_count(&mut self, phase: usize) {
 		self.delay_tokens += 1;
+		self.phases.increase_delay_count(phase);
+		if self.shutdown_reason.is_some() {
+			self.on_delay_count_change.wake_all();
+		}
 	}
 
-	fn decrease_delay_count(&mut self) {
+	fn decrease_delay_count(&mut self, phase: usize) {
 		self.delay_tokens -= 1;
+		let phase_drained = self.phases.decrease_delay_count(phase);
+		if phase_drained && self.shutdown_reason.is_some() {
+			self.phases.wake_drained();
+		}
+		if self.shutdown_reason.is_some() {
+			self.on_delay_count_change.wake_all();
+		}
 		if self.delay_tokens == 0 {
 			self.notify_shutdown_complete();
 		}
 	}
 
+	/// Get the shutdown reason if the shutdown is complete.
+	fn completed_reason(&self) -> Option<T> {
+		if self.delay_tokens == 0 || self.completion_forced {
+			self.shutdown_reason.clone()
+		} else {
+			None
+		}
+	}
+
+	/// Get the shutdown reason if the given phase is complete.
+	fn phase_completed(&self, phase: usize) -> Option<T> {
+		if self.phases.is_drained(phase) || self.completion_forced {
+			self.shutdown_reason.clone()
+		} else {
+			None
+		}
+	}
+
+	/// Force the shutdown to complete, even if there are still delay tokens.
+	///
+	/// Does nothing if the shutdown has not been triggered or if it already completed.
+	#[cfg(not(target_family = "wasm"))]
+	fn force_shutdown_complete(&mut self) {
+		if self.shutdown_reason.is_none() || self.completed_reason().is_some() {
+			return;
+		}
+		self.completion_forced = true;
+		self.phases.wake_all();
+		self.notify_shutdown_complete();
+	}
+
+	/// Set the shutdown reason and wake the waiting tasks.
+	///
+	/// Use [`Shared::shutdown()`] instead, which also updates the atomic flags.
 	fn shutdown(&mut self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
 		match &self.shutdown_reason {
 			Some(original_reason) => {
 				Err(ShutdownAlreadyStarted::new(original_reason.clone(), reason))
 			},
 			None => {
+				if self.drain_reason.is_none() {
+					self.drain(reason.clone());
+				}
 				self.shutdown_reason = Some(reason);
+				self.triggered_at = now();
+				self.resource.shutdown_triggered();
 				self.on_shutdown.wake_all();
+				self.on_delay_count_change.wake_all();
+				self.phases.wake_drained();
 				if self.delay_tokens == 0 {
 					self.notify_shutdown_complete()
 				}
@@ -506,11 +2493,80 @@ impl<T: Clone> ShutdownManagerInner<T> {
 		}
 	}
 
+	/// Set the drain reason and wake the waiting tasks.
+	fn drain(&mut self, reason: T) {
+		self.drain_reason = Some(reason);
+		self.on_drain.wake_all();
+	}
+
+	/// Get the reason of the event that a [`ShutdownSignal`] waits for, if it has been triggered.
+	fn signal_reason(&self, kind: SignalKind) -> Option<T> {
+		match kind {
+			SignalKind::Drain => self.drain_reason.clone(),
+			SignalKind::Shutdown => self.shutdown_reason.clone(),
+			SignalKind::Forced => self.forced_reason.clone(),
+		}
+	}
+
+	/// Get the list of tasks waiting for the event that a [`ShutdownSignal`] waits for.
+	fn shutdown_waiters(&mut self, kind: SignalKind) -> &mut WakerList {
+		match kind {
+			SignalKind::Drain => &mut self.on_drain,
+			SignalKind::Shutdown => &mut self.on_shutdown,
+			SignalKind::Forced => &mut self.on_forced_shutdown,
+		}
+	}
+
+	/// Wake all tasks that could run the async shutdown hooks.
+	fn wake_hook_drivers(&mut self) {
+		self.on_shutdown_complete.wake_all();
+		self.phases.wake_all();
+	}
+
 	fn notify_shutdown_complete(&mut self) {
+		if let Some(reason) = &self.shutdown_reason {
+			if self.completed_at.is_none() {
+				self.completed_at = now();
+				metrics_export::record_shutdown_duration(self.triggered_at, self.completed_at);
+			}
+			if !self.completion_callbacks.is_empty() {
+				// Run by the lock guard after the lock is released.
+				let callbacks = std::mem::take(&mut self.completion_callbacks);
+				self.ready_completion_callbacks = Some((callbacks, reason.clone()));
+			}
+			self.resource.shutdown_completed();
+		}
 		self.on_shutdown_complete.wake_all();
 	}
 }
 
+/// Force the shutdown of a generation to complete after a deadline, using the clock of the generation.
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+fn spawn_deadline<T: Clone + Send + 'static>(inner: std::sync::Weak<Shared<T>>, deadline: Duration) {
+	let clock = match inner.upgrade() {
+		Some(inner) => inner.clock(),
+		None => return,
+	};
+	clock.call_after(deadline, Box::new(move || {
+		if let Some(inner) = inner.upgrade() {
+			inner.lock().force_shutdown_complete();
+		}
+	}));
+}
+
+/// Get the current time, if the platform supports it.
+///
+/// [`Instant::now()`] panics on `wasm32-unknown-unknown`, so we don't record time stamps there.
+#[cfg(feature = "std")]
+fn now() -> Option<Instant> {
+	if cfg!(target_family = "wasm") {
+		None
+	} else {
+		Some(Instant::now())
+	}
+}
+
 /// Error returned when you try to trigger the shutdown multiple times on the same [`ShutdownManager`].
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -528,10 +2584,11 @@ impl<T> ShutdownAlreadyStarted<T> {
 	}
 }
 
+#[cfg(feature = "std")]
 impl<T: std::fmt::Debug> std::error::Error for ShutdownAlreadyStarted<T> {}
 
-impl<T> std::fmt::Display for ShutdownAlreadyStarted<T> {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<T> core::fmt::Display for ShutdownAlreadyStarted<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
 		write!(f, "shutdown has already started, can not delay shutdown completion")
 	}
 }
@@ -550,10 +2607,85 @@ impl<T> ShutdownAlreadyCompleted<T> {
 	}
 }
 
+#[cfg(feature = "std")]
 impl<T: std::fmt::Debug> std::error::Error for ShutdownAlreadyCompleted<T> {}
 
-impl<T> std::fmt::Display for ShutdownAlreadyCompleted<T> {
-	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<T> core::fmt::Display for ShutdownAlreadyCompleted<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
 		write!(f, "shutdown has already completed, can not delay shutdown completion")
 	}
 }
+
+/// Error returned when the shutdown did not complete before a timeout expired.
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ShutdownTimedOut<T> {
+	/// The shutdown reason, if the shutdown was triggered at all.
+	pub shutdown_reason: Option<T>,
+
+	/// The number of delay tokens that were still alive when the timeout expired.
+	pub delay_tokens: usize,
+
+	/// The delay tokens that were still holding up the shutdown when the timeout expired.
+	///
+	/// See [`ShutdownManager::pending_blockers()`] for more information.
+	pub blockers: Vec<ShutdownBlocker>,
+}
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+impl<T> ShutdownTimedOut<T> {
+	pub(crate) const fn new(shutdown_reason: Option<T>, delay_tokens: usize, blockers: Vec<ShutdownBlocker>) -> Self {
+		Self { shutdown_reason, delay_tokens, blockers }
+	}
+}
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+impl<T: std::fmt::Debug> std::error::Error for ShutdownTimedOut<T> {}
+
+#[cfg(feature = "std")]
+#[cfg(not(target_family = "wasm"))]
+impl<T> std::fmt::Display for ShutdownTimedOut<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		if self.shutdown_reason.is_none() {
+			write!(f, "timed out waiting for shutdown to complete: shutdown was never triggered")
+		} else {
+			write!(f, "timed out waiting for shutdown to complete: still waiting on {} delay token(s)", self.delay_tokens)?;
+			let mut statuses = self.blockers.iter().filter_map(|blocker| blocker.status.as_deref());
+			if let Some(first) = statuses.next() {
+				write!(f, " ({first}")?;
+				for status in statuses {
+					write!(f, ", {status}")?;
+				}
+				write!(f, ")")?;
+			}
+			Ok(())
+		}
+	}
+}
+
+/// Error returned when trying to reset a [`ShutdownManager`] before the shutdown has completed.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ShutdownNotCompleted {}
+
+#[cfg(feature = "std")]
+impl ShutdownNotCompleted {
+	pub(crate) const fn new() -> Self {
+		Self {}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ShutdownNotCompleted {}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for ShutdownNotCompleted {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "shutdown has not completed yet, can not reset the shutdown manager")
+	}
+}
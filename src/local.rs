@@ -0,0 +1,402 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use crate::waker_list::{Waiter, WakerList};
+use crate::{ShutdownAlreadyCompleted, ShutdownAlreadyStarted};
+
+/// Shutdown manager for single threaded code.
+///
+/// This is a cheaper version of [`ShutdownManager`][crate::ShutdownManager] that uses [`Rc`] and [`RefCell`] instead of atomics and a mutex.
+/// The manager and all futures and tokens created from it are `!Send`, so they can only be used on the thread that created them.
+/// This is useful for thread-per-core applications, where each thread can use a local manager on the hot path,
+/// while a regular [`ShutdownManager`][crate::ShutdownManager] coordinates the threads:
+/// ```
+/// # async fn example() {
+/// let global = async_shutdown::ShutdownManager::<i32>::new();
+/// let local = async_shutdown::LocalShutdownManager::new();
+///
+/// // Forward the global shutdown to the local manager.
+/// let reason = global.wait_shutdown_triggered().await;
+/// local.trigger_shutdown(reason).ok();
+/// local.wait_shutdown_complete().await;
+/// # }
+/// ```
+///
+/// The local manager supports the core functionality of the shutdown manager:
+/// triggering the shutdown, waiting for the shutdown, cancelling futures and delaying shutdown completion.
+///
+/// The manager can be cloned freely.
+/// Each clone refers to the same shutdown state.
+pub struct LocalShutdownManager<T: Clone> {
+	inner: Rc<RefCell<LocalInner<T>>>,
+}
+
+impl<T: Clone> std::fmt::Debug for LocalShutdownManager<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let inner = self.inner.borrow();
+		f.debug_struct("LocalShutdownManager")
+			.field("shutdown_triggered", &inner.shutdown_reason.is_some())
+			.field("shutdown_completed", &inner.completed)
+			.field("delay_tokens", &inner.delay_tokens)
+			.finish()
+	}
+}
+
+impl<T: Clone> Clone for LocalShutdownManager<T> {
+	fn clone(&self) -> Self {
+		Self {
+			inner: self.inner.clone(),
+		}
+	}
+}
+
+/// The shared state of a [`LocalShutdownManager`].
+struct LocalInner<T> {
+	/// The shutdown reason, if the shutdown has been triggered.
+	shutdown_reason: Option<T>,
+
+	/// The number of existing delay tokens.
+	delay_tokens: usize,
+
+	/// True if the shutdown has completed.
+	completed: bool,
+
+	/// Tasks to wake when the shutdown is triggered.
+	on_shutdown: WakerList,
+
+	/// Tasks to wake when the shutdown completes.
+	on_shutdown_complete: WakerList,
+}
+
+impl<T: Clone> LocalInner<T> {
+	/// Trigger the shutdown.
+	///
+	/// Returns the wakers that must be woken after releasing the borrow,
+	/// since waking a task may drop futures that need to borrow the state again.
+	fn shutdown(&mut self, reason: T) -> Result<Vec<Waker>, ShutdownAlreadyStarted<T>> {
+		if let Some(original_reason) = &self.shutdown_reason {
+			return Err(ShutdownAlreadyStarted::new(original_reason.clone(), reason));
+		}
+		self.shutdown_reason = Some(reason);
+		let mut wakers = self.on_shutdown.take_wakers();
+		wakers.extend(self.check_complete().into_iter().flatten());
+		Ok(wakers)
+	}
+
+	/// Complete the shutdown if it has been triggered and there are no delay tokens left.
+	///
+	/// Returns the wakers that must be woken after releasing the borrow.
+	fn check_complete(&mut self) -> Option<Vec<Waker>> {
+		if self.shutdown_reason.is_some() && self.delay_tokens == 0 && !self.completed {
+			self.completed = true;
+			Some(self.on_shutdown_complete.take_wakers())
+		} else {
+			None
+		}
+	}
+
+	/// Get the waker list and the reason for the event that a future is waiting for.
+	fn event(&mut self, complete: bool) -> (&mut WakerList, Option<&T>) {
+		if complete {
+			let reason = self.shutdown_reason.as_ref().filter(|_| self.completed);
+			(&mut self.on_shutdown_complete, reason)
+		} else {
+			(&mut self.on_shutdown, self.shutdown_reason.as_ref())
+		}
+	}
+}
+
+impl<T: Clone> LocalShutdownManager<T> {
+	/// Create a new local shutdown manager.
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			inner: Rc::new(RefCell::new(LocalInner {
+				shutdown_reason: None,
+				delay_tokens: 0,
+				completed: false,
+				on_shutdown: WakerList::new(),
+				on_shutdown_complete: WakerList::new(),
+			})),
+		}
+	}
+
+	/// Check if the shutdown has been triggered.
+	#[inline]
+	pub fn is_shutdown_triggered(&self) -> bool {
+		self.inner.borrow().shutdown_reason.is_some()
+	}
+
+	/// Check if the shutdown has completed.
+	#[inline]
+	pub fn is_shutdown_completed(&self) -> bool {
+		self.inner.borrow().completed
+	}
+
+	/// Get the shutdown reason, if the shutdown has been triggered.
+	///
+	/// Returns [`None`] if the shutdown has not been triggered yet.
+	#[inline]
+	pub fn shutdown_reason(&self) -> Option<T> {
+		self.inner.borrow().shutdown_reason.clone()
+	}
+
+	/// Asynchronously wait for the shutdown to be triggered.
+	///
+	/// The returned future completes with the shutdown reason.
+	#[inline]
+	pub fn wait_shutdown_triggered(&self) -> LocalShutdownSignal<T> {
+		LocalShutdownSignal::new(self.inner.clone(), false)
+	}
+
+	/// Asynchronously wait for the shutdown to complete.
+	///
+	/// The returned future completes with the shutdown reason.
+	#[inline]
+	pub fn wait_shutdown_complete(&self) -> LocalShutdownSignal<T> {
+		LocalShutdownSignal::new(self.inner.clone(), true)
+	}
+
+	/// Trigger the shutdown.
+	///
+	/// If the shutdown was already triggered, this function returns an error.
+	#[inline]
+	pub fn trigger_shutdown(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		let wakers = self.inner.borrow_mut().shutdown(reason)?;
+		wakers.into_iter().for_each(Waker::wake);
+		Ok(())
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with `Err(shutdown_reason)` if the shutdown is triggered,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	#[inline]
+	pub fn wrap_cancel<F: Future>(&self, future: F) -> LocalWrapCancel<T, F> {
+		LocalWrapCancel {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			future: Some(future),
+		}
+	}
+
+	/// Get a token that delays shutdown completion as long as it exists.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn delay_shutdown_token(&self) -> Result<LocalDelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
+		let mut inner = self.inner.borrow_mut();
+		if inner.completed {
+			// The reason is always set when the shutdown has completed.
+			let reason = inner.shutdown_reason.clone().unwrap();
+			return Err(ShutdownAlreadyCompleted::new(reason));
+		}
+		inner.delay_tokens += 1;
+		Ok(LocalDelayShutdownToken {
+			inner: self.inner.clone(),
+		})
+	}
+
+	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	pub fn wrap_delay_shutdown<F: Future>(&self, future: F) -> Result<LocalWrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(LocalWrapDelayShutdown {
+			delay_token: Some(self.delay_shutdown_token()?),
+			future,
+		})
+	}
+}
+
+impl<T: Clone> Default for LocalShutdownManager<T> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A future to wait for the shutdown of a [`LocalShutdownManager`] to be triggered or to complete.
+///
+/// Created by [`LocalShutdownManager::wait_shutdown_triggered()`] and [`LocalShutdownManager::wait_shutdown_complete()`].
+///
+/// The future stores its own node of the list of waiting tasks, so it is not [`Unpin`].
+#[must_use = "futures must be polled to make progress"]
+pub struct LocalShutdownSignal<T: Clone> {
+	inner: Rc<RefCell<LocalInner<T>>>,
+
+	/// True if the signal waits for the shutdown to complete instead of being triggered.
+	complete: bool,
+	waiter: Waiter,
+}
+
+impl<T: Clone> std::fmt::Debug for LocalShutdownSignal<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let inner = self.inner.borrow();
+		let triggered = if self.complete { inner.completed } else { inner.shutdown_reason.is_some() };
+		f.debug_struct("LocalShutdownSignal")
+			.field("complete", &self.complete)
+			.field("triggered", &triggered)
+			.field("waker_registered", &self.waiter.is_registered())
+			.finish()
+	}
+}
+
+impl<T: Clone> LocalShutdownSignal<T> {
+	fn new(inner: Rc<RefCell<LocalInner<T>>>, complete: bool) -> Self {
+		Self {
+			inner,
+			complete,
+			waiter: Waiter::new(),
+		}
+	}
+}
+
+impl<T: Clone> Clone for LocalShutdownSignal<T> {
+	fn clone(&self) -> Self {
+		// The waiter is personal to each future.
+		Self::new(self.inner.clone(), self.complete)
+	}
+}
+
+impl<T: Clone> Drop for LocalShutdownSignal<T> {
+	fn drop(&mut self) {
+		// Once the event happened, the waiter was already removed from the list.
+		if self.waiter.is_registered() {
+			let mut inner = self.inner.borrow_mut();
+			let (waiters, _) = inner.event(self.complete);
+			// SAFETY: The waiter is only registered with this list, and we hold the borrow.
+			unsafe { waiters.deregister(&self.waiter) };
+		}
+	}
+}
+
+impl<T: Clone> Future for LocalShutdownSignal<T> {
+	type Output = T;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut inner = self.inner.borrow_mut();
+		let (waiters, reason) = inner.event(self.complete);
+		if let Some(reason) = reason {
+			// Any registered waker was cleared when the event happened.
+			return Poll::Ready(reason.clone());
+		}
+		// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the borrow.
+		// The waiter is deregistered when the future is dropped.
+		unsafe { waiters.register(self.as_ref().map_unchecked(|me| &me.waiter), context.waker()) };
+		Poll::Pending
+	}
+}
+
+/// Token that delays the shutdown of a [`LocalShutdownManager`] from completing as long as it exists.
+///
+/// Created by [`LocalShutdownManager::delay_shutdown_token()`].
+pub struct LocalDelayShutdownToken<T: Clone> {
+	inner: Rc<RefCell<LocalInner<T>>>,
+}
+
+impl<T: Clone> std::fmt::Debug for LocalDelayShutdownToken<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("LocalDelayShutdownToken").finish_non_exhaustive()
+	}
+}
+
+impl<T: Clone> Clone for LocalDelayShutdownToken<T> {
+	fn clone(&self) -> Self {
+		self.inner.borrow_mut().delay_tokens += 1;
+		Self {
+			inner: self.inner.clone(),
+		}
+	}
+}
+
+impl<T: Clone> Drop for LocalDelayShutdownToken<T> {
+	fn drop(&mut self) {
+		let mut inner = self.inner.borrow_mut();
+		inner.delay_tokens -= 1;
+		let wakers = inner.check_complete();
+		drop(inner);
+		wakers.into_iter().flatten().for_each(Waker::wake);
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is cancelled when the shutdown of a [`LocalShutdownManager`] is triggered.
+	///
+	/// Created by [`LocalShutdownManager::wrap_cancel()`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct LocalWrapCancel<T: Clone, F> {
+		#[pin]
+		shutdown_signal: LocalShutdownSignal<T>,
+		#[pin]
+		future: Option<F>,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for LocalWrapCancel<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("LocalWrapCancel")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("cancelled", &self.future.is_none())
+			.finish()
+	}
+}
+
+impl<T: Clone, F: Future> Future for LocalWrapCancel<T, F> {
+	type Output = Result<F::Output, T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		let future = me.future.as_mut().as_pin_mut().expect("LocalWrapCancel polled after completion");
+		if let Poll::Ready(value) = future.poll(context) {
+			me.future.set(None);
+			return Poll::Ready(Ok(value));
+		}
+
+		match me.shutdown_signal.poll(context) {
+			Poll::Ready(reason) => {
+				me.future.set(None);
+				Poll::Ready(Err(reason))
+			},
+			Poll::Pending => Poll::Pending,
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that delays the shutdown of a [`LocalShutdownManager`] until it completes or until it is dropped.
+	///
+	/// Created by [`LocalShutdownManager::wrap_delay_shutdown()`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct LocalWrapDelayShutdown<T: Clone, F> {
+		delay_token: Option<LocalDelayShutdownToken<T>>,
+		#[pin]
+		future: F,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for LocalWrapDelayShutdown<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("LocalWrapDelayShutdown")
+			.field("delay_token", &self.delay_token)
+			.finish()
+	}
+}
+
+impl<T: Clone, F: Future> Future for LocalWrapDelayShutdown<T, F> {
+	type Output = F::Output;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		match me.future.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(value) => {
+				*me.delay_token = None;
+				Poll::Ready(value)
+			},
+		}
+	}
+}
@@ -0,0 +1,19 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Extension trait to lock a mutex without caring about poisoning.
+///
+/// The shutdown manager is used during teardown, when panics are most likely to happen.
+/// A panic in one task while it holds a lock must not cause panics in unrelated tasks that use the same shutdown manager.
+/// The only user code that runs while a lock is held (like cloning a shutdown reason) does not modify the protected state,
+/// so it is safe to ignore the poison flag.
+pub(crate) trait MutexExt<T: ?Sized> {
+	/// Lock the mutex, ignoring poisoning.
+	fn lock_unpoisoned(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T: ?Sized> MutexExt<T> for Mutex<T> {
+	#[inline]
+	fn lock_unpoisoned(&self) -> MutexGuard<'_, T> {
+		self.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
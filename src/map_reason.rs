@@ -0,0 +1,237 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::protect::CancelScope;
+use crate::ShutdownManager;
+
+/// A read-only view of a [`ShutdownManager`] with a different shutdown reason type.
+///
+/// Created by [`ShutdownManager::map_reason()`].
+///
+/// This allows you to hand a shutdown manager to a library that wants its own reason type,
+/// while your application uses a different one.
+/// The view can observe the shutdown and cancel futures, but it can not trigger the shutdown.
+///
+/// The view can be cloned and sent to different threads and tasks freely.
+/// Each clone refers to the same shutdown manager.
+pub struct MappedShutdownManager<U> {
+	source: Arc<dyn MappedSource<U>>,
+}
+
+impl<U> std::fmt::Debug for MappedShutdownManager<U> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("MappedShutdownManager")
+			.field("shutdown_triggered", &self.is_shutdown_triggered())
+			.field("shutdown_completed", &self.is_shutdown_completed())
+			.finish()
+	}
+}
+
+impl<U> Clone for MappedShutdownManager<U> {
+	fn clone(&self) -> Self {
+		Self {
+			source: self.source.clone(),
+		}
+	}
+}
+
+/// A boxed future that completes with a mapped shutdown reason.
+type MappedFuture<U> = Pin<Box<dyn Future<Output = U> + Send>>;
+
+/// The type erased shutdown manager and mapping function of a [`MappedShutdownManager`].
+trait MappedSource<U>: Send + Sync {
+	fn is_shutdown_triggered(&self) -> bool;
+	fn is_shutdown_completed(&self) -> bool;
+	fn shutdown_reason(&self) -> Option<U>;
+	fn wait_shutdown_triggered(&self) -> MappedFuture<U>;
+	fn wait_shutdown_complete(&self) -> MappedFuture<U>;
+}
+
+/// A shutdown manager together with the function to map its shutdown reason.
+struct Mapped<T: Clone, F> {
+	manager: ShutdownManager<T>,
+	map_reason: Arc<F>,
+}
+
+impl<T, U, F> MappedSource<U> for Mapped<T, F>
+where
+	T: Clone + Send + 'static,
+	F: Fn(T) -> U + Send + Sync + 'static,
+{
+	fn is_shutdown_triggered(&self) -> bool {
+		self.manager.is_shutdown_triggered()
+	}
+
+	fn is_shutdown_completed(&self) -> bool {
+		self.manager.is_shutdown_completed()
+	}
+
+	fn shutdown_reason(&self) -> Option<U> {
+		self.manager.shutdown_reason().map(&*self.map_reason)
+	}
+
+	fn wait_shutdown_triggered(&self) -> MappedFuture<U> {
+		let signal = self.manager.wait_shutdown_triggered();
+		let map_reason = self.map_reason.clone();
+		Box::pin(async move { map_reason(signal.await) })
+	}
+
+	fn wait_shutdown_complete(&self) -> MappedFuture<U> {
+		let complete = self.manager.wait_shutdown_complete();
+		let map_reason = self.map_reason.clone();
+		Box::pin(async move { map_reason(complete.await) })
+	}
+}
+
+impl<U> MappedShutdownManager<U> {
+	/// Check if the shutdown has been triggered.
+	#[inline]
+	pub fn is_shutdown_triggered(&self) -> bool {
+		self.source.is_shutdown_triggered()
+	}
+
+	/// Check if the shutdown has completed.
+	#[inline]
+	pub fn is_shutdown_completed(&self) -> bool {
+		self.source.is_shutdown_completed()
+	}
+
+	/// Get the mapped shutdown reason, if the shutdown has been triggered.
+	#[inline]
+	pub fn shutdown_reason(&self) -> Option<U> {
+		self.source.shutdown_reason()
+	}
+
+	/// Asynchronously wait for the shutdown to be triggered.
+	///
+	/// The returned future completes with the mapped shutdown reason.
+	#[inline]
+	pub fn wait_shutdown_triggered(&self) -> MappedShutdownSignal<U> {
+		MappedShutdownSignal {
+			future: self.source.wait_shutdown_triggered(),
+		}
+	}
+
+	/// Asynchronously wait for the shutdown to complete.
+	///
+	/// The returned future completes with the mapped shutdown reason.
+	#[inline]
+	pub fn wait_shutdown_complete(&self) -> MappedShutdownSignal<U> {
+		MappedShutdownSignal {
+			future: self.source.wait_shutdown_complete(),
+		}
+	}
+
+	/// Wrap a future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with `Err(mapped_reason)` if the shutdown is triggered,
+	/// and with `Ok(x)` if the wrapped future completes first.
+	#[inline]
+	pub fn wrap_cancel<F: Future>(&self, future: F) -> MappedWrapCancel<U, F> {
+		MappedWrapCancel {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			future: Some(future),
+			scope: CancelScope::default(),
+		}
+	}
+}
+
+/// Future that completes with a mapped shutdown reason.
+///
+/// Created by [`MappedShutdownManager::wait_shutdown_triggered()`] and [`MappedShutdownManager::wait_shutdown_complete()`].
+#[must_use = "futures must be polled to make progress"]
+pub struct MappedShutdownSignal<U> {
+	future: MappedFuture<U>,
+}
+
+impl<U> std::fmt::Debug for MappedShutdownSignal<U> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("MappedShutdownSignal").finish_non_exhaustive()
+	}
+}
+
+impl<U> Future for MappedShutdownSignal<U> {
+	type Output = U;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		self.get_mut().future.as_mut().poll(context)
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is cancelled when the shutdown is triggered, with a mapped shutdown reason.
+	///
+	/// Created by [`MappedShutdownManager::wrap_cancel()`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct MappedWrapCancel<U, F> {
+		shutdown_signal: MappedShutdownSignal<U>,
+		#[pin]
+		future: Option<F>,
+		// The critical sections that hold off the cancellation.
+		scope: CancelScope,
+	}
+}
+
+impl<U, F> std::fmt::Debug for MappedWrapCancel<U, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("MappedWrapCancel")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("cancelled", &self.future.is_none())
+			.finish()
+	}
+}
+
+impl<U, F: Future> Future for MappedWrapCancel<U, F> {
+	type Output = Result<F::Output, U>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		let future = me.future.as_mut().as_pin_mut().expect("MappedWrapCancel polled after completion");
+		if let Poll::Ready(value) = me.scope.poll_in(|| future.poll(context)) {
+			me.future.set(None);
+			return Poll::Ready(Ok(value));
+		}
+
+		// Do not cancel the future while it is running a critical section.
+		if me.scope.is_protected() {
+			return Poll::Pending;
+		}
+
+		match Pin::new(&mut *me.shutdown_signal).poll(context) {
+			Poll::Ready(reason) => {
+				me.future.set(None);
+				Poll::Ready(Err(reason))
+			},
+			Poll::Pending => Poll::Pending,
+		}
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Create a read-only view of the shutdown manager with a different shutdown reason type.
+	///
+	/// The shutdown reason is converted with `map_reason` whenever the view reports it.
+	/// This is useful for libraries that want their own reason type:
+	/// ```
+	/// # #[derive(Clone)]
+	/// # enum AppReason { Signal, Error }
+	/// # struct LibraryReason;
+	/// let shutdown = async_shutdown::ShutdownManager::<AppReason>::new();
+	/// let library_view = shutdown.map_reason(|_reason| LibraryReason);
+	/// ```
+	pub fn map_reason<U, F>(&self, map_reason: F) -> MappedShutdownManager<U>
+	where
+		F: Fn(T) -> U + Send + Sync + 'static,
+	{
+		MappedShutdownManager {
+			source: Arc::new(Mapped {
+				manager: self.clone(),
+				map_reason: Arc::new(map_reason),
+			}),
+		}
+	}
+}
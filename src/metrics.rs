@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+/// A snapshot of the state of a [`ShutdownManager`][crate::ShutdownManager], for monitoring purposes.
+///
+/// The snapshot does not depend on any specific metrics library.
+/// You can periodically call [`ShutdownManager::metrics()`][crate::ShutdownManager::metrics]
+/// and export the values to the metrics system of your choice,
+/// for example to alert on a shutdown that takes too long to complete.
+/// With the `metrics` feature, the values are also exported through the [`metrics`](https://docs.rs/metrics) crate facade,
+/// see `describe_metrics()`.
+///
+/// To record the duration of each shutdown in a histogram, read it when the shutdown completes:
+/// ```
+/// # static RECORDED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+/// # fn record_histogram(_name: &str, _value: std::time::Duration) { RECORDED.store(true, std::sync::atomic::Ordering::Relaxed) }
+/// let shutdown = async_shutdown::ShutdownManager::new();
+/// let manager = shutdown.clone();
+/// shutdown.on_complete(move |_reason| {
+///     if let Some(duration) = manager.metrics().shutdown_duration() {
+///         record_histogram("shutdown_duration", duration);
+///     }
+/// });
+/// # shutdown.trigger_shutdown(()).unwrap();
+/// # assert!(RECORDED.load(std::sync::atomic::Ordering::Relaxed));
+/// ```
+///
+/// All values refer to the current generation of the shutdown manager,
+/// so they are reset by [`ShutdownManager::reset()`][crate::ShutdownManager::reset].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ShutdownMetrics {
+	/// The number of delay tokens that currently exist, including clones.
+	pub delay_tokens: usize,
+
+	/// The number of futures that are waiting for the shutdown to be triggered.
+	pub trigger_waiters: usize,
+
+	/// The number of futures that are waiting for the shutdown to complete.
+	pub completion_waiters: usize,
+
+	/// The number of wrapped futures that have been cancelled by the shutdown.
+	pub cancelled_futures: u64,
+
+	/// The number of shutdown hooks and services that were abandoned because they did not finish within their timeout.
+	///
+	/// See [`ShutdownManager::on_shutdown_with_timeout()`][crate::ShutdownManager::on_shutdown_with_timeout]
+	/// and [`Services::register_with_timeout()`][crate::Services::register_with_timeout].
+	pub timed_out_hooks: u64,
+
+	/// The moment the shutdown was triggered, if it was triggered.
+	pub triggered_at: Option<Instant>,
+
+	/// The moment the shutdown completed, if it completed.
+	pub completed_at: Option<Instant>,
+}
+
+impl ShutdownMetrics {
+	/// Get the time it took from triggering the shutdown until it completed.
+	///
+	/// Returns [`None`] if the shutdown has not completed yet.
+	pub fn shutdown_duration(&self) -> Option<Duration> {
+		Some(self.completed_at?.saturating_duration_since(self.triggered_at?))
+	}
+
+	/// Get the time since the shutdown was triggered, if it was triggered.
+	///
+	/// After the shutdown completed, this keeps increasing.
+	/// Use [`Self::shutdown_duration()`] to get the time it took to complete the shutdown.
+	pub fn time_since_trigger(&self) -> Option<Duration> {
+		Some(self.triggered_at?.elapsed())
+	}
+}
@@ -0,0 +1,120 @@
+//! Export of shutdown metrics through the [`metrics`](https://docs.rs/metrics) crate facade.
+//!
+//! With the `metrics` feature enabled, the shutdown managers report their state to the installed recorder.
+//! See [`describe_metrics()`] for the list of metrics.
+//!
+//! Without the feature, all types in this module are zero sized and all functions are no-ops.
+
+#[cfg(feature = "metrics")]
+use std::panic::AssertUnwindSafe;
+
+/// The name of the gauge with the number of live delay tokens.
+pub(crate) const DELAY_TOKENS: &str = "async_shutdown_delay_tokens";
+
+/// The name of the gauge with the number of futures that wait for the shutdown to be triggered.
+pub(crate) const TRIGGER_WAITERS: &str = "async_shutdown_trigger_waiters";
+
+/// The name of the gauge with the number of futures that wait for the shutdown to complete.
+pub(crate) const COMPLETION_WAITERS: &str = "async_shutdown_completion_waiters";
+
+#[cfg(feature = "metrics")]
+const CANCELLED_FUTURES: &str = "async_shutdown_cancelled_futures";
+
+#[cfg(feature = "metrics")]
+const TIMED_OUT_HOOKS: &str = "async_shutdown_timed_out_hooks";
+
+#[cfg(feature = "metrics")]
+const SHUTDOWN_DURATION: &str = "async_shutdown_duration_seconds";
+
+/// Describe the metrics of the shutdown managers to the installed recorder.
+///
+/// With the `metrics` feature enabled, all shutdown managers report these metrics through the [`metrics`](https://docs.rs/metrics) crate:
+/// * `async_shutdown_delay_tokens`: gauge with the number of live delay tokens, including clones.
+/// * `async_shutdown_trigger_waiters`: gauge with the number of futures that wait for the shutdown to be triggered.
+/// * `async_shutdown_completion_waiters`: gauge with the number of futures that wait for the shutdown to complete.
+/// * `async_shutdown_cancelled_futures`: counter of wrapped futures that were cancelled by the shutdown.
+/// * `async_shutdown_timed_out_hooks`: counter of shutdown hooks and services that were abandoned because they timed out.
+/// * `async_shutdown_duration_seconds`: histogram of the time from triggering the shutdown until it completed.
+///
+/// The values of all shutdown managers are added together.
+/// The gauges are registered when a shutdown manager is created, so install the recorder before creating any shutdown manager.
+///
+/// This function registers the unit and a description of each metric, which some exporters show next to the values.
+/// Call it after installing the recorder.
+#[cfg(feature = "metrics")]
+pub fn describe_metrics() {
+	use metrics::Unit;
+	metrics::describe_gauge!(DELAY_TOKENS, Unit::Count, "The number of live delay tokens, including clones.");
+	metrics::describe_gauge!(TRIGGER_WAITERS, Unit::Count, "The number of futures that wait for the shutdown to be triggered.");
+	metrics::describe_gauge!(COMPLETION_WAITERS, Unit::Count, "The number of futures that wait for the shutdown to complete.");
+	metrics::describe_counter!(CANCELLED_FUTURES, Unit::Count, "The number of wrapped futures that were cancelled by the shutdown.");
+	metrics::describe_counter!(TIMED_OUT_HOOKS, Unit::Count, "The number of shutdown hooks and services that were abandoned because they timed out.");
+	metrics::describe_histogram!(SHUTDOWN_DURATION, Unit::Seconds, "The time from triggering the shutdown until it completed.");
+}
+
+/// A gauge that counts live objects.
+///
+/// The handle is registered when the gauge is created, so the recorder must be installed before the shutdown manager is created.
+/// The default gauge is not exported.
+#[derive(Default)]
+pub(crate) struct Gauge {
+	// The handle of the recorder is not unwind safe, but a gauge that misses an update can not break the shutdown manager.
+	#[cfg(feature = "metrics")]
+	gauge: Option<AssertUnwindSafe<metrics::Gauge>>,
+}
+
+impl Gauge {
+	/// Create a gauge that is exported with the given name.
+	#[inline]
+	pub fn new(_name: &'static str) -> Self {
+		Self {
+			#[cfg(feature = "metrics")]
+			gauge: Some(AssertUnwindSafe(metrics::gauge!(_name))),
+		}
+	}
+
+	/// Add a number of objects to the gauge.
+	#[inline]
+	pub fn add(&self, _count: usize) {
+		#[cfg(feature = "metrics")]
+		if let Some(gauge) = &self.gauge {
+			if _count > 0 {
+				gauge.increment(_count as f64);
+			}
+		}
+	}
+
+	/// Remove a number of objects from the gauge.
+	#[inline]
+	pub fn sub(&self, _count: usize) {
+		#[cfg(feature = "metrics")]
+		if let Some(gauge) = &self.gauge {
+			if _count > 0 {
+				gauge.decrement(_count as f64);
+			}
+		}
+	}
+}
+
+/// Count a wrapped future that was cancelled by the shutdown.
+#[inline]
+pub(crate) fn record_cancelled() {
+	#[cfg(feature = "metrics")]
+	metrics::counter!(CANCELLED_FUTURES).increment(1);
+}
+
+/// Count a shutdown hook or service that was abandoned because it timed out.
+#[inline]
+pub(crate) fn record_timed_out() {
+	#[cfg(feature = "metrics")]
+	metrics::counter!(TIMED_OUT_HOOKS).increment(1);
+}
+
+/// Record the time it took for a shutdown to complete.
+#[inline]
+pub(crate) fn record_shutdown_duration(_triggered_at: Option<std::time::Instant>, _completed_at: Option<std::time::Instant>) {
+	#[cfg(feature = "metrics")]
+	if let (Some(triggered_at), Some(completed_at)) = (_triggered_at, _completed_at) {
+		metrics::histogram!(SHUTDOWN_DURATION).record(completed_at.saturating_duration_since(triggered_at));
+	}
+}
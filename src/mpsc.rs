@@ -0,0 +1,134 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+use crate::{DelayShutdownToken, ShutdownAlreadyCompleted, ShutdownManager, ShutdownSignal};
+
+/// The receiving half of a tokio [`mpsc`] channel that is closed when the shutdown is triggered.
+///
+/// Created by [`ShutdownManager::wrap_receiver()`] or [`ShutdownManager::mpsc_channel()`].
+///
+/// When the receiver notices that the shutdown has been triggered, it closes the channel,
+/// so that senders can no longer send new messages.
+/// Messages that were already buffered are still delivered, so that no enqueued work is lost.
+///
+/// The receiver holds a delay token,
+/// so the shutdown does not complete until all buffered messages have been received, or until the receiver is dropped.
+pub struct ShutdownReceiver<T: Clone, M> {
+	receiver: mpsc::Receiver<M>,
+	// Boxed so the receiver can be polled through `&mut self`.
+	shutdown_signal: Pin<Box<ShutdownSignal<T>>>,
+	delay_token: Option<DelayShutdownToken<T>>,
+	closed: bool,
+}
+
+impl<T: Clone, M> ShutdownReceiver<T, M> {
+	/// Receive the next message.
+	///
+	/// Returns [`None`] when the channel is closed and all buffered messages have been received.
+	/// This happens when all senders are dropped, or when the shutdown has been triggered.
+	///
+	/// After this function returned [`None`], the receiver no longer delays the shutdown.
+	#[inline]
+	pub async fn recv(&mut self) -> Option<M> {
+		std::future::poll_fn(|context| self.poll_recv(context)).await
+	}
+
+	/// Poll to receive the next message.
+	///
+	/// See [`Self::recv()`] for more details.
+	pub fn poll_recv(&mut self, context: &mut Context) -> Poll<Option<M>> {
+		if !self.closed && self.shutdown_signal.as_mut().poll(context).is_ready() {
+			self.receiver.close();
+			self.closed = true;
+		}
+		let message = match self.receiver.poll_recv(context) {
+			Poll::Ready(message) => message,
+			Poll::Pending => return Poll::Pending,
+		};
+		if message.is_none() {
+			self.delay_token = None;
+		}
+		Poll::Ready(message)
+	}
+
+	/// Close the channel and process all buffered messages.
+	///
+	/// The function is called for each remaining message, and the returned future is run to completion before the next message is taken.
+	/// The shutdown does not complete until all messages have been processed.
+	///
+	/// Returns the number of messages that were processed.
+	pub async fn drain<F, Fut>(mut self, mut function: F) -> usize
+	where
+		F: FnMut(M) -> Fut,
+		Fut: Future<Output = ()>,
+	{
+		self.receiver.close();
+		let mut processed = 0;
+		while let Some(message) = self.receiver.recv().await {
+			function(message).await;
+			processed += 1;
+		}
+		processed
+	}
+
+	/// Get the wrapped receiver back.
+	///
+	/// The returned receiver no longer delays the shutdown.
+	#[inline]
+	pub fn into_inner(self) -> mpsc::Receiver<M> {
+		self.receiver
+	}
+}
+
+/// The sending and receiving half of a channel created by [`ShutdownManager::mpsc_channel()`].
+type Channel<T, M> = (mpsc::Sender<M>, ShutdownReceiver<T, M>);
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap the receiving half of a tokio [`mpsc`] channel so that the channel is closed when the shutdown is triggered.
+	///
+	/// See [`ShutdownReceiver`] for more details.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_receiver<M>(&self, receiver: mpsc::Receiver<M>) -> Result<ShutdownReceiver<T, M>, ShutdownAlreadyCompleted<T>> {
+		let shutdown_signal = self.wait_shutdown_triggered();
+		let delay_token = DelayShutdownToken::new(shutdown_signal.inner.clone(), 0)?;
+		Ok(ShutdownReceiver {
+			receiver,
+			shutdown_signal: Box::pin(shutdown_signal),
+			delay_token: Some(delay_token),
+			closed: false,
+		})
+	}
+
+	/// Create a bounded tokio [`mpsc`] channel that is closed when the shutdown is triggered.
+	///
+	/// This is the same as calling [`Self::wrap_receiver()`] on the receiver of a new [`mpsc::channel()`]:
+	/// ```
+	/// # async fn handle(_job: u32) {}
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// let (sender, mut receiver) = shutdown.mpsc_channel(128).unwrap();
+	/// tokio::spawn(async move {
+	///     // Keeps receiving buffered jobs after the shutdown is triggered, until the queue is empty.
+	///     while let Some(job) = receiver.recv().await {
+	///         handle(job).await;
+	///     }
+	/// });
+	/// sender.send(1).await.ok();
+	/// # }
+	/// ```
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	///
+	/// # Panics
+	/// This function panics if `buffer` is zero.
+	#[inline]
+	#[track_caller]
+	pub fn mpsc_channel<M>(&self, buffer: usize) -> Result<Channel<T, M>, ShutdownAlreadyCompleted<T>> {
+		let (sender, receiver) = mpsc::channel(buffer);
+		Ok((sender, self.wrap_receiver(receiver)?))
+	}
+}
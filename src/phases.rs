@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::task::Waker;
+
+use crate::waker_list::{Waiter, WakerList};
+
+/// Bookkeeping for the shutdown phases of a shutdown manager.
+///
+/// Phase `N` is complete when the shutdown has been triggered,
+/// and all delay tokens for phase `N` and all earlier phases have been dropped.
+#[derive(Default)]
+pub(crate) struct Phases {
+	/// Number of delay tokens per phase.
+	///
+	/// Phases without delay tokens are removed from the map.
+	delay_tokens: BTreeMap<usize, usize>,
+
+	/// Tasks to wake when a phase is complete.
+	waiters: BTreeMap<usize, WakerList>,
+}
+
+impl Phases {
+	/// Create a new empty set of phases.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Increase the number of delay tokens for a phase.
+	pub fn increase_delay_count(&mut self, phase: usize) {
+		*self.delay_tokens.entry(phase).or_insert(0) += 1;
+	}
+
+	/// Decrease the number of delay tokens for a phase.
+	///
+	/// Returns `true` if the last delay token for the phase was dropped.
+	pub fn decrease_delay_count(&mut self, phase: usize) -> bool {
+		let count = self.delay_tokens.get_mut(&phase).expect("no delay tokens for phase");
+		*count -= 1;
+		if *count == 0 {
+			self.delay_tokens.remove(&phase);
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Check if there are no more delay tokens for a phase or any of the phases before it.
+	///
+	/// Note that this does not check if the shutdown has been triggered.
+	pub fn is_drained(&self, phase: usize) -> bool {
+		self.delay_tokens.range(..=phase).next().is_none()
+	}
+
+	/// Register a waker to be woken up when a phase is complete.
+	///
+	/// # Safety
+	/// See [`WakerList::register()`]. The waiter must always be registered for the same phase.
+	pub unsafe fn register(&mut self, phase: usize, waiter: Pin<&Waiter>, waker: &Waker) {
+		self.waiters.entry(phase).or_default().register(waiter, waker)
+	}
+
+	/// Deregister a waiter for a phase.
+	///
+	/// # Safety
+	/// See [`WakerList::deregister()`]. The waiter must always be registered for the same phase.
+	pub unsafe fn deregister(&mut self, phase: usize, waiter: &Waiter) {
+		if let Some(waiters) = self.waiters.get_mut(&phase) {
+			waiters.deregister(waiter);
+		}
+	}
+
+	/// Release unused memory of the waiter lists.
+	pub fn shrink_waiters(&mut self) {
+		self.waiters.retain(|_, waiters| waiters.len() > 0);
+		for waiters in self.waiters.values_mut() {
+			waiters.shrink_to_fit();
+		}
+	}
+
+	/// Wake the waiters of all phases.
+	pub fn wake_all(&mut self) {
+		for waiters in self.waiters.values_mut() {
+			waiters.wake_all();
+		}
+	}
+
+	/// Wake the waiters of all drained phases.
+	///
+	/// This should only be called after the shutdown has been triggered.
+	pub fn wake_drained(&mut self) {
+		let first_busy = self.delay_tokens.keys().next().copied();
+		for (&phase, waiters) in &mut self.waiters {
+			if first_busy.map(|first_busy| phase >= first_busy).unwrap_or(false) {
+				break;
+			}
+			waiters.wake_all();
+		}
+	}
+}
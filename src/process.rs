@@ -0,0 +1,198 @@
+use std::future::Future;
+use std::process::ExitStatus;
+use std::time::{Duration, Instant};
+
+use tokio::process::Child;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager};
+
+/// Interval for checking if all processes in a process group have exited.
+const PROCESS_GROUP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Terminate a child process when the shutdown is triggered.
+	///
+	/// The returned future waits for the child to exit, and it delays shutdown completion while doing so.
+	/// When the shutdown is triggered, `SIGTERM` is sent to the child.
+	/// The child is then given `grace_period` to exit, after which it is killed with `SIGKILL`.
+	///
+	/// The future completes with the exit status of the child.
+	/// You must spawn or await the future for the child to be terminated:
+	/// ```no_run
+	/// # async fn run() -> std::io::Result<()> {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// use std::time::Duration;
+	///
+	/// let child = tokio::process::Command::new("./sidecar").spawn()?;
+	/// let sidecar = shutdown.terminate_child_on_shutdown(child, Duration::from_secs(5)).unwrap();
+	/// tokio::spawn(sidecar);
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// Use [`Self::terminate_process_group_on_shutdown()`] if the child spawns processes of its own.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	pub fn terminate_child_on_shutdown(
+		&self,
+		child: Child,
+		grace_period: Duration,
+	) -> Result<impl Future<Output = std::io::Result<ExitStatus>> + Send + 'static, ShutdownAlreadyCompleted<T>> {
+		self.terminate_child_on_shutdown_with_signal(child, libc::SIGTERM, grace_period)
+	}
+
+	/// Terminate a child process with a custom signal when the shutdown is triggered.
+	///
+	/// This is the same as [`Self::terminate_child_on_shutdown()`],
+	/// except that `signal` is sent to the child instead of `SIGTERM`.
+	/// For example, some programs expect `SIGINT` or `SIGQUIT` to shut down gracefully.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn terminate_child_on_shutdown_with_signal(
+		&self,
+		mut child: Child,
+		signal: std::os::raw::c_int,
+		grace_period: Duration,
+	) -> Result<impl Future<Output = std::io::Result<ExitStatus>> + Send + 'static, ShutdownAlreadyCompleted<T>> {
+		let shutdown_signal = self.wait_shutdown_triggered();
+		let future = async move {
+			if let Ok(status) = shutdown_signal.wrap_cancel(child.wait()).await {
+				return status;
+			}
+
+			// The PID is only gone if the child has already been reaped.
+			let pid = match child.id() {
+				Some(x) => x as libc::pid_t,
+				None => return child.wait().await,
+			};
+
+			signal_process(pid, signal)?;
+			match tokio::time::timeout(grace_period, child.wait()).await {
+				Ok(status) => status,
+				Err(_) => {
+					child.kill().await?;
+					child.wait().await
+				},
+			}
+		};
+		self.wrap_delay_shutdown(future)
+	}
+
+	/// Terminate the process group of a child process when the shutdown is triggered.
+	///
+	/// The returned future waits for the child to exit, and it delays shutdown completion while doing so.
+	/// When the shutdown is triggered, `SIGTERM` is sent to the process group of the child.
+	/// The processes in the group are then given `grace_period` to exit,
+	/// after which `SIGKILL` is sent to the process group.
+	///
+	/// The future completes with the exit status of the child itself.
+	///
+	/// This is useful when the child spawns processes of its own, like shell scripts or media pipelines.
+	/// The child must be the leader of its own process group,
+	/// so it should be spawned with [`process_group(0)`][tokio::process::Command::process_group]:
+	/// ```no_run
+	/// # async fn run() -> std::io::Result<()> {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// use std::time::Duration;
+	///
+	/// let child = tokio::process::Command::new("./run-pipeline.sh")
+	///     .process_group(0)
+	///     .spawn()?;
+	/// let status = shutdown.terminate_process_group_on_shutdown(child, Duration::from_secs(5))
+	///     .unwrap()
+	///     .await?;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn terminate_process_group_on_shutdown(
+		&self,
+		mut child: Child,
+		grace_period: Duration,
+	) -> Result<impl Future<Output = std::io::Result<ExitStatus>> + Send + 'static, ShutdownAlreadyCompleted<T>> {
+		let shutdown_signal = self.wait_shutdown_triggered();
+		let future = async move {
+			// Get the process group before the child exits, since we can not get the PID afterwards.
+			let process_group = child.id();
+			if let Ok(status) = shutdown_signal.wrap_cancel(child.wait()).await {
+				return status;
+			}
+
+			let process_group = match process_group {
+				Some(x) => x as libc::pid_t,
+				None => return child.wait().await,
+			};
+
+			let deadline = Instant::now() + grace_period;
+			signal_process_group(process_group, libc::SIGTERM)?;
+			let status = match tokio::time::timeout_at(deadline.into(), child.wait()).await {
+				Ok(status) => status?,
+				Err(_) => {
+					signal_process_group(process_group, libc::SIGKILL)?;
+					return child.wait().await;
+				},
+			};
+
+			// The child exited, but other processes in the group may still be running.
+			while process_group_exists(process_group)? {
+				if Instant::now() >= deadline {
+					signal_process_group(process_group, libc::SIGKILL)?;
+					break;
+				}
+				tokio::time::sleep(PROCESS_GROUP_POLL_INTERVAL).await;
+			}
+
+			Ok(status)
+		};
+		self.wrap_delay_shutdown(future)
+	}
+}
+
+/// Send a signal to a process.
+///
+/// It is not an error if the process does not exist anymore.
+fn signal_process(pid: libc::pid_t, signal: libc::c_int) -> std::io::Result<()> {
+	// SAFETY: `kill` has no memory safety requirements.
+	if unsafe { libc::kill(pid, signal) } == 0 {
+		return Ok(());
+	}
+	let error = std::io::Error::last_os_error();
+	if error.raw_os_error() == Some(libc::ESRCH) {
+		Ok(())
+	} else {
+		Err(error)
+	}
+}
+
+/// Send a signal to a process group.
+///
+/// It is not an error if the process group does not exist anymore.
+fn signal_process_group(process_group: libc::pid_t, signal: libc::c_int) -> std::io::Result<()> {
+	// SAFETY: `killpg` has no memory safety requirements.
+	if unsafe { libc::killpg(process_group, signal) } == 0 {
+		return Ok(());
+	}
+	let error = std::io::Error::last_os_error();
+	if error.raw_os_error() == Some(libc::ESRCH) {
+		Ok(())
+	} else {
+		Err(error)
+	}
+}
+
+/// Check if a process group still has any processes in it.
+fn process_group_exists(process_group: libc::pid_t) -> std::io::Result<bool> {
+	// SAFETY: `killpg` has no memory safety requirements.
+	if unsafe { libc::killpg(process_group, 0) } == 0 {
+		return Ok(true);
+	}
+	let error = std::io::Error::last_os_error();
+	match error.raw_os_error() {
+		Some(libc::ESRCH) => Ok(false),
+		// We're not allowed to signal the processes, but they do exist.
+		Some(libc::EPERM) => Ok(true),
+		_ => Err(error),
+	}
+}
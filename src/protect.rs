@@ -0,0 +1,155 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager, WrapDelayShutdown};
+
+thread_local! {
+	/// The cancellation layers that are currently polling their wrapped future on this thread, from outer to inner.
+	static CANCEL_SCOPES: RefCell<Vec<Option<Arc<AtomicUsize>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The critical sections running inside a cancellation layer like [`WrapCancel`][crate::WrapCancel].
+///
+/// The counter is only allocated when a [`Protect`] future is polled inside the layer.
+#[derive(Default)]
+pub(crate) struct CancelScope {
+	sections: Option<Arc<AtomicUsize>>,
+}
+
+impl CancelScope {
+	/// Run `poll` with this scope on the stack, so that [`Protect`] futures polled by it can find the scope.
+	pub fn poll_in<R>(&mut self, poll: impl FnOnce() -> R) -> R {
+		CANCEL_SCOPES.with(|scopes| scopes.borrow_mut().push(self.sections.take()));
+		let _guard = PopScope { scope: self };
+		poll()
+	}
+
+	/// Check if a critical section is running inside the cancellation layer.
+	pub fn is_protected(&self) -> bool {
+		self.sections.as_ref().is_some_and(|sections| sections.load(Ordering::Acquire) > 0)
+	}
+}
+
+/// Guard that takes a scope back off the stack, even if the wrapped future panicked.
+struct PopScope<'a> {
+	scope: &'a mut CancelScope,
+}
+
+impl Drop for PopScope<'_> {
+	fn drop(&mut self) {
+		self.scope.sections = CANCEL_SCOPES.with(|scopes| scopes.borrow_mut().pop().flatten());
+	}
+}
+
+/// The cancellation layers that a [`Protect`] future is holding off.
+struct Sections {
+	scopes: Vec<Arc<AtomicUsize>>,
+}
+
+impl Sections {
+	/// Enter a critical section in all cancellation layers that are currently polling on this thread.
+	fn enter() -> Self {
+		let scopes = CANCEL_SCOPES.with(|scopes| {
+			scopes
+				.borrow_mut()
+				.iter_mut()
+				.map(|scope| {
+					let scope = scope.get_or_insert_with(Default::default).clone();
+					scope.fetch_add(1, Ordering::AcqRel);
+					scope
+				})
+				.collect()
+		});
+		Self { scopes }
+	}
+}
+
+impl Drop for Sections {
+	fn drop(&mut self) {
+		for scope in &self.scopes {
+			scope.fetch_sub(1, Ordering::AcqRel);
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that runs as a critical section that can not be cancelled by the shutdown.
+	///
+	/// Created by [`ShutdownManager::protect()`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct Protect<T: Clone, F> {
+		#[pin]
+		future: WrapDelayShutdown<T, F>,
+		// The critical sections entered on the first poll, released when the future completes.
+		sections: Option<Sections>,
+		entered: bool,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for Protect<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("Protect")
+			.field("future", &self.future)
+			.field("active", &self.sections.is_some())
+			.finish()
+	}
+}
+
+impl<T: Clone, F: Future> Future for Protect<T, F> {
+	type Output = F::Output;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		if !*me.entered {
+			*me.entered = true;
+			*me.sections = Some(Sections::enter());
+		}
+		let output = me.future.poll(context);
+		if output.is_ready() {
+			*me.sections = None;
+		}
+		output
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap a future to run it as a critical section that is not interrupted by the shutdown.
+	///
+	/// Cancellation layers like [`Self::wrap_cancel()`] that poll the returned future do not cancel it when the shutdown is triggered.
+	/// Instead, they wait for the critical section to finish, and only cancel the rest of their wrapped future after that.
+	/// The critical section also holds a delay token, so the shutdown does not complete before it finishes:
+	/// ```
+	/// # async fn begin() -> Transaction { Transaction }
+	/// # struct Transaction;
+	/// # impl Transaction { async fn commit(self) {} }
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// shutdown.wrap_cancel(async {
+	///     let transaction = begin().await;
+	///     // Once started, the commit is never interrupted halfway.
+	///     shutdown.protect(transaction.commit()).unwrap().await;
+	/// }).await.ok();
+	/// # }
+	/// ```
+	///
+	/// The critical section starts when the returned future is polled for the first time.
+	/// It holds off all cancellation layers that poll it at that moment,
+	/// including those of [`ShutdownSignal::wrap_cancel()`][crate::ShutdownSignal::wrap_cancel] and the [mapped views][Self::map_reason] of the shutdown manager.
+	/// Cancellation layers in other tasks are not affected,
+	/// and aborting the task that runs the critical section still stops it.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn protect<F: Future>(&self, future: F) -> Result<Protect<T, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(Protect {
+			future: self.wrap_delay_shutdown(future)?,
+			sections: None,
+			entered: false,
+		})
+	}
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::{ShutdownComplete, ShutdownManager};
+use crate::lock::MutexExt;
+
+/// Registry of named shutdown managers.
+///
+/// Large applications may be composed of several independent components that each have their own [`ShutdownManager`].
+/// The registry allows you to trigger a shutdown for all of them at once, and to wait for all of them to complete.
+///
+/// The registry can be cloned and shared with multiple tasks.
+/// Each clone uses the same internal state.
+#[derive(Clone)]
+pub struct ShutdownRegistry<T: Clone> {
+	managers: Arc<Mutex<BTreeMap<String, ShutdownManager<T>>>>,
+}
+
+impl<T: Clone> ShutdownRegistry<T> {
+	/// Create a new empty registry.
+	#[inline]
+	pub fn new() -> Self {
+		Self {
+			managers: Arc::new(Mutex::new(BTreeMap::new())),
+		}
+	}
+
+	/// Register a shutdown manager under the given name.
+	///
+	/// If a manager was already registered with the same name, it is replaced and returned.
+	#[inline]
+	pub fn register(&self, name: impl Into<String>, manager: ShutdownManager<T>) -> Option<ShutdownManager<T>> {
+		self.managers.lock_unpoisoned().insert(name.into(), manager)
+	}
+
+	/// Remove a shutdown manager from the registry.
+	///
+	/// Returns the removed manager, or [`None`] if no manager was registered with the given name.
+	#[inline]
+	pub fn unregister(&self, name: &str) -> Option<ShutdownManager<T>> {
+		self.managers.lock_unpoisoned().remove(name)
+	}
+
+	/// Get the shutdown manager registered under the given name.
+	#[inline]
+	pub fn get(&self, name: &str) -> Option<ShutdownManager<T>> {
+		self.managers.lock_unpoisoned().get(name).cloned()
+	}
+
+	/// Get the names of all registered shutdown managers, in sorted order.
+	#[inline]
+	pub fn names(&self) -> Vec<String> {
+		self.managers.lock_unpoisoned().keys().cloned().collect()
+	}
+
+	/// Trigger the shutdown of all registered managers.
+	///
+	/// The shutdown reason for each manager is determined by calling `reason` with the name of the manager.
+	///
+	/// Managers that already had their shutdown triggered keep their original shutdown reason.
+	pub fn trigger_all<F: FnMut(&str) -> T>(&self, mut reason: F) {
+		// Don't hold the lock while calling user code.
+		let managers = self.managers.lock_unpoisoned().clone();
+		for (name, manager) in &managers {
+			manager.trigger_shutdown(reason(name)).ok();
+		}
+	}
+
+	/// Asynchronously wait for the shutdown of all registered managers to complete.
+	///
+	/// The returned future waits for the managers that are registered at the time this function is called.
+	/// It completes with the name and shutdown reason of each manager, in sorted order by name.
+	pub fn wait_all_complete(&self) -> WaitAllComplete<T> {
+		let waiting = self
+			.managers
+			.lock_unpoisoned()
+			.iter()
+			.map(|(name, manager)| (name.clone(), Box::pin(manager.wait_shutdown_complete())))
+			.collect();
+		WaitAllComplete {
+			waiting,
+			completed: Vec::new(),
+		}
+	}
+}
+
+impl<T: Clone> Default for ShutdownRegistry<T> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Future to wait for the shutdown of all managers in a [`ShutdownRegistry`] to complete.
+#[must_use = "futures must be polled to make progress"]
+pub struct WaitAllComplete<T: Clone> {
+	// Boxed, because finished futures are removed from the middle of the list.
+	waiting: Vec<(String, Pin<Box<ShutdownComplete<T>>>)>,
+	completed: Vec<(String, T)>,
+}
+
+// We never pin the shutdown reasons, so we can be `Unpin` even if `T` is not.
+impl<T: Clone> Unpin for WaitAllComplete<T> {}
+
+impl<T: Clone> Future for WaitAllComplete<T> {
+	type Output = Vec<(String, T)>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.get_mut();
+
+		let mut i = 0;
+		while i < me.waiting.len() {
+			if let Poll::Ready(reason) = me.waiting[i].1.as_mut().poll(context) {
+				let (name, _) = me.waiting.remove(i);
+				me.completed.push((name, reason));
+			} else {
+				i += 1;
+			}
+		}
+
+		if me.waiting.is_empty() {
+			let mut completed = std::mem::take(&mut me.completed);
+			completed.sort_by(|a, b| a.0.cmp(&b.0));
+			Poll::Ready(completed)
+		} else {
+			Poll::Pending
+		}
+	}
+}
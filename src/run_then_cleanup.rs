@@ -0,0 +1,175 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::shutdown_signal::ShutdownSignal;
+use crate::DelayShutdownToken;
+
+/// Summary of a [`RunThenCleanup`] future.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RunSummary<O, T, R> {
+	/// The main future completed before the shutdown was triggered, so the clean-up did not run.
+	Completed(O),
+
+	/// The shutdown was triggered, so the main future was cancelled and the clean-up future ran to completion.
+	CleanedUp {
+		/// The shutdown reason.
+		reason: T,
+
+		/// The output of the clean-up future.
+		cleanup: R,
+	},
+}
+
+impl<O, T, R> RunSummary<O, T, R> {
+	/// Check if the main future completed before the shutdown was triggered.
+	#[inline]
+	pub fn is_completed(&self) -> bool {
+		matches!(self, Self::Completed(_))
+	}
+
+	/// Check if the main future was cancelled and the clean-up ran.
+	#[inline]
+	pub fn is_cleaned_up(&self) -> bool {
+		matches!(self, Self::CleanedUp { .. })
+	}
+
+	/// Get the output of the main future, if it completed.
+	#[inline]
+	pub fn completed(self) -> Option<O> {
+		match self {
+			Self::Completed(output) => Some(output),
+			Self::CleanedUp { .. } => None,
+		}
+	}
+
+	/// Get the shutdown reason, if the main future was cancelled.
+	#[inline]
+	pub fn shutdown_reason(self) -> Option<T> {
+		match self {
+			Self::Completed(_) => None,
+			Self::CleanedUp { reason, .. } => Some(reason),
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Future that runs a main future until the shutdown is triggered, and then runs a clean-up future.
+	///
+	/// Created by [`ShutdownManager::run_then_cleanup()`][crate::ShutdownManager::run_then_cleanup].
+	///
+	/// If the main future completes before the shutdown is triggered, the future completes with [`RunSummary::Completed`].
+	/// Otherwise, the main future is dropped and the clean-up function is called with the shutdown reason.
+	/// When the clean-up future completes, the future completes with [`RunSummary::CleanedUp`].
+	///
+	/// The future delays shutdown completion until the main future or the clean-up future completes, or until it is dropped.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct RunThenCleanup<T: Clone, F, C, CF> {
+		#[pin]
+		pub(crate) shutdown_signal: ShutdownSignal<T>,
+		pub(crate) delay_token: Option<DelayShutdownToken<T>>,
+		pub(crate) reason: Option<T>,
+		#[pin]
+		pub(crate) state: CleanupState<F, C, CF>,
+	}
+}
+
+impl<T: Clone, F, C, CF> std::fmt::Debug for RunThenCleanup<T, F, C, CF> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let state = match &self.state {
+			CleanupState::Running { .. } => "running",
+			CleanupState::Cleanup { .. } => "cleanup",
+			CleanupState::Done => "done",
+		};
+		f.debug_struct("RunThenCleanup")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("delay_token", &self.delay_token)
+			.field("state", &state)
+			.finish()
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// The state of a [`RunThenCleanup`] future.
+	#[project = CleanupStateProj]
+	pub(crate) enum CleanupState<F, C, CF> {
+		/// The main future is still running.
+		Running {
+			#[pin]
+			future: F,
+			cleanup: Option<C>,
+		},
+
+		/// The shutdown was triggered and the clean-up future is running.
+		Cleanup {
+			#[pin]
+			cleanup: CF,
+		},
+
+		/// The future has completed.
+		Done,
+	}
+}
+
+impl<T: Clone, F, C, CF> RunThenCleanup<T, F, C, CF> {
+	/// Create a new future for a shutdown signal.
+	pub(crate) fn new(shutdown_signal: ShutdownSignal<T>, delay_token: DelayShutdownToken<T>, future: F, cleanup: C) -> Self {
+		Self {
+			shutdown_signal,
+			delay_token: Some(delay_token),
+			reason: None,
+			state: CleanupState::Running {
+				future,
+				cleanup: Some(cleanup),
+			},
+		}
+	}
+}
+
+impl<T, F, C, CF> Future for RunThenCleanup<T, F, C, CF>
+where
+	T: Clone,
+	F: Future,
+	C: FnOnce(T) -> CF,
+	CF: Future,
+{
+	type Output = RunSummary<F::Output, T, CF::Output>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		loop {
+			match me.state.as_mut().project() {
+				CleanupStateProj::Running { future, cleanup } => {
+					if let Poll::Ready(value) = future.poll(context) {
+						me.state.set(CleanupState::Done);
+						*me.delay_token = None;
+						return Poll::Ready(RunSummary::Completed(value));
+					}
+
+					let reason = match me.shutdown_signal.as_mut().poll(context) {
+						Poll::Ready(reason) => reason,
+						Poll::Pending => return Poll::Pending,
+					};
+					me.shutdown_signal.inner.record_cancelled();
+					let cleanup = cleanup.take().expect("RunThenCleanup is missing the clean-up function");
+					// Drop the main future before starting the clean-up.
+					me.state.set(CleanupState::Done);
+					me.state.set(CleanupState::Cleanup { cleanup: cleanup(reason.clone()) });
+					*me.reason = Some(reason);
+				},
+				CleanupStateProj::Cleanup { cleanup } => {
+					let output = match cleanup.poll(context) {
+						Poll::Ready(output) => output,
+						Poll::Pending => return Poll::Pending,
+					};
+					me.state.set(CleanupState::Done);
+					*me.delay_token = None;
+					let reason = me.reason.take().expect("RunThenCleanup is missing the shutdown reason");
+					return Poll::Ready(RunSummary::CleanedUp { reason, cleanup: output });
+				},
+				CleanupStateProj::Done => panic!("RunThenCleanup polled after completion"),
+			}
+		}
+	}
+}
@@ -0,0 +1,255 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::lock::MutexExt;
+use crate::shutdown_signal::ShutdownSignal;
+use crate::{DelayShutdownToken, ShutdownAlreadyCompleted, ShutdownManager};
+
+/// A future spawned in a [`Scope`].
+type Child = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Handle to spawn futures in a structured-concurrency scope.
+///
+/// Created by [`ShutdownManager::scope()`], which passes the handle to the body of the scope.
+///
+/// Every future spawned in the scope is cancelled when the shutdown is triggered.
+/// The [`WrapScope`] future does not complete until its body and all spawned futures have finished or have been cancelled.
+///
+/// The handle can be cloned and sent to other tasks freely.
+/// Each clone spawns futures in the same scope.
+pub struct Scope<T: Clone> {
+	shutdown_signal: ShutdownSignal<T>,
+	state: Arc<Mutex<ScopeState>>,
+}
+
+/// The state of a scope that is shared with the [`Scope`] handles.
+struct ScopeState {
+	/// Futures that have been spawned, but that were not polled by the scope yet.
+	spawned: Vec<Child>,
+
+	/// The number of spawned futures that did not finish yet.
+	children: usize,
+
+	/// If true, the scope has finished or it has been dropped, and new futures are dropped immediately.
+	closed: bool,
+
+	/// The waker of the task that polls the scope.
+	waker: Option<Waker>,
+}
+
+impl<T: Clone> Clone for Scope<T> {
+	#[inline]
+	fn clone(&self) -> Self {
+		Self {
+			shutdown_signal: self.shutdown_signal.clone(),
+			state: self.state.clone(),
+		}
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for Scope<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let state = self.state.lock_unpoisoned();
+		f.debug_struct("Scope")
+			.field("children", &state.children)
+			.field("closed", &state.closed)
+			.finish()
+	}
+}
+
+impl<T: Clone + Send + 'static> Scope<T> {
+	/// Spawn a future in the scope.
+	///
+	/// The future is run concurrently with the body of the scope and the other spawned futures,
+	/// by the task that polls the [`WrapScope`] future.
+	/// It is cancelled (dropped) when the shutdown is triggered, and its output is discarded.
+	///
+	/// If the scope already finished or has been dropped, the future is dropped without being polled.
+	pub fn spawn<F>(&self, future: F)
+	where
+		F: Future + Send + 'static,
+	{
+		let future = self.shutdown_signal.wrap_cancel(future);
+		let mut state = self.state.lock_unpoisoned();
+		if state.closed {
+			return;
+		}
+		state.spawned.push(Box::pin(async move {
+			future.await.ok();
+		}));
+		state.children += 1;
+		if let Some(waker) = &state.waker {
+			waker.wake_by_ref();
+		}
+	}
+}
+
+impl<T: Clone> Scope<T> {
+	/// Get the number of spawned futures that did not finish yet.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.state.lock_unpoisoned().children
+	}
+
+	/// Check if all spawned futures have finished.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+/// Guard that closes the scope when the [`WrapScope`] future is dropped.
+struct CloseOnDrop {
+	state: Arc<Mutex<ScopeState>>,
+}
+
+impl Drop for CloseOnDrop {
+	fn drop(&mut self) {
+		let mut state = self.state.lock_unpoisoned();
+		state.closed = true;
+		state.children = 0;
+		state.waker = None;
+		let spawned = std::mem::take(&mut state.spawned);
+		drop(state);
+		// Drop the futures without holding the lock, in case they use the scope themselves.
+		drop(spawned);
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Future that runs the body of a [`Scope`] and all futures spawned in it.
+	///
+	/// Created by [`ShutdownManager::scope()`].
+	///
+	/// The future completes with the output of the body once the body and all spawned futures have finished.
+	/// It delays shutdown completion until then, or until it is dropped.
+	/// Dropping the future also drops all spawned futures.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapScope<T: Clone, F: Future> {
+		#[pin]
+		body: Option<F>,
+		output: Option<F::Output>,
+		// The spawned futures that have been polled at least once.
+		running: Vec<Child>,
+		delay_token: Option<DelayShutdownToken<T>>,
+		close: CloseOnDrop,
+	}
+}
+
+impl<T: Clone, F: Future> std::fmt::Debug for WrapScope<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapScope")
+			.field("body_finished", &self.body.is_none())
+			.field("children", &self.close.state.lock_unpoisoned().children)
+			.field("delay_token", &self.delay_token)
+			.finish()
+	}
+}
+
+impl<T: Clone, F: Future> Future for WrapScope<T, F> {
+	type Output = F::Output;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		if let Some(body) = me.body.as_mut().as_pin_mut() {
+			if let Poll::Ready(output) = body.poll(context) {
+				me.body.set(None);
+				*me.output = Some(output);
+			}
+		}
+
+		let mut finished = poll_children(me.running, context);
+		loop {
+			let mut spawned = {
+				let mut state = me.close.state.lock_unpoisoned();
+				state.children -= finished;
+				if state.spawned.is_empty() {
+					if me.output.is_some() && state.children == 0 {
+						state.closed = true;
+						state.waker = None;
+						break;
+					}
+					if !state.waker.as_ref().is_some_and(|waker| waker.will_wake(context.waker())) {
+						state.waker = Some(context.waker().clone());
+					}
+					return Poll::Pending;
+				}
+				std::mem::take(&mut state.spawned)
+			};
+			// The new futures may spawn more futures themselves, so poll them before checking again.
+			finished = poll_children(&mut spawned, context);
+			me.running.append(&mut spawned);
+		}
+
+		me.delay_token.take();
+		Poll::Ready(me.output.take().expect("WrapScope polled after completion"))
+	}
+}
+
+/// Poll the spawned futures of a scope, and remove the ones that finished.
+///
+/// Returns the number of futures that finished.
+fn poll_children(children: &mut Vec<Child>, context: &mut Context) -> usize {
+	let before = children.len();
+	children.retain_mut(|child| child.as_mut().poll(context).is_pending());
+	before - children.len()
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Run a structured-concurrency scope, where all spawned futures are cancelled when the shutdown is triggered.
+	///
+	/// The `body` function is called with a [`Scope`] handle to spawn futures in the scope.
+	/// The returned [`WrapScope`] future runs the body and all spawned futures concurrently,
+	/// and it does not complete until all of them have finished or have been cancelled.
+	/// This way, no spawned future can outlive the scope:
+	/// ```
+	/// # async fn handle(connection: u32) {}
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<()>) {
+	/// let served = shutdown.scope(|scope| async move {
+	///     for connection in 0..10 {
+	///         scope.spawn(handle(connection));
+	///     }
+	///     "accepted all connections"
+	/// }).unwrap().await;
+	/// # }
+	/// ```
+	///
+	/// The body itself is not cancelled when the shutdown is triggered,
+	/// but you can use [`Self::wrap_cancel()`] inside the body for that.
+	/// The scope holds a delay token until it completes, so the shutdown does not complete before the scope does.
+	///
+	/// The spawned futures are not spawned on an executor.
+	/// They are polled by the task that polls the [`WrapScope`] future, so they do not run in parallel.
+	/// If you need that, spawn them as tasks on your executor instead.
+	///
+	/// If the shutdown has already completed, this function returns an error and `body` is not called.
+	#[inline]
+	#[track_caller]
+	pub fn scope<F, B>(&self, body: B) -> Result<WrapScope<T, F>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		B: FnOnce(Scope<T>) -> F,
+	{
+		let delay_token = self.delay_shutdown_token()?;
+		let state = Arc::new(Mutex::new(ScopeState {
+			spawned: Vec::new(),
+			children: 0,
+			closed: false,
+			waker: None,
+		}));
+		let scope = Scope {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			state: state.clone(),
+		};
+		Ok(WrapScope {
+			body: Some(body(scope)),
+			output: None,
+			running: Vec::new(),
+			delay_token: Some(delay_token),
+			close: CloseOnDrop { state },
+		})
+	}
+}
@@ -0,0 +1,63 @@
+//! Support code for the [`shutdown_select!`][crate::shutdown_select] macro.
+
+use crate::{ShutdownManager, ShutdownSignal};
+
+#[doc(hidden)]
+pub use tokio;
+
+/// Get a shutdown signal for a `shutdown_select!` block.
+///
+/// Implemented for shutdown managers and shutdown signals, so that both can be used in the macro.
+#[doc(hidden)]
+pub trait SelectShutdown<T: Clone> {
+	/// Get a shutdown signal that completes when the shutdown is triggered.
+	fn select_shutdown_signal(&self) -> ShutdownSignal<T>;
+}
+
+impl<T: Clone> SelectShutdown<T> for ShutdownManager<T> {
+	#[inline]
+	fn select_shutdown_signal(&self) -> ShutdownSignal<T> {
+		self.wait_shutdown_triggered()
+	}
+}
+
+impl<T: Clone> SelectShutdown<T> for ShutdownSignal<T> {
+	#[inline]
+	fn select_shutdown_signal(&self) -> ShutdownSignal<T> {
+		self.clone()
+	}
+}
+
+/// Wait on multiple futures like [`tokio::select!`], with an implicit branch for the shutdown signal.
+///
+/// The first argument is a [`ShutdownManager`] or a [`ShutdownSignal`], followed by an `on_shutdown(reason) => handler` arm.
+/// The remaining branches use the syntax of [`tokio::select!`]:
+/// ```
+/// # async fn example(shutdown: async_shutdown::ShutdownManager<&'static str>, mut jobs: tokio::sync::mpsc::Receiver<u32>) -> Result<(), &'static str> {
+/// loop {
+///     async_shutdown::shutdown_select! {
+///         shutdown,
+///         on_shutdown(reason) => return Err(reason),
+///         Some(job) = jobs.recv() => println!("processing job {job}"),
+///         else => return Ok(()),
+///     }
+/// }
+/// # }
+/// ```
+///
+/// The shutdown branch has the highest priority:
+/// if the shutdown has been triggered, the `on_shutdown` handler runs even if other branches are ready too.
+/// To make that possible, the block is evaluated in `biased` mode,
+/// so the other branches are also polled in the order in which they are written instead of in random order.
+///
+/// The shutdown manager or signal is borrowed, so it can be used again in the next iteration of a loop.
+#[macro_export]
+macro_rules! shutdown_select {
+	($shutdown:expr, on_shutdown($reason:pat) => $handler:expr, $($branches:tt)*) => {
+		$crate::__select::tokio::select! {
+			biased;
+			$reason = $crate::__select::SelectShutdown::select_shutdown_signal(&$shutdown) => { $handler }
+			$($branches)*
+		}
+	};
+}
@@ -0,0 +1,102 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::task::Poll;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{DelayShutdownToken, ShutdownManager};
+
+/// A semaphore that rejects new work once the shutdown is triggered.
+///
+/// Created by [`ShutdownManager::semaphore()`].
+///
+/// [`Self::acquire()`] waits for a permit like a regular semaphore,
+/// but it fails with the shutdown reason once the shutdown has been triggered.
+/// Outstanding permits hold a delay token, so the shutdown does not complete until all permits have been dropped.
+/// This allows you to limit the number of concurrent requests, and to reject new requests while the old ones are drained.
+///
+/// The semaphore can be cloned and sent to different threads and tasks freely.
+/// Each clone uses the same internal state.
+#[derive(Clone)]
+pub struct ShutdownSemaphore<T: Clone> {
+	manager: ShutdownManager<T>,
+	semaphore: Arc<Semaphore>,
+}
+
+/// A permit from a [`ShutdownSemaphore`].
+///
+/// The permit is returned to the semaphore when it is dropped.
+/// The shutdown does not complete while the permit exists.
+#[must_use = "the permit is released immediately if it is not used"]
+pub struct ShutdownPermit<T: Clone> {
+	_permit: OwnedSemaphorePermit,
+	_delay_token: DelayShutdownToken<T>,
+}
+
+impl<T: Clone> ShutdownSemaphore<T> {
+	/// Get the number of permits that are currently available.
+	#[inline]
+	pub fn available_permits(&self) -> usize {
+		self.semaphore.available_permits()
+	}
+
+	/// Acquire a permit from the semaphore.
+	///
+	/// If the shutdown is triggered while waiting for a permit, or if it was already triggered,
+	/// this function returns `Err(shutdown_reason)`.
+	pub async fn acquire(&self) -> Result<ShutdownPermit<T>, T> {
+		let mut shutdown_signal = std::pin::pin!(self.manager.wait_shutdown_triggered());
+		let acquire = self.semaphore.clone().acquire_owned();
+		let mut acquire = std::pin::pin!(acquire);
+		let permit = std::future::poll_fn(|context| {
+			if let Poll::Ready(reason) = shutdown_signal.as_mut().poll(context) {
+				return Poll::Ready(Err(reason));
+			}
+			acquire
+				.as_mut()
+				.poll(context)
+				.map(|permit| Ok(permit.expect("ShutdownSemaphore is never closed")))
+		})
+		.await?;
+		self.permit(permit)
+	}
+
+	/// Try to acquire a permit from the semaphore without waiting.
+	///
+	/// Returns `Ok(None)` if no permits are available,
+	/// and `Err(shutdown_reason)` if the shutdown has been triggered.
+	pub fn try_acquire(&self) -> Result<Option<ShutdownPermit<T>>, T> {
+		if let Some(reason) = self.manager.shutdown_reason() {
+			return Err(reason);
+		}
+		match self.semaphore.clone().try_acquire_owned() {
+			Ok(permit) => self.permit(permit).map(Some),
+			Err(_) => Ok(None),
+		}
+	}
+
+	/// Wrap a semaphore permit together with a delay token.
+	fn permit(&self, permit: OwnedSemaphorePermit) -> Result<ShutdownPermit<T>, T> {
+		let delay_token = self.manager.delay_shutdown_token().map_err(|e| e.shutdown_reason)?;
+		Ok(ShutdownPermit {
+			_permit: permit,
+			_delay_token: delay_token,
+		})
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Create a semaphore with the given number of permits that rejects new work once the shutdown is triggered.
+	///
+	/// See [`ShutdownSemaphore`] for more details.
+	///
+	/// # Panics
+	/// This function panics if `permits` exceeds [`Semaphore::MAX_PERMITS`].
+	#[inline]
+	#[track_caller]
+	pub fn semaphore(&self, permits: usize) -> ShutdownSemaphore<T> {
+		ShutdownSemaphore {
+			manager: self.clone(),
+			semaphore: Arc::new(Semaphore::new(permits)),
+		}
+	}
+}
@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::hooks::HookFuture;
+use crate::lock::MutexExt;
+use crate::{DelayShutdownToken, ShutdownAlreadyCompleted, ShutdownManager};
+#[cfg(not(target_family = "wasm"))]
+use crate::{timer, Clock};
+
+/// A component that needs to run async clean-up code when the shutdown is triggered.
+///
+/// Services are registered with [`Services::register()`].
+/// The trait is implemented for all closures that take the shutdown reason and return a future,
+/// so you only need to implement it for your own types if you want to.
+pub trait Service<T>: Send + 'static {
+	/// The future returned by [`Self::shutdown()`].
+	type Future: Future<Output = ()> + Send + 'static;
+
+	/// Shut down the service.
+	fn shutdown(self, reason: T) -> Self::Future;
+}
+
+impl<T, F, Fut> Service<T> for F
+where
+	F: FnOnce(T) -> Fut + Send + 'static,
+	Fut: Future<Output = ()> + Send + 'static,
+{
+	type Future = Fut;
+
+	#[inline]
+	fn shutdown(self, reason: T) -> Self::Future {
+		self(reason)
+	}
+}
+
+/// A set of named services that are shut down one by one in registration order.
+///
+/// Created by [`Services::new()`] or [`ShutdownManager::services()`].
+///
+/// When the shutdown is triggered, the [`Service::shutdown()`] function of each service is called in the order in which the services were registered.
+/// The next service is only shut down after the previous one finished or timed out.
+/// Services can declare dependencies on other services with [`Self::depends_on()`],
+/// in which case a service is always shut down before the services that it depends on.
+/// Each service holds a delay token until it has been shut down, so the shutdown does not complete before all services are done.
+/// The name of the service is used as status of its delay token, so it shows up in [`ShutdownManager::pending_blockers()`].
+/// ```
+/// # async fn example() {
+/// # async fn stop_accepting() {}
+/// # async fn flush_database() {}
+/// let shutdown = async_shutdown::ShutdownManager::new();
+/// let services = shutdown.services();
+/// services.register("http", |_reason| stop_accepting()).unwrap();
+/// services.register("database", |_reason| flush_database()).unwrap();
+///
+/// shutdown.trigger_shutdown(0).ok();
+/// shutdown.wait_shutdown_complete().await;
+/// # }
+/// ```
+///
+/// The services are shut down by an [async shutdown hook][ShutdownManager::on_shutdown],
+/// so you must wait for the shutdown to complete somewhere in your application for them to run.
+///
+/// The set can be cloned and shared with multiple tasks.
+/// Each clone uses the same internal state.
+#[derive(Clone)]
+pub struct Services<T: Clone> {
+	manager: ShutdownManager<T>,
+	state: Arc<Mutex<ServicesState<T>>>,
+}
+
+/// The state of a set of services.
+struct ServicesState<T: Clone> {
+	/// The services that have not been shut down yet, in order.
+	queue: VecDeque<RegisteredService<T>>,
+
+	/// If true, a shutdown hook is registered to shut down the services in the queue.
+	running: bool,
+
+	/// The names of the services that timed out.
+	timed_out: Vec<String>,
+
+	/// The dependencies of each service, by name.
+	dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl<T: Clone> ServicesState<T> {
+	/// Check if `service` depends on `dependency`, directly or indirectly.
+	fn depends_on(&self, service: &str, dependency: &str) -> bool {
+		let mut todo = vec![service];
+		let mut seen = BTreeSet::new();
+		while let Some(service) = todo.pop() {
+			if service == dependency {
+				return true;
+			}
+			if seen.insert(service) {
+				if let Some(dependencies) = self.dependencies.get(service) {
+					todo.extend(dependencies.iter().map(String::as_str));
+				}
+			}
+		}
+		false
+	}
+
+	/// Take the next service to shut down.
+	///
+	/// This is the first service in registration order that no other queued service depends on.
+	fn take_next(&mut self) -> Option<RegisteredService<T>> {
+		let is_needed = |service: &RegisteredService<T>| {
+			self.queue.iter().any(|other| {
+				self.dependencies
+					.get(&other.name)
+					.is_some_and(|dependencies| dependencies.contains(&service.name))
+			})
+		};
+		// There are no cycles, so there is always a service that nothing depends on.
+		let index = self.queue.iter().position(|service| !is_needed(service)).unwrap_or(0);
+		self.queue.remove(index)
+	}
+}
+
+/// A service in the queue of a [`Services`] set.
+struct RegisteredService<T: Clone> {
+	name: String,
+	timeout: Option<Duration>,
+	shutdown: Box<dyn FnOnce(T) -> HookFuture + Send>,
+	delay_token: DelayShutdownToken<T>,
+}
+
+impl<T: Clone + Send + 'static> Services<T> {
+	/// Create a new empty set of services for a shutdown manager.
+	#[inline]
+	pub fn new(manager: &ShutdownManager<T>) -> Self {
+		Self {
+			manager: manager.clone(),
+			state: Arc::new(Mutex::new(ServicesState {
+				queue: VecDeque::new(),
+				running: false,
+				timed_out: Vec::new(),
+				dependencies: BTreeMap::new(),
+			})),
+		}
+	}
+
+	/// Register a service to be shut down when the shutdown is triggered.
+	///
+	/// If the shutdown has already been triggered, the service is shut down after the services that are still in the queue.
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn register<S: Service<T>>(&self, name: impl Into<String>, service: S) -> Result<(), ShutdownAlreadyCompleted<T>> {
+		self.add(RegisteredService {
+			name: name.into(),
+			timeout: None,
+			shutdown: Box::new(move |reason| Box::pin(service.shutdown(reason))),
+			delay_token: self.manager.delay_shutdown_token()?,
+		})
+	}
+
+	/// Register a service with a timeout for its shutdown.
+	///
+	/// If the shutdown of the service takes longer than `timeout`, the future returned by [`Service::shutdown()`] is dropped,
+	/// the service is reported by [`Self::timed_out()`], and the next service is shut down.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[cfg(not(target_family = "wasm"))]
+	pub fn register_with_timeout<S: Service<T>>(&self, name: impl Into<String>, timeout: Duration, service: S) -> Result<(), ShutdownAlreadyCompleted<T>> {
+		self.add(RegisteredService {
+			name: name.into(),
+			timeout: Some(timeout),
+			shutdown: Box::new(move |reason| Box::pin(service.shutdown(reason))),
+			delay_token: self.manager.delay_shutdown_token()?,
+		})
+	}
+
+	/// Declare that the service named `dependent` depends on the service named `dependency`.
+	///
+	/// The dependent service is shut down before its dependency,
+	/// so that for example an HTTP server stops before the database connection that it uses is closed:
+	/// ```
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// # let services = shutdown.services();
+	/// services.depends_on("http", "database").unwrap();
+	/// ```
+	///
+	/// The services do not need to be registered yet.
+	///
+	/// If the dependency would create a cycle, this function returns an error and the dependency is not added.
+	pub fn depends_on(&self, dependent: impl Into<String>, dependency: impl Into<String>) -> Result<(), DependencyCycle> {
+		let dependent = dependent.into();
+		let dependency = dependency.into();
+		let mut state = self.state.lock_unpoisoned();
+		if state.depends_on(&dependency, &dependent) {
+			return Err(DependencyCycle { dependent, dependency });
+		}
+		state.dependencies.entry(dependent).or_default().insert(dependency);
+		Ok(())
+	}
+
+	/// Get the names of the services that did not shut down within their timeout.
+	#[inline]
+	pub fn timed_out(&self) -> Vec<String> {
+		self.state.lock_unpoisoned().timed_out.clone()
+	}
+
+	/// Add a service to the queue, and make sure a shutdown hook is registered to process the queue.
+	fn add(&self, service: RegisteredService<T>) -> Result<(), ShutdownAlreadyCompleted<T>> {
+		service.delay_token.set_status(format!("service {}", service.name));
+		let mut state = self.state.lock_unpoisoned();
+		state.queue.push_back(service);
+		if state.running {
+			return Ok(());
+		}
+		state.running = true;
+		drop(state);
+
+		let state = self.state.clone();
+		#[cfg(not(target_family = "wasm"))]
+		let clock = self.manager.shared().clock();
+		let inner = Arc::downgrade(&self.manager.inner());
+		self.manager.on_shutdown(move |reason| async move {
+			loop {
+				let service = {
+					let mut state = state.lock_unpoisoned();
+					match state.take_next() {
+						Some(service) => service,
+						None => {
+							state.running = false;
+							break;
+						},
+					}
+				};
+				let RegisteredService { name, timeout, shutdown, delay_token } = service;
+				#[cfg(not(target_family = "wasm"))]
+				let finished = run_service(&*clock, timeout, shutdown(reason.clone())).await;
+				#[cfg(target_family = "wasm")]
+				let finished = run_service(timeout, shutdown(reason.clone())).await;
+				if !finished {
+					state.lock_unpoisoned().timed_out.push(name);
+					if let Some(inner) = inner.upgrade() {
+						inner.record_timed_out();
+					}
+				}
+				drop(delay_token);
+			}
+		})
+	}
+}
+
+/// Run the shutdown future of a service, and return `false` if it timed out.
+#[cfg(not(target_family = "wasm"))]
+async fn run_service(clock: &dyn Clock, timeout: Option<Duration>, future: HookFuture) -> bool {
+	match timeout {
+		Some(timeout) => timer::timeout(clock, timeout, future).await.is_some(),
+		None => {
+			future.await;
+			true
+		},
+	}
+}
+
+/// Run the shutdown future of a service.
+#[cfg(target_family = "wasm")]
+async fn run_service(_timeout: Option<Duration>, future: HookFuture) -> bool {
+	future.await;
+	true
+}
+
+/// Error returned by [`Services::depends_on()`] when the dependency would create a cycle.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DependencyCycle {
+	/// The name of the dependent service.
+	pub dependent: String,
+
+	/// The name of the service that it would depend on.
+	pub dependency: String,
+}
+
+impl std::error::Error for DependencyCycle {}
+
+impl std::fmt::Display for DependencyCycle {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "service {:?} can not depend on {:?}: that would create a dependency cycle", self.dependent, self.dependency)
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Create a new empty set of services that are shut down in order when the shutdown is triggered.
+	///
+	/// See [`Services`] for more details.
+	#[inline]
+	pub fn services(&self) -> Services<T> {
+		Services::new(self)
+	}
+}
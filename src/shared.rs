@@ -0,0 +1,345 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{RepeatTrigger, ShutdownAlreadyStarted, ShutdownManagerInner};
+use crate::lock::MutexExt;
+use crate::metrics_export;
+
+/// The generation of a shutdown manager, and whether the shutdown of that generation has been triggered.
+///
+/// This is shared by the shutdown manager and the state of all its generations,
+/// so that [`ShutdownManager::is_shutdown_triggered()`][crate::ShutdownManager::is_shutdown_triggered] does not need to take a lock.
+pub(crate) struct GenerationStatus {
+	/// The generation number shifted left by one, with the lowest bit set if the shutdown has been triggered.
+	value: AtomicU64,
+}
+
+impl GenerationStatus {
+	/// Create the status for generation 0.
+	pub fn new() -> Self {
+		Self { value: AtomicU64::new(0) }
+	}
+
+	/// Get the current generation.
+	pub fn generation(&self) -> u64 {
+		self.value.load(Ordering::Acquire) >> 1
+	}
+
+	/// Check if the shutdown of the current generation has been triggered.
+	pub fn is_triggered(&self) -> bool {
+		self.value.load(Ordering::Acquire) & 1 != 0
+	}
+
+	/// Mark the shutdown as triggered, if `generation` is still the current generation.
+	fn set_triggered(&self, generation: u64) {
+		self.value
+			.compare_exchange(generation << 1, generation << 1 | 1, Ordering::AcqRel, Ordering::Acquire)
+			.ok();
+	}
+
+	/// Start a new generation.
+	pub fn start(&self, generation: u64) {
+		self.value.store(generation << 1, Ordering::Release);
+	}
+}
+
+/// The state of one generation of a shutdown manager.
+///
+/// Most of the state is protected by a mutex,
+/// but the values that are checked in hot paths are also available as atomics.
+pub(crate) struct Shared<T> {
+	/// The status of the shutdown manager, shared with all generations.
+	status: Arc<GenerationStatus>,
+
+	/// The generation that this state belongs to.
+	generation: u64,
+
+	/// If true, the shutdown of this generation has been triggered.
+	///
+	/// This is set before the shutdown reason is stored, so if it is false, the shutdown has certainly not been triggered yet.
+	triggered: AtomicBool,
+
+	/// The total number of delay tokens in existence, including clones.
+	pub(crate) delay_tokens: AtomicUsize,
+
+	/// The number of wrapped futures that have been cancelled by the shutdown.
+	pub(crate) cancelled_futures: AtomicU64,
+
+	/// The number of shutdown hooks and services that were abandoned because they timed out.
+	pub(crate) timed_out_hooks: AtomicU64,
+
+	/// The exported gauge with the number of delay tokens.
+	pub(crate) delay_token_gauge: metrics_export::Gauge,
+
+	/// The state that is protected by the mutex.
+	inner: Mutex<ShutdownManagerInner<T>>,
+}
+
+impl<T: Clone> Shared<T> {
+	/// Create the state for a new generation.
+	#[track_caller]
+	pub fn new(status: Arc<GenerationStatus>, generation: u64) -> Self {
+		Self {
+			status,
+			generation,
+			triggered: AtomicBool::new(false),
+			delay_tokens: AtomicUsize::new(0),
+			cancelled_futures: AtomicU64::new(0),
+			timed_out_hooks: AtomicU64::new(0),
+			delay_token_gauge: metrics_export::Gauge::new(metrics_export::DELAY_TOKENS),
+			inner: Mutex::new(ShutdownManagerInner::new()),
+		}
+	}
+
+	/// Get the generation that this state belongs to.
+	pub fn generation(&self) -> u64 {
+		self.generation
+	}
+
+	/// Lock the mutex protected part of the state.
+	///
+	/// If the shutdown completes while the lock is held, the completion callbacks are run when the guard is dropped.
+	pub fn lock(&self) -> LockGuard<'_, T> {
+		LockGuard {
+			guard: Some(self.inner.lock_unpoisoned()),
+		}
+	}
+
+	/// Check if the shutdown has been triggered without taking the lock.
+	pub fn is_triggered(&self) -> bool {
+		self.triggered.load(Ordering::Acquire)
+	}
+
+	/// Record that a wrapped future was cancelled by the shutdown.
+	pub fn record_cancelled(&self) {
+		self.cancelled_futures.fetch_add(1, Ordering::Relaxed);
+		metrics_export::record_cancelled();
+	}
+
+	/// Get the clock that drives the timeouts and deadlines of this generation.
+	#[cfg(not(target_family = "wasm"))]
+	pub fn clock(&self) -> Arc<dyn crate::Clock> {
+		self.lock().clock.clone()
+	}
+
+	/// Get a handle to the shared grace timer for the given grace period, starting it if it is not running yet.
+	#[cfg(not(target_family = "wasm"))]
+	pub fn grace_timer(&self, grace: std::time::Duration) -> crate::timer::Timer {
+		let mut inner = self.lock();
+		if let Some((_, timer)) = inner.grace_timers.iter().find(|(duration, _)| *duration == grace) {
+			return timer.share();
+		}
+		let timer = crate::timer::Timer::new(&*inner.clock, grace);
+		let handle = timer.share();
+		inner.grace_timers.push((grace, timer));
+		handle
+	}
+
+	/// Record that a shutdown hook or service was abandoned because it timed out.
+	pub fn record_timed_out(&self) {
+		self.timed_out_hooks.fetch_add(1, Ordering::Relaxed);
+		metrics_export::record_timed_out();
+	}
+
+	/// Trigger the shutdown on request of the user, and apply the [`RepeatTrigger`] policy if it was already triggered.
+	///
+	/// Internal triggers, like dropped trigger tokens, must use [`Self::shutdown_idempotent()`] instead,
+	/// so they never escalate to a forced shutdown.
+	pub fn trigger(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		let inner = self.lock();
+		if inner.shutdown_reason.is_some() && inner.repeat_trigger == RepeatTrigger::Force {
+			drop(inner);
+			return self.shutdown_forced(reason);
+		}
+		drop(inner);
+		self.shutdown(reason)
+	}
+
+	/// Trigger the shutdown.
+	pub fn shutdown(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		let mut inner = self.lock();
+		self.shutdown_locked(&mut inner, reason.clone())?;
+		inner.unlock_and_run_trigger_callbacks(&reason);
+		Ok(())
+	}
+
+	/// Trigger the shutdown if it was not triggered yet.
+	///
+	/// Returns `true` if this call triggered the shutdown.
+	pub fn shutdown_idempotent(&self, reason: T) -> bool {
+		let mut inner = self.lock();
+		if inner.shutdown_reason.is_some() || self.shutdown_locked(&mut inner, reason.clone()).is_err() {
+			return false;
+		}
+		inner.unlock_and_run_trigger_callbacks(&reason);
+		true
+	}
+
+	/// Trigger the drain.
+	pub fn drain(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		let mut inner = self.lock();
+		if let Some(original_reason) = &inner.drain_reason {
+			return Err(ShutdownAlreadyStarted::new(original_reason.clone(), reason));
+		}
+		inner.drain(reason);
+		Ok(())
+	}
+
+	/// Trigger the forced shutdown, and the regular shutdown if it was not triggered yet.
+	pub fn shutdown_forced(&self, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		let mut inner = self.lock();
+		if let Some(original_reason) = &inner.forced_reason {
+			return Err(ShutdownAlreadyStarted::new(original_reason.clone(), reason));
+		}
+		if inner.shutdown_reason.is_none() {
+			self.shutdown_locked(&mut inner, reason.clone())?;
+		}
+		inner.forced_reason = Some(reason.clone());
+		inner.on_forced_shutdown.wake_all();
+		inner.unlock_and_run_trigger_callbacks(&reason);
+		Ok(())
+	}
+
+	/// Register a callback to run synchronously when the shutdown is triggered.
+	///
+	/// The callback runs on the thread that triggers the shutdown, without holding the lock.
+	/// If the shutdown has already been triggered, the callback runs immediately.
+	pub fn on_trigger(&self, callback: TriggerCallback<T>) {
+		let mut inner = self.lock();
+		match inner.shutdown_reason.clone() {
+			Some(reason) => {
+				drop(inner);
+				callback(&reason);
+			},
+			None => inner.trigger_callbacks.push(callback),
+		}
+	}
+
+	/// Register a callback to run synchronously when the shutdown completes.
+	///
+	/// The callback runs on the thread that completes the shutdown, without holding the lock.
+	/// If the shutdown has already completed, the callback runs immediately.
+	pub fn on_complete(&self, callback: TriggerCallback<T>) {
+		let mut inner = self.lock();
+		match inner.completed_reason() {
+			Some(reason) => {
+				drop(inner);
+				callback(&reason);
+			},
+			None => inner.completion_callbacks.push(callback),
+		}
+	}
+
+	/// Trigger the shutdown while already holding the lock.
+	fn shutdown_locked(&self, inner: &mut ShutdownManagerInner<T>, reason: T) -> Result<(), ShutdownAlreadyStarted<T>> {
+		if let Some(original_reason) = &inner.shutdown_reason {
+			return Err(ShutdownAlreadyStarted::new(original_reason.clone(), reason));
+		}
+		// Set the flags before waking anyone, so woken tasks are guaranteed to see them.
+		self.triggered.store(true, Ordering::Release);
+		self.status.set_triggered(self.generation);
+		inner.shutdown(reason)
+	}
+}
+
+/// A callback that runs synchronously when the shutdown is triggered or completed.
+pub(crate) type TriggerCallback<T> = Box<dyn FnOnce(&T) + Send>;
+
+/// Guard for the mutex protected part of the state of a shutdown manager.
+///
+/// When the guard is dropped, it releases the lock and then runs the completion callbacks if the shutdown completed.
+/// This way, the callbacks can never run while the lock is held, no matter where the shutdown completes.
+pub(crate) struct LockGuard<'a, T: Clone> {
+	guard: Option<MutexGuard<'a, ShutdownManagerInner<T>>>,
+}
+
+impl<T: Clone> LockGuard<'_, T> {
+	/// Release the lock and run the trigger callbacks, followed by the completion callbacks if the shutdown also completed.
+	fn unlock_and_run_trigger_callbacks(mut self, reason: &T) {
+		let callbacks = std::mem::take(&mut self.trigger_callbacks);
+		let ready = self.ready_completion_callbacks.take();
+		drop(self);
+		run_trigger_callbacks(callbacks, reason);
+		if let Some((callbacks, reason)) = ready {
+			run_trigger_callbacks(callbacks, &reason);
+		}
+	}
+}
+
+impl<T: Clone> Deref for LockGuard<'_, T> {
+	type Target = ShutdownManagerInner<T>;
+
+	#[inline]
+	fn deref(&self) -> &Self::Target {
+		self.guard.as_ref().unwrap()
+	}
+}
+
+impl<T: Clone> DerefMut for LockGuard<'_, T> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.guard.as_mut().unwrap()
+	}
+}
+
+impl<T: Clone> Drop for LockGuard<'_, T> {
+	#[inline]
+	fn drop(&mut self) {
+		if let Some(mut guard) = self.guard.take() {
+			let ready = guard.ready_completion_callbacks.take();
+			drop(guard);
+			if let Some((callbacks, reason)) = ready {
+				run_trigger_callbacks(callbacks, &reason);
+			}
+		}
+	}
+}
+
+/// Run the trigger callbacks in the order in which they were registered.
+fn run_trigger_callbacks<T>(callbacks: Vec<TriggerCallback<T>>, reason: &T) {
+	for callback in callbacks {
+		callback(reason);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use assert2::{assert, let_assert};
+
+	#[test]
+	fn cloning_delay_token_does_not_lock() {
+		let shutdown = crate::ShutdownManager::<()>::new();
+		let_assert!(Ok(token) = shutdown.delay_shutdown_token());
+
+		// Cloning and dropping a token that is not the last clone must not take the lock, or it would deadlock here.
+		let inner = shutdown.inner();
+		let guard = inner.lock();
+		let clone = token.clone();
+		drop(token);
+		assert!(inner.delay_tokens.load(std::sync::atomic::Ordering::Relaxed) == 1);
+		drop(guard);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(clone);
+		assert!(shutdown.is_shutdown_completed());
+	}
+
+	#[test]
+	fn generation_status() {
+		let status = super::GenerationStatus::new();
+		assert!(status.generation() == 0);
+		assert!(status.is_triggered() == false);
+		status.set_triggered(0);
+		assert!(status.is_triggered());
+
+		status.start(1);
+		assert!(status.generation() == 1);
+		assert!(status.is_triggered() == false);
+
+		// Triggering an old generation does nothing.
+		status.set_triggered(0);
+		assert!(status.is_triggered() == false);
+	}
+}
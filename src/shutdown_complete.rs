@@ -1,33 +1,57 @@
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use crate::waker_list::WakerToken;
-use crate::ShutdownManagerInner;
+use crate::async_drop::drive_async_drops;
+use crate::hooks::drive_hooks;
+use crate::instrument::AsyncOp;
+use crate::waker_list::Waiter;
+use crate::shared::Shared;
 
 /// Future to wait for a shutdown to complete.
+///
+/// Like [`ShutdownSignal`][crate::ShutdownSignal], the future stores its own node of the list of waiting tasks,
+/// so it is not [`Unpin`].
 pub struct ShutdownComplete<T: Clone> {
-	pub(crate) inner: Arc<Mutex<ShutdownManagerInner<T>>>,
-	pub(crate) waker_token: Option<WakerToken>,
+	pub(crate) inner: Arc<Shared<T>>,
+	pub(crate) waiter: Waiter,
+	pub(crate) driving_hooks: bool,
+	pub(crate) async_op: AsyncOp,
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownComplete<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownComplete")
+			.field("completed", &self.inner.lock().completed_reason().is_some())
+			.field("waker_registered", &self.waiter.is_registered())
+			.finish()
+	}
 }
 
 impl<T: Clone> Clone for ShutdownComplete<T> {
 	fn clone(&self) -> Self {
-		// Clone only the reference to the shutdown manager, not the waker token.
-		// The waker token is personal to each future.
+		// Clone only the reference to the shutdown manager, not the waiter.
+		// The waiter is personal to each future.
 		Self {
 			inner: self.inner.clone(),
-			waker_token: None,
+			waiter: Waiter::new(),
+			driving_hooks: false,
+			async_op: AsyncOp::default(),
 		}
 	}
 }
 
 impl<T: Clone> Drop for ShutdownComplete<T> {
 	fn drop(&mut self) {
-		if let Some(token) = self.waker_token.take() {
-			let mut inner = self.inner.lock().unwrap();
-			inner.on_shutdown_complete.deregister(token);
+		if self.waiter.is_registered() {
+			let mut inner = self.inner.lock();
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { inner.on_shutdown_complete.deregister(&self.waiter) };
+		}
+		if self.driving_hooks {
+			// Let another future take over running the shutdown hooks.
+			self.inner.lock().wake_hook_drivers();
 		}
 	}
 }
@@ -37,28 +61,70 @@ impl<T: Clone> Future for ShutdownComplete<T> {
 
 	#[inline]
 	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
-		let me = self.get_mut();
-		let mut inner = me.inner.lock().unwrap();
-
-		// We're being polled, so we should deregister the waker (if any).
-		if let Some(token) = me.waker_token.take() {
-			inner.on_shutdown_complete.deregister(token);
-		}
+		// SAFETY: We never move the waiter out of the pinned future.
+		let me = unsafe { self.get_unchecked_mut() };
+		me.driving_hooks = drive_hooks(&me.inner, context);
+		let mut inner = me.inner.lock();
+		inner.resource.init_async_op(&mut me.async_op, "ShutdownComplete::poll");
+		let _entered = me.async_op.enter();
 
 		// Check if the shutdown is completed.
-		if inner.delay_tokens == 0 {
-			if let Some(reason) = inner.shutdown_reason.clone() {
+		if let Some(reason) = inner.completed_reason() {
+			// Run the async drop hooks first, and wait for them to finish.
+			if !inner.async_drops.is_done() {
+				// Register the waker before releasing the lock, so we do not miss the wake-up when another future finishes the hooks.
+				// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+				unsafe { inner.on_shutdown_complete.register(Pin::new_unchecked(&me.waiter), context.waker()) };
+				drop(inner);
+				if !drive_async_drops(&me.inner, &reason, context) {
+					me.driving_hooks = true;
+					return Poll::Pending;
+				}
+				inner = me.inner.lock();
+			}
+
+			// We're done waiting for the completion, so we should deregister the waker (if any).
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { inner.on_shutdown_complete.deregister(&me.waiter) };
+
+			// Run the flush hooks if nobody else did yet.
+			if let Some(hooks) = inner.flush_hooks.start() {
+				drop(inner);
+				let _guard = FinishFlushHooks { inner: &me.inner };
+				for hook in hooks {
+					hook();
+				}
+				return Poll::Ready(reason);
+			}
+
+			// Only resolve once the flush hooks are done.
+			if inner.flush_hooks.is_done() {
 				return Poll::Ready(reason);
 			}
 		}
 
-		// We're not ready, so register the waker to wake us on shutdown completion.
-		me.waker_token = Some(inner.on_shutdown_complete.register(context.waker().clone()));
+		// We're not ready, so register (or update) the waker to wake us on shutdown completion.
+		// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+		// The waiter is deregistered when the future is dropped.
+		unsafe { inner.on_shutdown_complete.register(Pin::new_unchecked(&me.waiter), context.waker()) };
 
 		Poll::Pending
 	}
 }
 
+/// Guard that marks the flush hooks as done when dropped, even if one of the hooks panicked.
+struct FinishFlushHooks<'a, T: Clone> {
+	inner: &'a Shared<T>,
+}
+
+impl<T: Clone> Drop for FinishFlushHooks<'_, T> {
+	fn drop(&mut self) {
+		let mut inner = self.inner.lock();
+		inner.flush_hooks.finish();
+		inner.on_shutdown_complete.wake_all();
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use assert2::assert;
@@ -86,7 +152,7 @@ mod test {
 	async fn waker_list_doesnt_grow_infinitely() {
 		let shutdown = crate::ShutdownManager::<()>::new();
 		for i in 0..100_000 {
-			let mut wait_shutdown_complete = shutdown.wait_shutdown_complete();
+			let mut wait_shutdown_complete = Box::pin(shutdown.wait_shutdown_complete());
 			let task = tokio::spawn(async move {
 				assert!(let Poll::Pending = poll_once(&mut wait_shutdown_complete).await);
 			});
@@ -94,46 +160,35 @@ mod test {
 		}
 
 		// Since we wait for each task to complete before spawning another,
-		// the total amount of waker slots used should be only 1.
-		let inner = shutdown.inner.lock().unwrap();
-		assert!(inner.on_shutdown_complete.total_slots() == 1);
-		assert!(inner.on_shutdown_complete.empty_slots() == 1);
+		// no waiters should be left in the list.
+		let inner = shutdown.inner();
+		let inner = inner.lock();
+		assert!(inner.on_shutdown_complete.len() == 0);
 	}
 
 	#[tokio::test]
-	async fn cloning_does_not_clone_waker_token() {
+	async fn cloning_does_not_clone_waiter() {
 		let shutdown = crate::ShutdownManager::<()>::new();
 
-		let mut signal = shutdown.wait_shutdown_complete();
-		assert!(let None = &signal.waker_token);
+		let mut signal = Box::pin(shutdown.wait_shutdown_complete());
+		assert!(!signal.waiter.is_registered());
 
 		assert!(let Poll::Pending = poll_once(&mut signal).await);
-		assert!(let Some(_) = &signal.waker_token);
+		assert!(signal.waiter.is_registered());
 
-		let mut cloned = signal.clone();
-		assert!(let None = &cloned.waker_token);
-		assert!(let Some(_) = &signal.waker_token);
+		let mut cloned = Box::pin((*signal).clone());
+		assert!(!cloned.waiter.is_registered());
+		assert!(signal.waiter.is_registered());
 
 		assert!(let Poll::Pending = poll_once(&mut cloned).await);
-		assert!(let Some(_) = &cloned.waker_token);
-		assert!(let Some(_) = &signal.waker_token);
+		assert!(cloned.waiter.is_registered());
+		assert!(signal.waiter.is_registered());
+		assert!(shutdown.completion_waiter_count() == 2);
 
-		{
-			let inner = shutdown.inner.lock().unwrap();
-			assert!(inner.on_shutdown_complete.total_slots() == 2);
-			assert!(inner.on_shutdown_complete.empty_slots() == 0);
-		}
+		drop(signal);
+		assert!(shutdown.completion_waiter_count() == 1);
 
-		{
-			drop(signal);
-			let inner = shutdown.inner.lock().unwrap();
-			assert!(inner.on_shutdown_complete.empty_slots() == 1);
-		}
-
-		{
-			drop(cloned);
-			let inner = shutdown.inner.lock().unwrap();
-			assert!(inner.on_shutdown_complete.empty_slots() == 2);
-		}
+		drop(cloned);
+		assert!(shutdown.completion_waiter_count() == 0);
 	}
 }
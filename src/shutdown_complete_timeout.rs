@@ -0,0 +1,51 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::task::{Context, Poll};
+
+use crate::timer::Timer;
+use crate::{ShutdownComplete, ShutdownTimedOut};
+
+pin_project_lite::pin_project! {
+	/// Future to wait for a shutdown to complete, with a timeout.
+	///
+	/// Created by [`ShutdownManager::wait_shutdown_complete_timeout()`][crate::ShutdownManager::wait_shutdown_complete_timeout].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct ShutdownCompleteTimeout<T: Clone> {
+		#[pin]
+		pub(crate) shutdown_complete: ShutdownComplete<T>,
+		#[pin]
+		pub(crate) timer: Timer,
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownCompleteTimeout<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownCompleteTimeout")
+			.field("shutdown_complete", &self.shutdown_complete)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: Clone> Future for ShutdownCompleteTimeout<T> {
+	type Output = Result<T, ShutdownTimedOut<T>>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+		if let Poll::Ready(reason) = me.shutdown_complete.as_mut().poll(context) {
+			return Poll::Ready(Ok(reason));
+		}
+		if me.timer.poll_expired(context).is_pending() {
+			return Poll::Pending;
+		}
+
+		let inner = &me.shutdown_complete.inner;
+		let locked = inner.lock();
+		let shutdown_reason = locked.shutdown_reason.clone();
+		let blockers = locked.blockers.snapshot();
+		drop(locked);
+		let delay_tokens = inner.delay_tokens.load(Ordering::Relaxed);
+		Poll::Ready(Err(ShutdownTimedOut::new(shutdown_reason, delay_tokens, blockers)))
+	}
+}
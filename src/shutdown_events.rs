@@ -0,0 +1,82 @@
+use futures_core::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{ShutdownComplete, ShutdownSignal};
+
+/// An event in the lifetime of a shutdown.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ShutdownEvent<T> {
+	/// The shutdown was triggered with the given reason.
+	Triggered(T),
+
+	/// The forced shutdown was triggered with the given reason.
+	ForcedTriggered(T),
+
+	/// The shutdown completed.
+	Completed(T),
+}
+
+/// Stream of [`ShutdownEvent`]s.
+///
+/// Created by [`ShutdownManager::events()`][crate::ShutdownManager::events].
+///
+/// The stream yields each event at most once, in the order in which they happened.
+/// It ends after yielding [`ShutdownEvent::Completed`].
+/// A forced shutdown that is triggered after the shutdown completed is not reported.
+#[must_use = "streams do nothing unless polled"]
+pub struct ShutdownEvents<T: Clone> {
+	// Boxed so the stream is `Unpin`.
+	pub(crate) triggered: Option<Pin<Box<ShutdownSignal<T>>>>,
+	pub(crate) forced: Option<Pin<Box<ShutdownSignal<T>>>>,
+	pub(crate) completed: Option<Pin<Box<ShutdownComplete<T>>>>,
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownEvents<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownEvents")
+			.field("triggered", &self.triggered)
+			.field("forced", &self.forced)
+			.field("completed", &self.completed)
+			.finish()
+	}
+}
+
+impl<T: Clone> Stream for ShutdownEvents<T> {
+	type Item = ShutdownEvent<T>;
+
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+		let me = self.get_mut();
+
+		if let Some(triggered) = &mut me.triggered {
+			let reason = match triggered.as_mut().poll(context) {
+				Poll::Ready(reason) => reason,
+				Poll::Pending => return Poll::Pending,
+			};
+			me.triggered = None;
+			return Poll::Ready(Some(ShutdownEvent::Triggered(reason)));
+		}
+
+		let completed = match &mut me.completed {
+			Some(completed) => completed,
+			None => return Poll::Ready(None),
+		};
+
+		if let Poll::Ready(reason) = completed.as_mut().poll(context) {
+			me.completed = None;
+			me.forced = None;
+			return Poll::Ready(Some(ShutdownEvent::Completed(reason)));
+		}
+
+		if let Some(forced) = &mut me.forced {
+			if let Poll::Ready(reason) = forced.as_mut().poll(context) {
+				me.forced = None;
+				return Poll::Ready(Some(ShutdownEvent::ForcedTriggered(reason)));
+			}
+		}
+
+		Poll::Pending
+	}
+}
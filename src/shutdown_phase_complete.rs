@@ -0,0 +1,95 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::hooks::drive_hooks;
+use crate::instrument::AsyncOp;
+use crate::waker_list::Waiter;
+use crate::shared::Shared;
+
+/// Future to wait for a shutdown phase to complete.
+///
+/// A phase is complete when the shutdown has been triggered,
+/// and all delay tokens for that phase and all earlier phases have been dropped.
+///
+/// The future stores its own node of the list of waiting tasks, so it is not [`Unpin`].
+pub struct ShutdownPhaseComplete<T: Clone> {
+	pub(crate) inner: Arc<Shared<T>>,
+	pub(crate) phase: usize,
+	pub(crate) waiter: Waiter,
+	pub(crate) driving_hooks: bool,
+	pub(crate) async_op: AsyncOp,
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownPhaseComplete<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownPhaseComplete")
+			.field("phase", &self.phase)
+			.field("waker_registered", &self.waiter.is_registered())
+			.finish()
+	}
+}
+
+impl<T: Clone> ShutdownPhaseComplete<T> {
+	/// Get the phase that this future is waiting for.
+	#[inline]
+	pub fn phase(&self) -> usize {
+		self.phase
+	}
+}
+
+impl<T: Clone> Clone for ShutdownPhaseComplete<T> {
+	fn clone(&self) -> Self {
+		// Clone only the reference to the shutdown manager, not the waiter.
+		// The waiter is personal to each future.
+		Self {
+			inner: self.inner.clone(),
+			phase: self.phase,
+			waiter: Waiter::new(),
+			driving_hooks: false,
+			async_op: AsyncOp::default(),
+		}
+	}
+}
+
+impl<T: Clone> Drop for ShutdownPhaseComplete<T> {
+	fn drop(&mut self) {
+		if self.waiter.is_registered() {
+			let mut inner = self.inner.lock();
+			// SAFETY: The waiter is only registered for this phase, and we hold the lock.
+			unsafe { inner.phases.deregister(self.phase, &self.waiter) };
+		}
+		if self.driving_hooks {
+			// Let another future take over running the shutdown hooks.
+			self.inner.lock().wake_hook_drivers();
+		}
+	}
+}
+
+impl<T: Clone> Future for ShutdownPhaseComplete<T> {
+	type Output = T;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		// SAFETY: We never move the waiter out of the pinned future.
+		let me = unsafe { self.get_unchecked_mut() };
+		me.driving_hooks = drive_hooks(&me.inner, context);
+		let mut inner = me.inner.lock();
+		inner.resource.init_async_op(&mut me.async_op, "ShutdownPhaseComplete::poll");
+		let _entered = me.async_op.enter();
+
+		if let Some(reason) = inner.phase_completed(me.phase) {
+			// The phase is complete, so we should deregister the waker (if any).
+			// SAFETY: The waiter is only registered for this phase, and we hold the lock.
+			unsafe { inner.phases.deregister(me.phase, &me.waiter) };
+			return Poll::Ready(reason);
+		}
+
+		// We're not ready, so register (or update) the waker to wake us on phase completion.
+		// SAFETY: The waiter is pinned, it is only registered for this phase, and we hold the lock.
+		// The waiter is deregistered when the future is dropped.
+		unsafe { inner.phases.register(me.phase, Pin::new_unchecked(&me.waiter), context.waker()) };
+		Poll::Pending
+	}
+}
@@ -1,42 +1,191 @@
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::{Arc, Mutex};
-use std::task::{Context, Poll};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+#[cfg(not(target_family = "wasm"))]
+use std::time::Duration;
 
-use crate::waker_list::WakerToken;
-use crate::{WrapCancel, ShutdownManagerInner};
+use crate::protect::CancelScope;
+use crate::instrument::AsyncOp;
+use crate::waker_list::Waiter;
+use crate::shared::Shared;
+use crate::{DelayShutdownToken, IgnoreShutdownReason, RunThenCleanup, ShutdownAlreadyCompleted, ShutdownSignalThen, TryWrapCancel, WrapCancel, WrapCancelMapErr, WrapCancelOr, WrapCancelOrElse, WrapCancelWithCleanup};
+#[cfg(not(target_family = "wasm"))]
+use crate::WrapCancelWithGrace;
+#[cfg(feature = "stream")]
+use crate::ForEachUntilShutdown;
 
 /// A future to wait for a shutdown signal.
 ///
 /// The future completes when the associated [`ShutdownManager`][crate::ShutdownManager] triggers a shutdown.
 ///
 /// The shutdown signal can be cloned and sent between threads freely.
+///
+/// Signals created with [`ShutdownManager::wait_forced_shutdown_triggered()`][crate::ShutdownManager::wait_forced_shutdown_triggered]
+/// complete when the forced shutdown is triggered instead,
+/// and signals created with [`ShutdownManager::wait_drain_triggered()`][crate::ShutdownManager::wait_drain_triggered]
+/// complete when the drain is triggered.
+///
+/// The signal stores its own node of the list of waiting tasks, so waiting for the shutdown never allocates.
+/// Because the list points to the node, the signal is not [`Unpin`]:
+/// use [`std::pin::pin!`] or [`Box::pin()`] to poll it through a mutable reference.
 pub struct ShutdownSignal<T: Clone> {
-	pub(crate) inner: Arc<Mutex<ShutdownManagerInner<T>>>,
-	pub(crate) waker_token: Option<WakerToken>,
+	pub(crate) inner: Arc<Shared<T>>,
+
+	/// The event that the signal waits for.
+	pub(crate) kind: SignalKind,
+	pub(crate) waiter: Waiter,
+
+	/// The waker that was registered with the waiter.
+	///
+	/// Used to skip taking the lock if the same task polls the signal again before the shutdown is triggered.
+	pub(crate) waker: Option<Waker>,
+	pub(crate) async_op: AsyncOp,
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownSignal<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownSignal")
+			.field("kind", &self.kind)
+			.field("triggered", &self.is_triggered())
+			.field("waker_registered", &self.waiter.is_registered())
+			.finish()
+	}
+}
+
+/// The event that a [`ShutdownSignal`] waits for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum SignalKind {
+	/// The drain, which is also triggered by the regular shutdown.
+	Drain,
+
+	/// The regular shutdown.
+	Shutdown,
+
+	/// The forced shutdown.
+	Forced,
 }
 
 impl<T: Clone> Clone for ShutdownSignal<T> {
 	fn clone(&self) -> Self {
-		// Clone only the reference to the shutdown manager, not the waker token.
-		// The waker token is personal to each future.
+		// Clone only the reference to the shutdown manager, not the waiter.
+		// The waiter is personal to each future.
 		Self {
 			inner: self.inner.clone(),
-			waker_token: None,
+			kind: self.kind,
+			waiter: Waiter::new(),
+			waker: None,
+			async_op: AsyncOp::default(),
 		}
 	}
 }
 
 impl<T: Clone> Drop for ShutdownSignal<T> {
 	fn drop(&mut self) {
-		if let Some(token) = self.waker_token.take() {
-			let mut inner = self.inner.lock().unwrap();
-			inner.on_shutdown.deregister(token);
+		// Triggering the shutdown clears the list of waiters,
+		// so we only need to take the lock if the waiter is still registered.
+		if self.waiter.is_registered() {
+			let mut inner = self.inner.lock();
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { inner.shutdown_waiters(self.kind).deregister(&self.waiter) };
 		}
 	}
 }
 
 impl<T: Clone> ShutdownSignal<T> {
+	/// Check if the event that this signal waits for has happened, without polling the signal.
+	///
+	/// For signals from [`ShutdownManager::wait_shutdown_triggered()`][crate::ShutdownManager::wait_shutdown_triggered],
+	/// this is a single atomic load, so it is cheap enough to call in a hot loop:
+	/// ```
+	/// # fn process(_item: u32) {}
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// let signal = shutdown.wait_shutdown_triggered();
+	/// for item in 0..1000 {
+	///     if signal.is_triggered() {
+	///         break;
+	///     }
+	///     process(item);
+	/// }
+	/// ```
+	///
+	/// The signal belongs to the generation of the shutdown manager that created it,
+	/// so it keeps reporting the shutdown of that generation after the manager is [reset][crate::ShutdownManager::reset].
+	#[inline]
+	pub fn is_triggered(&self) -> bool {
+		match self.kind {
+			SignalKind::Shutdown => self.inner.is_triggered(),
+			_ => self.try_reason().is_some(),
+		}
+	}
+
+	/// Get the reason of the event that this signal waits for, without polling the signal.
+	///
+	/// Returns [`None`] if the event has not happened yet.
+	#[inline]
+	pub fn try_reason(&self) -> Option<T> {
+		// A forced shutdown always triggers the regular shutdown too, so we can skip the lock for forced signals as well.
+		if self.kind != SignalKind::Drain && !self.inner.is_triggered() {
+			return None;
+		}
+		self.inner.lock().signal_reason(self.kind)
+	}
+
+	/// Turn the signal into a future that completes with `()` instead of the shutdown reason.
+	///
+	/// This is useful for APIs that expect a `Future<Output = ()>` as shutdown signal,
+	/// like the `with_graceful_shutdown()` functions of `hyper`, `axum` and `tonic`.
+	#[inline]
+	pub fn ignore_reason(self) -> IgnoreShutdownReason<T> {
+		IgnoreShutdownReason { shutdown_signal: self }
+	}
+
+	/// Wait for the shutdown signal, and then run a future that depends on the shutdown reason.
+	///
+	/// The returned future completes with the output of the future returned by `then`.
+	/// This keeps the reaction to a shutdown local to a task, for example in a `tokio::select!`:
+	/// ```
+	/// # async fn next_request() {}
+	/// # async fn say_goodbye(_reason: &str) {}
+	/// # async fn run(shutdown: async_shutdown::ShutdownManager<&'static str>) {
+	/// let mut stopped = Box::pin(shutdown.wait_shutdown_triggered().then(|reason| say_goodbye(reason)));
+	/// loop {
+	///     tokio::select! {
+	///         _ = next_request() => continue,
+	///         () = &mut stopped => break,
+	///     }
+	/// }
+	/// # }
+	/// ```
+	#[inline]
+	pub fn then<F, Fut>(self, then: F) -> ShutdownSignalThen<T, F, Fut>
+	where
+		F: FnOnce(T) -> Fut,
+		Fut: Future,
+	{
+		ShutdownSignalThen::new(self, then)
+	}
+
+	/// Process the items of a stream until the shutdown signal is received.
+	///
+	/// The function is called for each item of the stream, and the returned future is run to completion before the next item is taken.
+	/// When the shutdown signal is received, the item that is currently being processed is finished first,
+	/// but no new items are taken from the stream.
+	///
+	/// The returned future completes with a [`ForEachSummary`][crate::ForEachSummary]
+	/// that holds the number of processed items and the shutdown reason.
+	/// If the stream ends before the shutdown signal is received, the shutdown reason is [`None`].
+	#[cfg(feature = "stream")]
+	#[inline]
+	pub fn for_each_until_shutdown<S, F, Fut>(&self, stream: S, function: F) -> ForEachUntilShutdown<T, S, F, Fut>
+	where
+		S: futures_core::Stream,
+		F: FnMut(S::Item) -> Fut,
+		Fut: Future<Output = ()>,
+	{
+		ForEachUntilShutdown::new(self.clone(), stream, function)
+	}
+
 	/// Wrap a future so that it is cancelled when a shutdown is triggered.
 	///
 	/// The returned future completes with `Err(reason)` containing the shutdown reason if a shutdown is triggered,
@@ -47,9 +196,128 @@ impl<T: Clone> ShutdownSignal<T> {
 	pub fn wrap_cancel<F: Future>(&self, future: F) -> WrapCancel<T, F> {
 		WrapCancel {
 			shutdown_signal: self.clone(),
-			future: Ok(future),
+			future: Some(future),
+			cancelled: None,
+			scope: CancelScope::default(),
+		}
+	}
+
+	/// Wrap a future so that it is cancelled when a shutdown is triggered, with a custom error.
+	///
+	/// The returned future completes with `Err(map_err(reason))` if a shutdown is triggered,
+	/// and with `Ok(x)` when the wrapped future completes.
+	///
+	/// The wrapped future is dropped if the shutdown starts before the wrapped future completes.
+	#[inline]
+	pub fn wrap_cancel_map_err<F: Future, M: FnOnce(T) -> E, E>(&self, future: F, map_err: M) -> WrapCancelMapErr<T, F, M> {
+		WrapCancelMapErr {
+			wrap_cancel: self.wrap_cancel(future),
+			map_err: Some(map_err),
+		}
+	}
+
+	/// Wrap a fallible future so that it is cancelled when a shutdown is triggered.
+	///
+	/// The returned future completes with `Err(E::from(reason))` if a shutdown is triggered,
+	/// and with the output of the wrapped future when it completes.
+	///
+	/// The wrapped future is dropped if the shutdown starts before the wrapped future completes.
+	#[inline]
+	pub fn try_wrap_cancel<F, V, E>(&self, future: F) -> TryWrapCancel<T, F>
+	where
+		F: Future<Output = Result<V, E>>,
+		E: From<T>,
+	{
+		TryWrapCancel {
+			wrap_cancel: self.wrap_cancel(future),
+		}
+	}
+
+	/// Wrap a future so that it is cancelled when a shutdown is triggered, with a fallback value.
+	///
+	/// The returned future completes with `default` if a shutdown is triggered,
+	/// and with the output of the wrapped future when it completes.
+	///
+	/// The wrapped future is dropped if the shutdown starts before the wrapped future completes.
+	#[inline]
+	pub fn wrap_cancel_or<F: Future>(&self, default: F::Output, future: F) -> WrapCancelOr<T, F> {
+		WrapCancelOr {
+			wrap_cancel: self.wrap_cancel(future),
+			default: Some(default),
+		}
+	}
+
+	/// Wrap a future so that it is cancelled when a shutdown is triggered, with a computed fallback value.
+	///
+	/// The returned future completes with `default(reason)` if a shutdown is triggered,
+	/// and with the output of the wrapped future when it completes.
+	///
+	/// The wrapped future is dropped if the shutdown starts before the wrapped future completes.
+	#[inline]
+	pub fn wrap_cancel_or_else<F: Future, D: FnOnce(T) -> F::Output>(&self, default: D, future: F) -> WrapCancelOrElse<T, F, D> {
+		WrapCancelOrElse {
+			wrap_cancel: self.wrap_cancel(future),
+			default: Some(default),
 		}
 	}
+
+	/// Wrap a future so that it is cancelled when a shutdown is triggered, and then run an async clean-up function.
+	///
+	/// When the shutdown is triggered, the wrapped future is dropped and the clean-up function is called with the shutdown reason.
+	/// The returned future then completes with `Err(reason)` once the clean-up future completes.
+	/// If the wrapped future completes first, the returned future completes with `Ok(x)`.
+	///
+	/// The returned future delays shutdown completion until the wrapped future or the clean-up future has completed.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_cancel_with_cleanup<F, C, CF>(&self, future: F, cleanup: C) -> Result<WrapCancelWithCleanup<T, F, C, CF>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		C: FnOnce(T) -> CF,
+		CF: Future<Output = ()>,
+	{
+		Ok(WrapCancelWithCleanup {
+			run: self.run_then_cleanup(future, cleanup)?,
+		})
+	}
+
+	/// Run a future until a shutdown is triggered, and then run a clean-up future.
+	///
+	/// See [`ShutdownManager::run_then_cleanup()`][crate::ShutdownManager::run_then_cleanup] for more details.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn run_then_cleanup<F, C, CF>(&self, future: F, cleanup: C) -> Result<RunThenCleanup<T, F, C, CF>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		C: FnOnce(T) -> CF,
+		CF: Future,
+	{
+		let delay_token = DelayShutdownToken::new(self.inner.clone(), 0)?;
+		Ok(RunThenCleanup::new(self.clone(), delay_token, future, cleanup))
+	}
+
+	/// Wrap a future so that it is cancelled when a grace period expires after the shutdown is triggered.
+	///
+	/// See [`ShutdownManager::wrap_cancel_with_grace()`][crate::ShutdownManager::wrap_cancel_with_grace] for more details.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[cfg(not(target_family = "wasm"))]
+	#[inline]
+	#[track_caller]
+	pub fn wrap_cancel_with_grace<F: Future>(&self, grace: Duration, future: F) -> Result<WrapCancelWithGrace<T, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(WrapCancelWithGrace {
+			shutdown_signal: self.clone(),
+			delay_token: Some(DelayShutdownToken::new(self.inner.clone(), 0)?),
+			grace,
+			timer: None,
+			reason: None,
+			future: Some(future),
+		})
+	}
 }
 
 impl<T: Clone> Future for ShutdownSignal<T> {
@@ -57,20 +325,41 @@ impl<T: Clone> Future for ShutdownSignal<T> {
 
 	#[inline]
 	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
-		let me = self.get_mut();
-		let mut inner = me.inner.lock().unwrap();
+		// SAFETY: We never move the waiter out of the pinned signal.
+		let me = unsafe { self.get_unchecked_mut() };
 
-		// We're being polled, so we should deregister the waker (if any).
-		if let Some(token) = me.waker_token.take() {
-			inner.on_shutdown.deregister(token);
+		// Fast path: if the shutdown has not been triggered and our waker is already registered,
+		// there is nothing to do, so we don't need to take the lock.
+		// If the shutdown is triggered right after we checked, the registered waker will still be woken.
+		// A forced shutdown always triggers the regular shutdown too, so this also works for forced signals.
+		// The drain can be triggered without the regular shutdown, so drain signals can not use the fast path.
+		if me.kind != SignalKind::Drain && !me.inner.is_triggered() && me.waiter.is_registered() {
+			if let Some(waker) = &me.waker {
+				if waker.will_wake(context.waker()) {
+					return Poll::Pending;
+				}
+			}
 		}
 
-		if let Some(reason) = inner.shutdown_reason.clone() {
-			// Shutdown started, so we're ready.
+		let mut inner = me.inner.lock();
+		inner.resource.init_async_op(&mut me.async_op, "ShutdownSignal::poll");
+		let _entered = me.async_op.enter();
+
+		let reason = inner.signal_reason(me.kind);
+		let waiters = inner.shutdown_waiters(me.kind);
+
+		if let Some(reason) = reason {
+			// Shutdown started, so we're ready and we should deregister the waker (if any).
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { waiters.deregister(&me.waiter) };
+			me.waker = None;
 			Poll::Ready(reason)
 		} else {
-			// We're not ready, so register the waker to wake us on shutdown start.
-			me.waker_token = Some(inner.on_shutdown.register(context.waker().clone()));
+			// We're not ready, so register (or update) the waker to wake us on shutdown start.
+			// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+			// The waiter is deregistered when the signal is dropped.
+			unsafe { waiters.register(Pin::new_unchecked(&me.waiter), context.waker()) };
+			me.waker = Some(context.waker().clone());
 			Poll::Pending
 		}
 	}
@@ -110,46 +399,84 @@ mod test {
 		}
 
 		// Since we wait for each task to complete before spawning another,
-		// the total amount of waker slots used should be only 1.
-		let inner = shutdown.inner.lock().unwrap();
-		assert!(inner.on_shutdown.total_slots() == 1);
-		assert!(inner.on_shutdown.empty_slots() == 1);
+		// no waiters should be left in the list.
+		let inner = shutdown.inner();
+		let inner = inner.lock();
+		assert!(inner.on_shutdown.len() == 0);
 	}
 
 	#[tokio::test]
-	async fn cloning_does_not_clone_waker_token() {
+	async fn dropped_waiters_are_unlinked() {
 		let shutdown = crate::ShutdownManager::<()>::new();
+		let mut signals: Vec<_> = (0..50).map(|_| Box::pin(shutdown.wait_shutdown_triggered())).collect();
+		for signal in &mut signals {
+			assert!(let Poll::Pending = poll_once(signal).await);
+		}
+		assert!(shutdown.trigger_waiter_count() == 50);
 
-		let mut signal = shutdown.wait_shutdown_triggered();
-		assert!(let None = &signal.waker_token);
+		// Drop waiters from the start, the middle and the end of the list.
+		signals.remove(0);
+		signals.remove(20);
+		signals.pop();
+		assert!(shutdown.trigger_waiter_count() == 47);
+		shutdown.shrink_waiters();
 
+		// Polling again must not register the waiters twice.
+		for signal in &mut signals {
+			assert!(let Poll::Pending = poll_once(signal).await);
+		}
+		assert!(shutdown.trigger_waiter_count() == 47);
+
+		let mut signal = Box::pin(shutdown.wait_shutdown_triggered());
 		assert!(let Poll::Pending = poll_once(&mut signal).await);
-		assert!(let Some(_) = &signal.waker_token);
+		assert!(shutdown.trigger_waiter_count() == 48);
 
-		let mut cloned = signal.clone();
-		assert!(let None = &cloned.waker_token);
-		assert!(let Some(_) = &signal.waker_token);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(shutdown.trigger_waiter_count() == 0);
+		assert!(!signal.waiter.is_registered());
+		for signal in &mut signals {
+			assert!(!signal.waiter.is_registered());
+			assert!(let Poll::Ready(()) = poll_once(signal).await);
+		}
+	}
 
-		assert!(let Poll::Pending = poll_once(&mut cloned).await);
-		assert!(let Some(_) = &cloned.waker_token);
-		assert!(let Some(_) = &signal.waker_token);
+	#[test]
+	fn repeated_poll_does_not_lock() {
+		let shutdown = crate::ShutdownManager::<()>::new();
+		let mut signal = std::pin::pin!(shutdown.wait_shutdown_triggered());
+		let mut context = std::task::Context::from_waker(futures::task::noop_waker_ref());
+		assert!(let Poll::Pending = signal.as_mut().poll(&mut context));
 
-		{
-			let inner = shutdown.inner.lock().unwrap();
-			assert!(inner.on_shutdown.total_slots() == 2);
-			assert!(inner.on_shutdown.empty_slots() == 0);
-		}
+		// Polling again with the same waker must not take the lock, or it would deadlock here.
+		let inner = shutdown.inner();
+		let guard = inner.lock();
+		assert!(let Poll::Pending = signal.as_mut().poll(&mut context));
+		drop(guard);
+	}
 
-		{
-			drop(signal);
-			let inner = shutdown.inner.lock().unwrap();
-			assert!(inner.on_shutdown.empty_slots() == 1);
-		}
+	#[tokio::test]
+	async fn cloning_does_not_clone_waiter() {
+		let shutdown = crate::ShutdownManager::<()>::new();
 
-		{
-			drop(cloned);
-			let inner = shutdown.inner.lock().unwrap();
-			assert!(inner.on_shutdown.empty_slots() == 2);
-		}
+		let mut signal = Box::pin(shutdown.wait_shutdown_triggered());
+		assert!(!signal.waiter.is_registered());
+
+		assert!(let Poll::Pending = poll_once(&mut signal).await);
+		assert!(signal.waiter.is_registered());
+
+		let mut cloned = Box::pin((*signal).clone());
+		assert!(!cloned.waiter.is_registered());
+		assert!(signal.waiter.is_registered());
+
+		assert!(let Poll::Pending = poll_once(&mut cloned).await);
+		assert!(cloned.waiter.is_registered());
+		assert!(signal.waiter.is_registered());
+		assert!(shutdown.trigger_waiter_count() == 2);
+
+		drop(signal);
+		assert!(shutdown.trigger_waiter_count() == 1);
+
+		drop(cloned);
+		assert!(shutdown.trigger_waiter_count() == 0);
 	}
 }
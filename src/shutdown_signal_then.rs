@@ -0,0 +1,97 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::ShutdownSignal;
+
+pin_project_lite::pin_project! {
+	/// A future that waits for a shutdown signal, and then runs a future that depends on the shutdown reason.
+	///
+	/// Create one with [`ShutdownSignal::then()`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct ShutdownSignalThen<T: Clone, F, Fut> {
+		#[pin]
+		state: ThenState<T, F, Fut>,
+	}
+}
+
+impl<T: Clone, F, Fut> std::fmt::Debug for ShutdownSignalThen<T, F, Fut> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let mut f = f.debug_struct("ShutdownSignalThen");
+		match &self.state {
+			ThenState::Waiting { shutdown_signal, .. } => f.field("shutdown_signal", shutdown_signal).field("state", &"waiting"),
+			ThenState::Running { .. } => f.field("state", &"running"),
+			ThenState::Done => f.field("state", &"done"),
+		};
+		f.finish()
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// The state of a [`ShutdownSignalThen`] future.
+	#[project = ThenStateProj]
+	enum ThenState<T: Clone, F, Fut> {
+		/// Waiting for the shutdown signal.
+		Waiting {
+			#[pin]
+			shutdown_signal: ShutdownSignal<T>,
+			then: Option<F>,
+		},
+
+		/// The shutdown signal was received and the follow-up future is running.
+		Running {
+			#[pin]
+			future: Fut,
+		},
+
+		/// The future has completed.
+		Done,
+	}
+}
+
+impl<T: Clone, F, Fut> ShutdownSignalThen<T, F, Fut> {
+	/// Create a new future for a shutdown signal.
+	pub(crate) fn new(shutdown_signal: ShutdownSignal<T>, then: F) -> Self {
+		Self {
+			state: ThenState::Waiting {
+				shutdown_signal,
+				then: Some(then),
+			},
+		}
+	}
+}
+
+impl<T, F, Fut> Future for ShutdownSignalThen<T, F, Fut>
+where
+	T: Clone,
+	F: FnOnce(T) -> Fut,
+	Fut: Future,
+{
+	type Output = Fut::Output;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut state = self.project().state;
+
+		loop {
+			match state.as_mut().project() {
+				ThenStateProj::Waiting { shutdown_signal, then } => {
+					let reason = match shutdown_signal.poll(context) {
+						Poll::Ready(reason) => reason,
+						Poll::Pending => return Poll::Pending,
+					};
+					let then = then.take().expect("ShutdownSignalThen is missing the function");
+					state.set(ThenState::Running { future: then(reason) });
+				},
+				ThenStateProj::Running { future } => {
+					let output = match future.poll(context) {
+						Poll::Ready(output) => output,
+						Poll::Pending => return Poll::Pending,
+					};
+					state.set(ThenState::Done);
+					return Poll::Ready(output);
+				},
+				ThenStateProj::Done => panic!("ShutdownSignalThen polled after completion"),
+			}
+		}
+	}
+}
@@ -0,0 +1,134 @@
+use futures_core::stream::{FusedStream, Stream};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{ShutdownComplete, ShutdownSignal};
+
+/// Stream that yields the shutdown reason once when the shutdown is triggered, and then ends.
+///
+/// Created by [`ShutdownSignal::into_stream()`].
+#[must_use = "streams do nothing unless polled"]
+pub struct ShutdownSignalStream<T: Clone> {
+	// Boxed so the stream is `Unpin`.
+	signal: Option<Pin<Box<ShutdownSignal<T>>>>,
+}
+
+/// Stream that yields the shutdown reason once when the shutdown completes, and then ends.
+///
+/// Created by [`ShutdownComplete::into_stream()`].
+#[must_use = "streams do nothing unless polled"]
+pub struct ShutdownCompleteStream<T: Clone> {
+	// Boxed so the stream is `Unpin`.
+	complete: Option<Pin<Box<ShutdownComplete<T>>>>,
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownSignalStream<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownSignalStream")
+			.field("signal", &self.signal)
+			.finish()
+	}
+}
+
+impl<T: Clone> std::fmt::Debug for ShutdownCompleteStream<T> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("ShutdownCompleteStream")
+			.field("complete", &self.complete)
+			.finish()
+	}
+}
+
+impl<T: Clone> ShutdownSignal<T> {
+	/// Convert the signal into a stream that yields the shutdown reason once, and then ends.
+	///
+	/// This makes it easy to merge the shutdown signal with other event streams:
+	/// ```
+	/// # use futures::stream::{self, StreamExt};
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<()>, messages: impl futures::Stream<Item = String> + Unpin) {
+	/// enum Event {
+	///     Message(String),
+	///     Shutdown,
+	/// }
+	///
+	/// let shutdown = shutdown.wait_shutdown_triggered().into_stream().map(|()| Event::Shutdown);
+	/// let mut events = stream::select(messages.map(Event::Message), shutdown);
+	/// while let Some(event) = events.next().await {
+	///     match event {
+	///         Event::Message(message) => println!("{message}"),
+	///         Event::Shutdown => break,
+	///     }
+	/// }
+	/// # }
+	/// ```
+	#[inline]
+	pub fn into_stream(self) -> ShutdownSignalStream<T> {
+		ShutdownSignalStream { signal: Some(Box::pin(self)) }
+	}
+}
+
+impl<T: Clone> ShutdownComplete<T> {
+	/// Convert the future into a stream that yields the shutdown reason once when the shutdown completes, and then ends.
+	///
+	/// See [`ShutdownSignal::into_stream()`] for an example.
+	#[inline]
+	pub fn into_stream(self) -> ShutdownCompleteStream<T> {
+		ShutdownCompleteStream { complete: Some(Box::pin(self)) }
+	}
+}
+
+impl<T: Clone> Stream for ShutdownSignalStream<T> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+		let me = self.get_mut();
+		let signal = match &mut me.signal {
+			Some(signal) => signal,
+			None => return Poll::Ready(None),
+		};
+		let reason = std::task::ready!(signal.as_mut().poll(context));
+		me.signal = None;
+		Poll::Ready(Some(reason))
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = usize::from(self.signal.is_some());
+		(remaining, Some(remaining))
+	}
+}
+
+impl<T: Clone> FusedStream for ShutdownSignalStream<T> {
+	#[inline]
+	fn is_terminated(&self) -> bool {
+		self.signal.is_none()
+	}
+}
+
+impl<T: Clone> Stream for ShutdownCompleteStream<T> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+		let me = self.get_mut();
+		let complete = match &mut me.complete {
+			Some(complete) => complete,
+			None => return Poll::Ready(None),
+		};
+		let reason = std::task::ready!(complete.as_mut().poll(context));
+		me.complete = None;
+		Poll::Ready(Some(reason))
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		let remaining = usize::from(self.complete.is_some());
+		(remaining, Some(remaining))
+	}
+}
+
+impl<T: Clone> FusedStream for ShutdownCompleteStream<T> {
+	#[inline]
+	fn is_terminated(&self) -> bool {
+		self.complete.is_none()
+	}
+}
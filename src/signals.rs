@@ -0,0 +1,191 @@
+use std::future::Future;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::signal::unix::{Signal, SignalKind};
+
+use crate::ShutdownManager;
+
+/// Escalation policy for repeated signals.
+///
+/// Used with [`ShutdownManager::attach_unix_signals_with_escalation()`].
+///
+/// The first signal triggers the shutdown, the second signal triggers the [forced shutdown][ShutdownManager::trigger_forced_shutdown],
+/// and the third signal exits the process immediately with [`std::process::exit()`].
+/// Optionally, the forced shutdown can also be triggered automatically if the shutdown did not complete some time after it was triggered.
+#[derive(Debug, Clone)]
+pub struct SignalEscalation {
+	exit_code: i32,
+	force_after: Option<Duration>,
+}
+
+impl SignalEscalation {
+	/// Create a new escalation policy that exits the process with the given exit code on the third signal.
+	#[inline]
+	pub fn new(exit_code: i32) -> Self {
+		Self {
+			exit_code,
+			force_after: None,
+		}
+	}
+
+	/// Trigger the forced shutdown automatically if the shutdown did not complete within `duration` after it was triggered.
+	///
+	/// The forced shutdown is triggered with the reason of the regular shutdown.
+	#[inline]
+	pub fn force_after(mut self, duration: Duration) -> Self {
+		self.force_after = Some(duration);
+		self
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Trigger the shutdown when the process receives one of the given Unix signals.
+	///
+	/// Each signal is mapped to its own shutdown reason,
+	/// so that your application can tell which signal arrived, for example to pick an exit code:
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() -> std::io::Result<()> {
+	/// use tokio::signal::unix::SignalKind;
+	///
+	/// let shutdown = async_shutdown::ShutdownManager::new();
+	/// shutdown.attach_unix_signals([
+	///     (SignalKind::terminate(), 143),
+	///     (SignalKind::interrupt(), 130),
+	///     (SignalKind::hangup(), 129),
+	/// ])?;
+	///
+	/// // Run your application...
+	///
+	/// let exit_code = shutdown.wait_shutdown_complete().await;
+	/// std::process::exit(exit_code);
+	/// # }
+	/// ```
+	///
+	/// The signal handlers are installed before this function returns,
+	/// and a task is spawned on the current tokio runtime to wait for the signals.
+	/// The shutdown is triggered with the reason of the first signal that arrives.
+	/// The task stops on its own when the shutdown is triggered by other means.
+	///
+	/// If installing one of the signal handlers fails, this function returns an error and no task is spawned.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn attach_unix_signals<I>(&self, signals: I) -> std::io::Result<tokio::task::JoinHandle<()>>
+	where
+		I: IntoIterator<Item = (SignalKind, T)>,
+	{
+		let mut signals = install_signals(signals)?;
+
+		let shutdown = self.clone();
+		Ok(tokio::spawn(async move {
+			let received = std::future::poll_fn(|context| poll_signals(&mut signals, context));
+			if let Ok(reason) = shutdown.wrap_cancel(received).await {
+				shutdown.trigger_shutdown(reason).ok();
+			}
+		}))
+	}
+
+	/// Trigger the shutdown when the process receives one of the given Unix signals, and escalate on repeated signals.
+	///
+	/// This is the same as [`Self::attach_unix_signals()`], except that the task keeps listening for signals until the shutdown completes.
+	/// Each signal moves the shutdown up one step of the [`SignalEscalation`] ladder:
+	/// ```no_run
+	/// # #[tokio::main]
+	/// # async fn main() -> std::io::Result<()> {
+	/// use async_shutdown::SignalEscalation;
+	/// use std::time::Duration;
+	/// use tokio::signal::unix::SignalKind;
+	///
+	/// let shutdown = async_shutdown::ShutdownManager::new();
+	/// let escalation = SignalEscalation::new(130).force_after(Duration::from_secs(30));
+	/// shutdown.attach_unix_signals_with_escalation([(SignalKind::interrupt(), 130)], escalation)?;
+	///
+	/// // Run your application...
+	///
+	/// let exit_code = shutdown.wait_shutdown_complete().await;
+	/// std::process::exit(exit_code);
+	/// # }
+	/// ```
+	///
+	/// The step is based on the state of the shutdown manager:
+	/// if the shutdown was already triggered by other means, the first signal triggers the forced shutdown.
+	///
+	/// If installing one of the signal handlers fails, this function returns an error and no task is spawned.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn attach_unix_signals_with_escalation<I>(&self, signals: I, escalation: SignalEscalation) -> std::io::Result<tokio::task::JoinHandle<()>>
+	where
+		I: IntoIterator<Item = (SignalKind, T)>,
+	{
+		let mut signals = install_signals(signals)?;
+
+		let shutdown = self.clone();
+		Ok(tokio::spawn(async move {
+			let mut complete = std::pin::pin!(shutdown.wait_shutdown_complete());
+			let mut triggered = std::pin::pin!(shutdown.wait_shutdown_triggered());
+			let mut force_timer = None;
+			// The forced shutdown is only triggered once by the timer, even though the shutdown stays triggered.
+			let mut force_fired = false;
+			loop {
+				let event = std::future::poll_fn(|context| {
+					if complete.as_mut().poll(context).is_ready() {
+						return Poll::Ready(None);
+					}
+					if let Poll::Ready(reason) = poll_signals(&mut signals, context) {
+						return Poll::Ready(Some(reason));
+					}
+					if let (Some(force_after), None, false) = (escalation.force_after, &force_timer, force_fired) {
+						if triggered.as_mut().poll(context).is_ready() {
+							force_timer = Some(Box::pin(tokio::time::sleep(force_after)));
+						}
+					}
+					if let Some(timer) = &mut force_timer {
+						if timer.as_mut().poll(context).is_ready() {
+							force_timer = None;
+							force_fired = true;
+							if let Some(reason) = shutdown.shutdown_reason() {
+								shutdown.trigger_forced_shutdown(reason).ok();
+							}
+						}
+					}
+					Poll::Pending
+				});
+				let reason = match event.await {
+					Some(reason) => reason,
+					None => return,
+				};
+				if !shutdown.is_shutdown_triggered() {
+					shutdown.trigger_shutdown(reason).ok();
+				} else if !shutdown.is_forced_shutdown_triggered() {
+					shutdown.trigger_forced_shutdown(reason).ok();
+				} else {
+					std::process::exit(escalation.exit_code);
+				}
+			}
+		}))
+	}
+}
+
+/// Install signal handlers for the given signals.
+fn install_signals<T, I>(signals: I) -> std::io::Result<Vec<(Signal, T)>>
+where
+	I: IntoIterator<Item = (SignalKind, T)>,
+{
+	signals
+		.into_iter()
+		.map(|(kind, reason)| Ok((tokio::signal::unix::signal(kind)?, reason)))
+		.collect()
+}
+
+/// Poll a list of signals, and return the reason of the first one that was received.
+fn poll_signals<T: Clone>(signals: &mut [(Signal, T)], context: &mut Context) -> Poll<T> {
+	for (signal, reason) in signals {
+		// A closed signal stream can never fire again, so treat it as pending.
+		if let Poll::Ready(Some(())) = signal.poll_recv(context) {
+			return Poll::Ready(reason.clone());
+		}
+	}
+	Poll::Pending
+}
@@ -0,0 +1,109 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use smol::Task;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager, ShutdownSignal};
+
+pin_project_lite::pin_project! {
+	/// Wrapped smol [`Task`] that cancels the task when a shutdown is triggered.
+	///
+	/// Created by [`ShutdownManager::wrap_abort_smol()`].
+	///
+	/// If the task finishes before the shutdown is triggered, the output of the task is yielded as `Ok(output)`.
+	///
+	/// If the shutdown is triggered first, the task is cancelled.
+	/// The future then waits for the task to actually stop, and yields the shutdown reason as `Err(shutdown_reason)`.
+	/// If the task managed to finish anyway, the output is still yielded as `Ok(output)`.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct SmolWrapAbort<T: Clone, O> {
+		#[pin]
+		shutdown_signal: ShutdownSignal<T>,
+		task: Option<Task<O>>,
+		cancel: Option<Pin<Box<dyn Future<Output = Option<O>> + Send>>>,
+		reason: Option<T>,
+	}
+}
+
+impl<T: Clone, O> std::fmt::Debug for SmolWrapAbort<T, O> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("SmolWrapAbort")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("aborted", &self.reason.is_some())
+			.finish()
+	}
+}
+
+impl<T: Clone, O: Send + 'static> Future for SmolWrapAbort<T, O> {
+	type Output = Result<O, T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		if let Some(task) = me.task {
+			if let Poll::Ready(output) = Pin::new(task).poll(context) {
+				return Poll::Ready(Ok(output));
+			}
+			match me.shutdown_signal.as_mut().poll(context) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(reason) => {
+					*me.reason = Some(reason);
+					if let Some(task) = me.task.take() {
+						*me.cancel = Some(Box::pin(task.cancel()));
+					}
+					me.shutdown_signal.inner.record_cancelled();
+				},
+			}
+		}
+
+		// Wait for the task to actually stop.
+		let cancel = me.cancel.as_mut().expect("SmolWrapAbort polled after completion");
+		let output = std::task::ready!(cancel.as_mut().poll(context));
+		*me.cancel = None;
+		match output {
+			Some(output) => Poll::Ready(Ok(output)),
+			None => Poll::Ready(Err(me.reason.take().expect("SmolWrapAbort is missing the shutdown reason"))),
+		}
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Spawn a task on the global smol executor that is cancelled when the shutdown is triggered.
+	///
+	/// The future is wrapped with [`Self::wrap_cancel()`] and [`Self::wrap_delay_shutdown()`] *before* it is spawned,
+	/// so the shutdown does not complete until the task has been cancelled or has finished.
+	///
+	/// The returned [`Task`] resolves to `Ok(output)` if the future completed,
+	/// or to `Err(shutdown_reason)` if it was cancelled.
+	/// Note that smol cancels a task when its [`Task`] is dropped.
+	/// Call [`Task::detach()`] if you want the task to keep running in the background.
+	///
+	/// If the shutdown has already completed, this function returns an error and the future is not spawned.
+	pub fn spawn_smol<F>(&self, future: F) -> Result<Task<Result<F::Output, T>>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let future = self.wrap_delay_shutdown(self.wrap_cancel(future))?;
+		Ok(smol::spawn(future))
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap a smol [`Task`] so that the task is cancelled when the shutdown is triggered.
+	///
+	/// Dropping a [`Task`] also cancels the task, but it does not wait for the task to stop.
+	/// The returned future waits until the task has actually been cancelled.
+	///
+	/// See [`SmolWrapAbort`] for the output of the returned future.
+	#[inline]
+	pub fn wrap_abort_smol<O: Send + 'static>(&self, task: Task<O>) -> SmolWrapAbort<T, O> {
+		SmolWrapAbort {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			task: Some(task),
+			cancel: None,
+			reason: None,
+		}
+	}
+}
@@ -0,0 +1,61 @@
+use std::future::Future;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager};
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Spawn a task on the current tokio runtime that is cancelled when the shutdown is triggered.
+	///
+	/// The future is wrapped with [`Self::wrap_cancel()`] and [`Self::wrap_delay_shutdown()`] *before* it is spawned,
+	/// so the shutdown does not complete until the task has been cancelled or has finished.
+	///
+	/// The returned [`JoinHandle`][tokio::task::JoinHandle] resolves to `Ok(output)` if the future completed,
+	/// or to `Err(shutdown_reason)` if it was cancelled.
+	/// Dropping the handle does not affect the task.
+	///
+	/// If the shutdown has already completed, this function returns an error and the future is not spawned.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn spawn<F>(&self, future: F) -> Result<tokio::task::JoinHandle<Result<F::Output, T>>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let future = self.wrap_delay_shutdown(self.wrap_cancel(future))?;
+		Ok(tokio::spawn(future))
+	}
+
+	/// Spawn a named task on the current tokio runtime that is cancelled when the shutdown is triggered.
+	///
+	/// This is the same as [`Self::spawn()`], except that the task can be identified by its name.
+	/// The name is used as status of the [delay token][crate::DelayShutdownToken::set_status] of the task,
+	/// so it shows up in [`Self::pending_blockers()`] if the task holds up the shutdown.
+	///
+	/// If the `tracing` feature is enabled and the crate is compiled with `--cfg tokio_unstable`,
+	/// the name is also passed to `tokio::task::Builder`, so that the task can be identified in `tokio-console`.
+	///
+	/// If the shutdown has already completed, this function returns an error and the future is not spawned.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn spawn_named<F>(&self, name: &str, future: F) -> Result<tokio::task::JoinHandle<Result<F::Output, T>>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let delay_token = self.delay_shutdown_token()?;
+		delay_token.set_status(format!("task {}", name));
+		let future = delay_token.wrap_future(self.wrap_cancel(future));
+
+		#[cfg(all(tokio_unstable, feature = "tracing"))]
+		{
+			Ok(tokio::task::Builder::new()
+				.name(name)
+				.spawn(future)
+				.expect("failed to spawn task"))
+		}
+
+		#[cfg(not(all(tokio_unstable, feature = "tracing")))]
+		Ok(tokio::spawn(future))
+	}
+}
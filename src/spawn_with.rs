@@ -0,0 +1,42 @@
+use std::future::Future;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager, WrapCancel, WrapDelayShutdown};
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Spawn a task with any executor, and cancel it when the shutdown is triggered.
+	///
+	/// This is the runtime independent version of the tokio specific `spawn()`.
+	/// The future is wrapped with [`Self::wrap_cancel()`] and [`Self::wrap_delay_shutdown()`] *before* it is passed to `spawn`,
+	/// so the shutdown does not complete until the task has been cancelled or has finished.
+	/// The wrapped future resolves to `Ok(output)` if the future completed, or to `Err(shutdown_reason)` if it was cancelled.
+	///
+	/// Whatever `spawn` returns is passed back to the caller, so you can use it with the spawn function of your executor,
+	/// like `async_std::task::spawn` or `smol::spawn`:
+	/// ```
+	/// # fn smol_spawn<F: std::future::Future>(future: F) -> F { future }
+	/// let shutdown = async_shutdown::ShutdownManager::new();
+	/// let task = shutdown.spawn_with(smol_spawn, async {
+	///     // Do some work...
+	/// });
+	/// # shutdown.trigger_shutdown(()).ok();
+	/// # drop(task);
+	/// ```
+	///
+	/// Note that some executors cancel a task when its handle is dropped.
+	/// Call `detach()` on those handles if you want the task to keep running in the background.
+	///
+	/// For async-std and smol, the `async-std` and `smol` features also provide `spawn_async_std()` and `spawn_smol()`,
+	/// and `wrap_abort_async_std()` and `wrap_abort_smol()` to cancel a task that was spawned without the shutdown manager.
+	/// To trigger the shutdown on a signal, call [`Self::trigger_shutdown()`] from the signal handling of your runtime.
+	///
+	/// If the shutdown has already completed, this function returns an error and `spawn` is not called.
+	#[track_caller]
+	pub fn spawn_with<F, S, H>(&self, spawn: S, future: F) -> Result<H, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		S: FnOnce(WrapDelayShutdown<T, WrapCancel<T, F>>) -> H,
+	{
+		let future = self.wrap_delay_shutdown(self.wrap_cancel(future))?;
+		Ok(spawn(future))
+	}
+}
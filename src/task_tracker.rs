@@ -0,0 +1,172 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::waker_list::{Waiter, WakerList};
+use crate::{ShutdownAlreadyCompleted, ShutdownManager};
+use crate::lock::MutexExt;
+
+/// Tracker for tasks that are spawned on a tokio runtime.
+///
+/// Every task spawned through the tracker is cancelled when the shutdown is triggered,
+/// and the shutdown does not complete until all tracked tasks have finished.
+/// This means you don't have to pass a delay token to every task manually.
+///
+/// You can also wait for all tasks spawned through a specific tracker with [`Self::wait()`].
+///
+/// The tracker can be cloned and shared with multiple tasks.
+/// Each clone tracks the same set of tasks.
+#[derive(Clone)]
+pub struct TaskTracker<T: Clone> {
+	shutdown: ShutdownManager<T>,
+	state: Arc<Mutex<TrackerState>>,
+}
+
+#[derive(Default)]
+struct TrackerState {
+	/// The number of tracked tasks that did not finish yet.
+	tasks: usize,
+
+	/// Tasks to wake when all tracked tasks have finished.
+	on_empty: WakerList,
+}
+
+impl<T: Clone + Send + 'static> TaskTracker<T> {
+	/// Create a new task tracker for a shutdown manager.
+	#[inline]
+	pub fn new(shutdown: &ShutdownManager<T>) -> Self {
+		Self {
+			shutdown: shutdown.clone(),
+			state: Arc::new(Mutex::new(TrackerState::default())),
+		}
+	}
+
+	/// Spawn a tracked task on the current tokio runtime.
+	///
+	/// This is the same as [`ShutdownManager::spawn()`], except that the task is also tracked by this tracker.
+	///
+	/// If the shutdown has already completed, this function returns an error and the future is not spawned.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn spawn<F>(&self, future: F) -> Result<tokio::task::JoinHandle<Result<F::Output, T>>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let guard = TrackedTask::new(self.state.clone());
+		self.shutdown.spawn(async move {
+			let _guard = guard;
+			future.await
+		})
+	}
+
+	/// Spawn a named task that is tracked and cancelled when the shutdown is triggered.
+	///
+	/// See [`ShutdownManager::spawn_named()`] for how the name is used.
+	///
+	/// # Panics
+	/// This function panics if it is called outside of a tokio runtime.
+	pub fn spawn_named<F>(&self, name: &str, future: F) -> Result<tokio::task::JoinHandle<Result<F::Output, T>>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let guard = TrackedTask::new(self.state.clone());
+		self.shutdown.spawn_named(name, async move {
+			let _guard = guard;
+			future.await
+		})
+	}
+
+	/// Get the number of tracked tasks that have not finished yet.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.state.lock_unpoisoned().tasks
+	}
+
+	/// Check if all tracked tasks have finished.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Asynchronously wait for all tracked tasks to finish.
+	///
+	/// The returned future completes as soon as there are no running tracked tasks,
+	/// even if more tasks are spawned later.
+	#[inline]
+	pub fn wait(&self) -> TaskTrackerWait {
+		TaskTrackerWait {
+			state: self.state.clone(),
+			waiter: Waiter::new(),
+		}
+	}
+}
+
+/// Guard that keeps a task registered with a tracker until it is dropped.
+struct TrackedTask {
+	state: Arc<Mutex<TrackerState>>,
+}
+
+impl TrackedTask {
+	fn new(state: Arc<Mutex<TrackerState>>) -> Self {
+		state.lock_unpoisoned().tasks += 1;
+		Self { state }
+	}
+}
+
+impl Drop for TrackedTask {
+	fn drop(&mut self) {
+		let mut state = self.state.lock_unpoisoned();
+		state.tasks -= 1;
+		if state.tasks == 0 {
+			state.on_empty.wake_all();
+		}
+	}
+}
+
+/// Future to wait for all tasks of a [`TaskTracker`] to finish.
+///
+/// The future stores its own node of the list of waiting tasks, so it is not [`Unpin`].
+#[must_use = "futures must be polled to make progress"]
+pub struct TaskTrackerWait {
+	state: Arc<Mutex<TrackerState>>,
+	waiter: Waiter,
+}
+
+impl Drop for TaskTrackerWait {
+	fn drop(&mut self) {
+		if self.waiter.is_registered() {
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { self.state.lock_unpoisoned().on_empty.deregister(&self.waiter) };
+		}
+	}
+}
+
+impl Future for TaskTrackerWait {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut state = self.state.lock_unpoisoned();
+		if state.tasks == 0 {
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { state.on_empty.deregister(&self.waiter) };
+			Poll::Ready(())
+		} else {
+			// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+			// The waiter is deregistered when the future is dropped.
+			unsafe { state.on_empty.register(self.as_ref().map_unchecked(|me| &me.waiter), context.waker()) };
+			Poll::Pending
+		}
+	}
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Create a new [`TaskTracker`] for this shutdown manager.
+	#[inline]
+	pub fn task_tracker(&self) -> TaskTracker<T> {
+		TaskTracker::new(self)
+	}
+}
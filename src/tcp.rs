@@ -0,0 +1,185 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::task::Poll;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::wire::{follow_peer, notify_peer};
+use crate::{timer, ShutdownManager};
+
+/// The result of [`ShutdownManager::coordinate_tcp()`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct CoordinationReport {
+	/// The workers that acknowledged the completion of their shutdown within the deadline.
+	pub acknowledged: Vec<SocketAddr>,
+
+	/// The workers that did not acknowledge the completion of their shutdown within the deadline.
+	pub timed_out: Vec<SocketAddr>,
+
+	/// The workers that could not be reached, or that closed the connection without acknowledging the completion.
+	pub failed: Vec<(SocketAddr, io::Error)>,
+}
+
+impl CoordinationReport {
+	/// Check if all workers acknowledged the completion of their shutdown within the deadline.
+	#[inline]
+	pub fn all_acknowledged(&self) -> bool {
+		self.timed_out.is_empty() && self.failed.is_empty()
+	}
+}
+
+/// The outcome of the shutdown of a single worker.
+enum WorkerOutcome {
+	Acknowledged,
+	TimedOut,
+	Failed(io::Error),
+}
+
+impl<T: Clone + Serialize + DeserializeOwned + Send + 'static> ShutdownManager<T> {
+	/// Coordinate the shutdown of remote worker processes over TCP.
+	///
+	/// The returned future connects to each worker, which must run [`Self::follow_tcp()`] on the given address.
+	/// When the shutdown of this manager is triggered, the shutdown reason is sent to all workers, which triggers their shutdown too.
+	/// The future then waits up to `deadline` for each worker to acknowledge that its own shutdown completed,
+	/// and reports which workers did:
+	/// ```no_run
+	/// # async fn run(workers: Vec<std::net::SocketAddr>) {
+	/// use std::time::Duration;
+	///
+	/// let shutdown = async_shutdown::ShutdownManager::<String>::new();
+	/// let coordinator = tokio::spawn(shutdown.coordinate_tcp(workers, Duration::from_secs(30)));
+	///
+	/// shutdown.trigger_shutdown("rolling deploy".into()).ok();
+	/// let report = coordinator.await.unwrap();
+	/// if !report.all_acknowledged() {
+	///     eprintln!("workers did not drain in time: {:?} {:?}", report.timed_out, report.failed);
+	/// }
+	/// # }
+	/// ```
+	///
+	/// Each worker holds a delay token until it acknowledged the completion, until the deadline expired, or until the connection failed.
+	/// The deadline is timed by the [clock][crate::ShutdownManagerBuilder::clock] of the shutdown manager.
+	/// The workers are handled on separate tasks on the current tokio runtime.
+	///
+	/// # Wire format
+	/// The shutdown reason is sent with its [`Serialize`] implementation, and decoded by the worker with its [`Deserialize`](serde::Deserialize) implementation.
+	/// The messages are length-prefixed frames, which are stable between versions of this crate and easy to implement in other languages.
+	/// Each frame consists of:
+	/// * a version byte, which is always `1` for this version of the protocol,
+	/// * the length of the message as a big-endian `u32`, at most 1 MiB,
+	/// * the message, encoded as JSON.
+	///
+	/// The coordinator sends `{"type":"trigger","reason":...}` with the shutdown reason,
+	/// and the worker answers with `{"type":"completed"}` once its own shutdown completed.
+	/// A peer that receives a frame with an unknown version or an invalid message closes the connection.
+	///
+	/// # Panics
+	/// The returned future panics if it is polled outside of a tokio runtime.
+	pub fn coordinate_tcp(
+		&self,
+		workers: impl IntoIterator<Item = SocketAddr>,
+		deadline: Duration,
+	) -> impl Future<Output = CoordinationReport> + Send + 'static {
+		let shutdown = self.clone();
+		let workers: Vec<_> = workers.into_iter().collect();
+		async move {
+			let mut report = CoordinationReport {
+				acknowledged: Vec::new(),
+				timed_out: Vec::new(),
+				failed: Vec::new(),
+			};
+			let mut tasks = Vec::with_capacity(workers.len());
+			for address in workers {
+				let delay_token = match shutdown.delay_shutdown_token() {
+					Ok(delay_token) => delay_token,
+					Err(_) => {
+						report.failed.push((address, io::Error::other("the shutdown already completed")));
+						continue;
+					},
+				};
+				delay_token.set_status(format!("tcp worker {address}"));
+				let signal = shutdown.wait_shutdown_triggered();
+				let clock = shutdown.shared().clock();
+				let task = tokio::spawn(async move {
+					let mut stream = TcpStream::connect(address).await?;
+					let reason = signal.await;
+					let acknowledged = timer::timeout(&*clock, deadline, notify_peer(reason, &mut stream)).await;
+					drop(delay_token);
+					match acknowledged {
+						Some(Ok(true)) => Ok(WorkerOutcome::Acknowledged),
+						Some(Ok(false)) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "worker closed the connection before acknowledging the completion")),
+						Some(Err(e)) => Err(e),
+						None => Ok(WorkerOutcome::TimedOut),
+					}
+				});
+				tasks.push((address, task));
+			}
+
+			for (address, task) in tasks {
+				let outcome = match task.await {
+					Ok(Ok(outcome)) => outcome,
+					Ok(Err(e)) => WorkerOutcome::Failed(e),
+					Err(e) => WorkerOutcome::Failed(io::Error::other(e)),
+				};
+				match outcome {
+					WorkerOutcome::Acknowledged => report.acknowledged.push(address),
+					WorkerOutcome::TimedOut => report.timed_out.push(address),
+					WorkerOutcome::Failed(e) => report.failed.push((address, e)),
+				}
+			}
+			report
+		}
+	}
+
+	/// Follow the shutdown of a coordinator over TCP.
+	///
+	/// The returned future accepts connections from coordinators that run [`Self::coordinate_tcp()`].
+	/// When a coordinator triggers its shutdown, the shutdown of this manager is triggered with the same reason.
+	/// When the shutdown of this manager completes, the completion is acknowledged to all connected coordinators:
+	/// ```no_run
+	/// # async fn run() -> std::io::Result<()> {
+	/// let shutdown = async_shutdown::ShutdownManager::<String>::new();
+	/// let listener = tokio::net::TcpListener::bind("0.0.0.0:9000").await?;
+	/// tokio::spawn(shutdown.follow_tcp(listener));
+	///
+	/// // Run the worker...
+	///
+	/// shutdown.wait_shutdown_complete().await;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// If the shutdown of this manager is triggered by something else, the completion is still acknowledged to the coordinators.
+	/// The connections are handled on separate tasks on the current tokio runtime.
+	/// The future completes when the shutdown completes, or with an error if accepting a connection fails.
+	///
+	/// The protocol is not authenticated or encrypted, so only accept connections from a trusted network.
+	///
+	/// # Panics
+	/// The returned future panics if it is polled outside of a tokio runtime.
+	pub fn follow_tcp(&self, listener: TcpListener) -> impl Future<Output = io::Result<()>> + Send + 'static {
+		let shutdown = self.clone();
+		async move {
+			let mut complete = std::pin::pin!(shutdown.wait_shutdown_complete());
+			loop {
+				let accepted = std::future::poll_fn(|context| {
+					if complete.as_mut().poll(context).is_ready() {
+						return Poll::Ready(None);
+					}
+					listener.poll_accept(context).map(Some)
+				});
+				let stream = match accepted.await {
+					Some(accepted) => accepted?.0,
+					None => return Ok(()),
+				};
+				let shutdown = shutdown.clone();
+				tokio::spawn(async move { follow_peer(&shutdown, stream).await });
+			}
+		}
+	}
+}
@@ -0,0 +1,235 @@
+//! Helpers for testing the shutdown behaviour of your own code.
+//!
+//! The helpers are runtime independent, so you can use them with any async runtime:
+//! ```
+//! # async fn example() {
+//! use async_shutdown::test_util::{assert_completes_within, assert_not_triggered};
+//! use std::time::Duration;
+//!
+//! let shutdown = async_shutdown::ShutdownManager::new();
+//! // Start your service...
+//! assert_not_triggered(&shutdown);
+//!
+//! shutdown.trigger_shutdown("test").ok();
+//! assert!(assert_completes_within(&shutdown, Duration::from_secs(1)).await == "test");
+//! # }
+//! ```
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_family = "wasm"))]
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+#[cfg(not(target_family = "wasm"))]
+use std::time::Duration;
+
+#[cfg(not(target_family = "wasm"))]
+use crate::lock::MutexExt;
+#[cfg(not(target_family = "wasm"))]
+use crate::timer::Timer;
+#[cfg(not(target_family = "wasm"))]
+use crate::Clock;
+use crate::ShutdownManager;
+
+/// Assert that the shutdown has not been triggered.
+///
+/// # Panics
+/// This function panics with the shutdown reason if the shutdown has been triggered.
+#[track_caller]
+pub fn assert_not_triggered<T: Clone + Debug>(manager: &ShutdownManager<T>) {
+	if let Some(reason) = manager.shutdown_reason() {
+		panic!("expected the shutdown not to be triggered, but it was triggered with reason: {:?}", reason);
+	}
+}
+
+/// Assert that the shutdown has been triggered, and get the shutdown reason.
+///
+/// # Panics
+/// This function panics if the shutdown has not been triggered.
+#[track_caller]
+pub fn assert_triggered<T: Clone>(manager: &ShutdownManager<T>) -> T {
+	match manager.shutdown_reason() {
+		Some(reason) => reason,
+		None => panic!("expected the shutdown to be triggered, but it was not"),
+	}
+}
+
+/// Wait for the shutdown to complete, and panic if it takes longer than `timeout`.
+///
+/// On success, the shutdown reason is returned.
+///
+/// # Panics
+/// This function panics if the shutdown does not complete within `timeout`.
+/// The panic message lists the delay tokens that are still holding up the shutdown.
+#[cfg(not(target_family = "wasm"))]
+pub async fn assert_completes_within<T: Clone>(manager: &ShutdownManager<T>, timeout: Duration) -> T {
+	let mut complete = std::pin::pin!(manager.wait_shutdown_complete());
+	let mut timer = std::pin::pin!(Timer::new(&crate::SystemClock, timeout));
+	let result = std::future::poll_fn(|context| {
+		if let Poll::Ready(reason) = complete.as_mut().poll(context) {
+			return Poll::Ready(Some(reason));
+		}
+		timer.as_mut().poll_expired(context).map(|()| None)
+	})
+	.await;
+	match result {
+		Some(reason) => reason,
+		None => panic!(
+			"shutdown did not complete within {timeout:?}, still waiting for: {:?}",
+			manager.pending_blockers(),
+		),
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Future wrapper that counts how often the wrapped future is polled.
+	///
+	/// This can be used to check that a future is not woken up needlessly,
+	/// or that it is woken up when the shutdown is triggered.
+	/// Get a [`PollCount`] with [`Self::count()`] to read the number of polls after the future has been moved into a task.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct PollCounter<F> {
+		#[pin]
+		future: F,
+		count: PollCount,
+	}
+}
+
+/// Shared handle to the number of polls of a [`PollCounter`].
+#[derive(Debug, Clone, Default)]
+pub struct PollCount {
+	polls: Arc<AtomicUsize>,
+}
+
+impl PollCount {
+	/// Get the number of times the future has been polled.
+	#[inline]
+	pub fn get(&self) -> usize {
+		self.polls.load(Ordering::Relaxed)
+	}
+}
+
+impl<F> PollCounter<F> {
+	/// Wrap a future to count how often it is polled.
+	#[inline]
+	pub fn new(future: F) -> Self {
+		Self {
+			future,
+			count: PollCount::default(),
+		}
+	}
+
+	/// Get a handle to the number of polls.
+	#[inline]
+	pub fn count(&self) -> PollCount {
+		self.count.clone()
+	}
+
+	/// Get the number of times the future has been polled.
+	#[inline]
+	pub fn polls(&self) -> usize {
+		self.count.get()
+	}
+}
+
+impl<F: Future> Future for PollCounter<F> {
+	type Output = F::Output;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		me.count.polls.fetch_add(1, Ordering::Relaxed);
+		me.future.poll(context)
+	}
+}
+
+/// A virtual clock that only advances when you tell it to.
+///
+/// Use it with [`ShutdownManagerBuilder::clock()`][crate::ShutdownManagerBuilder::clock]
+/// to test timeouts and deadlines deterministically, without real sleeps:
+/// ```
+/// use async_shutdown::test_util::TestClock;
+/// use std::time::Duration;
+///
+/// let clock = TestClock::new();
+/// let shutdown = async_shutdown::ShutdownManager::builder().clock(clock.clone()).build();
+/// let delay = shutdown.delay_shutdown_token().unwrap();
+///
+/// shutdown.trigger_shutdown_with_deadline("stop", Duration::from_secs(30)).unwrap();
+/// clock.advance(Duration::from_secs(29));
+/// assert!(!shutdown.is_shutdown_completed());
+/// clock.advance(Duration::from_secs(1));
+/// assert!(shutdown.is_shutdown_completed());
+/// # drop(delay);
+/// ```
+///
+/// Timers only expire from within [`Self::advance()`], even if they were started with a zero duration.
+/// The clock can be cloned, and all clones share the same time.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Clone, Default)]
+pub struct TestClock {
+	state: Arc<Mutex<TestClockState>>,
+}
+
+/// The shared state of a [`TestClock`].
+#[cfg(not(target_family = "wasm"))]
+#[derive(Default)]
+struct TestClockState {
+	/// The time that passed since the clock was created.
+	elapsed: Duration,
+
+	/// The pending timers, with the elapsed time at which they expire.
+	timers: Vec<(Duration, Box<dyn FnOnce() + Send>)>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl TestClock {
+	/// Create a new virtual clock.
+	#[inline]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Get the virtual time that passed since the clock was created.
+	#[inline]
+	pub fn elapsed(&self) -> Duration {
+		self.state.lock_unpoisoned().elapsed
+	}
+
+	/// Get the number of timers that have not expired yet.
+	#[inline]
+	pub fn pending_timers(&self) -> usize {
+		self.state.lock_unpoisoned().timers.len()
+	}
+
+	/// Advance the clock, and expire all timers that are due.
+	///
+	/// The timers are expired in order of their deadline, from the calling thread.
+	pub fn advance(&self, duration: Duration) {
+		let mut state = self.state.lock_unpoisoned();
+		state.elapsed += duration;
+		let elapsed = state.elapsed;
+		let (mut due, pending) = std::mem::take(&mut state.timers)
+			.into_iter()
+			.partition::<Vec<_>, _>(|(deadline, _)| *deadline <= elapsed);
+		state.timers = pending;
+		drop(state);
+
+		due.sort_by_key(|(deadline, _)| *deadline);
+		for (_deadline, callback) in due {
+			callback();
+		}
+	}
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Clock for TestClock {
+	fn call_after(&self, duration: Duration, callback: Box<dyn FnOnce() + Send>) {
+		let mut state = self.state.lock_unpoisoned();
+		let deadline = state.elapsed + duration;
+		state.timers.push((deadline, callback));
+	}
+}
@@ -0,0 +1,89 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::lock::MutexExt;
+use crate::waker_list::{Waiter, WakerList};
+use crate::Clock;
+
+/// Run a future to completion, or give up after a timeout.
+///
+/// Returns [`None`] if the timeout expired before the future completed.
+pub(crate) async fn timeout<F: Future>(clock: &dyn Clock, duration: Duration, future: F) -> Option<F::Output> {
+	let mut timer = std::pin::pin!(Timer::new(clock, duration));
+	let mut future = std::pin::pin!(future);
+	std::future::poll_fn(|context| {
+		if let Poll::Ready(output) = future.as_mut().poll(context) {
+			return Poll::Ready(Some(output));
+		}
+		timer.as_mut().poll_expired(context).map(|()| None)
+	})
+	.await
+}
+
+/// A runtime independent timer, driven by a [`Clock`].
+///
+/// A timer can have multiple handles created with [`Timer::share()`], which can each wait for the same timer.
+pub(crate) struct Timer {
+	state: Arc<Mutex<TimerState>>,
+
+	/// The node in the list of waiting tasks for this handle.
+	waiter: Waiter,
+}
+
+#[derive(Default)]
+struct TimerState {
+	/// If true, the timer has expired.
+	expired: bool,
+
+	/// The wakers of the tasks that are waiting for the timer.
+	wakers: WakerList,
+}
+
+impl Timer {
+	/// Start a timer that expires after the given duration.
+	pub fn new(clock: &dyn Clock, duration: Duration) -> Self {
+		let state = Arc::new(Mutex::new(TimerState::default()));
+		let weak_state = Arc::downgrade(&state);
+		clock.call_after(duration, Box::new(move || {
+			// If all handles of the timer were dropped already, nobody cares.
+			if let Some(state) = weak_state.upgrade() {
+				let mut state = state.lock_unpoisoned();
+				state.expired = true;
+				state.wakers.wake_all();
+			}
+		}));
+		Self { state, waiter: Waiter::new() }
+	}
+
+	/// Create a new handle to the same timer.
+	pub fn share(&self) -> Self {
+		Self {
+			state: self.state.clone(),
+			waiter: Waiter::new(),
+		}
+	}
+
+	/// Check if the timer expired, and register the current task to be woken up if it did not.
+	pub fn poll_expired(self: Pin<&mut Self>, context: &mut Context) -> Poll<()> {
+		let mut state = self.state.lock_unpoisoned();
+		if state.expired {
+			return Poll::Ready(());
+		}
+		// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+		// The waiter is deregistered when the timer is dropped.
+		unsafe { state.wakers.register(self.as_ref().map_unchecked(|me| &me.waiter), context.waker()) };
+		Poll::Pending
+	}
+}
+
+impl Drop for Timer {
+	fn drop(&mut self) {
+		if self.waiter.is_registered() {
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { self.state.lock_unpoisoned().wakers.deregister(&self.waiter) };
+		}
+	}
+}
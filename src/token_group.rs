@@ -0,0 +1,179 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::waker_list::{Waiter, WakerList};
+use crate::{DelayShutdownToken, ShutdownAlreadyCompleted, ShutdownManager, WrapDelayShutdown};
+use crate::lock::MutexExt;
+
+/// A named group of delay tokens.
+///
+/// Created by [`ShutdownManager::token_group()`].
+///
+/// Tokens created from a group delay the shutdown just like any other [`DelayShutdownToken`],
+/// but they can also be counted and awaited separately with [`Self::len()`] and [`Self::wait_idle()`].
+/// For example, you could wait for all HTTP connections to close before you shut down the metrics of the listener.
+///
+/// The group can be cloned and sent to different threads and tasks freely.
+/// Each clone uses the same internal state.
+#[derive(Clone)]
+pub struct DelayTokenGroup<T: Clone> {
+	manager: ShutdownManager<T>,
+	state: Arc<GroupState>,
+}
+
+/// The shared state of a [`DelayTokenGroup`].
+pub(crate) struct GroupState {
+	/// The name of the group.
+	name: String,
+
+	/// The number of tokens in the group, and the tasks waiting for the group to become idle.
+	tokens: Mutex<(usize, WakerList)>,
+}
+
+impl GroupState {
+	/// Get the name of the group.
+	pub(crate) fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Add a token to the group.
+	pub(crate) fn increase(&self) {
+		self.tokens.lock_unpoisoned().0 += 1;
+	}
+
+	/// Remove a token from the group, and wake the waiters if it was the last one.
+	pub(crate) fn decrease(&self) {
+		let mut tokens = self.tokens.lock_unpoisoned();
+		let (count, waiters) = &mut *tokens;
+		*count -= 1;
+		if *count == 0 {
+			waiters.wake_all();
+		}
+	}
+}
+
+impl<T: Clone> DelayTokenGroup<T> {
+	/// Create a new empty group for a shutdown manager.
+	pub(crate) fn new(manager: ShutdownManager<T>, name: String) -> Self {
+		Self {
+			manager,
+			state: Arc::new(GroupState {
+				name,
+				tokens: Mutex::new((0, WakerList::new())),
+			}),
+		}
+	}
+
+	/// Get the name of the group.
+	#[inline]
+	pub fn name(&self) -> &str {
+		&self.state.name
+	}
+
+	/// Get the number of delay tokens in the group.
+	///
+	/// Each clone of a token is counted separately.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.state.tokens.lock_unpoisoned().0
+	}
+
+	/// Check if there are no delay tokens in the group.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Get a delay token for the first shutdown phase that is counted in this group.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn delay_shutdown_token(&self) -> Result<DelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
+		self.delay_shutdown_token_in_phase(0)
+	}
+
+	/// Get a delay token for a specific shutdown phase that is counted in this group.
+	///
+	/// See [`ShutdownManager::delay_shutdown_token_in_phase()`] for more information about shutdown phases.
+	///
+	/// If the phase has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn delay_shutdown_token_in_phase(&self, phase: usize) -> Result<DelayShutdownToken<T>, ShutdownAlreadyCompleted<T>> {
+		DelayShutdownToken::new_in_group(self.manager.inner(), phase, Some(self.state.clone()))
+	}
+
+	/// Wrap a future to delay shutdown completion until the wrapped future completes or until it is dropped.
+	///
+	/// The wrapped future is counted in this group until it completes or is dropped.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_delay_shutdown<F: Future>(&self, future: F) -> Result<WrapDelayShutdown<T, F>, ShutdownAlreadyCompleted<T>> {
+		Ok(self.delay_shutdown_token()?.wrap_future(future))
+	}
+
+	/// Asynchronously wait for the group to become idle.
+	///
+	/// The returned future completes when there are no delay tokens left in the group.
+	/// If the group is already idle, the future completes immediately.
+	///
+	/// Note that a group can become busy again if new tokens are created after the future completed.
+	#[inline]
+	pub fn wait_idle(&self) -> GroupIdle {
+		GroupIdle {
+			state: self.state.clone(),
+			waiter: Waiter::new(),
+		}
+	}
+}
+
+/// Future to wait for a [`DelayTokenGroup`] to become idle.
+///
+/// The future stores its own node of the list of waiting tasks, so it is not [`Unpin`].
+#[must_use = "futures must be polled to make progress"]
+pub struct GroupIdle {
+	state: Arc<GroupState>,
+	waiter: Waiter,
+}
+
+impl Clone for GroupIdle {
+	fn clone(&self) -> Self {
+		// The waiter is personal to each future.
+		Self {
+			state: self.state.clone(),
+			waiter: Waiter::new(),
+		}
+	}
+}
+
+impl Drop for GroupIdle {
+	fn drop(&mut self) {
+		if self.waiter.is_registered() {
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { self.state.tokens.lock_unpoisoned().1.deregister(&self.waiter) };
+		}
+	}
+}
+
+impl Future for GroupIdle {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut tokens = self.state.tokens.lock_unpoisoned();
+		let (count, waiters) = &mut *tokens;
+		if *count == 0 {
+			// SAFETY: The waiter is only registered with this list, and we hold the lock.
+			unsafe { waiters.deregister(&self.waiter) };
+			return Poll::Ready(());
+		}
+		// SAFETY: The waiter is pinned, it is only registered with this list, and we hold the lock.
+		// The waiter is deregistered when the future is dropped.
+		unsafe { waiters.register(self.as_ref().map_unchecked(|me| &me.waiter), context.waker()) };
+		Poll::Pending
+	}
+}
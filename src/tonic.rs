@@ -0,0 +1,48 @@
+use std::future::Future;
+use std::net::SocketAddr;
+
+use tonic::transport::server::Router;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownManager};
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Serve a tonic gRPC router, and shut it down gracefully when the shutdown is triggered.
+	///
+	/// The returned future runs the server on the given address until the shutdown is triggered.
+	/// The server then stops accepting new connections and waits for in-flight requests to finish.
+	/// The future delays shutdown completion until the server has stopped.
+	///
+	/// If the server fails, the shutdown is triggered with the reason returned by `map_err`,
+	/// and the future completes with the error:
+	/// ```no_run
+	/// # async fn run(router: tonic::transport::server::Router) {
+	/// let shutdown = async_shutdown::ShutdownManager::new();
+	/// let address = "[::]:50051".parse().unwrap();
+	/// let server = shutdown.serve_tonic(router, address, |_error| 1).unwrap();
+	/// tokio::spawn(server);
+	///
+	/// let exit_code = shutdown.wait_shutdown_complete().await;
+	/// std::process::exit(exit_code);
+	/// # }
+	/// ```
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn serve_tonic<M>(
+		&self,
+		router: Router,
+		address: SocketAddr,
+		map_err: M,
+	) -> Result<impl Future<Output = Result<(), tonic::transport::Error>> + Send + 'static, ShutdownAlreadyCompleted<T>>
+	where
+		M: FnOnce(&tonic::transport::Error) -> T + Send + 'static,
+	{
+		let shutdown = self.clone();
+		self.wrap_graceful_server(move |signal| async move {
+			let result = router.serve_with_shutdown(address, signal).await;
+			if let Err(e) = &result {
+				shutdown.trigger_shutdown_idempotent(map_err(e));
+			}
+			result
+		})
+	}
+}
@@ -0,0 +1,112 @@
+use futures_core::Stream;
+use std::future::Future;
+
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+use crate::{ShutdownAlreadyCompleted, ShutdownBlocker, ShutdownManager};
+
+/// The progress of draining the server after the shutdown was triggered.
+///
+/// Passed to the callback of [`ShutdownManager::report_health_with_progress()`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DrainProgress {
+	/// The number of delay tokens that are still holding up the shutdown.
+	///
+	/// All clones of a delay token together count as one.
+	pub remaining: usize,
+
+	/// The delay tokens that are still holding up the shutdown, with their status.
+	pub blockers: Vec<ShutdownBlocker>,
+}
+
+impl<T: Clone + Send + 'static> ShutdownManager<T> {
+	/// Mark gRPC services as not serving as soon as the shutdown is triggered.
+	///
+	/// The returned future waits for the shutdown to be triggered,
+	/// and then sets the status of the overall server (the service named `""`)
+	/// and of all the given services to [`ServingStatus::NotServing`].
+	/// This allows gRPC load balancers to stop routing new requests to the server while it drains existing requests.
+	///
+	/// The future delays shutdown completion until the new status has been set,
+	/// so the status is always updated before the shutdown completes.
+	/// You should spawn the future on a separate task:
+	/// ```
+	/// # #[tokio::main(flavor = "current_thread")]
+	/// # async fn main() {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// let (health_reporter, health_service) = tonic_health::server::health_reporter();
+	/// tokio::spawn(shutdown.report_health_on_shutdown(health_reporter, ["my.package.MyService"]).unwrap());
+	/// # }
+	/// ```
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn report_health_on_shutdown<I, S>(
+		&self,
+		reporter: HealthReporter,
+		service_names: I,
+	) -> Result<impl Future<Output = ()> + Send + 'static, ShutdownAlreadyCompleted<T>>
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		let service_names: Vec<String> = service_names.into_iter().map(Into::into).collect();
+		let shutdown_signal = self.wait_shutdown_triggered();
+		self.wrap_delay_shutdown(async move {
+			shutdown_signal.await;
+			reporter.set_service_status("", ServingStatus::NotServing).await;
+			for service_name in service_names {
+				reporter.set_service_status(service_name, ServingStatus::NotServing).await;
+			}
+		})
+	}
+
+	/// Mark gRPC services as not serving as soon as the shutdown is triggered, and report the progress of draining the server.
+	///
+	/// This is the same as [`Self::report_health_on_shutdown()`],
+	/// except that the returned future keeps running until the shutdown completes.
+	/// After the status has been set, `on_progress` is called with the remaining delay tokens every time their number changes.
+	/// The gRPC health protocol has no way to report this progress,
+	/// so use the callback to expose it in your logs or metrics instead:
+	/// ```
+	/// # #[tokio::main(flavor = "current_thread")]
+	/// # async fn main() {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// let (health_reporter, health_service) = tonic_health::server::health_reporter();
+	/// let report = shutdown.report_health_with_progress(health_reporter, ["my.package.MyService"], |progress| {
+	///     eprintln!("draining: {} requests remaining", progress.remaining);
+	/// });
+	/// tokio::spawn(report.unwrap());
+	/// # }
+	/// ```
+	///
+	/// The last call reports `0` remaining delay tokens, unless the shutdown was forced to complete.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	pub fn report_health_with_progress<I, S, F>(
+		&self,
+		reporter: HealthReporter,
+		service_names: I,
+		mut on_progress: F,
+	) -> Result<impl Future<Output = ()> + Send + 'static, ShutdownAlreadyCompleted<T>>
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+		F: FnMut(DrainProgress) + Send + 'static,
+	{
+		let set_status = self.report_health_on_shutdown(reporter, service_names)?;
+		let shutdown = self.clone();
+		Ok(async move {
+			// The delay token of `set_status` is released before the progress is reported, so it is not counted.
+			set_status.await;
+			let mut progress = std::pin::pin!(shutdown.completion_progress());
+			while let Some(remaining) = std::future::poll_fn(|context| progress.as_mut().poll_next(context)).await {
+				on_progress(DrainProgress {
+					remaining,
+					blockers: shutdown.pending_blockers(),
+				});
+			}
+		})
+	}
+}
@@ -0,0 +1,164 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{DelayShutdownToken, ShutdownManager, WrapDelayShutdown};
+
+/// Tower layer that makes services shut down gracefully.
+///
+/// Services wrapped by this layer keep a delay token for every in-flight request,
+/// so the shutdown does not complete until all in-flight requests are done.
+/// Once the shutdown is triggered, new calls are rejected with an error created from the shutdown reason.
+///
+/// The error is created by the `make_error` function given to [`ShutdownLayer::new()`].
+/// It must return the error type of the wrapped service.
+#[derive(Clone)]
+pub struct ShutdownLayer<T: Clone, F> {
+	shutdown: ShutdownManager<T>,
+	make_error: F,
+}
+
+impl<T: Clone, F> ShutdownLayer<T, F> {
+	/// Create a new layer for the given shutdown manager.
+	///
+	/// The `make_error` function is called with the shutdown reason to create the error for rejected calls.
+	#[inline]
+	pub fn new(shutdown: ShutdownManager<T>, make_error: F) -> Self {
+		Self { shutdown, make_error }
+	}
+}
+
+impl<T: Clone, F: Clone, S> Layer<S> for ShutdownLayer<T, F> {
+	type Service = ShutdownService<T, S, F>;
+
+	#[inline]
+	fn layer(&self, service: S) -> Self::Service {
+		ShutdownService {
+			shutdown: self.shutdown.clone(),
+			make_error: self.make_error.clone(),
+			service,
+		}
+	}
+}
+
+/// Tower service that delays shutdown completion for in-flight requests, and rejects new calls once the shutdown is triggered.
+///
+/// See [`ShutdownLayer`] for more information.
+#[derive(Clone)]
+pub struct ShutdownService<T: Clone, S, F> {
+	shutdown: ShutdownManager<T>,
+	make_error: F,
+	service: S,
+}
+
+impl<T: Clone, S, F> ShutdownService<T, S, F> {
+	/// Get a reference to the wrapped service.
+	#[inline]
+	pub fn get_ref(&self) -> &S {
+		&self.service
+	}
+
+	/// Get a mutable reference to the wrapped service.
+	#[inline]
+	pub fn get_mut(&mut self) -> &mut S {
+		&mut self.service
+	}
+
+	/// Consume the wrapper and return the wrapped service.
+	#[inline]
+	pub fn into_inner(self) -> S {
+		self.service
+	}
+}
+
+impl<T, S, F, Request> Service<Request> for ShutdownService<T, S, F>
+where
+	T: Clone,
+	S: Service<Request>,
+	F: Fn(T) -> S::Error,
+{
+	type Response = S::Response;
+	type Error = S::Error;
+	type Future = ShutdownServiceFuture<T, S::Future, S::Error>;
+
+	#[inline]
+	fn poll_ready(&mut self, context: &mut Context) -> Poll<Result<(), Self::Error>> {
+		self.service.poll_ready(context)
+	}
+
+	fn call(&mut self, request: Request) -> Self::Future {
+		let inner = self.shutdown.inner();
+		// Only take the lock to get the shutdown reason if the shutdown has been triggered.
+		if inner.is_triggered() {
+			if let Some(reason) = inner.lock().shutdown_reason.clone() {
+				return ShutdownServiceFuture::rejected((self.make_error)(reason));
+			}
+		}
+		match DelayShutdownToken::new(inner, 0) {
+			Ok(token) => ShutdownServiceFuture {
+				inner: ShutdownServiceFutureInner::Running {
+					future: token.wrap_future(self.service.call(request)),
+				},
+			},
+			Err(e) => ShutdownServiceFuture::rejected((self.make_error)(e.shutdown_reason)),
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Response future of a [`ShutdownService`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct ShutdownServiceFuture<T: Clone, F, E> {
+		#[pin]
+		inner: ShutdownServiceFutureInner<T, F, E>,
+	}
+}
+
+impl<T: Clone, F, E> std::fmt::Debug for ShutdownServiceFuture<T, F, E> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let mut f = f.debug_struct("ShutdownServiceFuture");
+		match &self.inner {
+			ShutdownServiceFutureInner::Running { future } => f.field("future", future),
+			ShutdownServiceFutureInner::Rejected { .. } => f.field("rejected", &true),
+		};
+		f.finish()
+	}
+}
+
+pin_project_lite::pin_project! {
+	#[project = ShutdownServiceFutureProj]
+	enum ShutdownServiceFutureInner<T: Clone, F, E> {
+		Running { #[pin] future: WrapDelayShutdown<T, F> },
+		Rejected { error: Option<E> },
+	}
+}
+
+impl<T: Clone, F, E> ShutdownServiceFuture<T, F, E> {
+	/// Create a future that completes with an error right away.
+	fn rejected(error: E) -> Self {
+		Self {
+			inner: ShutdownServiceFutureInner::Rejected { error: Some(error) },
+		}
+	}
+}
+
+impl<T, F, R, E> Future for ShutdownServiceFuture<T, F, E>
+where
+	T: Clone,
+	F: Future<Output = Result<R, E>>,
+{
+	type Output = Result<R, E>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		match self.project().inner.project() {
+			ShutdownServiceFutureProj::Running { future } => future.poll(context),
+			ShutdownServiceFutureProj::Rejected { error } => {
+				Poll::Ready(Err(error.take().expect("ShutdownServiceFuture polled after completion")))
+			},
+		}
+	}
+}
@@ -0,0 +1,46 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::WrapCancel;
+
+pin_project_lite::pin_project! {
+	/// Wrapped fallible future that is automatically cancelled when a shutdown is triggered.
+	///
+	/// If the wrapped future completes before the shutdown is triggered,
+	/// the output of the original future is yielded as is.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the shutdown reason is converted into the error type of the future with [`From`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct TryWrapCancel<T: Clone, F> {
+		#[pin]
+		pub(crate) wrap_cancel: WrapCancel<T, F>,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for TryWrapCancel<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("TryWrapCancel")
+			.field("wrap_cancel", &self.wrap_cancel)
+			.finish()
+	}
+}
+
+impl<T, F, V, E> Future for TryWrapCancel<T, F>
+where
+	T: Clone,
+	F: Future<Output = Result<V, E>>,
+	E: From<T>,
+{
+	type Output = Result<V, E>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		match self.project().wrap_cancel.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(result)) => Poll::Ready(result),
+			Poll::Ready(Err(reason)) => Poll::Ready(Err(E::from(reason))),
+		}
+	}
+}
@@ -0,0 +1,113 @@
+use std::future::Future;
+use std::io;
+use std::path::Path;
+use std::task::Poll;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::wire::{follow_peer, notify_peer};
+use crate::ShutdownManager;
+
+impl<T: Clone + Serialize + DeserializeOwned + Send + 'static> ShutdownManager<T> {
+	/// Propagate the shutdown to the shutdown managers of other processes that connect to a Unix socket.
+	///
+	/// The returned future accepts connections from processes that call [`Self::follow_unix_socket()`] on the same socket path.
+	/// When the shutdown is triggered, the shutdown reason is sent to all connected processes, which triggers their shutdown too.
+	/// Each connected process holds a delay token until it reports that its own shutdown completed, or until it disconnects.
+	/// So the shutdown of this process does not complete before the shutdown of all connected processes completed:
+	/// ```no_run
+	/// # async fn run() -> std::io::Result<()> {
+	/// let shutdown = async_shutdown::ShutdownManager::<String>::new();
+	/// let listener = tokio::net::UnixListener::bind("/run/my-app/shutdown.sock")?;
+	/// tokio::spawn(shutdown.serve_unix_socket(listener));
+	///
+	/// // Spawn the worker processes...
+	///
+	/// shutdown.wait_shutdown_complete().await;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// The connections are handled on separate tasks on the current tokio runtime.
+	/// Processes that connect after the shutdown was triggered receive the shutdown reason immediately.
+	/// The future completes when the shutdown completes, or with an error if accepting a connection fails.
+	///
+	/// # Wire format
+	/// The shutdown reason is sent with its [`Serialize`] implementation, and decoded by the connected process with its [`Deserialize`](serde::Deserialize) implementation.
+	/// The messages are length-prefixed frames, which are stable between versions of this crate and easy to implement in other languages.
+	/// Each frame consists of:
+	/// * a version byte, which is always `1` for this version of the protocol,
+	/// * the length of the message as a big-endian `u32`, at most 1 MiB,
+	/// * the message, encoded as JSON.
+	///
+	/// The serving process sends `{"type":"trigger","reason":...}` with the shutdown reason,
+	/// and the connected process answers with `{"type":"completed"}` once its own shutdown completed.
+	/// A peer that receives a frame with an unknown version or an invalid message closes the connection.
+	///
+	/// # Panics
+	/// The returned future panics if it is polled outside of a tokio runtime.
+	pub fn serve_unix_socket(&self, listener: UnixListener) -> impl Future<Output = io::Result<()>> + Send + 'static {
+		let shutdown = self.clone();
+		async move {
+			let mut complete = std::pin::pin!(shutdown.wait_shutdown_complete());
+			loop {
+				let accepted = std::future::poll_fn(|context| {
+					if complete.as_mut().poll(context).is_ready() {
+						return Poll::Ready(None);
+					}
+					listener.poll_accept(context).map(Some)
+				});
+				let mut stream = match accepted.await {
+					Some(accepted) => accepted?.0,
+					None => return Ok(()),
+				};
+				let delay_token = match shutdown.delay_shutdown_token() {
+					Ok(delay_token) => delay_token,
+					Err(_) => return Ok(()),
+				};
+				delay_token.set_status("unix socket peer");
+				let signal = shutdown.wait_shutdown_triggered();
+				tokio::spawn(async move {
+					let reason = signal.await;
+					// The delay token is held until the peer acknowledged the completion or disconnected.
+					let result = notify_peer(reason, &mut stream).await;
+					drop(delay_token);
+					result
+				});
+			}
+		}
+	}
+
+	/// Follow the shutdown of another process over a Unix socket.
+	///
+	/// The returned future connects to a socket served by [`Self::serve_unix_socket()`] in the other process.
+	/// When the other process triggers its shutdown, the shutdown of this manager is triggered with the same reason.
+	/// When the shutdown of this manager completes, the completion is reported back to the other process:
+	/// ```no_run
+	/// # async fn run() -> std::io::Result<()> {
+	/// let shutdown = async_shutdown::ShutdownManager::<String>::new();
+	/// tokio::spawn(shutdown.follow_unix_socket("/run/my-app/shutdown.sock"));
+	///
+	/// // Run the worker...
+	///
+	/// shutdown.wait_shutdown_complete().await;
+	/// # Ok(())
+	/// # }
+	/// ```
+	///
+	/// If the shutdown of this manager is triggered by something else, the completion is still reported to the other process.
+	///
+	/// The future completes after the completion was reported.
+	/// It completes with an error if the connection fails, if the other process sends an invalid shutdown reason,
+	/// or if the other process closes the connection before triggering the shutdown.
+	pub fn follow_unix_socket(&self, path: impl AsRef<Path>) -> impl Future<Output = io::Result<()>> + Send + 'static {
+		let shutdown = self.clone();
+		let path = path.as_ref().to_path_buf();
+		async move {
+			let stream = UnixStream::connect(path).await?;
+			follow_peer(&shutdown, stream).await
+		}
+	}
+}
@@ -1,21 +1,124 @@
+use std::cell::UnsafeCell;
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::task::Waker;
 
-/// A list of wakers.
-#[derive(Debug, Default)]
+use crate::metrics_export::Gauge;
+
+/// The order in which waiting futures are woken up.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+#[non_exhaustive]
+pub enum WakeOrder {
+	/// No particular order is guaranteed.
+	///
+	/// This is the default.
+	#[default]
+	Unordered,
+
+	/// Futures are woken in the order in which they were first polled.
+	Fifo,
+}
+
+/// A node of a [`WakerList`], embedded in the future that waits.
+///
+/// Registering a waker links the node into the list, so it never allocates.
+/// The future must deregister the node before it is dropped.
+pub struct Waiter {
+	/// The registered waker and the links to the neighbouring nodes.
+	///
+	/// Only accessed by the list, while holding the lock that protects the list.
+	node: UnsafeCell<Node>,
+
+	/// True while the node is linked into a list.
+	///
+	/// The list clears the flag after it is done with the node,
+	/// so the owner can check it without taking the lock.
+	linked: AtomicBool,
+
+	/// The list points to the node, so it must not move.
+	_pinned: PhantomPinned,
+}
+
+/// The data of a [`Waiter`] that is owned by the list.
+struct Node {
+	waker: Option<Waker>,
+	prev: Option<NonNull<Waiter>>,
+	next: Option<NonNull<Waiter>>,
+}
+
+// SAFETY: The node is only accessed by the list while holding the lock that protects the list.
+unsafe impl Send for Waiter {}
+unsafe impl Sync for Waiter {}
+
+impl Waiter {
+	/// Create a new waiter that is not registered with any list.
+	pub fn new() -> Self {
+		Self {
+			node: UnsafeCell::new(Node {
+				waker: None,
+				prev: None,
+				next: None,
+			}),
+			linked: AtomicBool::new(false),
+			_pinned: PhantomPinned,
+		}
+	}
+
+	/// Check if the waiter is registered with a list.
+	///
+	/// If this returns `false`, the list will not touch the waiter again until it is registered again.
+	/// This does not need the lock that protects the list.
+	pub fn is_registered(&self) -> bool {
+		self.linked.load(Ordering::Acquire)
+	}
+}
+
+impl Default for Waiter {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// An intrusive list of wakers.
+///
+/// The nodes of the list are [`Waiter`]s embedded in the waiting futures,
+/// so the memory used by the list is bounded by the number of live waiters and registering a waker never allocates.
+/// The list wakes the waiters in the order in which they were first registered.
+#[derive(Default)]
 pub struct WakerList {
-	/// The wakers (with possibly empty slots)
-	wakers: Vec<Option<Waker>>,
+	/// The first node of the list.
+	head: Option<NonNull<Waiter>>,
+
+	/// The last node of the list.
+	tail: Option<NonNull<Waiter>>,
+
+	/// The number of linked nodes.
+	len: usize,
 
-	/// The empty slots in the list.
-	empty_slots: Vec<usize>,
+	/// Wakers that are not owned by a waiting future, like the ones of abort registrations.
+	detached: Vec<Waker>,
 
-	/// The current epoch, increased whenever `wake_all` is called.
-	epoch: usize,
+	/// The order in which to wake the wakers.
+	wake_order: WakeOrder,
+
+	/// The exported gauge with the number of registered wakers.
+	gauge: Gauge,
 }
 
-pub struct WakerToken {
-	epoch: usize,
-	index: usize,
+// SAFETY: The nodes are only accessed through a mutable reference to the list,
+// and the list is always protected by the same lock as the nodes.
+unsafe impl Send for WakerList {}
+unsafe impl Sync for WakerList {}
+
+impl std::fmt::Debug for WakerList {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WakerList")
+			.field("len", &self.len())
+			.field("wake_order", &self.wake_order)
+			.finish()
+	}
 }
 
 impl WakerList {
@@ -24,70 +127,159 @@ impl WakerList {
 		Self::default()
 	}
 
+	/// Create a new empty list of wakers, that exports the number of registered wakers as gauge.
+	pub fn with_gauge(name: &'static str) -> Self {
+		Self {
+			head: None,
+			tail: None,
+			len: 0,
+			detached: Vec::new(),
+			wake_order: WakeOrder::default(),
+			gauge: Gauge::new(name),
+		}
+	}
+
+	/// Release the memory used for detached wakers.
+	pub fn shrink_to_fit(&mut self) {
+		self.detached.shrink_to_fit();
+	}
+
 	/// Register a waker to be woken up when `wake_all` is called.
 	///
-	/// Returns a token that can be used to unregister the waker again.
-	pub fn register(&mut self, waker: Waker) -> WakerToken {
-		if let Some(index) = self.empty_slots.pop() {
-			debug_assert!(self.wakers[index].is_none());
-			self.wakers[index] = Some(waker);
-			self.token(index)
-		} else {
-			self.wakers.push(Some(waker));
-			self.token(self.wakers.len() - 1)
+	/// If the waiter is already registered, the waker is updated and the waiter keeps its position in the wake order.
+	///
+	/// # Safety
+	/// The waiter must only ever be registered with this list, and only while holding the lock that protects this list.
+	/// The waiter must be deregistered before it is dropped, unless [`Waiter::is_registered()`] returns `false`.
+	pub unsafe fn register(&mut self, waiter: Pin<&Waiter>, waker: &Waker) {
+		let waiter = waiter.get_ref();
+		let node = &mut *waiter.node.get();
+		if waiter.linked.load(Ordering::Relaxed) {
+			match &node.waker {
+				Some(registered) if registered.will_wake(waker) => (),
+				_ => node.waker = Some(waker.clone()),
+			}
+			return;
 		}
+
+		let pointer = NonNull::from(waiter);
+		node.waker = Some(waker.clone());
+		node.prev = self.tail;
+		node.next = None;
+		match self.tail {
+			Some(tail) => (*tail.as_ref().node.get()).next = Some(pointer),
+			None => self.head = Some(pointer),
+		}
+		self.tail = Some(pointer);
+		self.len += 1;
+		self.gauge.add(1);
+		waiter.linked.store(true, Ordering::Relaxed);
+	}
+
+	/// Register a waker that is not owned by a waiting future.
+	///
+	/// The waker stays registered until `wake_all` is called.
+	#[cfg(feature = "abortable")]
+	pub fn register_detached(&mut self, waker: Waker) {
+		self.detached.push(waker);
+		self.gauge.add(1);
 	}
 
-	/// Deregister a waker so it will not be woken up by `wake_all` any more.
+	/// Deregister a waiter so it will not be woken up by `wake_all` any more.
 	///
-	/// This should be called when a future that registered the waker is dropped,
-	/// to prevent the list of wakers growing infinitely large.
+	/// Does nothing if the waiter is not registered.
 	///
-	/// # Panic
-	/// May panic now or later if you give this function a token from another [`WakerList`].
-	pub fn deregister(&mut self, token: WakerToken) -> Option<Waker> {
-		if self.epoch != token.epoch {
-			None
-		} else if let Some(waker) = self.wakers[token.index].take() {
-			self.empty_slots.push(token.index);
-			Some(waker)
-		} else {
-			None
+	/// # Safety
+	/// The waiter must only ever be registered with this list, and this must be called while holding the lock that protects this list.
+	pub unsafe fn deregister(&mut self, waiter: &Waiter) {
+		if !waiter.linked.load(Ordering::Relaxed) {
+			return;
 		}
+		let node = &mut *waiter.node.get();
+		match node.prev {
+			Some(prev) => (*prev.as_ref().node.get()).next = node.next,
+			None => self.head = node.next,
+		}
+		match node.next {
+			Some(next) => (*next.as_ref().node.get()).prev = node.prev,
+			None => self.tail = node.prev,
+		}
+		node.prev = None;
+		node.next = None;
+		node.waker = None;
+		self.len -= 1;
+		self.gauge.sub(1);
+		waiter.linked.store(false, Ordering::Release);
 	}
 
-	/// Wake all wakers, clear the list and increase the epoch.
-	#[allow(clippy::manual_flatten)] // Ssssh.
+	/// Get the order in which to wake the wakers.
+	pub fn wake_order(&self) -> WakeOrder {
+		self.wake_order
+	}
+
+	/// Set the order in which to wake the wakers.
+	///
+	/// The waiters are always woken in registration order, which satisfies every [`WakeOrder`].
+	/// The setting is kept so it can be carried over to a new list.
+	pub fn set_wake_order(&mut self, wake_order: WakeOrder) {
+		self.wake_order = wake_order;
+	}
+
+	/// Wake all wakers and clear the list.
+	///
+	/// The waiters are woken in registration order, followed by the detached wakers.
 	pub fn wake_all(&mut self) {
-		for waker in &mut self.wakers {
-			if let Some(waker) = waker.take() {
-				waker.wake()
-			}
+		while let Some(waker) = self.pop_front() {
+			waker.wake();
+		}
+		self.gauge.sub(self.detached.len());
+		for waker in std::mem::take(&mut self.detached) {
+			waker.wake();
 		}
-		self.wakers.clear();
-		self.empty_slots.clear();
-		self.epoch += 1;
 	}
 
-	/// Create a token for the current epoch with the given index.
-	fn token(&self, index: usize) -> WakerToken {
-		WakerToken {
-			epoch: self.epoch,
-			index,
+	/// Clear the list and return the wakers, so they can be woken later.
+	///
+	/// Use this instead of taking the whole list if the wakers must be woken after releasing the lock.
+	pub fn take_wakers(&mut self) -> Vec<Waker> {
+		let mut wakers = Vec::with_capacity(self.len());
+		while let Some(waker) = self.pop_front() {
+			wakers.push(waker);
 		}
+		self.gauge.sub(self.detached.len());
+		wakers.append(&mut self.detached);
+		wakers
 	}
 
-	/// Get the total number of waker slots.
-	///
-	/// This includes empty slots.
-	#[cfg(test)]
-	pub fn total_slots(&self) -> usize {
-		self.wakers.len()
+	/// Unlink the first waiter and take its waker.
+	fn pop_front(&mut self) -> Option<Waker> {
+		let pointer = self.head?;
+		// SAFETY: Linked waiters are alive, since they must be deregistered before they are dropped.
+		let waiter = unsafe { pointer.as_ref() };
+		let node = unsafe { &mut *waiter.node.get() };
+		self.head = node.next.take();
+		match self.head {
+			Some(next) => unsafe { (*next.as_ref().node.get()).prev = None },
+			None => self.tail = None,
+		}
+		let waker = node.waker.take();
+		self.len -= 1;
+		self.gauge.sub(1);
+
+		// The owner may drop the waiter as soon as the flag is cleared, so don't touch it afterwards.
+		waiter.linked.store(false, Ordering::Release);
+		waker
 	}
 
-	/// Get the number of empty waker slots.
-	#[cfg(test)]
-	pub fn empty_slots(&self) -> usize {
-		self.empty_slots.len()
+	/// Get the number of registered wakers.
+	pub fn len(&self) -> usize {
+		self.len + self.detached.len()
+	}
+}
+
+impl Drop for WakerList {
+	fn drop(&mut self) {
+		// Linked waiters keep the list alive, so only detached wakers can be left.
+		self.gauge.sub(self.detached.len());
 	}
 }
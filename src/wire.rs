@@ -0,0 +1,125 @@
+//! The wire format for propagating the shutdown between processes.
+//!
+//! The format is a stable protocol that is documented on `ShutdownManager::coordinate_tcp()` and `ShutdownManager::serve_unix_socket()`,
+//! so that processes built with different versions of this crate, or implementations in other languages, can talk to each other.
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::ShutdownManager;
+
+/// The version of the wire format, sent as the first byte of every frame.
+const VERSION: u8 = 1;
+
+/// The maximum size of a message.
+///
+/// This protects against a misbehaving peer that makes us allocate huge buffers.
+const MAX_MESSAGE: u32 = 1 << 20;
+
+/// A message of the shutdown propagation protocol.
+///
+/// Messages are encoded as JSON objects with a `type` field.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum Message<T> {
+	/// The shutdown was triggered with a reason.
+	Trigger { reason: T },
+
+	/// The shutdown completed.
+	Completed,
+}
+
+impl<T> Message<T> {
+	/// Write the message to a stream.
+	pub async fn write<W: AsyncWrite + Unpin>(self, stream: &mut W) -> io::Result<()>
+	where
+		T: Serialize,
+	{
+		let message = serde_json::to_vec(&self).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+		let len = u32::try_from(message.len())
+			.ok()
+			.filter(|&len| len <= MAX_MESSAGE)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "shutdown reason is too large to send"))?;
+		let mut frame = Vec::with_capacity(5 + message.len());
+		frame.push(VERSION);
+		frame.extend_from_slice(&len.to_be_bytes());
+		frame.extend_from_slice(&message);
+		stream.write_all(&frame).await?;
+		stream.flush().await
+	}
+
+	/// Read the next message from a stream.
+	///
+	/// Returns [`None`] if the stream was closed cleanly before the next message.
+	pub async fn read<R: AsyncRead + Unpin>(stream: &mut R) -> io::Result<Option<Self>>
+	where
+		T: DeserializeOwned,
+	{
+		let mut header = [0; 5];
+		match stream.read_exact(&mut header[..1]).await {
+			Ok(_) => (),
+			Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+			Err(e) => return Err(e),
+		}
+		if header[0] != VERSION {
+			return Err(invalid_data("unsupported shutdown protocol version"));
+		}
+		stream.read_exact(&mut header[1..]).await?;
+		let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+		if len > MAX_MESSAGE {
+			return Err(invalid_data("shutdown message is too large"));
+		}
+		let mut message = vec![0; len as usize];
+		stream.read_exact(&mut message).await?;
+		match serde_json::from_slice(&message) {
+			Ok(message) => Ok(Some(message)),
+			Err(_) => Err(invalid_data("invalid shutdown message")),
+		}
+	}
+}
+
+/// Create an [`io::Error`] for a peer that violates the protocol.
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Send the shutdown to a peer that follows our shutdown manager, and wait for it to acknowledge completion.
+///
+/// Returns `false` if the peer closed the connection without acknowledging the completion.
+pub(crate) async fn notify_peer<T, S>(reason: T, stream: &mut S) -> io::Result<bool>
+where
+	T: Serialize,
+	S: AsyncRead + AsyncWrite + Unpin,
+{
+	Message::Trigger { reason }.write(stream).await?;
+	match Message::<IgnoredAny>::read(stream).await? {
+		Some(Message::Completed) => Ok(true),
+		None => Ok(false),
+		Some(Message::Trigger { .. }) => Err(invalid_data("unexpected shutdown message from peer")),
+	}
+}
+
+/// Trigger the shutdown of a manager when the peer sends the shutdown, and acknowledge the completion to the peer.
+///
+/// If the shutdown of the manager is triggered by something else, the completion is still acknowledged to the peer.
+pub(crate) async fn follow_peer<T, S>(shutdown: &ShutdownManager<T>, mut stream: S) -> io::Result<()>
+where
+	T: Clone + DeserializeOwned,
+	S: AsyncRead + AsyncWrite + Unpin,
+{
+	match shutdown.wait_shutdown_triggered().wrap_cancel(Message::read(&mut stream)).await {
+		Ok(Ok(Some(Message::Trigger { reason }))) => {
+			shutdown.trigger_shutdown_idempotent(reason);
+		},
+		Ok(Ok(Some(Message::Completed))) => return Err(invalid_data("unexpected shutdown message from peer")),
+		Ok(Ok(None)) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "peer closed the connection before triggering the shutdown")),
+		Ok(Err(e)) => return Err(e),
+		Err(_reason) => (),
+	}
+	shutdown.wait_shutdown_complete().await;
+	Message::<()>::Completed.write(&mut stream).await
+}
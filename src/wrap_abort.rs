@@ -0,0 +1,97 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::task::{JoinError, JoinHandle};
+
+use crate::{ShutdownManager, ShutdownSignal};
+
+pin_project_lite::pin_project! {
+	/// Wrapped [`JoinHandle`] that aborts the task when a shutdown is triggered.
+	///
+	/// If the task finishes before the shutdown is triggered, the join result is yielded as `Ok(join_result)`.
+	///
+	/// If the shutdown is triggered first, the task is aborted.
+	/// The future then waits for the task to actually stop, and yields the shutdown reason as `Err(shutdown_reason)`.
+	/// If the task managed to finish anyway, the join result is still yielded as `Ok(join_result)`.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapAbort<T: Clone, O> {
+		#[pin]
+		pub(crate) shutdown_signal: ShutdownSignal<T>,
+		pub(crate) join_handle: JoinHandle<O>,
+		pub(crate) reason: Option<T>,
+	}
+}
+
+impl<T: Clone, O> std::fmt::Debug for WrapAbort<T, O> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapAbort")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("task_id", &self.join_handle.id())
+			.field("aborted", &self.reason.is_some())
+			.finish()
+	}
+}
+
+impl<T: Clone, O> WrapAbort<T, O> {
+	/// Get a reference to the wrapped join handle.
+	#[inline]
+	pub fn join_handle(&self) -> &JoinHandle<O> {
+		&self.join_handle
+	}
+}
+
+impl<T: Clone, O> Future for WrapAbort<T, O> {
+	type Output = Result<Result<O, JoinError>, T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		if let Poll::Ready(result) = Pin::new(&mut *me.join_handle).poll(context) {
+			return Poll::Ready(join_output(me.reason.take(), result));
+		}
+
+		if me.reason.is_none() {
+			match me.shutdown_signal.as_mut().poll(context) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(reason) => {
+					*me.reason = Some(reason);
+					me.join_handle.abort();
+					me.shutdown_signal.inner.record_cancelled();
+				},
+			}
+			// Poll the handle again, so we get woken when the task stops.
+			if let Poll::Ready(result) = Pin::new(&mut *me.join_handle).poll(context) {
+				return Poll::Ready(join_output(me.reason.take(), result));
+			}
+		}
+		Poll::Pending
+	}
+}
+
+/// Get the output of a [`WrapAbort`] future for a finished task.
+///
+/// If the task was aborted by us, the shutdown reason is returned as error.
+fn join_output<T, O>(reason: Option<T>, result: Result<O, JoinError>) -> Result<Result<O, JoinError>, T> {
+	match reason {
+		Some(reason) if matches!(&result, Err(e) if e.is_cancelled()) => Err(reason),
+		_ => Ok(result),
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap a [`JoinHandle`] so that the task is aborted when the shutdown is triggered.
+	///
+	/// Dropping a [`JoinHandle`] does not stop the task, so wrapping the handle with [`Self::wrap_cancel()`] is usually a mistake.
+	/// This function aborts the task itself instead.
+	///
+	/// See [`WrapAbort`] for the output of the returned future.
+	#[inline]
+	pub fn wrap_abort<O>(&self, join_handle: JoinHandle<O>) -> WrapAbort<T, O> {
+		WrapAbort {
+			shutdown_signal: self.wait_shutdown_triggered(),
+			join_handle,
+			reason: None,
+		}
+	}
+}
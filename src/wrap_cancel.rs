@@ -2,19 +2,80 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use crate::protect::CancelScope;
 use crate::shutdown_signal::ShutdownSignal;
+use crate::WrapCancelOutcome;
 
-/// Wrapped future that is automatically cancelled when a shutdown is triggered.
-///
-/// If the wrapped future completes before the shutdown is triggered,
-/// the output of the original future is yielded as `Ok(value)`.
-///
-/// If the shutdown is triggered before the wrapped future completes,
-/// the original future is dropped and the shutdown reason is yielded as `Err(shutdown_reason)`.
-#[must_use = "futures must be polled to make progress"]
-pub struct WrapCancel<T: Clone, F> {
-	pub(crate) shutdown_signal: ShutdownSignal<T>,
-	pub(crate) future: Result<F, T>,
+pin_project_lite::pin_project! {
+	/// Wrapped future that is automatically cancelled when a shutdown is triggered.
+	///
+	/// If the wrapped future completes before the shutdown is triggered,
+	/// the output of the original future is yielded as `Ok(value)`.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the shutdown reason is yielded as `Err(shutdown_reason)`.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancel<T: Clone, F> {
+		#[pin]
+		pub(crate) shutdown_signal: ShutdownSignal<T>,
+		#[pin]
+		pub(crate) future: Option<F>,
+		// The shutdown reason, once the future has been cancelled.
+		pub(crate) cancelled: Option<T>,
+		// The critical sections that hold off the cancellation.
+		pub(crate) scope: CancelScope,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for WrapCancel<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancel")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("cancelled", &self.cancelled.is_some())
+			.finish()
+	}
+}
+
+impl<T: Clone, F> WrapCancel<T, F> {
+	/// Make the future complete with a [`Cancelled`][crate::Cancelled] value instead of a [`Result`].
+	///
+	/// This avoids confusing `Ok(Err(e))` nesting when the wrapped future itself returns a [`Result`]:
+	/// ```
+	/// # use async_shutdown::{Cancelled, ShutdownManager};
+	/// # async fn connect() -> std::io::Result<()> { Ok(()) }
+	/// # async fn example(shutdown: ShutdownManager<()>) {
+	/// match shutdown.wrap_cancel(connect()).outcome().await {
+	///     Cancelled::Completed(Ok(())) => println!("connected"),
+	///     Cancelled::Completed(Err(e)) => println!("failed to connect: {}", e),
+	///     Cancelled::ShutdownTriggered(()) => println!("shutdown triggered"),
+	/// }
+	/// # }
+	/// ```
+	#[inline]
+	pub fn outcome(self) -> WrapCancelOutcome<T, F> {
+		WrapCancelOutcome { wrap_cancel: self }
+	}
+
+	/// Get the shutdown signal that cancels the wrapped future.
+	///
+	/// This can be used to check the shutdown state, or to clone the signal for a sibling future.
+	#[inline]
+	pub fn shutdown_signal(&self) -> &ShutdownSignal<T> {
+		&self.shutdown_signal
+	}
+
+	/// Recover the wrapped future, so it is no longer cancelled when the shutdown is triggered.
+	///
+	/// Returns `Err(shutdown_reason)` if the future was already cancelled.
+	/// Note that the future is only cancelled when the wrapper is polled,
+	/// so this returns the future even if the shutdown was triggered after the last poll.
+	#[inline]
+	pub fn into_inner(self) -> Result<F, T> {
+		match self.future {
+			Some(future) => Ok(future),
+			None => Err(self.cancelled.expect("WrapCancel is missing the shutdown reason")),
+		}
+	}
 }
 
 impl<T: Clone, F: Future> Future for WrapCancel<T, F> {
@@ -22,25 +83,29 @@ impl<T: Clone, F: Future> Future for WrapCancel<T, F> {
 
 	#[inline]
 	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
-		// SAFETY: We never move `future`, so we can not violate the requirements of `F`.
-		// We do drop it, but that's allowed by `Pin`.
-		let me = unsafe { self.get_unchecked_mut() };
-
-		match &mut me.future {
-			Err(e) => return Poll::Ready(Err(e.clone())),
-			Ok(future) => {
-				let future = unsafe { Pin::new_unchecked(future) };
-				if let Poll::Ready(value) = future.poll(context) {
-					return Poll::Ready(Ok(value));
-				}
-			},
+		let mut me = self.project();
+
+		if let Some(reason) = me.cancelled {
+			return Poll::Ready(Err(reason.clone()));
+		}
+		if let Some(future) = me.future.as_mut().as_pin_mut() {
+			if let Poll::Ready(value) = me.scope.poll_in(|| future.poll(context)) {
+				return Poll::Ready(Ok(value));
+			}
+		}
+
+		// Do not cancel the future while it is running a critical section.
+		if me.scope.is_protected() {
+			return Poll::Pending;
 		}
 
 		// Otherwise check if the shutdown signal has been given.
-		let shutdown = Pin::new(&mut me.shutdown_signal).poll(context);
+		let shutdown = me.shutdown_signal.as_mut().poll(context);
 		match shutdown {
 			Poll::Ready(reason) => {
-				me.future = Err(reason.clone());
+				me.future.set(None);
+				*me.cancelled = Some(reason.clone());
+				me.shutdown_signal.inner.record_cancelled();
 				Poll::Ready(Err(reason))
 			},
 			Poll::Pending => Poll::Pending,
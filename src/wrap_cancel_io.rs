@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{ShutdownManager, ShutdownSignal, WrapCancel};
+
+/// Convert a shutdown reason into an [`io::Error`] with [`io::ErrorKind::Interrupted`].
+///
+/// This can be used with [`ShutdownManager::wrap_cancel_map_err()`] or with the output of a [`WrapCancel`] future
+/// to fold the shutdown into the error handling of an I/O loop:
+/// ```
+/// # async fn read_frame() -> std::io::Result<Vec<u8>> { Ok(Vec::new()) }
+/// # async fn example(shutdown: async_shutdown::ShutdownManager<&'static str>) -> std::io::Result<()> {
+/// let frame = shutdown.wrap_cancel(read_frame()).await.map_err(async_shutdown::shutdown_io_error)??;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The shutdown reason becomes the inner error of the [`io::Error`].
+/// Use [`ShutdownManager::map_reason()`] first if your shutdown reason can not be converted into an error.
+#[inline]
+pub fn shutdown_io_error<T>(reason: T) -> io::Error
+where
+	T: Into<Box<dyn Error + Send + Sync>>,
+{
+	io::Error::new(io::ErrorKind::Interrupted, reason)
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped I/O future that is automatically cancelled when a shutdown is triggered.
+	///
+	/// If the wrapped future completes before the shutdown is triggered,
+	/// the output of the original future is yielded as is.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the shutdown reason is yielded as an [`io::Error`] with [`io::ErrorKind::Interrupted`].
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelIo<T: Clone, F> {
+		#[pin]
+		wrap_cancel: WrapCancel<T, F>,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for WrapCancelIo<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelIo")
+			.field("wrap_cancel", &self.wrap_cancel)
+			.finish()
+	}
+}
+
+impl<T, F, V> Future for WrapCancelIo<T, F>
+where
+	T: Clone + Into<Box<dyn Error + Send + Sync>>,
+	F: Future<Output = io::Result<V>>,
+{
+	type Output = io::Result<V>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		match self.project().wrap_cancel.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(result)) => Poll::Ready(result),
+			Poll::Ready(Err(reason)) => Poll::Ready(Err(shutdown_io_error(reason))),
+		}
+	}
+}
+
+impl<T: Clone> ShutdownSignal<T> {
+	/// Wrap an I/O future so that it is cancelled when a shutdown is triggered.
+	///
+	/// The returned future completes with an [`io::Error`] of kind [`io::ErrorKind::Interrupted`] if a shutdown is triggered,
+	/// and with the output of the wrapped future when it completes.
+	///
+	/// The wrapped future is dropped if the shutdown starts before the wrapped future completes.
+	#[inline]
+	pub fn wrap_cancel_io<F, V>(&self, future: F) -> WrapCancelIo<T, F>
+	where
+		T: Into<Box<dyn Error + Send + Sync>>,
+		F: Future<Output = io::Result<V>>,
+	{
+		WrapCancelIo {
+			wrap_cancel: self.wrap_cancel(future),
+		}
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap an I/O future so that it is cancelled (dropped) when the shutdown is triggered.
+	///
+	/// The returned future completes with an [`io::Error`] of kind [`io::ErrorKind::Interrupted`] if the shutdown is triggered,
+	/// with the shutdown reason as inner error.
+	/// If the wrapped future completes first, the returned future completes with its output.
+	///
+	/// This allows protocol loops that already return [`io::Result`] to treat the shutdown like any other I/O error:
+	/// ```
+	/// # async fn read_frame() -> std::io::Result<Vec<u8>> { Ok(Vec::new()) }
+	/// # async fn example(shutdown: async_shutdown::ShutdownManager<&'static str>) -> std::io::Result<()> {
+	/// loop {
+	///     let frame = shutdown.wrap_cancel_io(read_frame()).await?;
+	///     // Process the frame...
+	/// #   break Ok(())
+	/// }
+	/// # }
+	/// ```
+	#[inline]
+	pub fn wrap_cancel_io<F, V>(&self, future: F) -> WrapCancelIo<T, F>
+	where
+		T: Into<Box<dyn Error + Send + Sync>>,
+		F: Future<Output = io::Result<V>>,
+	{
+		self.wait_shutdown_triggered().wrap_cancel_io(future)
+	}
+}
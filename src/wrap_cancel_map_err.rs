@@ -0,0 +1,51 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::WrapCancel;
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is automatically cancelled when a shutdown is triggered, with a custom error.
+	///
+	/// If the wrapped future completes before the shutdown is triggered,
+	/// the output of the original future is yielded as `Ok(value)`.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the shutdown reason is converted into an error by the mapping function.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelMapErr<T: Clone, F, M> {
+		#[pin]
+		pub(crate) wrap_cancel: WrapCancel<T, F>,
+		pub(crate) map_err: Option<M>,
+	}
+}
+
+impl<T: Clone, F, M> std::fmt::Debug for WrapCancelMapErr<T, F, M> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelMapErr")
+			.field("wrap_cancel", &self.wrap_cancel)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T, F, M, E> Future for WrapCancelMapErr<T, F, M>
+where
+	T: Clone,
+	F: Future,
+	M: FnOnce(T) -> E,
+{
+	type Output = Result<F::Output, E>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		match me.wrap_cancel.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(value)) => Poll::Ready(Ok(value)),
+			Poll::Ready(Err(reason)) => {
+				let map_err = me.map_err.take().expect("WrapCancelMapErr polled after completion");
+				Poll::Ready(Err(map_err(reason)))
+			},
+		}
+	}
+}
@@ -0,0 +1,90 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::WrapCancel;
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is automatically cancelled when a shutdown is triggered, with a fallback value.
+	///
+	/// If the wrapped future completes before the shutdown is triggered, the output of the original future is yielded.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the fallback value is yielded instead.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelOr<T: Clone, F: Future> {
+		#[pin]
+		pub(crate) wrap_cancel: WrapCancel<T, F>,
+		pub(crate) default: Option<F::Output>,
+	}
+}
+
+impl<T: Clone, F: Future> std::fmt::Debug for WrapCancelOr<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelOr")
+			.field("wrap_cancel", &self.wrap_cancel)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: Clone, F: Future> Future for WrapCancelOr<T, F> {
+	type Output = F::Output;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		match me.wrap_cancel.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(value)) => Poll::Ready(value),
+			Poll::Ready(Err(_reason)) => {
+				let default = me.default.take().expect("WrapCancelOr polled after completion");
+				Poll::Ready(default)
+			},
+		}
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is automatically cancelled when a shutdown is triggered, with a computed fallback value.
+	///
+	/// If the wrapped future completes before the shutdown is triggered, the output of the original future is yielded.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the fallback function is called with the shutdown reason to produce the output.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelOrElse<T: Clone, F, D> {
+		#[pin]
+		pub(crate) wrap_cancel: WrapCancel<T, F>,
+		pub(crate) default: Option<D>,
+	}
+}
+
+impl<T: Clone, F, D> std::fmt::Debug for WrapCancelOrElse<T, F, D> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelOrElse")
+			.field("wrap_cancel", &self.wrap_cancel)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T, F, D> Future for WrapCancelOrElse<T, F, D>
+where
+	T: Clone,
+	F: Future,
+	D: FnOnce(T) -> F::Output,
+{
+	type Output = F::Output;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		match me.wrap_cancel.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(value)) => Poll::Ready(value),
+			Poll::Ready(Err(reason)) => {
+				let default = me.default.take().expect("WrapCancelOrElse polled after completion");
+				Poll::Ready(default(reason))
+			},
+		}
+	}
+}
@@ -0,0 +1,50 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{RunSummary, RunThenCleanup};
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is cancelled when a shutdown is triggered, after which an async clean-up function is run.
+	///
+	/// If the wrapped future completes before the shutdown is triggered,
+	/// the output of the original future is yielded as `Ok(value)`.
+	///
+	/// If the shutdown is triggered before the wrapped future completes,
+	/// the original future is dropped and the clean-up function is called with the shutdown reason.
+	/// When the future returned by the clean-up function completes, the shutdown reason is yielded as `Err(shutdown_reason)`.
+	///
+	/// The wrapper delays shutdown completion until the wrapped future or the clean-up future completes, or until it is dropped.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelWithCleanup<T: Clone, F, C, CF> {
+		#[pin]
+		pub(crate) run: RunThenCleanup<T, F, C, CF>,
+	}
+}
+
+impl<T: Clone, F, C, CF> std::fmt::Debug for WrapCancelWithCleanup<T, F, C, CF> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelWithCleanup")
+			.field("run", &self.run)
+			.finish()
+	}
+}
+
+impl<T, F, C, CF> Future for WrapCancelWithCleanup<T, F, C, CF>
+where
+	T: Clone,
+	F: Future,
+	C: FnOnce(T) -> CF,
+	CF: Future<Output = ()>,
+{
+	type Output = Result<F::Output, T>;
+
+	#[inline]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		match self.project().run.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(RunSummary::Completed(value)) => Poll::Ready(Ok(value)),
+			Poll::Ready(RunSummary::CleanedUp { reason, cleanup: () }) => Poll::Ready(Err(reason)),
+		}
+	}
+}
@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::shutdown_signal::ShutdownSignal;
+use crate::timer::Timer;
+use crate::DelayShutdownToken;
+
+/// The output of a [`WrapCancelWithGrace`] future.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GraceOutcome<O, T> {
+	/// The wrapped future completed before the shutdown was triggered.
+	Finished(O),
+
+	/// The wrapped future completed after the shutdown was triggered, but within the grace period.
+	FinishedDuringGrace(O),
+
+	/// The wrapped future did not complete within the grace period, and it was cancelled.
+	Cancelled(T),
+}
+
+impl<O, T> GraceOutcome<O, T> {
+	/// Get the output of the wrapped future, if it completed.
+	#[inline]
+	pub fn output(self) -> Option<O> {
+		match self {
+			Self::Finished(output) => Some(output),
+			Self::FinishedDuringGrace(output) => Some(output),
+			Self::Cancelled(_) => None,
+		}
+	}
+
+	/// Check if the wrapped future was cancelled.
+	#[inline]
+	pub fn is_cancelled(&self) -> bool {
+		matches!(self, Self::Cancelled(_))
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that is cancelled when a grace period expires after a shutdown is triggered.
+	///
+	/// If the wrapped future completes before the shutdown is triggered, it yields [`GraceOutcome::Finished`].
+	/// If it completes after the shutdown is triggered but within the grace period, it yields [`GraceOutcome::FinishedDuringGrace`].
+	/// Otherwise, the wrapped future is dropped when the grace period expires, and the shutdown reason is yielded as [`GraceOutcome::Cancelled`].
+	///
+	/// The wrapper delays shutdown completion until it completes or until it is dropped.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCancelWithGrace<T: Clone, F> {
+		#[pin]
+		pub(crate) shutdown_signal: ShutdownSignal<T>,
+		pub(crate) delay_token: Option<DelayShutdownToken<T>>,
+		pub(crate) grace: Duration,
+		#[pin]
+		pub(crate) timer: Option<Timer>,
+		pub(crate) reason: Option<T>,
+		#[pin]
+		pub(crate) future: Option<F>,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for WrapCancelWithGrace<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCancelWithGrace")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("delay_token", &self.delay_token)
+			.field("grace", &self.grace)
+			.field("grace_started", &self.timer.is_some())
+			.field("cancelled", &self.reason.is_some())
+			.finish()
+	}
+}
+
+impl<T: Clone, F: Future> Future for WrapCancelWithGrace<T, F> {
+	type Output = GraceOutcome<F::Output, T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		let future = me.future.as_mut().as_pin_mut().expect("WrapCancelWithGrace polled after completion");
+		if let Poll::Ready(value) = future.poll(context) {
+			me.future.set(None);
+			*me.delay_token = None;
+			return match me.reason {
+				None => Poll::Ready(GraceOutcome::Finished(value)),
+				Some(_) => Poll::Ready(GraceOutcome::FinishedDuringGrace(value)),
+			};
+		}
+
+		if me.reason.is_none() {
+			match me.shutdown_signal.as_mut().poll(context) {
+				Poll::Ready(reason) => {
+					*me.reason = Some(reason);
+					me.timer.set(Some(me.shutdown_signal.inner.grace_timer(*me.grace)));
+				},
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+
+		let timer = me.timer.as_pin_mut().expect("WrapCancelWithGrace is missing the grace timer");
+		if timer.poll_expired(context).is_pending() {
+			return Poll::Pending;
+		}
+
+		me.future.set(None);
+		*me.delay_token = None;
+		me.shutdown_signal.inner.record_cancelled();
+		let reason = me.reason.take().expect("WrapCancelWithGrace is missing the shutdown reason");
+		Poll::Ready(GraceOutcome::Cancelled(reason))
+	}
+}
@@ -0,0 +1,91 @@
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::shared::Shared;
+use crate::ShutdownManager;
+
+pin_project_lite::pin_project! {
+	/// Wrapped future that triggers a shutdown when it panics.
+	///
+	/// If the wrapped future completes normally, the output is yielded as `Ok(value)`.
+	///
+	/// If the wrapped future panics, the panic is caught and the mapping function is called with the panic payload
+	/// to determine the shutdown reason.
+	/// Then the shutdown is triggered, and the panic payload is yielded as `Err(payload)`.
+	/// You can use [`std::panic::resume_unwind()`] to continue unwinding if you want.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapCatchPanic<T: Clone, F, M> {
+		pub(crate) inner: Arc<Shared<T>>,
+		pub(crate) map_panic: Option<M>,
+		#[pin]
+		pub(crate) future: F,
+	}
+}
+
+impl<T: Clone, F, M> std::fmt::Debug for WrapCatchPanic<T, F, M> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapCatchPanic").finish_non_exhaustive()
+	}
+}
+
+impl<T, F, M> Future for WrapCatchPanic<T, F, M>
+where
+	T: Clone,
+	F: Future,
+	M: FnOnce(&(dyn Any + Send)) -> T,
+{
+	type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+		let future = me.future;
+
+		// The future is never polled again after it panicked, so it can not be observed in a broken state.
+		match std::panic::catch_unwind(AssertUnwindSafe(|| future.poll(context))) {
+			Ok(poll) => poll.map(Ok),
+			Err(payload) => {
+				let map_panic = me.map_panic.take().expect("WrapCatchPanic polled after completion");
+				me.inner.shutdown_idempotent(map_panic(&*payload));
+				Poll::Ready(Err(payload))
+			},
+		}
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Wrap a future to trigger a shutdown when it panics.
+	///
+	/// If the future panics, the shutdown is triggered with the reason returned by `map_panic`,
+	/// and the returned future completes with `Err(payload)`.
+	/// This allows the rest of the application to shut down in an orderly fashion, instead of losing a worker silently.
+	///
+	/// If you want the panic to propagate after the shutdown is triggered, you can resume unwinding:
+	/// ```
+	/// # async fn worker() {}
+	/// # async fn run() {
+	/// # let shutdown = async_shutdown::ShutdownManager::new();
+	/// let result = shutdown.wrap_catch_panic(worker(), |_payload| "worker panicked").await;
+	/// if let Err(payload) = result {
+	///     std::panic::resume_unwind(payload);
+	/// }
+	/// # }
+	/// ```
+	///
+	/// Note that this does not work if your application is compiled with `panic = "abort"`.
+	#[inline]
+	pub fn wrap_catch_panic<F, M>(&self, future: F, map_panic: M) -> WrapCatchPanic<T, F, M>
+	where
+		F: Future,
+		M: FnOnce(&(dyn Any + Send)) -> T,
+	{
+		WrapCatchPanic {
+			inner: self.inner(),
+			map_panic: Some(map_panic),
+			future,
+		}
+	}
+}
@@ -4,11 +4,41 @@ use std::task::{Context, Poll};
 
 use crate::DelayShutdownToken;
 
-/// Wrapped future that delays shutdown completion until it completes or until it is droppped.
-#[must_use = "futures must be polled to make progress"]
-pub struct WrapDelayShutdown<T: Clone, F> {
-	pub(crate) delay_token: Option<DelayShutdownToken<T>>,
-	pub(crate) future: F,
+pin_project_lite::pin_project! {
+	/// Wrapped future that delays shutdown completion until it completes or until it is droppped.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapDelayShutdown<T: Clone, F> {
+		pub(crate) delay_token: Option<DelayShutdownToken<T>>,
+		#[pin]
+		pub(crate) future: F,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for WrapDelayShutdown<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapDelayShutdown")
+			.field("delay_token", &self.delay_token)
+			.finish()
+	}
+}
+
+impl<T: Clone, F> WrapDelayShutdown<T, F> {
+	/// Get the delay token of the wrapper.
+	///
+	/// The token can be cloned to delay the shutdown for a sibling future.
+	/// Returns [`None`] if the wrapped future already completed.
+	#[inline]
+	pub fn delay_token(&self) -> Option<&DelayShutdownToken<T>> {
+		self.delay_token.as_ref()
+	}
+
+	/// Recover the wrapped future, so it no longer delays the shutdown.
+	///
+	/// The delay token of the wrapper is dropped, which may cause the shutdown to complete.
+	#[inline]
+	pub fn into_inner(self) -> F {
+		self.future
+	}
 }
 
 impl<T: Clone, F: Future> Future for WrapDelayShutdown<T, F> {
@@ -16,16 +46,13 @@ impl<T: Clone, F: Future> Future for WrapDelayShutdown<T, F> {
 
 	#[inline]
 	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
-		// SAFETY: We never move `future`, so we can not violate the requirements of `F`.
-		unsafe {
-			let me = self.get_unchecked_mut();
-			match Pin::new_unchecked(&mut me.future).poll(context) {
-				Poll::Pending => Poll::Pending,
-				Poll::Ready(value) => {
-					me.delay_token = None;
-					Poll::Ready(value)
-				},
-			}
+		let me = self.project();
+		match me.future.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(value) => {
+				*me.delay_token = None;
+				Poll::Ready(value)
+			},
 		}
 	}
 }
@@ -0,0 +1,211 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::shutdown_signal::ShutdownSignal;
+use crate::{Cancelled, DelayShutdownToken, ShutdownAlreadyCompleted, ShutdownManager};
+
+/// The output of a [`WrapGraceful`] future, which tells how far it got.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum GracefulOutcome<R, T> {
+	/// The work completed before the shutdown was triggered, and the clean-up ran to completion.
+	Finished(R),
+
+	/// The shutdown was triggered, so the work was cancelled and the clean-up ran to completion.
+	CleanedUp {
+		/// The shutdown reason.
+		reason: T,
+
+		/// The output of the clean-up future.
+		cleanup: R,
+	},
+
+	/// The forced shutdown was triggered while the clean-up was running, so the clean-up was cancelled too.
+	ForcedDuringCleanup(T),
+}
+
+impl<R, T> GracefulOutcome<R, T> {
+	/// Get the output of the clean-up future, if it completed.
+	#[inline]
+	pub fn cleanup(self) -> Option<R> {
+		match self {
+			Self::Finished(cleanup) => Some(cleanup),
+			Self::CleanedUp { cleanup, .. } => Some(cleanup),
+			Self::ForcedDuringCleanup(_) => None,
+		}
+	}
+
+	/// Check if the clean-up was cancelled by a forced shutdown.
+	#[inline]
+	pub fn is_forced(&self) -> bool {
+		matches!(self, Self::ForcedDuringCleanup(_))
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// Future that runs some work until the shutdown is triggered, and then always runs a clean-up future.
+	///
+	/// Created by [`ShutdownManager::wrap_graceful()`].
+	///
+	/// The clean-up function is called with a [`Cancelled`] value that tells how the work ended:
+	/// with its output if it completed, or with the shutdown reason if it was cancelled.
+	/// The clean-up future is cancelled in turn if the forced shutdown is triggered.
+	/// See [`GracefulOutcome`] for the possible results.
+	///
+	/// The future delays shutdown completion until the clean-up future completes, or until it is dropped.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapGraceful<T: Clone, F, C, CF> {
+		#[pin]
+		shutdown_signal: ShutdownSignal<T>,
+		#[pin]
+		forced_signal: ShutdownSignal<T>,
+		delay_token: Option<DelayShutdownToken<T>>,
+		// The shutdown reason, if the work was cancelled.
+		reason: Option<T>,
+		#[pin]
+		state: GracefulState<F, C, CF>,
+	}
+}
+
+impl<T: Clone, F, C, CF> std::fmt::Debug for WrapGraceful<T, F, C, CF> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		let state = match &self.state {
+			GracefulState::Working { .. } => "working",
+			GracefulState::Cleanup { .. } => "cleanup",
+			GracefulState::Done => "done",
+		};
+		f.debug_struct("WrapGraceful")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("delay_token", &self.delay_token)
+			.field("state", &state)
+			.finish()
+	}
+}
+
+pin_project_lite::pin_project! {
+	/// The state of a [`WrapGraceful`] future.
+	#[project = GracefulStateProj]
+	enum GracefulState<F, C, CF> {
+		/// The work is still running.
+		Working {
+			#[pin]
+			work: F,
+			cleanup: Option<C>,
+		},
+
+		/// The work has ended and the clean-up future is running.
+		Cleanup {
+			#[pin]
+			cleanup: CF,
+		},
+
+		/// The future has completed.
+		Done,
+	}
+}
+
+impl<T, F, C, CF> Future for WrapGraceful<T, F, C, CF>
+where
+	T: Clone,
+	F: Future,
+	C: FnOnce(Cancelled<F::Output, T>) -> CF,
+	CF: Future,
+{
+	type Output = GracefulOutcome<CF::Output, T>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let mut me = self.project();
+
+		loop {
+			match me.state.as_mut().project() {
+				GracefulStateProj::Working { work, cleanup } => {
+					let work_state = match work.poll(context) {
+						Poll::Ready(value) => Cancelled::Completed(value),
+						Poll::Pending => match me.shutdown_signal.as_mut().poll(context) {
+							Poll::Ready(reason) => {
+								me.shutdown_signal.inner.record_cancelled();
+								*me.reason = Some(reason.clone());
+								Cancelled::ShutdownTriggered(reason)
+							},
+							Poll::Pending => return Poll::Pending,
+						},
+					};
+					let cleanup = cleanup.take().expect("WrapGraceful is missing the clean-up function");
+					// Drop the work before starting the clean-up.
+					me.state.set(GracefulState::Done);
+					me.state.set(GracefulState::Cleanup { cleanup: cleanup(work_state) });
+				},
+				GracefulStateProj::Cleanup { cleanup } => {
+					if let Poll::Ready(output) = cleanup.poll(context) {
+						me.state.set(GracefulState::Done);
+						*me.delay_token = None;
+						return match me.reason.take() {
+							Some(reason) => Poll::Ready(GracefulOutcome::CleanedUp { reason, cleanup: output }),
+							None => Poll::Ready(GracefulOutcome::Finished(output)),
+						};
+					}
+					let reason = match me.forced_signal.as_mut().poll(context) {
+						Poll::Ready(reason) => reason,
+						Poll::Pending => return Poll::Pending,
+					};
+					me.state.set(GracefulState::Done);
+					*me.delay_token = None;
+					return Poll::Ready(GracefulOutcome::ForcedDuringCleanup(reason));
+				},
+				GracefulStateProj::Done => panic!("WrapGraceful polled after completion"),
+			}
+		}
+	}
+}
+
+impl<T: Clone> ShutdownManager<T> {
+	/// Run some work until the shutdown is triggered, and then always run a clean-up future with time to do so.
+	///
+	/// The work is cancelled when the shutdown is triggered.
+	/// Either way, the clean-up function is then called with a [`Cancelled`] value that holds the output of the work or the shutdown reason.
+	/// The clean-up future runs under a delay token, so the shutdown does not complete before it is done,
+	/// unless the forced shutdown is triggered:
+	/// ```
+	/// # async fn serve() -> usize { 0 }
+	/// # async fn flush_buffers() {}
+	/// # async fn run() {
+	/// # let shutdown = async_shutdown::ShutdownManager::<()>::new();
+	/// use async_shutdown::{Cancelled, GracefulOutcome};
+	///
+	/// let outcome = shutdown.wrap_graceful(serve(), |work| async move {
+	///     if let Cancelled::Completed(requests) = work {
+	///         println!("served {requests} requests");
+	///     }
+	///     flush_buffers().await;
+	/// });
+	/// match outcome.unwrap().await {
+	///     GracefulOutcome::Finished(()) => println!("server stopped by itself"),
+	///     GracefulOutcome::CleanedUp { .. } => println!("server stopped by the shutdown"),
+	///     GracefulOutcome::ForcedDuringCleanup(()) => println!("clean-up was interrupted"),
+	/// }
+	/// # }
+	/// ```
+	///
+	/// Unlike [`Self::run_then_cleanup()`], the clean-up also runs when the work completes by itself.
+	///
+	/// If the shutdown has already completed, this function returns an error.
+	#[inline]
+	#[track_caller]
+	pub fn wrap_graceful<F, C, CF>(&self, work: F, cleanup: C) -> Result<WrapGraceful<T, F, C, CF>, ShutdownAlreadyCompleted<T>>
+	where
+		F: Future,
+		C: FnOnce(Cancelled<F::Output, T>) -> CF,
+		CF: Future,
+	{
+		Ok(WrapGraceful {
+			delay_token: Some(self.delay_shutdown_token()?),
+			shutdown_signal: self.wait_shutdown_triggered(),
+			forced_signal: self.wait_forced_shutdown_triggered(),
+			reason: None,
+			state: GracefulState::Working {
+				work,
+				cleanup: Some(cleanup),
+			},
+		})
+	}
+}
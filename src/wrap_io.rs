@@ -0,0 +1,181 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::ShutdownSignal;
+
+pin_project_lite::pin_project! {
+	/// Wrapped I/O object that shuts down gracefully when a shutdown is triggered.
+	///
+	/// Once the shutdown is triggered:
+	/// * the write side of the wrapped object is flushed and shut down,
+	/// * reads return end-of-file,
+	/// * writes fail with [`io::ErrorKind::BrokenPipe`].
+	///
+	/// For a TCP stream, this means that the connection is half-closed:
+	/// the peer receives all data that was written before the shutdown, followed by end-of-file.
+	///
+	/// The wrapper implements the `AsyncRead` and `AsyncWrite` traits from `tokio` (with the `tokio-io` feature)
+	/// and from `futures-io` (with the `futures-io` feature).
+	#[project = WrapIoProj]
+	#[must_use = "I/O objects do nothing unless polled"]
+	pub struct WrapIo<T: Clone, IO> {
+		pub(crate) state: WrapIoState<T>,
+		#[pin]
+		pub(crate) io: IO,
+	}
+}
+
+impl<T: Clone, IO> std::fmt::Debug for WrapIo<T, IO> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapIo")
+			.field("shutdown_signal", &self.state.shutdown_signal)
+			.field("triggered", &self.state.triggered)
+			.field("write_closed", &self.state.write_closed)
+			.finish_non_exhaustive()
+	}
+}
+
+/// The shutdown state of a [`WrapIo`].
+pub(crate) struct WrapIoState<T: Clone> {
+	// Boxed so the wrapper is `Unpin` if the I/O object is, like most I/O objects.
+	pub(crate) shutdown_signal: Pin<Box<ShutdownSignal<T>>>,
+	pub(crate) triggered: bool,
+	pub(crate) write_closed: bool,
+}
+
+impl<T: Clone> WrapIoState<T> {
+	/// Create the state for a new wrapper.
+	pub(crate) fn new(shutdown_signal: ShutdownSignal<T>) -> Self {
+		Self {
+			shutdown_signal: Box::pin(shutdown_signal),
+			triggered: false,
+			write_closed: false,
+		}
+	}
+
+	/// Check if the shutdown has been triggered, and register the current task to be woken up if it has not.
+	fn poll_triggered(&mut self, context: &mut Context) -> bool {
+		if !self.triggered {
+			self.triggered = self.shutdown_signal.as_mut().poll(context).is_ready();
+		}
+		self.triggered
+	}
+
+	/// Run a close function on the write side of the wrapped I/O object, unless it was already closed.
+	fn poll_close_once<F>(&mut self, close: F) -> Poll<io::Result<()>>
+	where
+		F: FnOnce() -> Poll<io::Result<()>>,
+	{
+		if self.write_closed {
+			return Poll::Ready(Ok(()));
+		}
+		let result = std::task::ready!(close());
+		self.write_closed = true;
+		Poll::Ready(result)
+	}
+}
+
+impl<T: Clone, IO> WrapIo<T, IO> {
+	/// Get a reference to the wrapped I/O object.
+	#[inline]
+	pub fn get_ref(&self) -> &IO {
+		&self.io
+	}
+
+	/// Get a mutable reference to the wrapped I/O object.
+	#[inline]
+	pub fn get_mut(&mut self) -> &mut IO {
+		&mut self.io
+	}
+
+	/// Consume the wrapper and return the wrapped I/O object.
+	#[inline]
+	pub fn into_inner(self) -> IO {
+		self.io
+	}
+}
+
+/// Create the error returned for writes after the shutdown was triggered.
+fn shutdown_error() -> io::Error {
+	io::Error::new(io::ErrorKind::BrokenPipe, "shutdown has been triggered")
+}
+
+#[cfg(feature = "tokio-io")]
+impl<T: Clone, IO: tokio::io::AsyncRead + tokio::io::AsyncWrite> tokio::io::AsyncRead for WrapIo<T, IO> {
+	fn poll_read(self: Pin<&mut Self>, context: &mut Context, buf: &mut tokio::io::ReadBuf) -> Poll<io::Result<()>> {
+		let WrapIoProj { state, io } = self.project();
+		if state.poll_triggered(context) {
+			// Shut down the write side before reporting end-of-file,
+			// so that the peer sees a graceful half-close even if we never write again.
+			std::task::ready!(state.poll_close_once(|| io.poll_shutdown(context)))?;
+			return Poll::Ready(Ok(()));
+		}
+		io.poll_read(context, buf)
+	}
+}
+
+#[cfg(feature = "tokio-io")]
+impl<T: Clone, IO: tokio::io::AsyncWrite> tokio::io::AsyncWrite for WrapIo<T, IO> {
+	fn poll_write(self: Pin<&mut Self>, context: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+		let WrapIoProj { state, io } = self.project();
+		if state.poll_triggered(context) {
+			std::task::ready!(state.poll_close_once(|| io.poll_shutdown(context)))?;
+			return Poll::Ready(Err(shutdown_error()));
+		}
+		io.poll_write(context, buf)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+		let WrapIoProj { state, io } = self.project();
+		if state.write_closed {
+			return Poll::Ready(Ok(()));
+		}
+		io.poll_flush(context)
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+		let WrapIoProj { state, io } = self.project();
+		state.poll_close_once(|| io.poll_shutdown(context))
+	}
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: Clone, IO: futures_io::AsyncRead + futures_io::AsyncWrite> futures_io::AsyncRead for WrapIo<T, IO> {
+	fn poll_read(self: Pin<&mut Self>, context: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+		let WrapIoProj { state, io } = self.project();
+		if state.poll_triggered(context) {
+			// Shut down the write side before reporting end-of-file,
+			// so that the peer sees a graceful half-close even if we never write again.
+			std::task::ready!(state.poll_close_once(|| io.poll_close(context)))?;
+			return Poll::Ready(Ok(0));
+		}
+		io.poll_read(context, buf)
+	}
+}
+
+#[cfg(feature = "futures-io")]
+impl<T: Clone, IO: futures_io::AsyncWrite> futures_io::AsyncWrite for WrapIo<T, IO> {
+	fn poll_write(self: Pin<&mut Self>, context: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+		let WrapIoProj { state, io } = self.project();
+		if state.poll_triggered(context) {
+			std::task::ready!(state.poll_close_once(|| io.poll_close(context)))?;
+			return Poll::Ready(Err(shutdown_error()));
+		}
+		io.poll_write(context, buf)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+		let WrapIoProj { state, io } = self.project();
+		if state.write_closed {
+			return Poll::Ready(Ok(()));
+		}
+		io.poll_flush(context)
+	}
+
+	fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+		let WrapIoProj { state, io } = self.project();
+		state.poll_close_once(|| io.poll_close(context))
+	}
+}
@@ -0,0 +1,118 @@
+use futures_sink::Sink;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{DelayShutdownToken, ShutdownSignal};
+
+pin_project_lite::pin_project! {
+	/// Wrapped sink that is flushed and closed when a shutdown is triggered.
+	///
+	/// The wrapper implements [`Sink`] by forwarding everything to the wrapped sink.
+	/// It also implements [`Future`]: the future waits for the shutdown to be triggered,
+	/// and then flushes and closes the wrapped sink.
+	/// It completes with the shutdown reason once the sink is closed, or with the error of the sink if closing failed.
+	///
+	/// The wrapper delays shutdown completion until the sink has been closed, or until the wrapper is dropped.
+	#[must_use = "sinks do nothing unless polled"]
+	pub struct WrapSink<T: Clone, S, Item> {
+		// Boxed so the wrapper is `Unpin` if the sink is, like most sinks.
+		pub(crate) shutdown_signal: Pin<Box<ShutdownSignal<T>>>,
+		pub(crate) delay_token: Option<DelayShutdownToken<T>>,
+		pub(crate) reason: Option<T>,
+		#[pin]
+		pub(crate) sink: S,
+		pub(crate) item: PhantomData<fn(Item)>,
+	}
+}
+
+impl<T: Clone, S, Item> std::fmt::Debug for WrapSink<T, S, Item> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapSink")
+			.field("shutdown_signal", &self.shutdown_signal)
+			.field("delay_token", &self.delay_token)
+			.field("closed", &self.reason.is_some())
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: Clone, S, Item> WrapSink<T, S, Item> {
+	/// Get a reference to the wrapped sink.
+	#[inline]
+	pub fn get_ref(&self) -> &S {
+		&self.sink
+	}
+
+	/// Get a mutable reference to the wrapped sink.
+	#[inline]
+	pub fn get_mut(&mut self) -> &mut S {
+		&mut self.sink
+	}
+
+	/// Consume the wrapper and return the wrapped sink.
+	///
+	/// This releases the delay token held by the wrapper.
+	#[inline]
+	pub fn into_inner(self) -> S {
+		self.sink
+	}
+
+	/// Get a pinned mutable reference to the wrapped sink.
+	#[inline]
+	fn sink(self: Pin<&mut Self>) -> Pin<&mut S> {
+		self.project().sink
+	}
+}
+
+impl<T: Clone, S: Sink<Item>, Item> Sink<Item> for WrapSink<T, S, Item> {
+	type Error = S::Error;
+
+	#[inline]
+	fn poll_ready(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<(), Self::Error>> {
+		self.sink().poll_ready(context)
+	}
+
+	#[inline]
+	fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+		self.sink().start_send(item)
+	}
+
+	#[inline]
+	fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<(), Self::Error>> {
+		self.sink().poll_flush(context)
+	}
+
+	#[inline]
+	fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<(), Self::Error>> {
+		self.sink().poll_close(context)
+	}
+}
+
+impl<T: Clone, S: Sink<Item>, Item> Future for WrapSink<T, S, Item> {
+	type Output = Result<T, S::Error>;
+
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+		let me = self.project();
+
+		let reason = match &me.reason {
+			Some(reason) => reason.clone(),
+			None => match me.shutdown_signal.as_mut().poll(context) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(reason) => {
+					*me.reason = Some(reason.clone());
+					reason
+				},
+			},
+		};
+
+		// Closing the sink also flushes it.
+		match me.sink.poll_close(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(result) => {
+				*me.delay_token = None;
+				Poll::Ready(result.map(|()| reason))
+			},
+		}
+	}
+}
@@ -4,11 +4,44 @@ use std::task::{Context, Poll};
 
 use crate::TriggerShutdownToken;
 
-/// Wrapped future that triggers a shutdown when it completes or when it is dropped.
-#[must_use = "futures must be polled to make progress"]
-pub struct WrapTriggerShutdown<T: Clone, F> {
-	pub(crate) trigger_shutdown_token: Option<TriggerShutdownToken<T>>,
-	pub(crate) future: F,
+pin_project_lite::pin_project! {
+	/// Wrapped future that triggers a shutdown when it completes or when it is dropped.
+	#[must_use = "futures must be polled to make progress"]
+	pub struct WrapTriggerShutdown<T: Clone, F> {
+		pub(crate) trigger_shutdown_token: Option<TriggerShutdownToken<T>>,
+		#[pin]
+		pub(crate) future: F,
+	}
+}
+
+impl<T: Clone, F> std::fmt::Debug for WrapTriggerShutdown<T, F> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapTriggerShutdown")
+			.field("trigger_shutdown_token", &self.trigger_shutdown_token)
+			.finish()
+	}
+}
+
+impl<T: Clone, F> WrapTriggerShutdown<T, F> {
+	/// Get the trigger token of the wrapper.
+	///
+	/// Note that dropping a clone of the token triggers the shutdown.
+	/// Returns [`None`] if the wrapped future already completed.
+	#[inline]
+	pub fn trigger_token(&self) -> Option<&TriggerShutdownToken<T>> {
+		self.trigger_shutdown_token.as_ref()
+	}
+
+	/// Recover the wrapped future together with the trigger token.
+	///
+	/// Dropping the returned token triggers the shutdown, just like dropping the wrapper would.
+	/// You can wrap the future again with [`TriggerShutdownToken::wrap_future()`] to hand it to another subsystem.
+	///
+	/// The token is [`None`] if the wrapped future already completed.
+	#[inline]
+	pub fn into_inner(self) -> (F, Option<TriggerShutdownToken<T>>) {
+		(self.future, self.trigger_shutdown_token)
+	}
 }
 
 impl<T: Clone, F: Future> Future for WrapTriggerShutdown<T, F> {
@@ -16,16 +49,13 @@ impl<T: Clone, F: Future> Future for WrapTriggerShutdown<T, F> {
 
 	#[inline]
 	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
-		// SAFETY: We never move `future`, so we can not violate the requirements of `F`.
-		unsafe {
-			let me = self.get_unchecked_mut();
-			match Pin::new_unchecked(&mut me.future).poll(context) {
-				Poll::Pending => Poll::Pending,
-				Poll::Ready(value) => {
-					me.trigger_shutdown_token = None;
-					Poll::Ready(value)
-				},
-			}
+		let me = self.project();
+		match me.future.poll(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(value) => {
+				*me.trigger_shutdown_token = None;
+				Poll::Ready(value)
+			},
 		}
 	}
 }
@@ -0,0 +1,45 @@
+use futures_core::Stream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::TriggerShutdownToken;
+
+pin_project_lite::pin_project! {
+	/// Wrapped stream that triggers a shutdown when it ends or when it is dropped.
+	#[must_use = "streams do nothing unless polled"]
+	pub struct WrapTriggerShutdownStream<T: Clone, S> {
+		pub(crate) trigger_shutdown_token: Option<TriggerShutdownToken<T>>,
+		#[pin]
+		pub(crate) stream: S,
+	}
+}
+
+impl<T: Clone, S> std::fmt::Debug for WrapTriggerShutdownStream<T, S> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.debug_struct("WrapTriggerShutdownStream")
+			.field("trigger_shutdown_token", &self.trigger_shutdown_token)
+			.finish()
+	}
+}
+
+impl<T: Clone, S: Stream> Stream for WrapTriggerShutdownStream<T, S> {
+	type Item = S::Item;
+
+	#[inline]
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+		let me = self.project();
+		match me.stream.poll_next(context) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Some(item)) => Poll::Ready(Some(item)),
+			Poll::Ready(None) => {
+				*me.trigger_shutdown_token = None;
+				Poll::Ready(None)
+			},
+		}
+	}
+
+	#[inline]
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.stream.size_hint()
+	}
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "abortable")]
+
+use assert2::{assert, let_assert};
+use futures::future::{self, Aborted};
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn wrap_abortable() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let ready = shutdown.wrap_abortable(future::ready(10));
+		let pending = tokio::spawn(shutdown.wrap_abortable(future::pending::<()>()));
+		assert!(let Ok(10) = ready.await);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(let Ok(Err(Aborted)) = pending.await);
+
+		// Registrations created after the shutdown are aborted immediately.
+		assert!(let Err(Aborted) = shutdown.wrap_abortable(future::pending::<()>()).await);
+	});
+}
+
+#[test]
+fn forced_abort_registration() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let registration = shutdown.wait_forced_shutdown_triggered().abort_registration();
+		let pending = tokio::spawn(future::Abortable::new(future::pending::<()>(), registration));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(!pending.is_finished());
+		assert!(let Ok(()) = shutdown.trigger_forced_shutdown(()));
+		assert!(let Ok(Err(Aborted)) = pending.await);
+	});
+}
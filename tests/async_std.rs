@@ -0,0 +1,55 @@
+#![cfg(feature = "async-std")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	async_std::task::block_on(async move {
+		let test = async_std::future::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn spawn_async_std() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(finished) = shutdown.spawn_async_std(future::ready(10)));
+		assert!(let Ok(10) = finished.await);
+
+		let_assert!(Ok(pending) = shutdown.spawn_async_std(future::pending::<()>()));
+		assert!(shutdown.delay_token_count() == 1);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Err(1) = pending.await);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+
+		let_assert!(Err(e) = shutdown.spawn_async_std(future::ready(())));
+		assert!(e.shutdown_reason == 1);
+	});
+}
+
+#[test]
+fn wrap_abort_async_std() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let finished = shutdown.wrap_abort_async_std(async_std::task::spawn(future::ready(10)));
+		assert!(let Ok(10) = finished.await);
+
+		let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+		let pending = shutdown.wrap_abort_async_std(async_std::task::spawn(async move {
+			let _sender = sender;
+			future::pending::<()>().await
+		}));
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		assert!(let Err(2) = pending.await);
+
+		// The task was cancelled, so it dropped the sender.
+		assert!(let Err(_) = receiver.await);
+		assert!(shutdown.metrics().cancelled_futures == 1);
+	});
+}
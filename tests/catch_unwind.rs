@@ -0,0 +1,37 @@
+#![cfg(feature = "catch-unwind")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn wrap_catch_panic() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let map_panic = |payload: &(dyn std::any::Any + Send)| {
+			payload.downcast_ref::<&str>().map(|message| message.to_string()).unwrap_or_default()
+		};
+
+		assert!(let Ok(1) = shutdown.wrap_catch_panic(future::ready(1), map_panic).await);
+		assert!(shutdown.is_shutdown_triggered() == false);
+
+		let panicking = async {
+			panic!("worker failed");
+		};
+		let_assert!(Err(payload) = shutdown.wrap_catch_panic(panicking, map_panic).await);
+		assert!(payload.downcast_ref::<&str>() == Some(&"worker failed"));
+		assert!(shutdown.shutdown_reason().as_deref() == Some("worker failed"));
+	});
+}
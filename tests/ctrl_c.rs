@@ -0,0 +1,44 @@
+#![cfg(all(unix, feature = "ctrl-c"))]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(500), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn trigger_on_ctrl_c() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let listener = shutdown.trigger_on_ctrl_c("ctrl-c");
+
+		// Give the listener a chance to install the signal handler.
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		assert!(shutdown.is_shutdown_triggered() == false);
+
+		let_assert!(Ok(status) = std::process::Command::new("kill").arg("-INT").arg(std::process::id().to_string()).status());
+		assert!(status.success());
+		assert!(shutdown.wait_shutdown_triggered().await == "ctrl-c");
+		assert!(let Ok(Ok(())) = listener.await);
+	});
+}
+
+#[test]
+fn ctrl_c_listener_stops_on_shutdown() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let listener = shutdown.trigger_on_ctrl_c("ctrl-c");
+		assert!(let Ok(()) = shutdown.trigger_shutdown("other"));
+		assert!(let Ok(Ok(())) = listener.await);
+		assert!(shutdown.shutdown_reason() == Some("other"));
+	});
+}
@@ -0,0 +1,24 @@
+#![cfg(feature = "debug-diagnostics")]
+
+use assert2::{assert, let_assert};
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[test]
+fn leaked_token_report() {
+	let shutdown = ShutdownManager::new();
+	let_assert!(Ok(token) = shutdown.delay_shutdown_token());
+	let line = line!() - 1;
+	let _clone = token.clone();
+	assert!(shutdown.leaked_token_report(Duration::ZERO).is_empty());
+
+	assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+	assert!(shutdown.leaked_token_report(Duration::from_secs(3600)).is_empty());
+
+	let report = shutdown.leaked_token_report(Duration::ZERO);
+	let_assert!([leaked] = report.as_slice());
+	assert!(leaked.location.file() == file!());
+	assert!(leaked.location.line() == line);
+	assert!(format!("{token:?}").contains(file!()));
+}
@@ -0,0 +1,140 @@
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::embedded::{NoopRawMutex, SpinRawMutex, StaticShutdownManager};
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn static_shutdown() {
+	static SHUTDOWN: StaticShutdownManager<i32, SpinRawMutex, 4> = StaticShutdownManager::new();
+	test_timeout(async {
+		assert!(SHUTDOWN.is_shutdown_triggered() == false);
+
+		let task = tokio::spawn(SHUTDOWN.wrap_cancel(future::pending::<()>()));
+		let triggered = tokio::spawn(SHUTDOWN.wait_shutdown_triggered());
+		tokio::task::yield_now().await;
+
+		// Trigger the shutdown from another thread, like an interrupt handler would.
+		let_assert!(Ok(Ok(())) = std::thread::spawn(|| SHUTDOWN.trigger_shutdown(1)).join());
+		let_assert!(Err(e) = SHUTDOWN.trigger_shutdown(2));
+		assert!(e.shutdown_reason == 1);
+		assert!(e.ignored_reason == 2);
+
+		assert!(let Ok(Err(1)) = task.await);
+		assert!(let Ok(1) = triggered.await);
+		assert!(SHUTDOWN.shutdown_reason() == Some(1));
+		assert!(SHUTDOWN.wait_shutdown_complete().await == 1);
+		assert!(SHUTDOWN.is_shutdown_completed());
+	});
+}
+
+#[test]
+fn static_delay_shutdown() {
+	static SHUTDOWN: StaticShutdownManager<&str, SpinRawMutex, 4> = StaticShutdownManager::new();
+	test_timeout(async {
+		let_assert!(Ok(delay) = SHUTDOWN.delay_shutdown_token());
+		let delay2 = delay.clone();
+		let complete = tokio::spawn(SHUTDOWN.wait_shutdown_complete());
+
+		assert!(let Ok(()) = SHUTDOWN.trigger_shutdown("stop"));
+		tokio::task::yield_now().await;
+		assert!(SHUTDOWN.is_shutdown_completed() == false);
+
+		drop(delay);
+		assert!(SHUTDOWN.is_shutdown_completed() == false);
+		drop(delay2);
+		assert!(SHUTDOWN.is_shutdown_completed());
+		assert!(let Ok("stop") = complete.await);
+		let_assert!(Err(e) = SHUTDOWN.delay_shutdown_token());
+		assert!(e.shutdown_reason == "stop");
+	});
+}
+
+#[test]
+fn static_wrap_delay_shutdown() {
+	static SHUTDOWN: StaticShutdownManager<(), SpinRawMutex, 4> = StaticShutdownManager::new();
+	test_timeout(async {
+		let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+		let_assert!(Ok(wrapped) = SHUTDOWN.wrap_delay_shutdown(receiver));
+		let task = tokio::spawn(wrapped);
+
+		assert!(let Ok(()) = SHUTDOWN.trigger_shutdown(()));
+		tokio::task::yield_now().await;
+		assert!(SHUTDOWN.is_shutdown_completed() == false);
+
+		assert!(let Ok(()) = sender.send(()));
+		assert!(let Ok(Ok(())) = task.await);
+		SHUTDOWN.wait_shutdown_complete().await;
+	});
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn critical_section_shutdown() {
+	use async_shutdown::embedded::CriticalSectionRawMutex;
+
+	static SHUTDOWN: StaticShutdownManager<u8, CriticalSectionRawMutex, 2> = StaticShutdownManager::new();
+	test_timeout(async {
+		let_assert!(Ok(delay) = SHUTDOWN.delay_shutdown_token());
+		let task = tokio::spawn(SHUTDOWN.wrap_cancel(future::pending::<()>()));
+		let complete = tokio::spawn(SHUTDOWN.wait_shutdown_complete());
+		tokio::task::yield_now().await;
+
+		let_assert!(Ok(Ok(())) = std::thread::spawn(|| SHUTDOWN.trigger_shutdown(4)).join());
+		assert!(let Ok(Err(4)) = task.await);
+		assert!(SHUTDOWN.is_shutdown_completed() == false);
+		drop(delay);
+		assert!(let Ok(4) = complete.await);
+	});
+}
+
+#[test]
+fn static_more_waiters_than_slots() {
+	static SHUTDOWN: StaticShutdownManager<u8, SpinRawMutex, 1> = StaticShutdownManager::new();
+	test_timeout(async {
+		// Only one waiter fits in the table, the others are polled again until the shutdown is triggered.
+		let waiters: Vec<_> = (0..4).map(|_| tokio::spawn(SHUTDOWN.wait_shutdown_triggered())).collect();
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(let Ok(()) = SHUTDOWN.trigger_shutdown(3));
+		for waiter in waiters {
+			assert!(let Ok(3) = waiter.await);
+		}
+	});
+}
+
+#[test]
+fn static_dropped_waiter_frees_slot() {
+	test_timeout(async {
+		let shutdown = StaticShutdownManager::<u8, NoopRawMutex, 1>::new();
+		let waker = futures::task::noop_waker();
+		let mut context = std::task::Context::from_waker(&waker);
+
+		let mut first = Box::pin(shutdown.wait_shutdown_triggered());
+		assert!(first.as_mut().poll(&mut context).is_pending());
+		assert!(format!("{first:?}").contains("waker_registered: true"));
+		drop(first);
+
+		let mut second = Box::pin(shutdown.wait_shutdown_triggered());
+		assert!(second.as_mut().poll(&mut context).is_pending());
+		assert!(format!("{second:?}").contains("waker_registered: true"));
+
+		// The table is full now, so a third waiter gets no slot.
+		let mut third = Box::pin(shutdown.wait_shutdown_triggered());
+		assert!(third.as_mut().poll(&mut context).is_pending());
+		assert!(format!("{third:?}").contains("waker_registered: false"));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(5));
+		assert!(second.await == 5);
+		assert!(third.await == 5);
+	});
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "ffi")]
+
+use assert2::assert;
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use async_shutdown::ffi::*;
+
+extern "C" fn store_reason(reason: c_int, user_data: *mut c_void) {
+	let target = unsafe { &*(user_data as *const AtomicI32) };
+	target.store(reason, Ordering::Relaxed);
+}
+
+#[test]
+fn c_api() {
+	unsafe {
+		let manager = async_shutdown_new();
+		let clone = async_shutdown_clone(manager);
+		let mut reason: c_int = 0;
+		let triggered_with = AtomicI32::new(0);
+		async_shutdown_on_trigger(manager, store_reason, &triggered_with as *const AtomicI32 as *mut c_void);
+
+		assert!(async_shutdown_is_triggered(clone) == false);
+		assert!(async_shutdown_is_completed(clone, &mut reason) == false);
+
+		let token = (*manager).manager().delay_shutdown_token().unwrap();
+		assert!(async_shutdown_trigger(clone, 3) == true);
+		assert!(async_shutdown_trigger(clone, 4) == false);
+		assert!(triggered_with.load(Ordering::Relaxed) == 3);
+		assert!(async_shutdown_is_triggered(manager) == true);
+		assert!(async_shutdown_is_completed(manager, &mut reason) == false);
+
+		drop(token);
+		assert!(async_shutdown_is_completed(manager, &mut reason) == true);
+		assert!(reason == 3);
+		assert!(async_shutdown_is_completed(manager, std::ptr::null_mut()) == true);
+
+		async_shutdown_free(clone);
+		async_shutdown_free(manager);
+		async_shutdown_free(std::ptr::null_mut());
+	}
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "http-probe")]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+use tower_service::Service;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+async fn probe(service: &mut async_shutdown::HttpProbe<i32>) -> http::StatusCode {
+	let_assert!(Ok(response) = service.call(http::Request::new(())).await);
+	response.status()
+}
+
+#[test]
+fn readiness_and_liveness() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut ready = shutdown.readiness_probe();
+		let mut live = shutdown.liveness_probe();
+		assert!(probe(&mut ready).await == http::StatusCode::OK);
+		assert!(probe(&mut live).await == http::StatusCode::OK);
+
+		let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(probe(&mut ready).await == http::StatusCode::SERVICE_UNAVAILABLE);
+		assert!(probe(&mut live).await == http::StatusCode::OK);
+
+		drop(delay);
+		assert!(probe(&mut live).await == http::StatusCode::SERVICE_UNAVAILABLE);
+		let response = live.response();
+		assert!(response.body() == "shutting down\n");
+		assert!(response.headers()[http::header::CONTENT_TYPE] == "text/plain");
+
+		assert!(let Ok(1) = shutdown.reset());
+		assert!(probe(&mut ready).await == http::StatusCode::OK);
+		assert!(probe(&mut live).await == http::StatusCode::OK);
+	});
+}
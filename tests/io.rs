@@ -0,0 +1,64 @@
+#![cfg(any(feature = "tokio-io", feature = "futures-io"))]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[cfg(feature = "tokio-io")]
+#[test]
+fn wrap_io_tokio() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (local, mut remote) = tokio::io::duplex(64);
+		let mut local = shutdown.wrap_io(local);
+
+		assert!(let Ok(()) = local.write_all(b"hello").await);
+		let reader = tokio::spawn(async move {
+			let mut buffer = [0; 16];
+			let read = local.read(&mut buffer).await;
+			(local, read)
+		});
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		let_assert!(Ok((mut local, read)) = reader.await);
+		// Pending reads return end-of-file when the shutdown is triggered.
+		assert!(let Ok(0) = read);
+		let_assert!(Err(e) = local.write_all(b"bye").await);
+		assert!(e.kind() == std::io::ErrorKind::BrokenPipe);
+
+		// The peer gets all data written before the shutdown, followed by end-of-file.
+		let mut received = Vec::new();
+		assert!(let Ok(5) = remote.read_to_end(&mut received).await);
+		assert!(received == b"hello");
+	});
+}
+
+#[cfg(feature = "futures-io")]
+#[test]
+fn wrap_io_futures() {
+	use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut io = shutdown.wrap_io(futures::io::Cursor::new(b"hello".to_vec()));
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+
+		let mut buffer = [0; 16];
+		assert!(let Ok(0) = io.read(&mut buffer).await);
+		let_assert!(Err(e) = io.write_all(b"bye").await);
+		assert!(e.kind() == std::io::ErrorKind::BrokenPipe);
+	});
+}
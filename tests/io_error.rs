@@ -0,0 +1,46 @@
+#![cfg(feature = "io-error")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn wrap_cancel_io() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(5) = shutdown.wrap_cancel_io(future::ready(Ok::<_, io::Error>(5))).await);
+		let_assert!(Err(e) = shutdown.wrap_cancel_io(future::ready(Err::<(), _>(io::Error::from(io::ErrorKind::BrokenPipe)))).await);
+		assert!(e.kind() == io::ErrorKind::BrokenPipe);
+
+		let pending = shutdown.wrap_cancel_io(future::pending::<io::Result<()>>());
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stopping"));
+		let_assert!(Err(e) = pending.await);
+		assert!(e.kind() == io::ErrorKind::Interrupted);
+		assert!(e.to_string() == "stopping");
+	});
+}
+
+#[test]
+fn shutdown_io_error() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(String::from("stopping")));
+		let_assert!(Err(e) = shutdown.wrap_cancel(future::pending::<()>()).await.map_err(async_shutdown::shutdown_io_error));
+		assert!(e.kind() == io::ErrorKind::Interrupted);
+		let_assert!(Some(inner) = e.get_ref());
+		assert!(inner.to_string() == "stopping");
+	});
+}
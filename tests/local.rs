@@ -0,0 +1,71 @@
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::LocalShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_time().build(), "failed to initialize tokio runtime");
+	let local = tokio::task::LocalSet::new();
+	local.block_on(&runtime, async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn local_shutdown() {
+	test_timeout(async {
+		let shutdown = LocalShutdownManager::new();
+		assert!(shutdown.is_shutdown_triggered() == false);
+
+		let task = tokio::task::spawn_local(shutdown.wrap_cancel(future::pending::<()>()));
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Err(_) = shutdown.trigger_shutdown(2));
+		assert!(let Ok(Err(1)) = task.await);
+		assert!(shutdown.wait_shutdown_triggered().await == 1);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(shutdown.is_shutdown_completed());
+	});
+}
+
+#[test]
+fn local_delay_shutdown() {
+	test_timeout(async {
+		let shutdown = LocalShutdownManager::new();
+		let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+		let delay2 = delay.clone();
+		let complete = tokio::task::spawn_local(shutdown.wait_shutdown_complete());
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		tokio::task::yield_now().await;
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		drop(delay);
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(delay2);
+		assert!(shutdown.is_shutdown_completed());
+		assert!(let Ok("stop") = complete.await);
+		assert!(let Err(_) = shutdown.delay_shutdown_token());
+	});
+}
+
+#[test]
+fn local_wrap_delay_shutdown() {
+	test_timeout(async {
+		let shutdown = LocalShutdownManager::new();
+		let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+		let_assert!(Ok(wrapped) = shutdown.wrap_delay_shutdown(receiver));
+		let task = tokio::task::spawn_local(wrapped);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		tokio::task::yield_now().await;
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		assert!(let Ok(()) = sender.send(()));
+		assert!(let Ok(Ok(())) = task.await);
+		shutdown.wait_shutdown_complete().await;
+	});
+}
@@ -0,0 +1,131 @@
+#![cfg(feature = "metrics")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use metrics::{Counter, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+/// Histogram that keeps all recorded values.
+#[derive(Default)]
+struct Values(Mutex<Vec<f64>>);
+
+impl HistogramFn for Values {
+	fn record(&self, value: f64) {
+		self.0.lock().unwrap().push(value);
+	}
+}
+
+/// Recorder that keeps the values of all metrics in memory.
+#[derive(Default)]
+struct TestRecorder {
+	described: Mutex<Vec<String>>,
+	values: Mutex<BTreeMap<String, Arc<AtomicU64>>>,
+	histograms: Mutex<BTreeMap<String, Arc<Values>>>,
+}
+
+impl TestRecorder {
+	fn value(&self, key: &Key) -> Arc<AtomicU64> {
+		self.values.lock().unwrap().entry(key.name().to_owned()).or_default().clone()
+	}
+
+	fn counter(&self, name: &str) -> u64 {
+		self.values.lock().unwrap().get(name).map_or(0, |value| value.load(Ordering::Relaxed))
+	}
+
+	fn gauge(&self, name: &str) -> f64 {
+		f64::from_bits(self.counter(name))
+	}
+
+	fn histogram(&self, name: &str) -> Vec<f64> {
+		match self.histograms.lock().unwrap().get(name) {
+			Some(values) => values.0.lock().unwrap().clone(),
+			None => Vec::new(),
+		}
+	}
+}
+
+impl Recorder for TestRecorder {
+	fn describe_counter(&self, key: KeyName, _unit: Option<Unit>, _description: SharedString) {
+		self.described.lock().unwrap().push(key.as_str().to_owned());
+	}
+
+	fn describe_gauge(&self, key: KeyName, _unit: Option<Unit>, _description: SharedString) {
+		self.described.lock().unwrap().push(key.as_str().to_owned());
+	}
+
+	fn describe_histogram(&self, key: KeyName, _unit: Option<Unit>, _description: SharedString) {
+		self.described.lock().unwrap().push(key.as_str().to_owned());
+	}
+
+	fn register_counter(&self, key: &Key, _metadata: &Metadata) -> Counter {
+		Counter::from_arc(self.value(key))
+	}
+
+	fn register_gauge(&self, key: &Key, _metadata: &Metadata) -> Gauge {
+		Gauge::from_arc(self.value(key))
+	}
+
+	fn register_histogram(&self, key: &Key, _metadata: &Metadata) -> Histogram {
+		Histogram::from_arc(self.histograms.lock().unwrap().entry(key.name().to_owned()).or_default().clone())
+	}
+}
+
+#[test]
+fn export_metrics() {
+	// The recorder is global, so all checks are in one test.
+	let recorder: &'static TestRecorder = Box::leak(Box::default());
+	assert!(let Ok(()) = metrics::set_global_recorder(recorder));
+	async_shutdown::describe_metrics();
+	assert!(recorder.described.lock().unwrap().len() == 6);
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+		let clone = delay.clone();
+		assert!(recorder.gauge("async_shutdown_delay_tokens") == 2.0);
+		drop(clone);
+		assert!(recorder.gauge("async_shutdown_delay_tokens") == 1.0);
+
+		let mut triggered = Box::pin(shutdown.wait_shutdown_triggered());
+		let mut cancelled = Box::pin(shutdown.wrap_cancel(future::pending::<()>()));
+		let mut complete = Box::pin(shutdown.wait_shutdown_complete());
+		assert!(futures::poll!(triggered.as_mut()).is_pending());
+		assert!(futures::poll!(cancelled.as_mut()).is_pending());
+		assert!(futures::poll!(complete.as_mut()).is_pending());
+		assert!(recorder.gauge("async_shutdown_trigger_waiters") == 2.0);
+		assert!(recorder.gauge("async_shutdown_completion_waiters") == 1.0);
+
+		// Dropping a waiting future unlinks it.
+		drop(triggered);
+		assert!(recorder.gauge("async_shutdown_trigger_waiters") == 1.0);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(recorder.gauge("async_shutdown_trigger_waiters") == 0.0);
+		assert!(let Err(1) = cancelled.await);
+		assert!(recorder.counter("async_shutdown_cancelled_futures") == 1);
+		assert!(recorder.histogram("async_shutdown_duration_seconds").is_empty());
+
+		drop(delay);
+		assert!(recorder.gauge("async_shutdown_delay_tokens") == 0.0);
+		assert!(complete.await == 1);
+		assert!(recorder.gauge("async_shutdown_completion_waiters") == 0.0);
+		let durations = recorder.histogram("async_shutdown_duration_seconds");
+		let_assert!([duration] = durations.as_slice());
+		assert!(*duration >= 0.0);
+	});
+}
@@ -0,0 +1,112 @@
+#![cfg(all(unix, feature = "process"))]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::os::unix::process::ExitStatusExt;
+use std::time::Duration;
+use tokio::io::AsyncBufReadExt;
+use tokio::process::Command;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_secs(5), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn terminate_child() {
+	// A child that exits on SIGTERM.
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(child) = Command::new("sleep").arg("10").spawn());
+		let_assert!(Ok(wait) = shutdown.terminate_child_on_shutdown(child, Duration::from_secs(10)));
+		let task = tokio::spawn(wait);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		shutdown.wait_shutdown_complete().await;
+		let_assert!(Ok(Ok(status)) = task.await);
+		assert!(status.signal() == Some(libc::SIGTERM));
+	});
+
+	// A child that ignores the signal.
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(mut child) = Command::new("sh")
+			.args(["-c", "trap '' INT; echo ready; sleep 10"])
+			.stdout(std::process::Stdio::piped())
+			.spawn());
+
+		// Wait for the shell to install the signal handler.
+		let_assert!(Some(stdout) = child.stdout.take());
+		let mut stdout = tokio::io::BufReader::new(stdout);
+		let mut line = String::new();
+		assert!(let Ok(_) = stdout.read_line(&mut line).await);
+		assert!(line == "ready\n");
+
+		let_assert!(Ok(wait) = shutdown.terminate_child_on_shutdown_with_signal(child, libc::SIGINT, Duration::from_millis(100)));
+		let task = tokio::spawn(wait);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		shutdown.wait_shutdown_complete().await;
+		let_assert!(Ok(Ok(status)) = task.await);
+		assert!(status.signal() == Some(libc::SIGKILL));
+	});
+}
+
+#[test]
+fn terminate_process_group() {
+	// A process group that exits on SIGTERM.
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(child) = Command::new("sh").args(["-c", "sleep 10 & wait"]).process_group(0).spawn());
+		let_assert!(Ok(wait) = shutdown.terminate_process_group_on_shutdown(child, Duration::from_secs(10)));
+		let task = tokio::spawn(wait);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		shutdown.wait_shutdown_complete().await;
+		let_assert!(Ok(Ok(status)) = task.await);
+		assert!(status.signal() == Some(libc::SIGTERM));
+	});
+
+	// A process group that ignores SIGTERM.
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(mut child) = Command::new("sh")
+			.args(["-c", "trap '' TERM; echo ready; sleep 10"])
+			.stdout(std::process::Stdio::piped())
+			.process_group(0)
+			.spawn());
+
+		// Wait for the shell to install the signal handler.
+		let_assert!(Some(stdout) = child.stdout.take());
+		let mut stdout = tokio::io::BufReader::new(stdout);
+		let mut line = String::new();
+		assert!(let Ok(_) = stdout.read_line(&mut line).await);
+		assert!(line == "ready\n");
+
+		let_assert!(Ok(wait) = shutdown.terminate_process_group_on_shutdown(child, Duration::from_millis(100)));
+		let task = tokio::spawn(wait);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		shutdown.wait_shutdown_complete().await;
+		let_assert!(Ok(Ok(status)) = task.await);
+		assert!(status.signal() == Some(libc::SIGKILL));
+	});
+}
+
+#[test]
+fn process_group_exits_by_itself() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::<()>::new();
+		let_assert!(Ok(child) = Command::new("true").process_group(0).spawn());
+		let_assert!(Ok(wait) = shutdown.terminate_process_group_on_shutdown(child, Duration::from_secs(10)));
+		let_assert!(Ok(status) = wait.await);
+		assert!(status.success());
+		assert!(shutdown.is_shutdown_triggered() == false);
+	});
+}
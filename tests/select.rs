@@ -0,0 +1,60 @@
+#![cfg(feature = "select")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::{shutdown_select, ShutdownManager};
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn shutdown_select() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (sender, mut receiver) = tokio::sync::mpsc::channel(4);
+		assert!(let Ok(()) = sender.send(1).await);
+		assert!(let Ok(()) = sender.send(2).await);
+
+		let mut received = Vec::new();
+		let reason = loop {
+			shutdown_select! {
+				shutdown,
+				on_shutdown(reason) => break reason,
+				Some(value) = receiver.recv() => {
+					received.push(value);
+					if value == 2 {
+						assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+					}
+				},
+			}
+		};
+		assert!(reason == "stop");
+		assert!(received == [1, 2]);
+	});
+}
+
+#[test]
+fn shutdown_select_priority() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let signal = shutdown.wait_shutdown_triggered();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+
+		// The shutdown branch wins, even if the other branch is ready too.
+		let value = shutdown_select! {
+			signal,
+			on_shutdown(reason) => reason,
+			value = future::ready(2) => value,
+		};
+		assert!(value == 1);
+	});
+}
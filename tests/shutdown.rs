@@ -220,3 +220,1698 @@ fn wrap_vital() {
 		assert!(shutdown.wait_shutdown_complete().await == "stop");
 	});
 }
+
+#[test]
+fn flush_hooks() {
+	// Flush hooks run before waiting for the shutdown completion resolves.
+	test_timeout(async {
+		use std::sync::atomic::{AtomicUsize, Ordering};
+		use std::sync::Arc;
+
+		let shutdown = ShutdownManager::new();
+		let flushed = Arc::new(AtomicUsize::new(0));
+		assert!(let Ok(()) = shutdown.add_flush_hook({
+			let flushed = flushed.clone();
+			move || {
+				flushed.fetch_add(1, Ordering::Relaxed);
+			}
+		}));
+
+		let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(flushed.load(Ordering::Relaxed) == 0);
+		drop(delay);
+
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(flushed.load(Ordering::Relaxed) == 1);
+
+		// The hooks run only once.
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(flushed.load(Ordering::Relaxed) == 1);
+
+		// And they can not be added anymore after they ran.
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { shutdown_reason: 1, .. }) = shutdown.add_flush_hook(|| ()));
+	});
+}
+
+#[test]
+fn registry() {
+	test_timeout(async {
+		let registry = async_shutdown::ShutdownRegistry::new();
+		let api = ShutdownManager::new();
+		let db = ShutdownManager::new();
+		assert!(let None = registry.register("api", api.clone()));
+		assert!(let None = registry.register("db", db.clone()));
+		assert!(registry.names() == ["api", "db"]);
+		assert!(let Some(_) = registry.get("api"));
+		assert!(let None = registry.get("metrics"));
+
+		// Keep the database busy for a little while.
+		let_assert!(Ok(delay) = db.delay_shutdown_token());
+		tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			drop(delay);
+		});
+
+		// A manager that was already triggered keeps its reason.
+		assert!(let Ok(()) = api.trigger_shutdown("api failed".to_string()));
+		registry.trigger_all(|name| format!("stop {name}"));
+		assert!(db.shutdown_reason().as_deref() == Some("stop db"));
+
+		let reasons = registry.wait_all_complete().await;
+		assert!(reasons == [("api".to_string(), "api failed".to_string()), ("db".to_string(), "stop db".to_string())]);
+	});
+}
+
+#[test]
+fn fifo_completion_wake_order() {
+	use futures::task::{waker, ArcWake};
+	use std::sync::{Arc, Mutex};
+	use std::task::{Context, Poll};
+
+	struct RecordWake {
+		id: usize,
+		woken: Arc<Mutex<Vec<usize>>>,
+	}
+
+	impl ArcWake for RecordWake {
+		fn wake_by_ref(arc_self: &Arc<Self>) {
+			arc_self.woken.lock().unwrap().push(arc_self.id);
+		}
+	}
+
+	let shutdown = ShutdownManager::new();
+	shutdown.set_completion_wake_order(async_shutdown::WakeOrder::Fifo);
+	let woken = Arc::new(Mutex::new(Vec::new()));
+
+	let poll = |future: std::pin::Pin<&mut async_shutdown::ShutdownComplete<()>>, id| {
+		let waker = waker(Arc::new(RecordWake { id, woken: woken.clone() }));
+		let mut context = Context::from_waker(&waker);
+		assert!(let Poll::Pending = future.poll(&mut context));
+	};
+
+	let mut futures: Vec<_> = (0..4).map(|_| Box::pin(shutdown.wait_shutdown_complete())).collect();
+	poll(futures[0].as_mut(), 0);
+	poll(futures[1].as_mut(), 1);
+	poll(futures[2].as_mut(), 2);
+
+	// Drop the first future, so it is unlinked from the list, and register a new future at the end.
+	futures.swap_remove(0);
+	poll(futures[0].as_mut(), 3);
+
+	// Polling again does not change the order.
+	poll(futures[1].as_mut(), 1);
+
+	assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+	assert!(*woken.lock().unwrap() == [1, 2, 3]);
+}
+
+#[test]
+fn wrap_cancel_map_err() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let task = tokio::spawn(shutdown.wrap_cancel_map_err(future::pending::<()>(), |code| format!("exit code {code}")));
+		assert!(let Ok(()) = shutdown.trigger_shutdown(3));
+		let_assert!(Ok(Err(error)) = task.await);
+		assert!(error == "exit code 3");
+	});
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::<()>::new();
+		let result = shutdown.wrap_cancel_map_err(future::ready(10), |()| "cancelled").await;
+		assert!(let Ok(10) = result);
+	});
+}
+
+#[test]
+fn waiter_counts() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let cancel = tokio::spawn(shutdown.wrap_cancel(future::pending::<()>()));
+		let complete = tokio::spawn(shutdown.wait_shutdown_complete());
+		let _unpolled = shutdown.wait_shutdown_triggered();
+		while shutdown.trigger_waiter_count() == 0 || shutdown.completion_waiter_count() == 0 {
+			tokio::task::yield_now().await;
+		}
+		assert!(shutdown.trigger_waiter_count() == 1);
+		assert!(shutdown.completion_waiter_count() == 1);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(shutdown.trigger_waiter_count() == 0);
+		assert!(shutdown.completion_waiter_count() == 0);
+		assert!(let Ok(Err(())) = cancel.await);
+		assert!(let Ok(()) = complete.await);
+	});
+}
+
+#[test]
+fn delay_token_count() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert!(shutdown.delay_token_count() == 0);
+
+		let token = shutdown.delay_shutdown_token().unwrap();
+		let clone = token.clone();
+		let wrapped = shutdown.wrap_delay_shutdown(future::pending::<()>()).unwrap();
+		assert!(shutdown.delay_token_count() == 3);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		drop(token);
+		assert!(shutdown.delay_token_count() == 2);
+		drop(wrapped);
+		assert!(shutdown.delay_token_count() == 1);
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(clone);
+		assert!(shutdown.delay_token_count() == 0);
+		assert!(shutdown.is_shutdown_completed() == true);
+	});
+}
+
+#[test]
+fn metrics() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let metrics = shutdown.metrics();
+		assert!(metrics.delay_tokens == 0);
+		assert!(metrics.cancelled_futures == 0);
+		assert!(metrics.triggered_at.is_none());
+		assert!(metrics.shutdown_duration().is_none());
+
+		let token = shutdown.delay_shutdown_token().unwrap();
+		let cancel = tokio::spawn(shutdown.wrap_cancel(future::pending::<()>()));
+		while shutdown.trigger_waiter_count() == 0 {
+			tokio::task::yield_now().await;
+		}
+		let metrics = shutdown.metrics();
+		assert!(metrics.delay_tokens == 1);
+		assert!(metrics.trigger_waiters == 1);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(let Ok(Err(())) = cancel.await);
+		let metrics = shutdown.metrics();
+		assert!(metrics.cancelled_futures == 1);
+		assert!(metrics.triggered_at.is_some());
+		assert!(metrics.completed_at.is_none());
+
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		drop(token);
+		let metrics = shutdown.metrics();
+		assert!(metrics.delay_tokens == 0);
+		let_assert!(Some(duration) = metrics.shutdown_duration());
+		assert!(duration >= Duration::from_millis(10));
+	});
+}
+
+#[test]
+fn wait_shutdown_complete_timeout() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let token = shutdown.delay_shutdown_token().unwrap();
+		let clone = token.clone();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+
+		let_assert!(Err(e) = shutdown.wait_shutdown_complete_timeout(Duration::from_millis(10)).await);
+		assert!(e.shutdown_reason == Some(1));
+		assert!(e.delay_tokens == 2);
+		assert!(e.blockers.len() == 1);
+		assert!(e.to_string() == "timed out waiting for shutdown to complete: still waiting on 2 delay token(s)");
+
+		let named = shutdown.delay_shutdown_token().unwrap();
+		named.set_status("flushing logs");
+		let_assert!(Err(e) = shutdown.wait_shutdown_complete_timeout(Duration::from_millis(10)).await);
+		assert!(e.blockers.len() == 2);
+		assert!(let Some(_) = e.blockers.iter().find(|blocker| blocker.status.as_deref() == Some("flushing logs")));
+		assert!(e.to_string() == "timed out waiting for shutdown to complete: still waiting on 3 delay token(s) (flushing logs)");
+
+		drop(token);
+		drop(clone);
+		drop(named);
+		assert!(let Ok(1) = shutdown.wait_shutdown_complete_timeout(Duration::from_millis(10)).await);
+	});
+}
+
+#[test]
+fn any_shutdown_reason() {
+	test_timeout(async {
+		use async_shutdown::AnyShutdownManager;
+
+		let shutdown = AnyShutdownManager::new();
+		assert!(shutdown.shutdown_reason_downcast::<i32>().is_none());
+		assert!(let Ok(()) = shutdown.trigger_shutdown_any(10i32));
+		assert!(shutdown.shutdown_reason_downcast::<i32>().as_deref() == Some(&10));
+		assert!(shutdown.shutdown_reason_downcast::<String>().is_none());
+
+		let reason = shutdown.wait_shutdown_complete().await;
+		assert!(reason.downcast_ref::<i32>() == Some(&10));
+	});
+}
+
+#[test]
+fn forced_shutdown() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let graceful = tokio::spawn(shutdown.wrap_cancel_graceful(future::pending::<()>()));
+		let forced = tokio::spawn(shutdown.wrap_cancel_forced(future::pending::<()>()));
+		let forced_signal = shutdown.wait_forced_shutdown_triggered();
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Ok(Err(1)) = graceful.await);
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(!forced.is_finished());
+		assert!(shutdown.is_forced_shutdown_triggered() == false);
+
+		assert!(let Ok(()) = shutdown.trigger_forced_shutdown(2));
+		assert!(shutdown.is_forced_shutdown_triggered() == true);
+		assert!(let Ok(Err(2)) = forced.await);
+		assert!(forced_signal.await == 2);
+		assert!(shutdown.wait_shutdown_triggered().await == 1);
+		assert!(let Err(async_shutdown::ShutdownAlreadyStarted { shutdown_reason: 2, ignored_reason: 3, .. }) = shutdown.trigger_forced_shutdown(3));
+	});
+}
+
+#[test]
+fn forced_shutdown_triggers_regular_shutdown() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let graceful = tokio::spawn(shutdown.wrap_cancel(future::pending::<()>()));
+		assert!(let Ok(()) = shutdown.trigger_forced_shutdown(1));
+		assert!(let Ok(Err(1)) = graceful.await);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+	});
+}
+
+#[test]
+fn weak_delay_token() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let weak = shutdown.delay_shutdown_token().unwrap().downgrade();
+		assert!(shutdown.delay_token_count() == 0);
+
+		let token = weak.upgrade().unwrap();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(token);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+
+		let_assert!(Err(e) = weak.upgrade());
+		assert!(e.shutdown_reason == 1);
+	});
+}
+
+#[test]
+fn disarm_trigger_shutdown_token() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let token = shutdown.trigger_shutdown_token(1);
+		let clone = token.clone();
+		assert!(token.is_armed());
+		assert!(token.disarm() == Some(1));
+		assert!(clone.is_armed() == false);
+		drop(token);
+		assert!(shutdown.is_shutdown_triggered() == false);
+
+		assert!(clone.rearm(2) == None);
+		drop(clone);
+		assert!(shutdown.shutdown_reason() == Some(2));
+	});
+}
+
+#[test]
+fn set_trigger_shutdown_token_reason() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let token = shutdown.trigger_shutdown_token("unexpected exit");
+		let clone = token.clone();
+		assert!(clone.set_reason("connection lost"));
+		drop(token);
+		assert!(shutdown.shutdown_reason() == Some("connection lost"));
+		assert!(clone.set_reason("too late") == false);
+	});
+}
+
+#[test]
+fn channels() {
+	test_timeout(async {
+		let channels = async_shutdown::ShutdownChannels::new();
+		let reload = channels.channel::<()>("reload");
+		let shutdown = channels.channel::<i32>("shutdown");
+		assert!(channels.keys() == ["reload", "shutdown"]);
+		assert!(let Some(_) = channels.get::<i32>(&"shutdown"));
+		assert!(let None = channels.get::<u8>(&"shutdown"));
+		assert!(let None = channels.get::<()>(&"pause"));
+
+		// Each channel has its own waiters and reason.
+		assert!(let Ok(()) = channels.channel::<()>("reload").trigger_shutdown(()));
+		reload.wait_shutdown_complete().await;
+		assert!(shutdown.is_shutdown_triggered() == false);
+		assert!(let Ok(()) = reload.reset());
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(15));
+		assert!(channels.channel::<i32>("shutdown").wait_shutdown_complete().await == 15);
+		assert!(reload.is_shutdown_triggered() == false);
+
+		assert!(channels.remove(&"reload"));
+		assert!(channels.contains(&"reload") == false);
+	});
+}
+
+#[test]
+#[should_panic(expected = "different reason type")]
+fn channel_with_wrong_reason_type() {
+	let channels = async_shutdown::ShutdownChannels::new();
+	channels.channel::<i32>("shutdown");
+	channels.channel::<String>("shutdown");
+}
+
+#[test]
+fn sync_callbacks() {
+	use std::sync::{Arc, Mutex};
+
+	let shutdown = ShutdownManager::new();
+	let log = Arc::new(Mutex::new(Vec::new()));
+	shutdown.on_trigger({
+		let log = log.clone();
+		let shutdown = shutdown.clone();
+		// The callbacks run without holding the lock, so they can use the shutdown manager.
+		move |reason| log.lock().unwrap().push(format!("triggered {reason} {}", shutdown.is_shutdown_completed()))
+	});
+	shutdown.on_complete({
+		let log = log.clone();
+		let shutdown = shutdown.clone();
+		move |reason| log.lock().unwrap().push(format!("completed {reason} {}", shutdown.is_shutdown_completed()))
+	});
+
+	let token = shutdown.delay_shutdown_token().unwrap();
+	assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+	assert!(*log.lock().unwrap() == ["triggered stop false"]);
+	drop(token);
+	assert!(*log.lock().unwrap() == ["triggered stop false", "completed stop true"]);
+
+	// Callbacks registered afterwards run immediately.
+	shutdown.on_trigger({
+		let log = log.clone();
+		move |reason| log.lock().unwrap().push(format!("late trigger {reason}"))
+	});
+	shutdown.on_complete({
+		let log = log.clone();
+		move |reason| log.lock().unwrap().push(format!("late complete {reason}"))
+	});
+	assert!(log.lock().unwrap()[2..] == ["late trigger stop", "late complete stop"]);
+
+	// Trigger callbacks still run first if the shutdown completes immediately.
+	let shutdown = ShutdownManager::new();
+	let log = Arc::new(Mutex::new(Vec::new()));
+	shutdown.on_complete({
+		let log = log.clone();
+		move |()| log.lock().unwrap().push("completed")
+	});
+	shutdown.on_trigger({
+		let log = log.clone();
+		move |()| log.lock().unwrap().push("triggered")
+	});
+	assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+	assert!(*log.lock().unwrap() == ["triggered", "completed"]);
+}
+
+#[test]
+fn propagate_to() {
+	test_timeout(async {
+		let app = ShutdownManager::new();
+		let library = ShutdownManager::new();
+		app.propagate_to(&library, |code: i32| format!("exit code {code}"));
+
+		let token = library.delay_shutdown_token().unwrap();
+		assert!(let Ok(()) = app.trigger_shutdown(3));
+		assert!(library.shutdown_reason().as_deref() == Some("exit code 3"));
+
+		// Without waiting, the app does not care about the library completing.
+		assert!(app.wait_shutdown_complete().await == 3);
+		assert!(library.is_shutdown_completed() == false);
+		drop(token);
+
+		// Propagating after the trigger takes effect immediately.
+		let late = ShutdownManager::new();
+		let app = ShutdownManager::new();
+		let _token = app.delay_shutdown_token().unwrap();
+		assert!(let Ok(()) = app.trigger_shutdown(4));
+		app.propagate_to(&late, |code| code * 2);
+		assert!(late.shutdown_reason() == Some(8));
+	});
+}
+
+#[test]
+fn propagate_to_and_wait() {
+	test_timeout(async {
+		let app = ShutdownManager::new();
+		let library = ShutdownManager::new();
+		assert!(let Ok(()) = app.propagate_to_and_wait(&library, |()| "stop"));
+
+		let token = library.delay_shutdown_token().unwrap();
+		assert!(let Ok(()) = app.trigger_shutdown(()));
+		tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			drop(token);
+		});
+		app.wait_shutdown_complete().await;
+		assert!(library.is_shutdown_completed());
+	});
+}
+
+#[test]
+fn panic_while_locked_does_not_poison() {
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	static PANIC_ON_CLONE: AtomicBool = AtomicBool::new(false);
+
+	#[derive(Debug, PartialEq)]
+	struct Reason;
+
+	impl Clone for Reason {
+		fn clone(&self) -> Self {
+			if PANIC_ON_CLONE.swap(false, Ordering::Relaxed) {
+				panic!("clone failed");
+			}
+			Reason
+		}
+	}
+
+	let shutdown = ShutdownManager::new();
+	assert!(let Ok(()) = shutdown.trigger_shutdown(Reason));
+
+	// Cloning the shutdown reason happens while the internal lock is held.
+	PANIC_ON_CLONE.store(true, Ordering::Relaxed);
+	let result = std::panic::catch_unwind(|| shutdown.shutdown_reason());
+	assert!(let Err(_) = result);
+
+	// The shutdown manager must still be usable afterwards.
+	assert!(shutdown.is_shutdown_triggered());
+	assert!(shutdown.is_shutdown_completed());
+	assert!(shutdown.shutdown_reason() == Some(Reason));
+}
+
+#[test]
+fn on_shutdown_with_timeout() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert!(let Ok(()) = shutdown.on_shutdown_with_timeout(Duration::from_millis(10), |_reason| future::pending()));
+		assert!(let Ok(()) = shutdown.on_shutdown_with_timeout(Duration::from_secs(10), |_reason| future::ready(())));
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(shutdown.metrics().timed_out_hooks == 1);
+	});
+}
+
+#[test]
+fn services() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let services = shutdown.services();
+		let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+
+		let order = order_tx.clone();
+		assert!(let Ok(()) = services.register("first", move |reason| async move {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			order.send(("first", reason)).unwrap();
+		}));
+		assert!(let Ok(()) = services.register_with_timeout("stuck", Duration::from_millis(10), |_reason| future::pending()));
+		let order = order_tx.clone();
+		assert!(let Ok(()) = services.register("last", move |reason| async move {
+			order.send(("last", reason)).unwrap();
+		}));
+		drop(order_tx);
+
+		let blockers = shutdown.pending_blockers();
+		assert!(blockers.iter().any(|blocker| blocker.status.as_deref() == Some("service stuck")));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(order_rx.recv().await == Some(("first", 1)));
+		assert!(order_rx.recv().await == Some(("last", 1)));
+		assert!(order_rx.recv().await == None);
+		assert!(services.timed_out() == ["stuck"]);
+		assert!(shutdown.metrics().timed_out_hooks == 1);
+	});
+}
+
+#[test]
+fn service_dependencies() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let services = shutdown.services();
+		let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+
+		assert!(let Ok(()) = services.depends_on("http", "database"));
+		assert!(let Ok(()) = services.depends_on("database", "config"));
+		let_assert!(Err(e) = services.depends_on("config", "http"));
+		assert!(e.dependent == "config");
+		assert!(e.dependency == "http");
+
+		for name in ["config", "database", "http", "metrics"] {
+			let order = order_tx.clone();
+			assert!(let Ok(()) = services.register(name, move |_reason| async move {
+				order.send(name).unwrap();
+			}));
+		}
+		drop(order_tx);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		shutdown.wait_shutdown_complete().await;
+		let mut order = Vec::new();
+		while let Some(name) = order_rx.recv().await {
+			order.push(name);
+		}
+		assert!(order == ["http", "database", "config", "metrics"]);
+	});
+}
+
+#[test]
+fn builder() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::builder()
+			.repeat_trigger(async_shutdown::RepeatTrigger::Force)
+			.grace_period(Duration::from_millis(10))
+			.build();
+		let token = shutdown.delay_shutdown_token().unwrap();
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		assert!(let Err(async_shutdown::ShutdownAlreadyStarted { .. }) = shutdown.trigger_shutdown(3));
+		assert!(shutdown.wait_forced_shutdown_triggered().await == 2);
+
+		// The grace period forces the shutdown to complete, even though the token is still alive.
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		drop(token);
+	});
+}
+
+#[test]
+fn repeat_trigger_force_ignores_trigger_tokens() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::builder().repeat_trigger(async_shutdown::RepeatTrigger::Force).build();
+		let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+		let vital = tokio::spawn(shutdown.wrap_trigger_shutdown(2, stopped));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		// The vital task exits normally after the graceful trigger, which must not force the shutdown.
+		assert!(let Ok(()) = stop.send(()));
+		assert!(let Ok(Ok(())) = vital.await);
+		assert!(shutdown.is_forced_shutdown_triggered() == false);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+
+		// An explicit second trigger still forces the shutdown.
+		assert!(let Ok(()) = shutdown.trigger_shutdown(3));
+		assert!(shutdown.wait_forced_shutdown_triggered().await == 3);
+	});
+}
+
+#[test]
+fn trigger_on_drop() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::builder().trigger_on_drop(1).build();
+		let clone = shutdown.clone();
+		let triggered = shutdown.wait_shutdown_triggered();
+		let complete = shutdown.wait_shutdown_complete();
+		let token = shutdown.delay_shutdown_token().unwrap();
+
+		drop(shutdown);
+		assert!(!clone.is_shutdown_triggered());
+		drop(clone);
+		assert!(triggered.await == 1);
+		drop(token);
+		assert!(complete.await == 1);
+	});
+}
+
+#[test]
+fn trigger_shutdown_idempotent() {
+	let shutdown = ShutdownManager::new();
+	assert!(shutdown.trigger_shutdown_idempotent(1));
+	assert!(!shutdown.trigger_shutdown_idempotent(2));
+	assert!(shutdown.shutdown_reason() == Some(1));
+}
+
+#[test]
+fn unit_shutdown() {
+	test_timeout(async {
+		let shutdown = async_shutdown::Shutdown::new();
+		let signal = shutdown.wait_shutdown_triggered();
+		let task = tokio::spawn(shutdown.wrap_cancel(future::pending::<()>()));
+		tokio::task::yield_now().await;
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(shutdown.is_shutdown_triggered());
+		assert!(let Ok(Err(())) = task.await);
+		drop(signal);
+		shutdown.wait_shutdown_complete().await;
+	});
+}
+
+#[test]
+fn dyn_shutdown_signal() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let signal = shutdown.dyn_shutdown_signal();
+		let copy = signal.clone();
+		assert!(!signal.is_shutdown_triggered());
+
+		let task = tokio::spawn(async move {
+			copy.wrap_cancel(future::pending::<()>()).await
+		});
+		assert!(let Ok(()) = shutdown.trigger_shutdown(10));
+		assert!(let Ok(Err(())) = task.await);
+		assert!(signal.is_shutdown_triggered());
+		signal.await;
+	});
+}
+
+#[test]
+fn scope() {
+	use futures::StreamExt;
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (release, released) = futures::channel::oneshot::channel::<()>();
+		let (finished_tx, mut finished) = futures::channel::mpsc::unbounded::<&str>();
+		let_assert!(Ok(scope) = shutdown.scope(|scope| async move {
+			scope.spawn({
+				let finished_tx = finished_tx.clone();
+				async move {
+					released.await.ok();
+					finished_tx.unbounded_send("child").ok();
+				}
+			});
+			scope.spawn({
+				let scope = scope.clone();
+				async move {
+					// Futures can spawn more futures in the same scope.
+					scope.spawn(future::pending::<()>());
+				}
+			});
+			"body"
+		}));
+		let task = tokio::spawn(scope);
+
+		// The scope waits for its children, even though the body finished.
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(task.is_finished() == false);
+		assert!(let Ok(()) = release.send(()));
+		assert!(let Some("child") = finished.next().await);
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(task.is_finished() == false);
+
+		// The shutdown cancels the remaining children, and the scope delays the completion until then.
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Ok("body") = task.await);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { shutdown_reason: 1, .. }) = shutdown.scope(|_| future::ready(())));
+	});
+}
+
+#[test]
+fn protect() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (release, released) = futures::channel::oneshot::channel::<()>();
+		let (entered_tx, entered) = futures::channel::oneshot::channel::<()>();
+		let_assert!(Ok(section) = shutdown.protect(async move {
+			entered_tx.send(()).ok();
+			released.await.ok();
+			"committed"
+		}));
+		let task = tokio::spawn(shutdown.wrap_cancel(shutdown.wrap_cancel(async move {
+			let committed = section.await;
+			// The cancellation takes effect at the next await point after the critical section.
+			future::pending::<()>().await;
+			committed
+		})));
+
+		// The critical section is not cancelled, and it delays the shutdown.
+		assert!(let Ok(()) = entered.await);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(task.is_finished() == false);
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		assert!(let Ok(()) = release.send(()));
+		assert!(let Ok(Ok(Err(1))) = task.await);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { shutdown_reason: 1, .. }) = shutdown.protect(future::ready(())));
+	});
+}
+
+#[test]
+fn register_async_drop() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let dropped = Arc::new(AtomicUsize::new(0));
+		let (release, released) = futures::channel::oneshot::channel::<()>();
+		let delay = shutdown.delay_shutdown_token();
+		assert!(let Ok(()) = shutdown.register_async_drop({
+			let shutdown = shutdown.clone();
+			let dropped = dropped.clone();
+			move |reason| async move {
+				// The hook only starts after the other delay tokens are gone.
+				assert!(shutdown.is_shutdown_completed());
+				released.await.ok();
+				dropped.fetch_add(reason, Ordering::Relaxed);
+			}
+		}));
+
+		// Two futures wait for the completion, but the hook must run only once.
+		let first = tokio::spawn(shutdown.wait_shutdown_complete());
+		let second = tokio::spawn(shutdown.wait_shutdown_complete());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		drop(delay);
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(first.is_finished() == false);
+		assert!(second.is_finished() == false);
+		assert!(let Ok(()) = release.send(()));
+		assert!(let Ok(1) = first.await);
+		assert!(let Ok(1) = second.await);
+		assert!(dropped.load(Ordering::Relaxed) == 1);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(dropped.load(Ordering::Relaxed) == 1);
+
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { shutdown_reason: 1, .. }) = shutdown.register_async_drop(|_| async {}));
+	});
+}
+
+#[test]
+fn wrap_graceful() {
+	use async_shutdown::{Cancelled, GracefulOutcome};
+
+	test_timeout(async {
+		// The clean-up also runs when the work completes by itself.
+		let shutdown = ShutdownManager::<i32>::new();
+		let_assert!(Ok(graceful) = shutdown.wrap_graceful(future::ready(1), |work| future::ready(work.completed())));
+		assert!(graceful.await == GracefulOutcome::Finished(Some(1)));
+
+		// The work is cancelled by the shutdown, and the clean-up delays the completion.
+		let (cleanup_done, cleanup_wait) = futures::channel::oneshot::channel::<()>();
+		let_assert!(Ok(graceful) = shutdown.wrap_graceful(future::pending::<()>(), |work| async move {
+			cleanup_wait.await.ok();
+			work.is_shutdown_triggered()
+		}));
+		let task = tokio::spawn(graceful);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(shutdown.is_shutdown_completed() == false);
+		assert!(let Ok(()) = cleanup_done.send(()));
+		let_assert!(Ok(outcome) = task.await);
+		assert!(outcome == GracefulOutcome::CleanedUp { reason: 2, cleanup: true });
+		assert!(shutdown.is_shutdown_completed());
+
+		// A forced shutdown cancels the clean-up.
+		let shutdown = ShutdownManager::<i32>::new();
+		let_assert!(Ok(graceful) = shutdown.wrap_graceful(future::pending::<()>(), |work: Cancelled<(), i32>| async move {
+			future::pending::<()>().await;
+			work
+		}));
+		let task = tokio::spawn(graceful);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(3));
+		assert!(let Ok(()) = shutdown.trigger_forced_shutdown(4));
+		let_assert!(Ok(outcome) = task.await);
+		assert!(outcome.is_forced());
+		assert!(outcome.cleanup() == None);
+		assert!(shutdown.wait_shutdown_complete().await == 3);
+	});
+}
+
+#[test]
+fn last_drop_trigger_token() {
+	let shutdown = ShutdownManager::new();
+	let token = shutdown.last_drop_trigger_token(1);
+	let clone = token.clone();
+	assert!(token.clone_count() == 2);
+	drop(token);
+	assert!(shutdown.is_shutdown_triggered() == false);
+	drop(clone);
+	assert!(shutdown.shutdown_reason() == Some(1));
+
+	// A disarmed token does not trigger the shutdown.
+	let shutdown = ShutdownManager::new();
+	let token = shutdown.last_drop_trigger_token(2);
+	let clone = token.clone();
+	assert!(let Some(2) = clone.disarm());
+	assert!(token.is_armed() == false);
+	drop(token);
+	drop(clone);
+	assert!(shutdown.is_shutdown_triggered() == false);
+
+	// The last drop does not replace an earlier shutdown reason.
+	let token = shutdown.last_drop_trigger_token(3);
+	assert!(let Ok(()) = shutdown.trigger_shutdown(4));
+	drop(token);
+	assert!(shutdown.shutdown_reason() == Some(4));
+}
+
+#[test]
+fn wrap_delay_shutdown_or_run() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let delayed = shutdown.wrap_delay_shutdown_or_run(future::ready(1));
+		assert!(let Some(_) = delayed.delay_token());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		assert!(shutdown.is_shutdown_completed() == false);
+		assert!(delayed.await == 1);
+		assert!(shutdown.is_shutdown_completed());
+
+		// After the shutdown completed, the future still runs, but it does not delay anything.
+		let delayed = shutdown.wrap_delay_shutdown_or_run(future::ready(3));
+		assert!(let None = delayed.delay_token());
+		assert!(delayed.await == 3);
+	});
+}
+
+#[test]
+fn delay_shutdown_guard() {
+	let shutdown = ShutdownManager::new();
+	{
+		let_assert!(Ok(guard) = shutdown.delay_shutdown_guard());
+		guard.set_status("flushing");
+		assert!(guard.phase() == 0);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(shutdown.delay_token_count() == 1);
+		assert!(shutdown.pending_blockers()[0].status.as_deref() == Some("flushing"));
+		assert!(shutdown.is_shutdown_completed() == false);
+	}
+	assert!(shutdown.is_shutdown_completed());
+	assert!(let Err(_) = shutdown.delay_shutdown_guard());
+}
+
+#[test]
+fn try_wrap_cancel() {
+	#[derive(Debug, PartialEq)]
+	enum Error {
+		Shutdown(i32),
+		Failed,
+	}
+
+	impl From<i32> for Error {
+		fn from(code: i32) -> Self {
+			Error::Shutdown(code)
+		}
+	}
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert!(shutdown.try_wrap_cancel(future::ready(Ok::<_, Error>(1))).await == Ok(1));
+		assert!(shutdown.try_wrap_cancel(future::ready(Err::<(), _>(Error::Failed))).await == Err(Error::Failed));
+
+		let pending = shutdown.try_wrap_cancel(future::pending::<Result<(), Error>>());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		assert!(pending.await == Err(Error::Shutdown(2)));
+	});
+}
+
+#[test]
+fn debug_impls() {
+	#[derive(Debug)]
+	#[allow(dead_code)]
+	struct Server {
+		shutdown: ShutdownManager<i32>,
+		trigger: async_shutdown::TriggerShutdownToken<i32>,
+		connection: async_shutdown::WrapCancel<i32, future::Pending<()>>,
+	}
+
+	let shutdown = ShutdownManager::new();
+	let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+	let server = Server {
+		trigger: shutdown.trigger_shutdown_token(1),
+		connection: shutdown.wrap_cancel(future::pending()),
+		shutdown: shutdown.clone(),
+	};
+	let debug = format!("{:?}", server);
+	assert!(debug.contains("ShutdownManager { generation: 0, shutdown_triggered: false"));
+	assert!(debug.contains("delay_tokens: 1"));
+	assert!(debug.contains("TriggerShutdownToken { armed: true }"));
+	assert!(debug.contains("WrapCancel { shutdown_signal: ShutdownSignal { kind: Shutdown, triggered: false, waker_registered: false }"));
+
+	assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+	assert!(format!("{:?}", server.shutdown).contains("shutdown_triggered: true"));
+	assert!(format!("{:?}", server.connection.shutdown_signal()).contains("triggered: true"));
+	drop(delay);
+	assert!(format!("{:?}", shutdown.wait_shutdown_complete()).contains("completed: true"));
+}
+
+#[test]
+fn unpin_wrappers() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		// Futures that wait for the shutdown signal embed a node of the waker list, so they must be pinned.
+		let cancelled = vec![Box::pin(shutdown.wrap_cancel(future::pending::<i32>())), Box::pin(shutdown.wrap_cancel(future::pending()))];
+		let_assert!(Ok(first) = shutdown.wrap_delay_shutdown(future::ready(1)));
+		let_assert!(Ok(second) = shutdown.wrap_delay_shutdown(future::ready(2)));
+		let (value, _index, rest) = future::select_all(vec![first, second]).await;
+		assert!(value == 1);
+		drop(rest);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(3));
+		let (result, _index, _rest) = future::select_all(cancelled).await;
+		assert!(let Err(3) = result);
+	});
+}
+
+#[test]
+fn wrapper_accessors() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let wrapped = shutdown.wrap_cancel(future::pending::<()>());
+		let signal = wrapped.shutdown_signal().clone();
+
+		let_assert!(Ok(mut delayed) = shutdown.wrap_delay_shutdown(future::ready(())));
+		let_assert!(Some(token) = delayed.delay_token());
+		let token = token.clone();
+		(&mut delayed).await;
+		assert!(let None = delayed.delay_token());
+
+		let triggering = shutdown.trigger_shutdown_token(1).wrap_future(future::pending::<()>());
+		let_assert!(Some(trigger) = triggering.trigger_token());
+		assert!(let Some(1) = trigger.disarm());
+
+		drop(triggering);
+		assert!(signal.is_triggered() == false);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		assert!(wrapped.shutdown_signal().is_triggered());
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(token);
+		assert!(shutdown.is_shutdown_completed());
+	});
+}
+
+#[test]
+fn into_inner() {
+	let shutdown = ShutdownManager::new();
+	let wrapped = shutdown.wrap_cancel(future::ready(1));
+	let_assert!(Ok(future) = wrapped.into_inner());
+	let_assert!(Ok(wrapped) = shutdown.wrap_delay_shutdown(future));
+	let future = wrapped.into_inner();
+
+	let wrapped = shutdown.trigger_shutdown_token(2).wrap_future(future);
+	let (future, token) = wrapped.into_inner();
+	assert!(shutdown.is_shutdown_triggered() == false);
+	let_assert!(Some(token) = token);
+	let wrapped = token.wrap_future(future);
+	drop(wrapped);
+	assert!(shutdown.is_shutdown_completed());
+
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let polled = shutdown.wrap_cancel(future::pending::<()>());
+		let wrapped = shutdown.wrap_cancel(future::pending::<()>());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(3));
+		assert!(let Err(3) = polled.await);
+
+		// The future is only cancelled when the wrapper is polled.
+		assert!(let Ok(_) = wrapped.into_inner());
+	});
+}
+
+#[test]
+fn signal_accessors() {
+	let shutdown = ShutdownManager::new();
+	let signal = shutdown.wait_shutdown_triggered();
+	let forced = shutdown.wait_forced_shutdown_triggered();
+	let drain = shutdown.wait_drain_triggered();
+	assert!(signal.is_triggered() == false);
+	assert!(let None = signal.try_reason());
+	assert!(drain.is_triggered() == false);
+
+	assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+	assert!(signal.is_triggered());
+	assert!(let Some(1) = signal.try_reason());
+	assert!(let Some(1) = drain.try_reason());
+	assert!(forced.is_triggered() == false);
+	assert!(let None = forced.try_reason());
+
+	assert!(let Ok(()) = shutdown.trigger_forced_shutdown(2));
+	assert!(forced.is_triggered());
+	assert!(let Some(2) = forced.try_reason());
+	assert!(let Some(1) = signal.try_reason());
+}
+
+#[test]
+fn spawn_with() {
+	let shutdown = ShutdownManager::new();
+	fn spawn_thread<F: Future + Send + 'static>(future: F) -> std::thread::JoinHandle<F::Output>
+	where
+		F::Output: Send + 'static,
+	{
+		std::thread::spawn(move || futures::executor::block_on(future))
+	}
+
+	let_assert!(Ok(task) = shutdown.spawn_with(spawn_thread, async { 10 }));
+	let_assert!(Ok(result) = task.join());
+	assert!(let Ok(10) = result);
+
+	let_assert!(Ok(task) = shutdown.spawn_with(spawn_thread, future::pending::<()>()));
+	assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+	let_assert!(Ok(result) = task.join());
+	assert!(let Err(1) = result);
+	assert!(shutdown.is_shutdown_completed());
+
+	assert!(let Err(_) = shutdown.spawn_with(spawn_thread, async { 10 }));
+}
+
+#[test]
+fn exit_reason() {
+	use async_shutdown::ExitReason;
+	use std::process::ExitCode;
+
+	assert!(3i32.exit_code() == 3);
+	assert!(130u8.exit_code() == 130);
+	assert!(().exit_code() == 0);
+	assert!(ExitCode::SUCCESS.exit_code() == 0);
+	assert!(ExitCode::from(42).exit_code() == 42);
+}
+
+#[test]
+fn map_reason() {
+	test_timeout(async {
+		#[derive(Clone, Debug, PartialEq)]
+		enum AppReason {
+			Signal(i32),
+		}
+
+		let shutdown = ShutdownManager::new();
+		let view = shutdown.map_reason(|AppReason::Signal(number)| format!("signal {number}"));
+		assert!(view.is_shutdown_triggered() == false);
+		assert!(view.shutdown_reason() == None);
+
+		let task = tokio::spawn(view.wrap_cancel(future::pending::<()>()));
+		let completed = tokio::spawn(view.clone().wait_shutdown_complete());
+		let token = shutdown.delay_shutdown_token().unwrap();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(AppReason::Signal(15)));
+
+		assert!(view.wait_shutdown_triggered().await == "signal 15");
+		let_assert!(Ok(Err(reason)) = task.await);
+		assert!(reason == "signal 15");
+		assert!(view.shutdown_reason().as_deref() == Some("signal 15"));
+		assert!(view.is_shutdown_completed() == false);
+
+		drop(token);
+		let_assert!(Ok(reason) = completed.await);
+		assert!(reason == "signal 15");
+		assert!(view.is_shutdown_completed());
+	});
+}
+
+#[test]
+fn global_manager() {
+	test_timeout(async {
+		// Use a private reason type, so other tests can not interfere.
+		#[derive(Clone, Debug, PartialEq)]
+		struct Reason(i32);
+
+		let shutdown = async_shutdown::global::<Reason>();
+		assert!(shutdown.is_shutdown_triggered() == false);
+		assert!(let Ok(()) = async_shutdown::global::<Reason>().trigger_shutdown(Reason(1)));
+		assert!(shutdown.wait_shutdown_complete().await == Reason(1));
+		assert!(async_shutdown::global::<i64>().is_shutdown_triggered() == false);
+	});
+}
+
+#[test]
+fn shutdown_timeline() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert!(shutdown.triggered_at().is_none());
+		let token = shutdown.delay_shutdown_token().unwrap();
+
+		let before = std::time::Instant::now();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		let_assert!(Some(triggered_at) = shutdown.triggered_at());
+		assert!(triggered_at >= before);
+		assert!(shutdown.completed_at().is_none());
+		assert!(shutdown.shutdown_duration().is_none());
+
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		drop(token);
+		let_assert!(Some(completed_at) = shutdown.completed_at());
+		assert!(completed_at >= triggered_at);
+		let_assert!(Some(duration) = shutdown.shutdown_duration());
+		assert!(duration >= Duration::from_millis(10));
+	});
+}
+
+#[test]
+fn shutdown_priorities() {
+	test_timeout(async {
+		use std::sync::{Arc, Mutex};
+
+		let shutdown = ShutdownManager::new();
+		let order = Arc::new(Mutex::new(Vec::new()));
+
+		let_assert!(Ok(network) = shutdown.wrap_delay_shutdown_with_priority(1, {
+			let shutdown = shutdown.clone();
+			let order = order.clone();
+			async move {
+				shutdown.wait_priority_complete(0).await;
+				order.lock().unwrap().push("network");
+			}
+		}));
+		let_assert!(Ok(wal) = shutdown.wrap_delay_shutdown_with_priority(0, {
+			let shutdown = shutdown.clone();
+			let order = order.clone();
+			async move {
+				shutdown.wait_shutdown_triggered().await;
+				tokio::time::sleep(Duration::from_millis(10)).await;
+				order.lock().unwrap().push("wal");
+			}
+		}));
+		tokio::spawn(network);
+		tokio::spawn(wal);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.wait_priority_complete(0).await == "stop");
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		assert!(*order.lock().unwrap() == ["wal", "network"]);
+	});
+}
+
+#[test]
+fn delay_token_group() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let connections = shutdown.token_group("http-connections");
+		assert!(connections.name() == "http-connections");
+		assert!(connections.is_empty());
+
+		let_assert!(Ok(token) = connections.delay_shutdown_token());
+		let other = shutdown.delay_shutdown_token().unwrap();
+		let clone = token.clone();
+		assert!(connections.len() == 2);
+		assert!(shutdown.delay_token_count() == 3);
+
+		// Tokens upgraded from a weak token are still part of the group.
+		let weak = token.downgrade();
+		drop(token);
+		drop(clone);
+		assert!(connections.is_empty());
+		connections.wait_idle().await;
+		let upgraded = weak.upgrade().unwrap();
+		assert!(connections.len() == 1);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			drop(upgraded);
+		});
+		connections.wait_idle().await;
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(other);
+		shutdown.wait_shutdown_complete().await;
+	});
+}
+
+#[test]
+fn pending_blockers() {
+	let shutdown = ShutdownManager::<()>::new();
+	assert!(shutdown.pending_blockers().is_empty());
+
+	let flush = shutdown.delay_shutdown_token_in_phase(1).unwrap();
+	let connections = shutdown.token_group("connections");
+	let connection = connections.delay_shutdown_token().unwrap();
+	flush.set_status("flushed 3/10 segments");
+
+	let clone = flush.clone();
+	assert!(clone.status().as_deref() == Some("flushed 3/10 segments"));
+	assert!(format!("{:?}", clone).contains("flushed 3/10 segments"));
+
+	let blockers = shutdown.pending_blockers();
+	assert!(blockers.len() == 2);
+	assert!(blockers[0].phase == 1);
+	assert!(blockers[0].group.is_none());
+	assert!(blockers[0].status.as_deref() == Some("flushed 3/10 segments"));
+	assert!(blockers[1].phase == 0);
+	assert!(blockers[1].group.as_deref() == Some("connections"));
+	assert!(blockers[1].status.is_none());
+
+	clone.clear_status();
+	drop(flush);
+	assert!(shutdown.pending_blockers()[0].status.is_none());
+	drop(clone);
+	drop(connection);
+	assert!(shutdown.pending_blockers().is_empty());
+}
+
+#[test]
+fn drain() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let accept = tokio::spawn(shutdown.wrap_cancel_on_drain(future::pending::<()>()));
+		let request = tokio::spawn(shutdown.wrap_cancel(future::pending::<()>()));
+		assert!(shutdown.is_drain_triggered() == false);
+
+		assert!(let Ok(()) = shutdown.trigger_drain("drain"));
+		assert!(let Err(async_shutdown::ShutdownAlreadyStarted { .. }) = shutdown.trigger_drain("again"));
+		assert!(shutdown.is_drain_triggered());
+		assert!(shutdown.is_shutdown_triggered() == false);
+		assert!(shutdown.wait_drain_triggered().await == "drain");
+		assert!(let Ok(Err("drain")) = accept.await);
+
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(request.is_finished() == false);
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(let Ok(Err("stop")) = request.await);
+		assert!(shutdown.wait_drain_triggered().await == "drain");
+	});
+}
+
+#[test]
+fn shutdown_triggers_drain() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let drained = tokio::spawn(shutdown.wait_drain_triggered());
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.is_drain_triggered());
+		assert!(let Ok("stop") = drained.await);
+	});
+}
+
+#[test]
+fn delay_shutdown_lease() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let lease = shutdown.delay_shutdown_lease(Duration::from_millis(30)).unwrap();
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+
+		// Keep renewing the lease for a while.
+		for _ in 0..4 {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			assert!(lease.renew());
+		}
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		// Then let it expire.
+		shutdown.wait_shutdown_complete().await;
+		assert!(lease.is_expired());
+		assert!(lease.renew() == false);
+	});
+}
+
+#[test]
+fn shutdown_phases() {
+	test_timeout(async {
+		use std::sync::{Arc, Mutex};
+
+		let shutdown = ShutdownManager::new();
+		let order = Arc::new(Mutex::new(Vec::new()));
+
+		// Spawn a task for each phase that waits for the previous phase before it finishes.
+		for phase in (0..3).rev() {
+			let_assert!(Ok(token) = shutdown.delay_shutdown_token_in_phase(phase));
+			assert!(token.phase() == phase);
+			tokio::spawn({
+				let shutdown = shutdown.clone();
+				let order = order.clone();
+				async move {
+					if phase == 0 {
+						shutdown.wait_shutdown_triggered().await;
+						tokio::time::sleep(Duration::from_millis(10)).await;
+					} else {
+						shutdown.wait_phase_complete(phase - 1).await;
+					}
+					order.lock().unwrap().push(phase);
+					drop(token);
+				}
+			});
+		}
+
+		// Phases are not complete before the shutdown is triggered.
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(order.lock().unwrap().is_empty());
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.wait_phase_complete(1).await == "stop");
+		assert!(order.lock().unwrap()[..2] == [0, 1]);
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		assert!(*order.lock().unwrap() == [0, 1, 2]);
+
+		// Phases without any tokens are complete as soon as all earlier phases are complete.
+		assert!(shutdown.wait_phase_complete(10).await == "stop");
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.delay_shutdown_token_in_phase(1));
+	});
+}
+
+#[test]
+fn shutdown_phase_token_allowed_while_phase_busy() {
+	let shutdown = ShutdownManager::new();
+	let_assert!(Ok(_phase1) = shutdown.delay_shutdown_token_in_phase(1));
+	assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+
+	// Phase 0 is complete, but phase 1 is not.
+	assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.delay_shutdown_token());
+	assert!(let Ok(_) = shutdown.delay_shutdown_token_in_phase(1));
+	assert!(let Ok(_) = shutdown.delay_shutdown_token_in_phase(2));
+}
+
+#[test]
+fn trigger_shutdown_with_deadline() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+		let_assert!(Ok(_phase1) = shutdown.delay_shutdown_token_in_phase(1));
+		assert!(let Ok(()) = shutdown.trigger_shutdown_with_deadline("too slow", Duration::from_millis(20)));
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		// The shutdown completes even though the delay tokens are still alive.
+		assert!(shutdown.wait_phase_complete(1).await == "too slow");
+		assert!(shutdown.wait_shutdown_complete().await == "too slow");
+		assert!(shutdown.is_shutdown_completed());
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.delay_shutdown_token());
+
+		// Dropping the tokens afterwards is fine.
+		drop(delay.clone());
+		drop(delay);
+	});
+
+	// The deadline is not applied if the shutdown was already triggered.
+	let shutdown = ShutdownManager::new();
+	assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+	assert!(let Err(async_shutdown::ShutdownAlreadyStarted { shutdown_reason: 1, ignored_reason: 2, .. }) = shutdown.trigger_shutdown_with_deadline(2, Duration::ZERO));
+}
+
+#[test]
+fn async_shutdown_hooks() {
+	test_timeout(async {
+		use std::sync::{Arc, Mutex};
+
+		let shutdown = ShutdownManager::new();
+		let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+		let done = Arc::new(Mutex::new(Vec::new()));
+
+		let hook_done = done.clone();
+		assert!(let Ok(()) = shutdown.on_shutdown(move |reason| async move {
+			rx.await.ok();
+			hook_done.lock().unwrap().push(reason);
+		}));
+
+		// The hook is not started before the shutdown is triggered.
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(done.lock().unwrap().is_empty());
+
+		let complete = tokio::spawn(shutdown.wait_shutdown_complete());
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		// Hooks registered after the shutdown was triggered are still run.
+		let hook_done = done.clone();
+		assert!(let Ok(()) = shutdown.on_shutdown(move |reason| async move {
+			hook_done.lock().unwrap().push(reason);
+		}));
+
+		tx.send(()).unwrap();
+		assert!(let Ok("stop") = complete.await);
+		assert!(done.lock().unwrap().len() == 2);
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.on_shutdown(|_| async {}));
+	});
+}
+
+#[test]
+fn on_shutdown_hook_panics() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+		assert!(let Ok(()) = shutdown.on_shutdown(|_reason| async { panic!("hook failed") }));
+		assert!(let Ok(()) = shutdown.on_shutdown(move |_reason| async move {
+			rx.await.ok();
+		}));
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+
+		// The task that polls the panicking hook panics too.
+		let panicked = tokio::spawn(shutdown.wait_shutdown_complete());
+		let_assert!(Err(e) = panicked.await);
+		assert!(e.is_panic());
+
+		// But the other hook still runs, and the shutdown still completes.
+		let complete = tokio::spawn(shutdown.wait_shutdown_complete());
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(shutdown.is_shutdown_completed() == false);
+		tx.send(()).unwrap();
+		assert!(let Ok("stop") = complete.await);
+	});
+}
+
+#[test]
+fn register_async_drop_panics() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (release, released) = tokio::sync::oneshot::channel::<()>();
+		assert!(let Ok(()) = shutdown.register_async_drop(|_reason| async { panic!("async drop failed") }));
+		assert!(let Ok(()) = shutdown.register_async_drop(move |_reason| async move {
+			released.await.ok();
+		}));
+
+		let first = tokio::spawn(shutdown.wait_shutdown_complete());
+		let second = tokio::spawn(shutdown.wait_shutdown_complete());
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		tokio::time::sleep(Duration::from_millis(10)).await;
+
+		// The task that polls the panicking hook panics too, but the other one keeps driving the remaining hook.
+		assert!(first.is_finished() != second.is_finished());
+		assert!(let Ok(()) = release.send(()));
+		let_assert!(([Err(e), Ok("stop")] | [Ok("stop"), Err(e)]) = [first.await, second.await]);
+		assert!(e.is_panic());
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+	});
+}
+
+#[test]
+fn reset() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let clone = shutdown.clone();
+		assert!(shutdown.generation() == 0);
+
+		let old_signal = shutdown.wait_shutdown_triggered();
+		let old_trigger = shutdown.trigger_shutdown_token("old token");
+		let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+		assert!(let Ok(()) = shutdown.trigger_shutdown("first"));
+
+		// Can't reset before the shutdown completed.
+		assert!(let Err(async_shutdown::ShutdownNotCompleted { .. }) = shutdown.reset());
+		let old_complete = shutdown.wait_shutdown_complete();
+		drop(delay);
+		assert!(let Ok("first") = clone.reset());
+
+		// All clones see the new generation.
+		assert!(shutdown.generation() == 1);
+		assert!(shutdown.is_shutdown_triggered() == false);
+		assert!(shutdown.is_shutdown_completed() == false);
+		assert!(let Ok(_) = shutdown.delay_shutdown_token());
+
+		// Futures and tokens from before the reset stay bound to the old generation.
+		assert!(old_signal.await == "first");
+		assert!(old_complete.await == "first");
+		drop(old_trigger);
+		assert!(shutdown.is_shutdown_triggered() == false);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("second"));
+		assert!(shutdown.wait_shutdown_complete().await == "second");
+		assert!(let Ok("second") = shutdown.reset());
+		assert!(shutdown.generation() == 2);
+	});
+}
+
+#[test]
+fn wrap_cancel_with_cleanup() {
+	test_timeout(async {
+		use std::sync::atomic::{AtomicBool, Ordering};
+		use std::sync::Arc;
+
+		let shutdown = ShutdownManager::new();
+		let cleaned_up = Arc::new(AtomicBool::new(false));
+
+		// The clean-up is not run if the wrapped future completes first.
+		let_assert!(Ok(wrapped) = shutdown.wrap_cancel_with_cleanup(async { 10 }, |_| async { unreachable!() }));
+		assert!(let Ok(10) = wrapped.await);
+
+		let flag = cleaned_up.clone();
+		let_assert!(Ok(wrapped) = shutdown.wrap_cancel_with_cleanup(future::pending::<()>(), move |reason| async move {
+			assert!(reason == "stop");
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			flag.store(true, Ordering::Relaxed);
+		}));
+		let task = tokio::spawn(wrapped);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		// The shutdown completes only after the clean-up finished.
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		assert!(cleaned_up.load(Ordering::Relaxed));
+		assert!(let Ok(Err("stop")) = task.await);
+
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.wrap_cancel_with_cleanup(async {}, |_| async {}));
+	});
+}
+
+#[test]
+fn shutdown_signal_then() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut stopped = Box::pin(shutdown.wait_shutdown_triggered().then(|reason| async move {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			format!("stopped: {reason}")
+		}));
+
+		let mut ticks = 0;
+		loop {
+			tokio::select! {
+				_ = tokio::time::sleep(Duration::from_millis(1)) => {
+					ticks += 1;
+					if ticks == 5 {
+						shutdown.trigger_shutdown("bye").ok();
+					}
+				},
+				message = &mut stopped => {
+					assert!(message == "stopped: bye");
+					break;
+				},
+			}
+		}
+		assert!(ticks >= 5);
+	});
+}
+
+#[test]
+fn run_then_cleanup() {
+	test_timeout(async {
+		use async_shutdown::RunSummary;
+
+		let shutdown = ShutdownManager::new();
+
+		// The clean-up is not run if the main future completes first.
+		let_assert!(Ok(run) = shutdown.run_then_cleanup(async { 10 }, |_| async { unreachable!() }));
+		let summary: RunSummary<i32, &str, ()> = run.await;
+		assert!(summary == RunSummary::Completed(10));
+		assert!(summary.is_completed());
+
+		let_assert!(Ok(run) = shutdown.run_then_cleanup(future::pending::<()>(), |reason| async move {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			format!("cleaned up after {reason}")
+		}));
+		let task = tokio::spawn(run);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		let_assert!(Ok(RunSummary::CleanedUp { reason: "stop", cleanup }) = task.await);
+		assert!(cleanup == "cleaned up after stop");
+	});
+}
+
+#[test]
+fn wrap_cancel_outcome() {
+	test_timeout(async {
+		use async_shutdown::Cancelled;
+
+		let shutdown = ShutdownManager::new();
+		let completed = shutdown.wrap_cancel(future::ready(Err::<(), _>("error"))).outcome().await;
+		assert!(completed == Cancelled::Completed(Err("error")));
+		assert!(completed.is_completed());
+		assert!(completed.unwrap_completed() == Err("error"));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		let cancelled = shutdown.wrap_cancel(future::pending::<()>()).outcome().await;
+		assert!(cancelled == Cancelled::ShutdownTriggered(1));
+		assert!(cancelled.completed().is_none());
+		assert!(cancelled.into_result() == Err(1));
+	});
+}
+
+#[test]
+fn wrap_cancel_or() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert!(shutdown.wrap_cancel_or(0, future::ready(1)).await == 1);
+		assert!(shutdown.wrap_cancel_or_else(|reason| reason * 10, future::ready(1)).await == 1);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(2));
+		assert!(shutdown.wrap_cancel_or(0, future::pending()).await == 0);
+		assert!(shutdown.wrap_cancel_or_else(|reason| reason * 10, future::pending()).await == 20);
+	});
+}
+
+#[test]
+fn wrap_cancel_with_grace() {
+	test_timeout(async {
+		use async_shutdown::GraceOutcome;
+
+		let shutdown = ShutdownManager::new();
+		let grace = Duration::from_millis(20);
+		let finished = shutdown.wrap_cancel_with_grace(grace, future::ready(1)).unwrap();
+		assert!(finished.await == GraceOutcome::Finished(1));
+
+		let during_grace = tokio::spawn(shutdown.wrap_cancel_with_grace(grace, async {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			2
+		}).unwrap());
+		let cancelled = tokio::spawn(shutdown.wrap_cancel_with_grace(grace, future::pending::<i32>()).unwrap());
+		tokio::task::yield_now().await;
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.is_shutdown_completed() == false);
+		assert!(let Ok(GraceOutcome::FinishedDuringGrace(2)) = during_grace.await);
+		assert!(let Ok(GraceOutcome::Cancelled("stop")) = cancelled.await);
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+	});
+}
+
+#[test]
+fn wrap_graceful_server() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (drained_tx, drained_rx) = tokio::sync::oneshot::channel::<()>();
+
+		// A fake server that drains its connections after the graceful shutdown signal.
+		let_assert!(Ok(server) = shutdown.wrap_graceful_server(|signal| async move {
+			signal.await;
+			drained_rx.await.ok();
+			"drained"
+		}));
+		let server = tokio::spawn(server);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		drained_tx.send(()).unwrap();
+		shutdown.wait_shutdown_complete().await;
+		assert!(let Ok("drained") = server.await);
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.wrap_graceful_server(|signal| signal));
+	});
+}
+
+#[test]
+fn system_clock_runs_timers_in_order() {
+	use async_shutdown::{Clock, SystemClock};
+	use std::sync::{Arc, Mutex};
+
+	test_timeout(async {
+		let fired = Arc::new(Mutex::new(Vec::new()));
+		let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+		let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+		for (i, millis) in [(2, 20), (0, 0), (1, 10)] {
+			let fired = fired.clone();
+			let done_tx = done_tx.clone();
+			SystemClock.call_after(Duration::from_millis(millis), Box::new(move || {
+				let mut fired = fired.lock().unwrap();
+				fired.push(i);
+				if fired.len() == 3 {
+					done_tx.lock().unwrap().take().unwrap().send(()).unwrap();
+				}
+			}));
+		}
+		// A panicking callback must not stop the other timers.
+		SystemClock.call_after(Duration::from_millis(5), Box::new(|| panic!("boom")));
+
+		assert!(let Ok(()) = done_rx.await);
+		assert!(*fired.lock().unwrap() == [0, 1, 2]);
+	});
+}
@@ -0,0 +1,77 @@
+#![cfg(all(unix, feature = "signals"))]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+use tokio::signal::unix::SignalKind;
+
+use async_shutdown::{ShutdownManager, SignalEscalation};
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(500), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn attach_unix_signals() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let_assert!(Ok(listener) = shutdown.attach_unix_signals([
+			(SignalKind::terminate(), "terminate"),
+			(SignalKind::user_defined1(), "usr1"),
+		]));
+
+		// The signal handlers are installed when the function returns, so we can send the signal right away.
+		let_assert!(Ok(status) = std::process::Command::new("kill").arg("-USR1").arg(std::process::id().to_string()).status());
+		assert!(status.success());
+		assert!(shutdown.wait_shutdown_triggered().await == "usr1");
+		assert!(let Ok(()) = listener.await);
+	});
+}
+
+#[test]
+fn attach_unix_signals_with_escalation() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let delay = shutdown.delay_shutdown_token();
+		let_assert!(Ok(listener) = shutdown.attach_unix_signals_with_escalation(
+			[(SignalKind::user_defined2(), "usr2")],
+			SignalEscalation::new(1),
+		));
+
+		let kill = || std::process::Command::new("kill").arg("-USR2").arg(std::process::id().to_string()).status();
+		let_assert!(Ok(status) = kill());
+		assert!(status.success());
+		assert!(shutdown.wait_shutdown_triggered().await == "usr2");
+		assert!(shutdown.is_forced_shutdown_triggered() == false);
+
+		let_assert!(Ok(status) = kill());
+		assert!(status.success());
+		assert!(shutdown.wait_forced_shutdown_triggered().await == "usr2");
+
+		// The listener stops when the shutdown completes.
+		drop(delay);
+		assert!(let Ok(()) = listener.await);
+	});
+}
+
+#[test]
+fn signal_escalation_force_after() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let delay = shutdown.delay_shutdown_token();
+		let_assert!(Ok(listener) = shutdown.attach_unix_signals_with_escalation(
+			[(SignalKind::hangup(), "hangup")],
+			SignalEscalation::new(1).force_after(Duration::from_millis(10)),
+		));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("manual"));
+		assert!(shutdown.wait_forced_shutdown_triggered().await == "manual");
+		drop(delay);
+		assert!(let Ok(()) = listener.await);
+	});
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "sink")]
+
+use assert2::{assert, let_assert};
+use futures::{SinkExt, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn wrap_sink_flushes_on_shutdown() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (tx, rx) = futures::channel::mpsc::unbounded::<u32>();
+
+		// Buffer the items in the wrapper, so they only reach the channel when the sink is flushed.
+		let_assert!(Ok(mut sink) = shutdown.wrap_sink(tx.buffer(10)));
+		assert!(let Ok(()) = sink.feed(1).await);
+		assert!(let Ok(()) = sink.feed(2).await);
+
+		let task = tokio::spawn(sink);
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		let_assert!(Ok(Ok("stop")) = task.await);
+
+		// The sink was flushed and closed.
+		assert!(rx.collect::<Vec<_>>().await == [1, 2]);
+		assert!(let Err(async_shutdown::ShutdownAlreadyCompleted { .. }) = shutdown.wrap_sink(futures::sink::drain::<u32>()));
+	});
+}
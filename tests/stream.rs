@@ -0,0 +1,139 @@
+#![cfg(feature = "stream")]
+
+use assert2::{assert, let_assert};
+use futures::stream::{self, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn wrap_trigger_shutdown_stream() {
+	// Trigger a shutdown when the stream ends.
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut stream = shutdown.wrap_trigger_shutdown_stream("stream ended", stream::iter([1, 2]));
+		assert!(let Some(1) = stream.next().await);
+		assert!(let Some(2) = stream.next().await);
+		assert!(shutdown.is_shutdown_triggered() == false);
+		assert!(let None = stream.next().await);
+		assert!(shutdown.shutdown_reason() == Some("stream ended"));
+	});
+
+	// Trigger a shutdown when the stream is dropped.
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let stream = shutdown.trigger_shutdown_token("dropped").wrap_stream(stream::pending::<()>());
+		drop(stream);
+		assert!(shutdown.wait_shutdown_triggered().await == "dropped");
+	});
+}
+
+#[test]
+fn into_stream() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let token = shutdown.delay_shutdown_token().unwrap();
+		let messages = stream::iter([1, 2]).chain(stream::pending());
+		let mut events = stream::select(messages, shutdown.wait_shutdown_triggered().into_stream().map(|reason| reason * 10));
+		assert!(let Some(1) = events.next().await);
+		assert!(let Some(2) = events.next().await);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(3));
+		assert!(let Some(30) = events.next().await);
+
+		let mut complete = shutdown.wait_shutdown_complete().into_stream();
+		drop(token);
+		assert!(let Some(3) = complete.next().await);
+		assert!(let None = complete.next().await);
+	});
+}
+
+#[test]
+fn events() {
+	test_timeout(async {
+		use async_shutdown::ShutdownEvent;
+
+		let shutdown = ShutdownManager::new();
+		let mut events = shutdown.events();
+		let token = shutdown.delay_shutdown_token().unwrap();
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Some(ShutdownEvent::Triggered(1)) = events.next().await);
+		assert!(let Ok(()) = shutdown.trigger_forced_shutdown(2));
+		assert!(let Some(ShutdownEvent::ForcedTriggered(2)) = events.next().await);
+		drop(token);
+		assert!(let Some(ShutdownEvent::Completed(1)) = events.next().await);
+		assert!(let None = events.next().await);
+
+		// Events that happened before the stream was created are reported too.
+		let events: Vec<_> = shutdown.events().collect().await;
+		assert!(events == [ShutdownEvent::Triggered(1), ShutdownEvent::Completed(1)]);
+	});
+}
+
+#[test]
+fn completion_progress() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut progress = shutdown.completion_progress();
+		let first = shutdown.delay_shutdown_token().unwrap();
+		let second = shutdown.delay_shutdown_token().unwrap();
+		let clone = second.clone();
+		assert!(futures::poll!(progress.next()).is_pending());
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(progress.next().await == Some(2));
+		assert!(futures::poll!(progress.next()).is_pending());
+
+		drop(first);
+		assert!(progress.next().await == Some(1));
+		drop(second);
+		drop(clone);
+		assert!(progress.next().await == Some(0));
+		assert!(progress.next().await == None);
+	});
+}
+
+#[test]
+fn for_each_until_shutdown() {
+	test_timeout(async {
+		use std::sync::{Arc, Mutex};
+
+		// Process all items if the stream ends first.
+		let shutdown = ShutdownManager::<()>::new();
+		let summary = shutdown.for_each_until_shutdown(stream::iter([1, 2, 3]), |_| async {}).await;
+		assert!(summary.processed == 3);
+		assert!(summary.shutdown_reason == None);
+
+		// Finish the in-flight item when the shutdown is triggered, but don't take new items.
+		let seen = Arc::new(Mutex::new(Vec::new()));
+		let task = tokio::spawn(shutdown.for_each_until_shutdown(stream::iter(0..), {
+			let shutdown = shutdown.clone();
+			let seen = seen.clone();
+			move |item| {
+				let shutdown = shutdown.clone();
+				let seen = seen.clone();
+				async move {
+					if item == 2 {
+						shutdown.trigger_shutdown(()).ok();
+						tokio::time::sleep(Duration::from_millis(10)).await;
+					}
+					seen.lock().unwrap().push(item);
+				}
+			}
+		}));
+		let_assert!(Ok(summary) = task.await);
+		assert!(summary.processed == 3);
+		assert!(summary.shutdown_reason == Some(()));
+		assert!(*seen.lock().unwrap() == [0, 1, 2]);
+	});
+}
@@ -0,0 +1,122 @@
+#![cfg(feature = "tcp")]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_secs(1), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+/// Start a worker that follows the shutdown of a coordinator.
+async fn worker() -> (ShutdownManager<u32>, std::net::SocketAddr) {
+	let shutdown = ShutdownManager::new();
+	let_assert!(Ok(listener) = TcpListener::bind("127.0.0.1:0").await);
+	let_assert!(Ok(address) = listener.local_addr());
+	tokio::spawn(shutdown.follow_tcp(listener));
+	(shutdown, address)
+}
+
+#[test]
+fn coordinate_tcp() {
+	test_timeout(async {
+		let (draining, draining_address) = worker().await;
+		let (stuck, stuck_address) = worker().await;
+		let busy = draining.delay_shutdown_token().unwrap();
+		let _stuck = stuck.delay_shutdown_token().unwrap();
+
+		// A worker that is not listening.
+		let_assert!(Ok(closed) = TcpListener::bind("127.0.0.1:0").await);
+		let_assert!(Ok(closed_address) = closed.local_addr());
+		drop(closed);
+
+		let coordinator = ShutdownManager::new();
+		let report = tokio::spawn(coordinator.coordinate_tcp([draining_address, stuck_address, closed_address], Duration::from_millis(100)));
+		tokio::time::sleep(Duration::from_millis(20)).await;
+
+		// The workers are triggered with the reason of the coordinator, which waits for them to complete.
+		assert!(let Ok(()) = coordinator.trigger_shutdown(7));
+		assert!(draining.wait_shutdown_triggered().await == 7);
+		assert!(stuck.wait_shutdown_triggered().await == 7);
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(coordinator.is_shutdown_completed() == false);
+		drop(busy);
+
+		let_assert!(Ok(report) = report.await);
+		assert!(report.all_acknowledged() == false);
+		assert!(report.acknowledged == [draining_address]);
+		assert!(report.timed_out == [stuck_address]);
+		assert!(report.failed.len() == 1);
+		assert!(report.failed[0].0 == closed_address);
+		assert!(coordinator.wait_shutdown_complete().await == 7);
+	});
+}
+
+#[test]
+fn follow_tcp_wire_format() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	test_timeout(async {
+		let (shutdown, address) = worker().await;
+		let_assert!(Ok(mut stream) = tokio::net::TcpStream::connect(address).await);
+
+		// A trigger frame with version 1, the length of the message as a big-endian `u32` and the message as JSON.
+		let trigger = br#"{"type":"trigger","reason":7}"#;
+		let mut frame = vec![1, 0, 0, 0, trigger.len() as u8];
+		frame.extend_from_slice(trigger);
+		assert!(let Ok(()) = stream.write_all(&frame).await);
+		assert!(shutdown.wait_shutdown_complete().await == 7);
+
+		// An acknowledgement frame with version 1.
+		let mut acknowledgement = Vec::new();
+		assert!(let Ok(_) = stream.read_to_end(&mut acknowledgement).await);
+		assert!(acknowledgement == [&[1, 0, 0, 0, 20][..], br#"{"type":"completed"}"#].concat());
+	});
+}
+
+#[test]
+fn follow_tcp_rejects_unknown_version() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	test_timeout(async {
+		let (shutdown, address) = worker().await;
+		let_assert!(Ok(mut stream) = tokio::net::TcpStream::connect(address).await);
+		assert!(let Ok(()) = stream.write_all(b"\x02\x00\x00\x00\x1d{\"type\":\"trigger\",\"reason\":7}").await);
+
+		// The worker closes the connection without triggering the shutdown.
+		// The unread part of the frame may turn the close into a connection reset.
+		let mut buffer = Vec::new();
+		stream.read_to_end(&mut buffer).await.ok();
+		assert!(buffer.is_empty());
+		assert!(shutdown.is_shutdown_triggered() == false);
+	});
+}
+
+#[test]
+fn follow_tcp_rejects_invalid_reason() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	test_timeout(async {
+		let (shutdown, address) = worker().await;
+		let_assert!(Ok(mut stream) = tokio::net::TcpStream::connect(address).await);
+
+		// The worker expects a `u32` as shutdown reason.
+		let trigger = br#"{"type":"trigger","reason":"seven"}"#;
+		let mut frame = vec![1, 0, 0, 0, trigger.len() as u8];
+		frame.extend_from_slice(trigger);
+		assert!(let Ok(()) = stream.write_all(&frame).await);
+
+		let mut buffer = Vec::new();
+		stream.read_to_end(&mut buffer).await.ok();
+		assert!(buffer.is_empty());
+		assert!(shutdown.is_shutdown_triggered() == false);
+	});
+}
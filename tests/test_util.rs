@@ -0,0 +1,190 @@
+#![cfg(feature = "test-util")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::test_util::{assert_completes_within, assert_not_triggered, assert_triggered, PollCounter, TestClock};
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn assert_helpers() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		assert_not_triggered(&shutdown);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(assert_triggered(&shutdown) == 1);
+		assert!(assert_completes_within(&shutdown, Duration::from_millis(10)).await == 1);
+	});
+}
+
+#[test]
+#[should_panic(expected = "shutdown did not complete within")]
+fn assert_completes_within_timeout() {
+	let runtime = tokio::runtime::Runtime::new().unwrap();
+	runtime.block_on(async {
+		let shutdown = ShutdownManager::new();
+		let _token = shutdown.delay_shutdown_token().unwrap();
+		shutdown.trigger_shutdown(1).unwrap();
+		assert_completes_within(&shutdown, Duration::from_millis(10)).await;
+	});
+}
+
+#[test]
+fn poll_counter() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut future = Box::pin(PollCounter::new(shutdown.wrap_cancel(future::pending::<()>())));
+		let count = future.count();
+		assert!(futures::poll!(&mut future).is_pending());
+		assert!(count.get() == 1);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Err(1) = future.as_mut().await);
+		assert!(future.polls() == 2);
+	});
+}
+
+#[test]
+fn test_clock_deadline() {
+	let clock = TestClock::new();
+	let shutdown = ShutdownManager::builder().clock(clock.clone()).build();
+	let_assert!(Ok(delay) = shutdown.delay_shutdown_token());
+
+	assert!(let Ok(()) = shutdown.trigger_shutdown_with_deadline(1, Duration::from_secs(60)));
+	assert!(clock.pending_timers() == 1);
+	clock.advance(Duration::from_secs(59));
+	assert!(shutdown.is_shutdown_completed() == false);
+	clock.advance(Duration::from_secs(1));
+	assert!(shutdown.is_shutdown_completed());
+	assert!(clock.elapsed() == Duration::from_secs(60));
+	assert!(clock.pending_timers() == 0);
+	drop(delay);
+
+	// The clock is kept when the manager is reset.
+	assert!(let Ok(1) = shutdown.reset());
+	let_assert!(Ok(_delay) = shutdown.delay_shutdown_token());
+	assert!(let Ok(()) = shutdown.trigger_shutdown_with_deadline(2, Duration::from_secs(60)));
+	clock.advance(Duration::from_secs(60));
+	assert!(shutdown.is_shutdown_completed());
+}
+
+#[test]
+fn test_clock_report_blockers() {
+	use std::sync::{Arc, Mutex};
+
+	let clock = TestClock::new();
+	let reports = Arc::new(Mutex::new(Vec::new()));
+	let shutdown = ShutdownManager::builder()
+		.clock(clock.clone())
+		.report_blockers(Duration::from_secs(5), {
+			let reports = reports.clone();
+			move |report| reports.lock().unwrap().push(report.clone())
+		})
+		.build();
+	let_assert!(Ok(database) = shutdown.delay_shutdown_token());
+	database.set_status("flushing database");
+	let_assert!(Ok(anonymous) = shutdown.delay_shutdown_token());
+
+	// Nothing is reported before the shutdown is triggered.
+	clock.advance(Duration::from_secs(10));
+	assert!(reports.lock().unwrap().is_empty());
+
+	assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+	clock.advance(Duration::from_secs(5));
+	drop(anonymous);
+	clock.advance(Duration::from_secs(5));
+	{
+		let reports = reports.lock().unwrap();
+		assert!(reports.len() == 2);
+		assert!(reports[0].elapsed == Duration::from_secs(5));
+		assert!(reports[0].delay_tokens == 2);
+		assert!(reports[1].elapsed == Duration::from_secs(10));
+		assert!(reports[1].delay_tokens == 1);
+		assert!(reports[1].blockers[0].status.as_deref() == Some("flushing database"));
+	}
+
+	// The reports stop when the shutdown completes.
+	drop(database);
+	clock.advance(Duration::from_secs(5));
+	assert!(reports.lock().unwrap().len() == 2);
+	assert!(clock.pending_timers() == 0);
+}
+
+#[test]
+fn test_clock_grace() {
+	test_timeout(async {
+		let clock = TestClock::new();
+		let shutdown = ShutdownManager::builder().clock(clock.clone()).build();
+		let_assert!(Ok(future) = shutdown.wrap_cancel_with_grace(Duration::from_secs(60), future::pending::<()>()));
+		let mut future = Box::pin(future);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(futures::poll!(&mut future).is_pending());
+		clock.advance(Duration::from_secs(59));
+		assert!(futures::poll!(&mut future).is_pending());
+		clock.advance(Duration::from_secs(1));
+		assert!(let async_shutdown::GraceOutcome::Cancelled(1) = future.await);
+	});
+}
+
+#[test]
+fn test_clock_grace_is_shared() {
+	test_timeout(async {
+		let clock = TestClock::new();
+		let shutdown = ShutdownManager::builder().clock(clock.clone()).build();
+		let grace = Duration::from_secs(60);
+		let_assert!(Ok(first) = shutdown.wrap_cancel_with_grace(grace, future::pending::<()>()));
+		let_assert!(Ok(second) = shutdown.wrap_cancel_with_grace(grace, future::pending::<()>()));
+		let_assert!(Ok(longer) = shutdown.wrap_cancel_with_grace(grace * 2, future::pending::<()>()));
+		let mut first = Box::pin(first);
+		let mut second = Box::pin(second);
+		let mut longer = Box::pin(longer);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(futures::poll!(&mut first).is_pending());
+		assert!(futures::poll!(&mut longer).is_pending());
+		assert!(clock.pending_timers() == 2);
+
+		// The second future notices the shutdown later, but it shares the timer of the first.
+		clock.advance(Duration::from_secs(30));
+		assert!(futures::poll!(&mut second).is_pending());
+		assert!(clock.pending_timers() == 2);
+
+		clock.advance(Duration::from_secs(30));
+		assert!(let async_shutdown::GraceOutcome::Cancelled(1) = first.await);
+		assert!(let async_shutdown::GraceOutcome::Cancelled(1) = second.await);
+		assert!(futures::poll!(&mut longer).is_pending());
+		clock.advance(Duration::from_secs(60));
+		assert!(let async_shutdown::GraceOutcome::Cancelled(1) = longer.await);
+	});
+}
+
+#[test]
+fn test_clock_lease() {
+	let clock = TestClock::new();
+	let shutdown = ShutdownManager::builder().clock(clock.clone()).build();
+	let lease = shutdown.delay_shutdown_lease(Duration::from_secs(30)).unwrap();
+	assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+
+	clock.advance(Duration::from_secs(20));
+	assert!(lease.renew());
+	clock.advance(Duration::from_secs(20));
+	assert!(lease.is_expired() == false);
+	assert!(shutdown.is_shutdown_completed() == false);
+
+	clock.advance(Duration::from_secs(10));
+	assert!(lease.is_expired());
+	assert!(shutdown.is_shutdown_completed());
+	assert!(lease.renew() == false);
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "tokio")]
+
+use assert2::{assert, let_assert};
+use futures::future;
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn spawn() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let finished = shutdown.spawn(future::ready(10)).unwrap();
+		assert!(let Ok(Ok(10)) = finished.await);
+
+		let pending = shutdown.spawn(future::pending::<()>()).unwrap();
+		assert!(shutdown.delay_token_count() == 1);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+		assert!(let Ok(Err(1)) = pending.await);
+
+		let_assert!(Err(e) = shutdown.spawn(future::ready(())));
+		assert!(e.shutdown_reason == 1);
+	});
+}
+
+#[test]
+fn spawn_named() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let pending = shutdown.spawn_named("worker", future::pending::<()>()).unwrap();
+		let blockers = shutdown.pending_blockers();
+		let_assert!([blocker] = blockers.as_slice());
+		assert!(blocker.status.as_deref() == Some("task worker"));
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Ok(Err(1)) = pending.await);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+	});
+}
+
+#[test]
+fn task_tracker() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let tracker = shutdown.task_tracker();
+		assert!(tracker.is_empty());
+		tracker.wait().await;
+
+		let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+		let finishing = tracker.spawn(async move {
+			receiver.await.ok();
+		}).unwrap();
+		let pending = tracker.spawn(future::pending::<()>()).unwrap();
+		assert!(tracker.len() == 2);
+
+		sender.send(()).unwrap();
+		assert!(let Ok(Ok(())) = finishing.await);
+		assert!(tracker.len() == 1);
+
+		let wait = tokio::spawn(tracker.wait());
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Ok(()) = wait.await);
+		assert!(tracker.is_empty());
+		assert!(let Ok(Err(1)) = pending.await);
+		assert!(shutdown.wait_shutdown_complete().await == 1);
+	});
+}
+
+#[test]
+fn wrap_abort() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let finished = shutdown.wrap_abort(tokio::spawn(future::ready(10)));
+		assert!(let Ok(Ok(10)) = finished.await);
+
+		let pending = tokio::spawn(shutdown.wrap_abort(tokio::spawn(future::pending::<()>())));
+		tokio::task::yield_now().await;
+		assert!(let Ok(()) = shutdown.trigger_shutdown(1));
+		assert!(let Ok(Err(1)) = pending.await);
+	});
+}
+
+#[test]
+fn interval() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let mut interval = shutdown.interval(Duration::from_millis(5));
+		assert!(interval.period() == Duration::from_millis(5));
+
+		let mut ticks = 0;
+		while interval.tick().await.is_some() {
+			ticks += 1;
+			if ticks == 3 {
+				shutdown.trigger_shutdown(()).ok();
+			}
+		}
+		assert!(ticks == 3);
+		assert!(let None = interval.tick().await);
+	});
+}
+
+#[test]
+fn mpsc_channel() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let (sender, mut receiver) = shutdown.mpsc_channel(10).unwrap();
+		assert!(let Ok(()) = sender.send(1).await);
+		assert!(let Some(1) = receiver.recv().await);
+
+		// Messages that were buffered before the shutdown are still delivered.
+		assert!(let Ok(()) = sender.send(2).await);
+		assert!(let Ok(()) = sender.send(3).await);
+		assert!(let Ok(()) = shutdown.trigger_shutdown(()));
+		assert!(let Some(2) = receiver.recv().await);
+		assert!(let Err(_) = sender.send(4).await);
+		assert!(shutdown.is_shutdown_completed() == false);
+		assert!(let Some(3) = receiver.recv().await);
+		assert!(let None = receiver.recv().await);
+		assert!(shutdown.is_shutdown_completed());
+	});
+}
+
+#[test]
+fn mpsc_drain() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::<()>::new();
+		let (sender, receiver) = shutdown.mpsc_channel(10).unwrap();
+		for i in 0..5 {
+			assert!(let Ok(()) = sender.send(i).await);
+		}
+
+		let mut seen = Vec::new();
+		let processed = receiver.drain(|message| {
+			seen.push(message);
+			future::ready(())
+		}).await;
+		assert!(processed == 5);
+		assert!(seen == [0, 1, 2, 3, 4]);
+		assert!(let Err(_) = sender.send(5).await);
+	});
+}
+
+#[test]
+fn semaphore() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let semaphore = shutdown.semaphore(2);
+		let_assert!(Ok(first) = semaphore.acquire().await);
+		let_assert!(Ok(Some(second)) = semaphore.try_acquire());
+		assert!(let Ok(None) = semaphore.try_acquire());
+		assert!(semaphore.available_permits() == 0);
+
+		// Waiting for a permit fails when the shutdown is triggered.
+		let waiting = tokio::spawn({
+			let semaphore = semaphore.clone();
+			async move { semaphore.acquire().await.map(drop) }
+		});
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(let Ok(Err("stop")) = waiting.await);
+
+		// Outstanding permits delay the shutdown, but no new permits are handed out.
+		drop(first);
+		assert!(let Err("stop") = semaphore.acquire().await.map(drop));
+		assert!(let Err("stop") = semaphore.try_acquire().map(drop));
+		assert!(shutdown.is_shutdown_completed() == false);
+		drop(second);
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+	});
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "tonic")]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+use tonic::service::Routes;
+use tonic::transport::Server;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(500), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn serve_tonic_stops_on_shutdown() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let router = Server::builder().add_routes(Routes::default());
+		let_assert!(Ok(server) = shutdown.serve_tonic(router, "127.0.0.1:0".parse().unwrap(), |_| "server failed"));
+		let server = tokio::spawn(server);
+
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		assert!(shutdown.is_shutdown_triggered() == false);
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		assert!(let Ok(Ok(())) = server.await);
+	});
+}
+
+#[test]
+fn serve_tonic_error_triggers_shutdown() {
+	test_timeout(async {
+		// Occupy a port so the server fails to bind to it.
+		let_assert!(Ok(listener) = std::net::TcpListener::bind("127.0.0.1:0"));
+		let_assert!(Ok(address) = listener.local_addr());
+
+		let shutdown = ShutdownManager::new();
+		let router = Server::builder().add_routes(Routes::default());
+		let_assert!(Ok(server) = shutdown.serve_tonic(router, address, |_| "server failed"));
+		assert!(let Err(_) = server.await);
+		assert!(shutdown.wait_shutdown_complete().await == "server failed");
+	});
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "tonic-health")]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::health_server::Health;
+use tonic_health::pb::HealthCheckRequest;
+use tonic_health::server::{HealthReporter, HealthService};
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+async fn status(service: &HealthService, name: &str) -> ServingStatus {
+	let request = tonic::Request::new(HealthCheckRequest { service: name.into() });
+	let_assert!(Ok(response) = service.check(request).await);
+	response.into_inner().status()
+}
+
+#[test]
+fn report_not_serving_on_shutdown() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let reporter = HealthReporter::new();
+		let service = HealthService::from_health_reporter(reporter.clone());
+		reporter.set_service_status("my.Service", tonic_health::ServingStatus::Serving).await;
+
+		let_assert!(Ok(report) = shutdown.report_health_on_shutdown(reporter, ["my.Service"]));
+		tokio::spawn(report);
+
+		tokio::task::yield_now().await;
+		assert!(status(&service, "").await == ServingStatus::Serving);
+		assert!(status(&service, "my.Service").await == ServingStatus::Serving);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		assert!(status(&service, "").await == ServingStatus::NotServing);
+		assert!(status(&service, "my.Service").await == ServingStatus::NotServing);
+	});
+}
+
+#[test]
+fn report_drain_progress() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let reporter = HealthReporter::new();
+		let service = HealthService::from_health_reporter(reporter.clone());
+		let token = shutdown.delay_shutdown_token().unwrap();
+		token.set_status("draining connections");
+
+		let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+		let_assert!(Ok(report) = shutdown.report_health_with_progress(reporter, ["my.Service"], move |progress| {
+			progress_tx.send(progress).unwrap();
+		}));
+		let report = tokio::spawn(report);
+
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		let_assert!(Some(progress) = progress_rx.recv().await);
+		assert!(progress.remaining == 1);
+		assert!(progress.blockers.len() == 1);
+		assert!(progress.blockers[0].status.as_deref() == Some("draining connections"));
+		assert!(status(&service, "my.Service").await == ServingStatus::NotServing);
+
+		drop(token);
+		let_assert!(Some(progress) = progress_rx.recv().await);
+		assert!(progress.remaining == 0);
+		assert!(progress.blockers.is_empty());
+		assert!(let Ok(()) = report.await);
+		assert!(progress_rx.recv().await.is_none());
+	});
+}
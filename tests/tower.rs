@@ -0,0 +1,63 @@
+#![cfg(feature = "tower")]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use async_shutdown::{ShutdownLayer, ShutdownManager};
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_millis(100), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+/// Service that echoes the request after a short delay.
+struct SlowEcho;
+
+impl Service<u32> for SlowEcho {
+	type Response = u32;
+	type Error = String;
+	type Future = Pin<Box<dyn Future<Output = Result<u32, String>> + Send>>;
+
+	fn poll_ready(&mut self, _context: &mut Context) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, request: u32) -> Self::Future {
+		Box::pin(async move {
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			Ok(request)
+		})
+	}
+}
+
+#[test]
+fn shutdown_layer() {
+	test_timeout(async {
+		let shutdown = ShutdownManager::new();
+		let layer = ShutdownLayer::new(shutdown.clone(), |reason: &str| format!("shutting down: {reason}"));
+		let mut service = layer.layer(SlowEcho);
+
+		assert!(let Ok(1) = service.call(1).await);
+
+		// In-flight requests delay the shutdown completion.
+		let in_flight = tokio::spawn(service.call(2));
+		assert!(let Ok(()) = shutdown.trigger_shutdown("stop"));
+		assert!(shutdown.is_shutdown_completed() == false);
+
+		// New requests are rejected.
+		let_assert!(Err(e) = service.call(3).await);
+		assert!(e == "shutting down: stop");
+
+		assert!(shutdown.wait_shutdown_complete().await == "stop");
+		assert!(let Ok(Ok(2)) = in_flight.await);
+	});
+}
@@ -0,0 +1,44 @@
+#![cfg(all(unix, feature = "unix-socket"))]
+
+use assert2::{assert, let_assert};
+use std::future::Future;
+use std::time::Duration;
+
+use async_shutdown::ShutdownManager;
+
+#[track_caller]
+fn test_timeout(test: impl Future<Output = ()>) {
+	let_assert!(Ok(runtime) = tokio::runtime::Runtime::new(), "failed to initialize tokio runtime");
+	runtime.block_on(async move {
+		let test = tokio::time::timeout(Duration::from_secs(1), test);
+		assert!(let Ok(()) = test.await, "test timed out");
+	});
+}
+
+#[test]
+fn propagate_shutdown() {
+	test_timeout(async {
+		let path = std::env::temp_dir().join(format!("async-shutdown-test-{}.sock", std::process::id()));
+		std::fs::remove_file(&path).ok();
+		let_assert!(Ok(listener) = tokio::net::UnixListener::bind(&path));
+
+		let parent = ShutdownManager::<String>::new();
+		let server = tokio::spawn(parent.serve_unix_socket(listener));
+		let child = ShutdownManager::<String>::new();
+		let follower = tokio::spawn(child.follow_unix_socket(&path));
+		let busy = child.delay_shutdown_token().unwrap();
+		tokio::time::sleep(Duration::from_millis(20)).await;
+
+		// The child is triggered with the reason of the parent, and the parent waits for the child to complete.
+		assert!(let Ok(()) = parent.trigger_shutdown("deploy".into()));
+		assert!(child.wait_shutdown_triggered().await == "deploy");
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(parent.is_shutdown_completed() == false);
+
+		drop(busy);
+		assert!(let Ok(Ok(())) = follower.await);
+		assert!(parent.wait_shutdown_complete().await == "deploy");
+		assert!(let Ok(Ok(())) = server.await);
+		std::fs::remove_file(&path).ok();
+	});
+}